@@ -1,5 +1,9 @@
 use crate::{
-    constants::{COMPRESSION_CHUNK_SIZE, PAYLOAD_CHUNK_SIZE},
+    constants::{
+        COMPRESSION_CHUNK_SIZE, DEFAULT_EXTRACTION_MAX_ENTRY_COUNT,
+        DEFAULT_EXTRACTION_MAX_TOTAL_SIZE, MAX_PACKAGE_TOML_SIZE, PACKAGE_TOML, PAYLOAD_CHUNK_SIZE,
+        TAR_ENTRY_OVERHEAD,
+    },
     project::Project,
     validation,
 };
@@ -19,15 +23,230 @@ use gzp::{
     Compression, ZWriter,
 };
 use ignore::{DirEntry, WalkBuilder};
+use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashSet,
     fs::{create_dir_all, remove_file, rename, File},
     io::{Read, Write},
     iter::Iterator,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
     pin::Pin,
     task::{Context, Poll},
 };
-use tar::{Archive, Builder, Entry};
+use tar::{Archive, Builder, Entry, EntryType};
+use thiserror::Error as ThisError;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct ExtractionLimits {
+    pub max_total_size: u64,
+    pub max_entry_count: u64,
+}
+
+impl Default for ExtractionLimits {
+    fn default() -> Self {
+        Self {
+            max_total_size: DEFAULT_EXTRACTION_MAX_TOTAL_SIZE,
+            max_entry_count: DEFAULT_EXTRACTION_MAX_ENTRY_COUNT,
+        }
+    }
+}
+
+#[derive(ThisError, Debug)]
+pub enum ExtractionError {
+    #[error("Archive contains unsafe entries: {}", .0.join(", "))]
+    UnsafeEntries(Vec<String>),
+    #[error("Archive contains more than {0} entries")]
+    TooManyEntries(u64),
+    #[error("Archive contents exceed the size limit of {0} bytes")]
+    SizeLimitExceeded(u64),
+    #[error("Unsafe path requested from the archive: {0}")]
+    UnsafeSearchPath(String),
+    #[error("package.toml in the archive exceeds the size limit of {0} bytes")]
+    PackageTomlTooLarge(u64),
+}
+
+fn is_safe_relative_path(path: &Path) -> bool {
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+/// Checks that a link target resolved against `base` does not climb above the archive root.
+fn is_safe_link_target(base: &Path, target: &Path) -> bool {
+    let mut depth = base
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .count();
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if depth == 0 {
+                    return false;
+                }
+                depth -= 1;
+            }
+            Component::RootDir | Component::Prefix(_) => return false,
+        }
+    }
+    true
+}
+
+/// Checks the entries of an archive in order. `is_safe_link_target` only resolves a link target
+/// lexically, so the symlinks seen so far are remembered and any later entry written through one
+/// of them is rejected, the link could point to a directory outside of the destination.
+#[derive(Default)]
+struct EntryValidator {
+    symlinks: HashSet<PathBuf>,
+}
+
+impl EntryValidator {
+    fn unsafe_entry_reason<R: Read>(
+        &mut self,
+        entry: &Entry<R>,
+        entry_path: &Path,
+    ) -> Result<Option<String>> {
+        if !is_safe_relative_path(entry_path) {
+            return Ok(Some("path escapes the destination".to_string()));
+        }
+        let normalized_path = normalize_archive_path(entry_path);
+        if let Some(symlink) = normalized_path
+            .ancestors()
+            .find(|ancestor| self.symlinks.contains(*ancestor))
+        {
+            return Ok(Some(format!(
+                "path is written through the link {}",
+                symlink.display()
+            )));
+        }
+        let entry_type = entry.header().entry_type();
+        match entry_type {
+            EntryType::Regular | EntryType::Continuous | EntryType::Directory => Ok(None),
+            EntryType::Symlink | EntryType::Link => {
+                let link_target = entry
+                    .link_name()?
+                    .ok_or_else(|| anyhow!("Link entry is missing its target"))?
+                    .to_path_buf();
+                let link_base = match entry_type {
+                    EntryType::Symlink => entry_path.parent().unwrap_or_else(|| Path::new("")),
+                    _ => Path::new(""),
+                };
+                if !is_safe_link_target(link_base, &link_target) {
+                    return Ok(Some(format!(
+                        "link target {} escapes the destination",
+                        link_target.display()
+                    )));
+                }
+                if entry_type == EntryType::Symlink {
+                    self.symlinks.insert(normalized_path);
+                }
+                Ok(None)
+            }
+            _ => Ok(Some(format!("unsupported entry type {entry_type:?}"))),
+        }
+    }
+}
+
+/// Walks through all the entries of an uncompressed archive without unpacking anything and
+/// fails if the archive exceeds the given `limits` or contains entries that could be
+/// written outside of the destination folder.
+pub fn validate_archive(archive_path: &Path, limits: &ExtractionLimits) -> Result<()> {
+    let mut archiver = Archive::new(File::open(archive_path)?);
+    let mut validator = EntryValidator::default();
+    let mut unsafe_entries = Vec::new();
+    let mut entry_count: u64 = 0;
+    let mut total_size: u64 = 0;
+
+    for entry in archiver.entries()? {
+        let entry = entry?;
+        entry_count += 1;
+        if entry_count > limits.max_entry_count {
+            return Err(ExtractionError::TooManyEntries(limits.max_entry_count).into());
+        }
+        total_size = total_size.saturating_add(entry.size());
+        if total_size > limits.max_total_size {
+            return Err(ExtractionError::SizeLimitExceeded(limits.max_total_size).into());
+        }
+
+        let entry_path = entry.path()?.to_path_buf();
+        if let Some(reason) = validator.unsafe_entry_reason(&entry, &entry_path)? {
+            unsafe_entries.push(format!("{} ({reason})", entry_path.display()));
+        }
+    }
+
+    if !unsafe_entries.is_empty() {
+        return Err(ExtractionError::UnsafeEntries(unsafe_entries).into());
+    }
+    Ok(())
+}
+
+/// Regular files of a package archive together with the contents of its `package.toml`.
+#[derive(Debug, Default, Clone)]
+pub struct ArchiveContents {
+    pub files: HashSet<PathBuf>,
+    pub package_toml: Option<String>,
+}
+
+impl ArchiveContents {
+    pub fn contains(&self, path: &Path) -> bool {
+        self.files.contains(&normalize_archive_path(path))
+    }
+}
+
+fn normalize_archive_path(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect()
+}
+
+/// Walks through all the entries of a compressed package archive without unpacking it, collecting
+/// the paths of the regular files and reading the `package.toml` in the archive root.
+pub fn read_archive_contents(
+    package_path: &Path,
+    limits: &ExtractionLimits,
+) -> Result<ArchiveContents> {
+    let mut archive = ArchiveStreamer::prepare_archive(package_path.to_path_buf())?;
+    let mut contents = ArchiveContents::default();
+    let mut validator = EntryValidator::default();
+    let mut total_size: u64 = 0;
+
+    for (index, entry) in archive.entries()?.enumerate() {
+        if index as u64 >= limits.max_entry_count {
+            return Err(ExtractionError::TooManyEntries(limits.max_entry_count).into());
+        }
+        let mut entry = entry?;
+        total_size = total_size.saturating_add(entry.size());
+        if total_size > limits.max_total_size {
+            return Err(ExtractionError::SizeLimitExceeded(limits.max_total_size).into());
+        }
+        let entry_path = entry.path()?.to_path_buf();
+        if let Some(reason) = validator.unsafe_entry_reason(&entry, &entry_path)? {
+            return Err(ExtractionError::UnsafeEntries(vec![format!(
+                "{} ({reason})",
+                entry_path.display()
+            )])
+            .into());
+        }
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let entry_path = normalize_archive_path(&entry_path);
+        if entry_path == Path::new(PACKAGE_TOML) {
+            if entry.size() > MAX_PACKAGE_TOML_SIZE {
+                return Err(ExtractionError::PackageTomlTooLarge(MAX_PACKAGE_TOML_SIZE).into());
+            }
+            let mut package_toml = String::new();
+            (&mut entry)
+                .take(MAX_PACKAGE_TOML_SIZE)
+                .read_to_string(&mut package_toml)?;
+            contents.package_toml = Some(package_toml);
+        }
+        contents.files.insert(entry_path);
+    }
+
+    Ok(contents)
+}
 
 fn get_destination_file_path(toml_path: &Path) -> Result<PathBuf> {
     let mut file = File::open(toml_path)?;
@@ -49,24 +268,54 @@ fn get_destination_file_path(toml_path: &Path) -> Result<PathBuf> {
 type ParallelCompression = ParCompress<Mgzip>;
 type ParallelDecompression = ParDecompress<Mgzip>;
 
-pub fn decompress_archive(compressed_file_path: &Path) -> Result<PathBuf> {
+/// Decompresses a package archive next to it and checks the result with [`validate_archive`].
+/// Decompression stops as soon as more is written than the `limits` allow for the entries with
+/// their tar headers, so a small archive that expands to a huge one never fills the disk.
+pub fn decompress_archive(
+    compressed_file_path: &Path,
+    limits: &ExtractionLimits,
+) -> Result<PathBuf> {
     let archive_path = compressed_file_path.with_extension("tar");
-    let mut archive_file = File::create(&archive_path)?;
+    if let Err(error) = decompress_with_limits(compressed_file_path, &archive_path, limits)
+        .and_then(|_| validate_archive(&archive_path, limits))
+    {
+        let _ = remove_file(&archive_path);
+        return Err(error);
+    }
+    Ok(archive_path)
+}
+
+fn decompress_with_limits(
+    compressed_file_path: &Path,
+    archive_path: &Path,
+    limits: &ExtractionLimits,
+) -> Result<()> {
+    let max_archive_size = limits
+        .max_entry_count
+        .saturating_add(1)
+        .saturating_mul(TAR_ENTRY_OVERHEAD)
+        .saturating_add(limits.max_total_size);
+    let mut archive_file = File::create(archive_path)?;
     let compressed_file = File::open(compressed_file_path)?;
     let mut parallel_decompressor: ParallelDecompression = ParDecompressBuilder::new()
         .num_threads(num_cpus::get())?
         .from_reader(compressed_file);
     let mut buffer = Vec::with_capacity(COMPRESSION_CHUNK_SIZE);
+    let mut written_size: u64 = 0;
     loop {
         let mut limit = (&mut parallel_decompressor).take(COMPRESSION_CHUNK_SIZE as u64);
         limit.read_to_end(&mut buffer)?;
         if buffer.is_empty() {
             break;
         }
+        written_size = written_size.saturating_add(buffer.len() as u64);
+        if written_size > max_archive_size {
+            return Err(ExtractionError::SizeLimitExceeded(limits.max_total_size).into());
+        }
         archive_file.write_all(&buffer)?;
         buffer.clear();
     }
-    Ok(archive_path)
+    Ok(())
 }
 
 fn compress_archive(archive_path: &Path, compression: u32) -> Result<PathBuf> {
@@ -128,10 +377,29 @@ impl<'a> ArchiveStreamer<'a> {
     pub fn populate_file(
         archiver: &'a mut Archive<MultiGzDecoder<File>>,
         search_path: &Path,
+        limits: &ExtractionLimits,
     ) -> Result<Option<Entry<'a, MultiGzDecoder<File>>>> {
-        for file in archiver.entries()? {
+        if !is_safe_relative_path(search_path) {
+            return Err(
+                ExtractionError::UnsafeSearchPath(search_path.display().to_string()).into(),
+            );
+        }
+        for (index, file) in archiver.entries()?.enumerate() {
+            if index as u64 >= limits.max_entry_count {
+                return Err(ExtractionError::TooManyEntries(limits.max_entry_count).into());
+            }
             let file = file?;
             if file.path()?.to_str() == search_path.as_os_str().to_str() {
+                if !file.header().entry_type().is_file() {
+                    let file_path = file.path()?.display().to_string();
+                    return Err(ExtractionError::UnsafeEntries(vec![format!(
+                        "{file_path} (not a regular file)"
+                    )])
+                    .into());
+                }
+                if file.size() > limits.max_total_size {
+                    return Err(ExtractionError::SizeLimitExceeded(limits.max_total_size).into());
+                }
                 return Ok(Some(file));
             }
         }
@@ -141,8 +409,9 @@ impl<'a> ArchiveStreamer<'a> {
     pub fn try_new(
         archiver: &'a mut Archive<MultiGzDecoder<File>>,
         search_path: PathBuf,
+        limits: &ExtractionLimits,
     ) -> Result<Option<Pin<Box<ArchiveStreamer<'a>>>>> {
-        if let Some(file) = ArchiveStreamer::populate_file(archiver, &search_path)? {
+        if let Some(file) = ArchiveStreamer::populate_file(archiver, &search_path, limits)? {
             let streamer = Self {
                 file,
                 // SAFETY Cannot be dropped until the stream is dropped
@@ -169,11 +438,26 @@ impl<'a> Stream for ArchiveStreamer<'a> {
     }
 }
 
-pub fn unpack_archive(archive_path: &Path, destination: &Path) -> Result<()> {
+/// Unpacks an uncompressed archive into `destination` after checking it with [`validate_archive`],
+/// so nothing is written to the disk when the archive is rejected.
+pub fn unpack_archive(
+    archive_path: &Path,
+    destination: &Path,
+    limits: &ExtractionLimits,
+) -> Result<()> {
+    validate_archive(archive_path, limits)?;
+
     let archive_file = File::open(archive_path)?;
     let mut archiver = Archive::new(archive_file);
     create_dir_all(destination)?;
-    archiver.unpack(destination)?;
+    for entry in archiver.entries()? {
+        let mut entry = entry?;
+        if !entry.unpack_in(destination)? {
+            return Err(
+                ExtractionError::UnsafeEntries(vec![entry.path()?.display().to_string()]).into(),
+            );
+        }
+    }
 
     Ok(())
 }
@@ -227,8 +511,35 @@ mod tests {
     use super::*;
     use crate::test::TempArchive;
     use anyhow::Result;
-    use tar::Archive;
-    use tempfile::Builder;
+    use tar::{Archive, Header};
+    use tempfile::{Builder, NamedTempFile, TempDir};
+
+    fn create_raw_archive(entries: &[(&str, EntryType, Option<&str>)]) -> Result<NamedTempFile> {
+        let archive_file = NamedTempFile::new()?;
+        let mut archiver = tar::Builder::new(archive_file.reopen()?);
+        for (path, entry_type, link_name) in entries {
+            let data = b"content";
+            let mut header = Header::new_gnu();
+            header.as_gnu_mut().unwrap().name[..path.len()].copy_from_slice(path.as_bytes());
+            header.set_entry_type(*entry_type);
+            header.set_mode(0o644);
+            match link_name {
+                Some(link_name) => {
+                    header.set_link_name(link_name)?;
+                    header.set_size(0);
+                    header.set_cksum();
+                    archiver.append(&header, &[][..])?;
+                }
+                None => {
+                    header.set_size(data.len() as u64);
+                    header.set_cksum();
+                    archiver.append(&header, &data[..])?;
+                }
+            }
+        }
+        archiver.finish()?;
+        Ok(archive_file)
+    }
 
     #[test]
     fn archive_was_created() -> Result<()> {
@@ -252,7 +563,7 @@ mod tests {
         let toml_file_path = temp_project.toml_file.path().to_path_buf();
 
         let compressed_file_path = create_package(&toml_file_path, 0)?;
-        let archive_path = decompress_archive(&compressed_file_path)?;
+        let archive_path = decompress_archive(&compressed_file_path, &ExtractionLimits::default())?;
         let extraction_dir = Builder::new()
             .prefix("extracts")
             .rand_bytes(0)
@@ -270,4 +581,196 @@ mod tests {
         temp_project.root_dir.close()?;
         Ok(())
     }
+
+    #[test]
+    fn safe_archive_is_unpacked() -> Result<()> {
+        let archive_file = create_raw_archive(&[
+            ("folder", EntryType::Directory, None),
+            ("folder/file.txt", EntryType::Regular, None),
+            ("folder/link.txt", EntryType::Symlink, Some("file.txt")),
+            (
+                "other-link.txt",
+                EntryType::Symlink,
+                Some("folder/../folder/file.txt"),
+            ),
+        ])?;
+        let destination = TempDir::new()?;
+
+        unpack_archive(
+            archive_file.path(),
+            destination.path(),
+            &ExtractionLimits::default(),
+        )?;
+
+        assert!(destination.path().join("folder/file.txt").is_file());
+        Ok(())
+    }
+
+    #[test]
+    fn archive_with_traversing_paths_is_rejected() -> Result<()> {
+        let archive_file = create_raw_archive(&[
+            ("file.txt", EntryType::Regular, None),
+            ("../evil.txt", EntryType::Regular, None),
+            ("/tmp/evil.txt", EntryType::Regular, None),
+        ])?;
+        let destination = TempDir::new()?.path().join("extracted");
+
+        let error = unpack_archive(
+            archive_file.path(),
+            &destination,
+            &ExtractionLimits::default(),
+        )
+        .unwrap_err();
+
+        let error_message = error.to_string();
+        assert!(error_message.contains("../evil.txt"));
+        assert!(error_message.contains("/tmp/evil.txt"));
+        assert!(!destination.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn archive_with_escaping_links_is_rejected() -> Result<()> {
+        let archive_file = create_raw_archive(&[
+            (
+                "folder/link.txt",
+                EntryType::Symlink,
+                Some("../../etc/passwd"),
+            ),
+            ("absolute-link.txt", EntryType::Symlink, Some("/etc/passwd")),
+            ("hard-link.txt", EntryType::Link, Some("../etc/passwd")),
+        ])?;
+        let destination = TempDir::new()?;
+
+        let error = unpack_archive(
+            archive_file.path(),
+            destination.path(),
+            &ExtractionLimits::default(),
+        )
+        .unwrap_err();
+
+        match error.downcast_ref::<ExtractionError>() {
+            Some(ExtractionError::UnsafeEntries(entries)) => assert_eq!(entries.len(), 3),
+            _ => panic!("Unexpected error: {error}"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn archive_writing_through_earlier_links_is_rejected() -> Result<()> {
+        let archive_file = create_raw_archive(&[
+            ("folder/up", EntryType::Symlink, Some("..")),
+            ("folder/up/escape", EntryType::Symlink, Some("..")),
+            ("folder/up/escape/evil.txt", EntryType::Regular, None),
+        ])?;
+        let destination = TempDir::new()?;
+
+        let error = unpack_archive(
+            archive_file.path(),
+            destination.path(),
+            &ExtractionLimits::default(),
+        )
+        .unwrap_err();
+
+        match error.downcast_ref::<ExtractionError>() {
+            Some(ExtractionError::UnsafeEntries(entries)) => {
+                assert_eq!(entries.len(), 2);
+                assert!(entries
+                    .iter()
+                    .all(|entry| entry.contains("written through the link folder/up")));
+            }
+            _ => panic!("Unexpected error: {error}"),
+        }
+        assert!(!destination.path().join("folder").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn decompression_stops_at_the_size_limit() -> Result<()> {
+        let archive_file = NamedTempFile::new()?;
+        let mut archiver = tar::Builder::new(archive_file.reopen()?);
+        let zeros = vec![0; 1_048_576];
+        let mut header = Header::new_gnu();
+        header.set_size(zeros.len() as u64);
+        header.set_mode(0o644);
+        archiver.append_data(&mut header, "zeros.bin", &zeros[..])?;
+        archiver.finish()?;
+        let compressed_path = compress_archive(archive_file.path(), 6)?;
+        let limits = ExtractionLimits {
+            max_total_size: 1,
+            max_entry_count: 1,
+        };
+
+        let error = decompress_archive(&compressed_path, &limits).unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<ExtractionError>(),
+            Some(ExtractionError::SizeLimitExceeded(1))
+        ));
+        assert!(!compressed_path.with_extension("tar").exists());
+        let archive_path = decompress_archive(&compressed_path, &ExtractionLimits::default())?;
+        assert!(archive_path.is_file());
+        remove_file(compressed_path)?;
+        remove_file(archive_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn archive_exceeding_limits_is_rejected() -> Result<()> {
+        let archive_file = create_raw_archive(&[
+            ("first.txt", EntryType::Regular, None),
+            ("second.txt", EntryType::Regular, None),
+            ("third.txt", EntryType::Regular, None),
+        ])?;
+
+        let entry_count_error = validate_archive(
+            archive_file.path(),
+            &ExtractionLimits {
+                max_entry_count: 2,
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(
+            entry_count_error.downcast_ref::<ExtractionError>(),
+            Some(ExtractionError::TooManyEntries(2))
+        ));
+
+        let size_error = validate_archive(
+            archive_file.path(),
+            &ExtractionLimits {
+                max_total_size: 10,
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(
+            size_error.downcast_ref::<ExtractionError>(),
+            Some(ExtractionError::SizeLimitExceeded(10))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn oversized_package_toml_is_rejected() -> Result<()> {
+        let archive_file = NamedTempFile::new()?;
+        let mut archiver = tar::Builder::new(archive_file.reopen()?);
+        let package_toml = vec![b'#'; MAX_PACKAGE_TOML_SIZE as usize + 1];
+        let mut header = Header::new_gnu();
+        header.set_size(package_toml.len() as u64);
+        header.set_mode(0o644);
+        archiver.append_data(&mut header, PACKAGE_TOML, &package_toml[..])?;
+        archiver.finish()?;
+        let compressed_path = compress_archive(archive_file.path(), 6)?;
+
+        let error =
+            read_archive_contents(&compressed_path, &ExtractionLimits::default()).unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<ExtractionError>(),
+            Some(ExtractionError::PackageTomlTooLarge(MAX_PACKAGE_TOML_SIZE))
+        ));
+        remove_file(compressed_path)?;
+        Ok(())
+    }
 }
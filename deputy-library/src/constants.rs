@@ -18,12 +18,21 @@ lazy_static! {
         { LOCKFILE_TIMEOUT_DURATION.as_millis() / LOCKFILE_SLEEP_DURATION.as_millis() } as u64;
 }
 
+pub const PACKAGE_TOML: &str = "package.toml";
+pub const MAX_PACKAGE_TOML_SIZE: u64 = 1_048_576;
+
 pub const SHA256_LENGTH: usize = 64;
 
 pub const COMPRESSION_CHUNK_SIZE: usize = 131_072;
 
 pub const PAYLOAD_CHUNK_SIZE: u64 = 8192;
 
+pub const DEFAULT_EXTRACTION_MAX_TOTAL_SIZE: u64 = 10_737_418_240;
+pub const DEFAULT_EXTRACTION_MAX_ENTRY_COUNT: u64 = 100_000;
+/// Upper bound of the bytes a tar archive adds per entry: the header, an extension header with a
+/// path of up to 4096 bytes and the padding of the contents to the 512 byte block size.
+pub const TAR_ENTRY_OVERHEAD: u64 = 512 + 512 + 4096 + 512;
+
 pub const INDEX_REPOSITORY_BRANCH: &str = "master";
 pub const INDEX_REPOSITORY_REMOTE: &str = "origin";
 pub const CONFIGURATION_FOLDER_PATH_ENV_KEY: &str = "DEPUTY_CONFIG_FOLDER";
@@ -1,7 +1,8 @@
 use crate::project::{Content, ContentType};
 use crate::{
-    archiver::{self, ArchiveStreamer},
+    archiver::{self, ArchiveStreamer, ExtractionLimits},
     project::Body,
+    validation::validate_package_contents,
 };
 use actix_http::error::PayloadError;
 use actix_web::web::Bytes;
@@ -10,19 +11,61 @@ use base64::{engine::general_purpose, Engine};
 use futures::{Stream, StreamExt};
 use pulldown_cmark::{html, Event, Options, Parser, Tag};
 use regex::Regex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::{
+    fmt::{self, Formatter},
     fs::{self, File},
     io::{copy, Write},
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
     pin::Pin,
+    str::FromStr,
 };
 use tempfile::TempPath;
+use thiserror::Error as ThisError;
 use tokio::fs::File as TokioFile;
 use tokio_util::codec::{BytesCodec, FramedRead};
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    #[default]
+    Public,
+    Authenticated,
+    OwnersOnly,
+}
+
+impl Visibility {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Visibility::Public => "public",
+            Visibility::Authenticated => "authenticated",
+            Visibility::OwnersOnly => "owners_only",
+        }
+    }
+}
+
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Visibility {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "public" => Ok(Visibility::Public),
+            "authenticated" => Ok(Visibility::Authenticated),
+            "owners_only" => Ok(Visibility::OwnersOnly),
+            _ => Err(anyhow!("Unknown package visibility: {value}")),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PackageMetadata {
     pub name: String,
@@ -33,7 +76,11 @@ pub struct PackageMetadata {
     pub readme_path: String,
     pub package_size: u64,
     pub categories: Option<Vec<String>>,
+    #[serde(default)]
+    pub keywords: Option<Vec<String>>,
     pub checksum: String,
+    #[serde(default)]
+    pub visibility: Visibility,
 }
 
 async fn process_image(
@@ -41,11 +88,14 @@ async fn process_image(
     alt_text: &str,
     width: &str,
     package_path: &Path,
+    limits: &ExtractionLimits,
 ) -> Result<String> {
     if !url_str.starts_with("http") {
         let image_path = url_str.to_string();
         let mut archive = ArchiveStreamer::prepare_archive(package_path.to_path_buf())?;
-        if let Some(mut image_stream) = ArchiveStreamer::try_new(&mut archive, image_path.into())? {
+        if let Some(mut image_stream) =
+            ArchiveStreamer::try_new(&mut archive, image_path.into(), limits)?
+        {
             let mut img_bytes = Vec::new();
             while let Some(bytes) = image_stream.next().await {
                 let bytes =
@@ -69,14 +119,16 @@ async fn process_image(
 }
 
 impl PackageMetadata {
-    pub async fn readme_html(&self, package_base_path: PathBuf) -> Result<Option<String>> {
+    pub async fn readme_html(
+        &self,
+        package_path: PathBuf,
+        limits: &ExtractionLimits,
+    ) -> Result<Option<String>> {
         let readme_path = self.readme_path.clone();
-        let package_path_end = Package::normalize_file_path(&self.name, &self.version);
-        let package_path = package_base_path.join(package_path_end);
 
         let mut archive = ArchiveStreamer::prepare_archive(package_path.clone())?;
         if let Some(mut archive_stream) =
-            ArchiveStreamer::try_new(&mut archive, readme_path.clone().into())?
+            ArchiveStreamer::try_new(&mut archive, readme_path.clone().into(), limits)?
         {
             let mut readme_markdown_string = String::new();
             while let Some(bytes) = archive_stream.next().await {
@@ -107,7 +159,8 @@ impl PackageMetadata {
                     Event::Start(Tag::Image(_, url, _)) => {
                         in_alt_text = true;
                         let url_str = url.to_string();
-                        let img_tag = process_image(&url_str, &alt_text, "", &package_path).await?;
+                        let img_tag =
+                            process_image(&url_str, &alt_text, "", &package_path, limits).await?;
                         buffer.push_str(&img_tag);
 
                         alt_text.clear();
@@ -120,7 +173,7 @@ impl PackageMetadata {
                         let width = captures.get(3).map_or("", |m| m.as_str());
 
                         let img_tag =
-                            process_image(url_str, alt_text, width, &package_path).await?;
+                            process_image(url_str, alt_text, width, &package_path, limits).await?;
                         buffer.push_str(&img_tag);
                     }
 
@@ -183,6 +236,14 @@ impl PackageMetadata {
     }
 }
 
+#[derive(ThisError, Debug)]
+pub enum PackageSizeError {
+    #[error("Package exceeds the maximum allowed size of {0} bytes")]
+    LimitExceeded(u64),
+    #[error("Package size mismatch, declared {declared} bytes but received {received} bytes")]
+    Mismatch { declared: u64, received: u64 },
+}
+
 #[derive(Debug)]
 pub struct PackageFile(pub File, pub Option<TempPath>);
 
@@ -218,36 +279,65 @@ impl PackageFile {
         Ok(format!("{hash_bytes:x}",))
     }
 
-    pub async fn from_stream(mut stream: PackageStream) -> Result<Self> {
+    /// Reads the 8-byte size prefix and the package file that follows it from the stream.
+    ///
+    /// Reading stops with [`PackageSizeError::LimitExceeded`] as soon as either the declared or the
+    /// received size goes over `max_size`, and the received byte count has to match the declared size.
+    pub async fn from_stream(mut stream: PackageStream, max_size: Option<u64>) -> Result<Self> {
         let mut file = tempfile::NamedTempFile::new()?;
         let mut file_size: Option<u64> = None;
+        let mut received_size: u64 = 0;
         let mut intermediate_buffer: Vec<u8> = Vec::new();
         while let Some(chunk) = stream.next().await {
             let chunk = chunk?;
-            let mut first = false;
-            if file_size.is_none() {
-                if chunk.len() <= 8 {
-                    intermediate_buffer.append(&mut chunk.to_vec());
-                } else {
-                    intermediate_buffer = chunk.to_vec();
+            let file_bytes = match file_size {
+                Some(_) => &chunk[..],
+                None => {
+                    intermediate_buffer.extend_from_slice(&chunk);
+                    if intermediate_buffer.len() < 8 {
+                        continue;
+                    }
+                    let declared_size =
+                        u64::from_bytes(Bytes::copy_from_slice(&intermediate_buffer[0..8]))?;
+                    if let Some(max_size) = max_size {
+                        if declared_size > max_size {
+                            return Err(PackageSizeError::LimitExceeded(max_size).into());
+                        }
+                    }
+                    file_size = Some(declared_size);
+                    &intermediate_buffer[8..]
                 }
-                if intermediate_buffer.len() > 7 {
-                    let mut u64_bytes = [0u8; 8];
-                    u64_bytes.copy_from_slice(&intermediate_buffer[0..8]);
-                    file_size = Some(u64::from_le_bytes(u64_bytes));
-                    first = true;
+            };
+
+            received_size += file_bytes.len() as u64;
+            if let Some(max_size) = max_size {
+                if received_size > max_size {
+                    return Err(PackageSizeError::LimitExceeded(max_size).into());
                 }
             }
-            if file_size.is_some() {
-                if first {
-                    file.write_all(&intermediate_buffer[8..])?;
-                } else {
-                    file.write_all(&chunk)?;
+            if let Some(declared_size) = file_size {
+                if received_size > declared_size {
+                    return Err(PackageSizeError::Mismatch {
+                        declared: declared_size,
+                        received: received_size,
+                    }
+                    .into());
                 }
             }
+            file.write_all(file_bytes)?;
         }
         file.flush()?;
 
+        let declared_size = file_size
+            .ok_or_else(|| anyhow!("Stream ended before the package size was received"))?;
+        if received_size != declared_size {
+            return Err(PackageSizeError::Mismatch {
+                declared: declared_size,
+                received: received_size,
+            }
+            .into());
+        }
+
         let new_handler = file.reopen()?;
         let temporary_path = file.into_temp_path();
 
@@ -296,6 +386,24 @@ impl Package {
         Ok(())
     }
 
+    /// Path of the temporary file holding the package archive.
+    pub fn archive_path(&self) -> Result<&Path> {
+        let archive_path = self
+            .file
+            .1
+            .as_ref()
+            .ok_or_else(|| anyhow!("Temporary file path not found"))?;
+        Ok(archive_path)
+    }
+
+    /// Reads the uploaded archive and checks its `package.toml` and files against the metadata.
+    pub fn validate_contents(&self, limits: &ExtractionLimits) -> Result<()> {
+        let archive_path = self.archive_path()?;
+        let contents = archiver::read_archive_contents(archive_path, limits)?;
+        validate_package_contents(&self.metadata, &contents)?;
+        Ok(())
+    }
+
     fn gather_metadata(toml_path: &Path, archive_path: &Path) -> Result<PackageMetadata> {
         let package_body = Body::create_from_toml(toml_path)?;
         let package_content = Content::create_from_toml(toml_path)?;
@@ -308,8 +416,10 @@ impl Package {
             license: package_body.license,
             readme_path: package_body.readme,
             categories: package_body.categories,
+            keywords: package_body.keywords,
             package_size: archive_file.metadata()?.len(),
             checksum: PackageFile(archive_file, None).calculate_checksum()?,
+            visibility: Visibility::default(),
         })
     }
 
@@ -428,13 +538,28 @@ impl Streamer for TokioFile {
 
 #[cfg(test)]
 mod tests {
-    use crate::package::Package;
+    use crate::archiver::ExtractionLimits;
+    use crate::package::{Package, PackageFile, PackageSizeError, PackageStream, Streamer};
+    use crate::project::ContentType;
     use crate::test::TempArchive;
+    use crate::validation::PackageContentError;
+    use actix_web::web::Bytes;
     use anyhow::{Ok, Result};
     use futures::StreamExt;
     use std::{fs::File, io::Read, path::PathBuf};
     use tempfile::tempdir;
 
+    fn create_file_stream(declared_size: u64, content: &'static [u8]) -> PackageStream {
+        let content_stream: PackageStream =
+            Box::pin(futures::stream::iter(vec![std::result::Result::Ok(
+                Bytes::from_static(content),
+            )]));
+        declared_size
+            .to_stream()
+            .chain(content_stream)
+            .boxed_local()
+    }
+
     #[test]
     fn package_file_can_be_saved() -> Result<()> {
         let target_directory = tempdir()?;
@@ -490,6 +615,26 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn package_contents_are_validated_against_metadata() -> Result<()> {
+        let archive = TempArchive::builder().build()?;
+        let mut package: Package = (&archive).try_into()?;
+        package.validate_contents(&ExtractionLimits::default())?;
+
+        package.metadata.license = "MIT".to_string();
+        package.metadata.package_type = ContentType::Feature;
+        let error = package
+            .validate_contents(&ExtractionLimits::default())
+            .unwrap_err();
+        match error.downcast_ref::<PackageContentError>() {
+            Some(PackageContentError::MetadataMismatch(mismatches)) => {
+                assert_eq!(mismatches.len(), 2)
+            }
+            _ => panic!("Expected a metadata mismatch, got {error}"),
+        }
+        Ok(())
+    }
+
     #[actix_web::test]
     async fn package_is_converted_to_stream() -> Result<()> {
         let archive = TempArchive::builder().build()?;
@@ -505,4 +650,51 @@ mod tests {
         assert_eq!(counter, 3);
         Ok(())
     }
+
+    #[actix_web::test]
+    async fn package_file_is_read_from_stream() -> Result<()> {
+        let mut package_file =
+            PackageFile::from_stream(create_file_stream(7, b"content"), Some(7)).await?;
+
+        let mut file_content = String::new();
+        package_file.read_to_string(&mut file_content)?;
+        assert_eq!(file_content, "content");
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn package_file_with_mismatching_size_is_rejected() -> Result<()> {
+        let error = PackageFile::from_stream(create_file_stream(10, b"content"), None)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<PackageSizeError>(),
+            Some(PackageSizeError::Mismatch {
+                declared: 10,
+                received: 7
+            })
+        ));
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn package_file_over_size_limit_is_rejected() -> Result<()> {
+        let declared_error = PackageFile::from_stream(create_file_stream(7, b"content"), Some(5))
+            .await
+            .unwrap_err();
+        let received_error = PackageFile::from_stream(create_file_stream(3, b"content"), Some(5))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            declared_error.downcast_ref::<PackageSizeError>(),
+            Some(PackageSizeError::LimitExceeded(5))
+        ));
+        assert!(matches!(
+            received_error.downcast_ref::<PackageSizeError>(),
+            Some(PackageSizeError::LimitExceeded(5))
+        ));
+        Ok(())
+    }
 }
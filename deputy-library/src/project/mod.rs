@@ -77,14 +77,16 @@ impl Project {
     }
 
     pub fn validate_asset_files(&self, package_path: &Path) -> Result<()> {
+        self.validate_asset_files_with(|path| package_path.join(path).exists())
+    }
+
+    /// Same as [`Project::validate_asset_files`] but asks `file_exists` whether a path relative to
+    /// the package root exists, so the assets can also be checked against an archive listing.
+    pub fn validate_asset_files_with(&self, file_exists: impl Fn(&Path) -> bool) -> Result<()> {
         if let Some(assets) = &self.package.assets {
             for asset in assets {
-                let asset_path = package_path.join(&asset[0]);
-                if !asset_path.exists() {
-                    return Err(anyhow!(
-                        "Asset '{}' not found in package files",
-                        asset_path.display()
-                    ));
+                if !file_exists(Path::new(&asset[0])) {
+                    return Err(anyhow!("Asset '{}' not found in package files", asset[0]));
                 }
             }
         } else {
@@ -95,6 +97,11 @@ impl Project {
     }
 
     pub fn validate_content(&mut self) -> Result<()> {
+        self.validate_content_type()?;
+        self.validate_previews(|path| path.exists())
+    }
+
+    pub fn validate_content_type(&self) -> Result<()> {
         let mut content_types = vec![
             self.virtual_machine.as_ref().map(|_| ContentType::VM),
             self.feature.as_ref().map(|_| ContentType::Feature),
@@ -161,12 +168,16 @@ impl Project {
             }
         }
 
+        Ok(())
+    }
+
+    pub fn validate_previews(&self, file_exists: impl Fn(&Path) -> bool) -> Result<()> {
         if let Some(preview) = &self.content.preview {
             for preview_item in preview {
                 match preview_item {
                     Preview::Picture(paths) | Preview::Video(paths) | Preview::Code(paths) => {
                         for path in paths {
-                            if !Path::new(path).exists() {
+                            if !file_exists(Path::new(path)) {
                                 return Err(anyhow!("Preview file \"{path}\" not found"));
                             }
                         }
@@ -179,41 +190,42 @@ impl Project {
     }
 
     pub fn validate_files(&self, package_path: &Path) -> Result<()> {
-        let readme_path = package_path.join(&self.package.readme);
-        if !readme_path.exists() {
+        self.validate_files_with(|path| package_path.join(path).exists())
+    }
+
+    /// Checks that the files referenced by the package exist, `file_exists` receives the paths
+    /// relative to the package root.
+    pub fn validate_files_with(&self, file_exists: impl Fn(&Path) -> bool) -> Result<()> {
+        if !file_exists(Path::new(&self.package.readme)) {
             return Err(anyhow!("Readme not found"));
         }
 
         if let Some(vm) = &self.virtual_machine {
-            let file_path = package_path.join(&vm.file_path);
-            if !file_path.exists() {
+            if !file_exists(Path::new(&vm.file_path)) {
                 return Err(anyhow!("Virtual machine file not found"));
             }
         }
 
         if let Some(event) = &self.event {
-            let file_path = package_path.join(&event.file_path);
-            if !file_path.exists() {
+            if !file_exists(Path::new(&event.file_path)) {
                 return Err(anyhow!("Event file not found"));
             }
         }
 
         if let Some(exercise) = &self.exercise {
-            let file_path = package_path.join(&exercise.file_path);
-            if !file_path.exists() {
+            if !file_exists(Path::new(&exercise.file_path)) {
                 return Err(anyhow!("Exercise file not found"));
             }
         }
 
         if let Some(banner) = &self.banner {
-            let file_path = package_path.join(&banner.file_path);
-            if !file_path.exists() {
+            if !file_exists(Path::new(&banner.file_path)) {
                 return Err(anyhow!("Banner file not found"));
             }
         }
 
         if ASSETS_REQUIRED_PACKAGE_TYPES.contains(&self.content.content_type) {
-            self.validate_asset_files(package_path)?;
+            self.validate_asset_files_with(&file_exists)?;
         }
         Ok(())
     }
@@ -348,6 +360,7 @@ pub struct Body {
     pub license: String,
     pub readme: String,
     pub categories: Option<Vec<String>>,
+    pub keywords: Option<Vec<String>>,
     pub assets: Option<Vec<Vec<String>>>,
 }
 
@@ -362,12 +375,13 @@ impl Body {
             license: deserialized_toml.package.license,
             readme: deserialized_toml.package.readme,
             categories: deserialized_toml.package.categories,
+            keywords: deserialized_toml.package.keywords,
             assets: deserialized_toml.package.assets,
         })
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
 pub enum ContentType {
     #[serde(alias = "vm")]
     VM,
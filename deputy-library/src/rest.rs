@@ -1,10 +1,17 @@
-use anyhow::Result;
-use chrono::NaiveDateTime;
+use crate::package::Visibility;
+use anyhow::{anyhow, Result};
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use schemars::JsonSchema;
 use semver::{Error, Version};
 use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Formatter},
+    str::FromStr,
+};
 use uuid::Uuid;
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct VersionRest {
     pub id: Uuid,
@@ -13,6 +20,9 @@ pub struct VersionRest {
     pub description: String,
     pub license: String,
     pub is_yanked: bool,
+    /// The archive of the version failed the storage scrub and can not be downloaded.
+    #[serde(default)]
+    pub is_unavailable: bool,
     pub readme_html: String,
     pub package_size: u64,
     pub checksum: String,
@@ -59,15 +69,109 @@ pub struct OwnerRest {
     pub email: String,
 }
 
+#[derive(Clone, Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamMemberRest {
+    pub email: String,
+    pub is_admin: bool,
+}
+
+#[derive(Clone, Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamRest {
+    pub name: String,
+    #[serde(default)]
+    pub members: Vec<TeamMemberRest>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    Publish,
+    Yank,
+    ManageOwners,
+    ReadPrivate,
+}
+
+impl TokenScope {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TokenScope::Publish => "publish",
+            TokenScope::Yank => "yank",
+            TokenScope::ManageOwners => "manage_owners",
+            TokenScope::ReadPrivate => "read_private",
+        }
+    }
+}
+
+impl fmt::Display for TokenScope {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for TokenScope {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "publish" => Ok(TokenScope::Publish),
+            "yank" => Ok(TokenScope::Yank),
+            "manage_owners" => Ok(TokenScope::ManageOwners),
+            "read_private" => Ok(TokenScope::ReadPrivate),
+            _ => Err(anyhow!("Unknown token scope: {value}")),
+        }
+    }
+}
+
+/// Restrictions of a new API token, an empty scope or package list leaves the token unrestricted
+/// in that regard.
+#[derive(Clone, Deserialize, Serialize, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenRestrictionsRest {
+    #[serde(default)]
+    pub scopes: Vec<TokenScope>,
+    #[serde(default)]
+    pub packages: Vec<String>,
+    #[serde(default)]
+    pub expires_in_days: Option<u32>,
+}
+
+#[derive(Clone, Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NewScopedTokenRest {
+    pub name: String,
+    #[serde(flatten)]
+    pub restrictions: TokenRestrictionsRest,
+}
+
+#[derive(Clone, Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedTokenRest {
+    pub name: String,
+    pub token: String,
+    #[serde(default)]
+    pub scopes: Vec<TokenScope>,
+    #[serde(default)]
+    pub packages: Vec<String>,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
 #[derive(Clone, Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct PackageWithVersionsRest {
     pub id: Uuid,
     pub name: String,
     pub package_type: String,
+    #[serde(default)]
+    pub visibility: Visibility,
+    #[serde(default)]
+    pub downloads: u64,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub versions: Vec<VersionRest>,
+    #[serde(default)]
+    pub deprecation: Option<DeprecationRest>,
 }
 
 impl PackageWithVersionsRest {
@@ -79,10 +183,294 @@ impl PackageWithVersionsRest {
         packages.retain(|package| !package.versions.is_empty());
     }
 }
+
+/// A deprecated package keeps serving its versions, the message and the optional replacement
+/// package are shown to everyone who installs or looks it up.
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeprecationRest {
+    pub message: String,
+    pub replacement: Option<String>,
+}
+
+impl fmt::Display for DeprecationRest {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(replacement) = &self.replacement {
+            write!(f, " (use {replacement} instead)")?;
+        }
+        Ok(())
+    }
+}
 #[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PackagesWithVersionsAndPagesRest {
     pub packages: Vec<PackageWithVersionsRest>,
     pub total_pages: i64,
     pub total_packages: i64,
+    #[serde(default)]
+    pub facets: SearchFacetsRest,
+}
+
+/// Number of matching packages per package type and per category, counted over every page of
+/// the search results.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchFacetsRest {
+    pub package_types: BTreeMap<String, u64>,
+    pub categories: BTreeMap<String, u64>,
+}
+
+/// Without a search term `Relevance` falls back to ordering by name.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageSort {
+    #[default]
+    Relevance,
+    Name,
+    Updated,
+    Downloads,
+}
+
+impl PackageSort {
+    pub fn as_str(&self) -> &str {
+        match self {
+            PackageSort::Relevance => "relevance",
+            PackageSort::Name => "name",
+            PackageSort::Updated => "updated",
+            PackageSort::Downloads => "downloads",
+        }
+    }
+}
+
+impl fmt::Display for PackageSort {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionDownloadsRest {
+    pub version: String,
+    pub downloads: u64,
+}
+
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyDownloadsRest {
+    pub date: NaiveDate,
+    pub downloads: u64,
+}
+
+/// Download totals are counted over the whole lifetime of the package, the daily series only
+/// covers the requested time window.
+#[derive(Clone, Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadStatsRest {
+    pub package_name: String,
+    pub total_downloads: u64,
+    pub versions: Vec<VersionDownloadsRest>,
+    pub daily: Vec<DailyDownloadsRest>,
+}
+
+#[derive(Clone, Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogRest {
+    pub id: Uuid,
+    pub actor_email: String,
+    pub actor_id: String,
+    pub token_id: Option<Uuid>,
+    pub action: String,
+    pub package_name: Option<String>,
+    pub package_version: Option<String>,
+    pub details: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogsWithPagesRest {
+    pub audit_logs: Vec<AuditLogRest>,
+    pub total_pages: i64,
+    pub total_audit_logs: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    Publish,
+    Yank,
+    Unyank,
+    OwnerAdd,
+    OwnerRemove,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &str {
+        match self {
+            WebhookEvent::Publish => "publish",
+            WebhookEvent::Yank => "yank",
+            WebhookEvent::Unyank => "unyank",
+            WebhookEvent::OwnerAdd => "owner_add",
+            WebhookEvent::OwnerRemove => "owner_remove",
+        }
+    }
+}
+
+impl fmt::Display for WebhookEvent {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for WebhookEvent {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "publish" => Ok(WebhookEvent::Publish),
+            "yank" => Ok(WebhookEvent::Yank),
+            "unyank" => Ok(WebhookEvent::Unyank),
+            "owner_add" => Ok(WebhookEvent::OwnerAdd),
+            "owner_remove" => Ok(WebhookEvent::OwnerRemove),
+            _ => Err(anyhow!("Unknown webhook event: {value}")),
+        }
+    }
+}
+
+/// A webhook without events is called for every event. When no secret is given the server
+/// generates one and returns it only in the creation response.
+#[derive(Clone, Deserialize, Serialize, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NewWebhookRest {
+    pub url: String,
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+#[derive(Clone, Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookRest {
+    pub id: Uuid,
+    pub package_name: Option<String>,
+    pub url: String,
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    pub created_by: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Clone, Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDeliveryRest {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event: WebhookEvent,
+    pub status: String,
+    pub attempts: u32,
+    pub response_status: Option<u16>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: NaiveDateTime,
+    pub delivered_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+/// Body of the request sent to the webhook url, signed with the webhook secret.
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookPayloadRest {
+    pub event: WebhookEvent,
+    pub package_name: String,
+    pub version: Option<String>,
+    pub actor: String,
+    pub details: Option<String>,
+    pub timestamp: NaiveDateTime,
+}
+
+impl WebhookPayloadRest {
+    pub fn new(event: WebhookEvent, package_name: &str, actor: String) -> Self {
+        Self {
+            event,
+            package_name: package_name.to_lowercase(),
+            version: None,
+            actor,
+            details: None,
+            timestamp: Utc::now().naive_utc(),
+        }
+    }
+
+    pub fn version(mut self, version: &str) -> Self {
+        self.version = Some(version.to_string());
+        self
+    }
+
+    pub fn details(mut self, details: String) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+/// Status of a health check, the variants are ordered from the best to the worst.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Up,
+    Degraded,
+    Down,
+}
+
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentHealthRest {
+    pub status: HealthStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub free_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pending_migrations: Vec<String>,
+}
+
+impl ComponentHealthRest {
+    pub fn new(status: HealthStatus) -> Self {
+        Self {
+            status,
+            message: None,
+            free_bytes: None,
+            pending_migrations: Vec::new(),
+        }
+    }
+
+    pub fn message(mut self, message: String) -> Self {
+        self.message = Some(message);
+        self
+    }
+}
+
+/// The overall status is the worst status of the checked components.
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthRest {
+    pub status: HealthStatus,
+    pub checks: BTreeMap<String, ComponentHealthRest>,
+}
+
+impl From<BTreeMap<String, ComponentHealthRest>> for HealthRest {
+    fn from(checks: BTreeMap<String, ComponentHealthRest>) -> Self {
+        Self {
+            status: checks
+                .values()
+                .map(|check| check.status)
+                .max()
+                .unwrap_or(HealthStatus::Up),
+            checks,
+        }
+    }
 }
@@ -3,6 +3,7 @@ use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 
+use crate::archiver::ArchiveContents;
 use crate::package::PackageMetadata;
 use crate::{
     constants::{self},
@@ -12,6 +13,19 @@ use crate::{
 use anyhow::{anyhow, Result};
 use semver::{Version, VersionReq};
 use spdx;
+use thiserror::Error as ThisError;
+
+#[derive(ThisError, Debug, PartialEq, Eq)]
+pub enum PackageContentError {
+    #[error("Package archive does not contain a package.toml")]
+    MissingPackageToml,
+    #[error("Package archive contains an invalid package.toml: {0}")]
+    InvalidPackageToml(String),
+    #[error("Package metadata does not match package.toml: {}", .0.join(", "))]
+    MetadataMismatch(Vec<String>),
+    #[error("Package archive is missing files: {0}")]
+    InvalidFiles(String),
+}
 
 pub trait Validate {
     fn validate(&mut self) -> Result<()>;
@@ -55,6 +69,7 @@ impl Validate for Project {
         validate_version_semantic(self.package.version.clone())?;
         validate_license(self.package.license.clone())?;
         validate_categories(self.package.categories.clone())?;
+        validate_keywords(self.package.keywords.clone())?;
         self.validate_assets()?;
         Ok(())
     }
@@ -102,6 +117,19 @@ pub fn validate_categories(categories: Option<Vec<String>>) -> Result<()> {
     Ok(())
 }
 
+pub fn validate_keywords(keywords: Option<Vec<String>>) -> Result<()> {
+    if let Some(keywords) = keywords {
+        for keyword in keywords.iter() {
+            if keyword.trim().is_empty() {
+                return Err(anyhow!(
+                    "A keyword cannot be an empty string or only whitespace"
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
 pub fn validate_package_toml<P: AsRef<Path> + Debug>(package_path: P) -> Result<()> {
     let mut file = File::open(package_path)?;
     let mut contents = String::new();
@@ -112,6 +140,96 @@ pub fn validate_package_toml<P: AsRef<Path> + Debug>(package_path: P) -> Result<
     Ok(())
 }
 
+fn compare_field<T: PartialEq + Debug>(
+    mismatches: &mut Vec<String>,
+    field: &str,
+    metadata_value: T,
+    toml_value: T,
+) {
+    if metadata_value != toml_value {
+        mismatches.push(format!(
+            "{field} is {metadata_value:?} in metadata but {toml_value:?} in package.toml"
+        ));
+    }
+}
+
+/// Validates the `package.toml` found in the uploaded archive, checks that the files it references
+/// are part of the archive and that the metadata sent along with the package describes the same
+/// package.
+pub fn validate_package_contents(
+    metadata: &PackageMetadata,
+    contents: &ArchiveContents,
+) -> Result<(), PackageContentError> {
+    let package_toml = contents
+        .package_toml
+        .as_ref()
+        .ok_or(PackageContentError::MissingPackageToml)?;
+    let project: Project = toml::from_str(package_toml)
+        .map_err(|error| PackageContentError::InvalidPackageToml(error.to_string()))?;
+    project
+        .validate_content_type()
+        .and_then(|_| validate_name(project.package.name.clone()))
+        .and_then(|_| validate_version_semantic(project.package.version.clone()))
+        .and_then(|_| validate_license(project.package.license.clone()))
+        .and_then(|_| validate_categories(project.package.categories.clone()))
+        .and_then(|_| validate_keywords(project.package.keywords.clone()))
+        .and_then(|_| project.validate_assets())
+        .map_err(|error| PackageContentError::InvalidPackageToml(error.to_string()))?;
+
+    let mut mismatches = Vec::new();
+    compare_field(
+        &mut mismatches,
+        "name",
+        metadata.name.to_lowercase(),
+        project.package.name.to_lowercase(),
+    );
+    compare_field(
+        &mut mismatches,
+        "version",
+        &metadata.version,
+        &project.package.version,
+    );
+    compare_field(
+        &mut mismatches,
+        "type",
+        &metadata.package_type,
+        &project.content.content_type,
+    );
+    compare_field(
+        &mut mismatches,
+        "license",
+        &metadata.license,
+        &project.package.license,
+    );
+    compare_field(
+        &mut mismatches,
+        "categories",
+        &metadata.categories,
+        &project.package.categories,
+    );
+    compare_field(
+        &mut mismatches,
+        "keywords",
+        &metadata.keywords,
+        &project.package.keywords,
+    );
+    compare_field(
+        &mut mismatches,
+        "readme path",
+        &metadata.readme_path,
+        &project.package.readme,
+    );
+    if !mismatches.is_empty() {
+        return Err(PackageContentError::MetadataMismatch(mismatches));
+    }
+
+    project
+        .validate_previews(|path| contents.contains(path))
+        .and_then(|_| project.validate_files_with(|path| contents.contains(path)))
+        .map_err(|error| PackageContentError::InvalidFiles(error.to_string()))?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1 @@
+ALTER TABLE tokens ADD COLUMN roles TEXT NOT NULL DEFAULT '' AFTER user_id;
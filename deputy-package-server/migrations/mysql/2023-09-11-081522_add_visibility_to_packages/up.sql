@@ -0,0 +1 @@
+ALTER TABLE packages ADD COLUMN visibility TINYTEXT NOT NULL DEFAULT 'public' AFTER package_type;
@@ -0,0 +1,3 @@
+DROP TABLE team_owners;
+DROP TABLE team_members;
+DROP TABLE teams;
@@ -0,0 +1,32 @@
+CREATE TABLE teams (
+    id BINARY(16) PRIMARY KEY,
+    name TINYTEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:01',
+    CONSTRAINT unique_team_name_deleted UNIQUE (name, deleted_at)
+);
+
+CREATE TABLE team_members (
+    id BINARY(16) PRIMARY KEY,
+    team_id BINARY(16) NOT NULL,
+    email TINYTEXT NOT NULL,
+    is_admin BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:01',
+    CONSTRAINT FK_Team_Members FOREIGN KEY (team_id) REFERENCES teams(id),
+    CONSTRAINT unique_member_email_team_deleted UNIQUE (email, team_id, deleted_at)
+);
+
+CREATE TABLE team_owners (
+    id BINARY(16) PRIMARY KEY,
+    team_id BINARY(16) NOT NULL,
+    package_id BINARY(16) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:01',
+    CONSTRAINT FK_Team_Owners_Team FOREIGN KEY (team_id) REFERENCES teams(id),
+    CONSTRAINT FK_Team_Owners_Package FOREIGN KEY (package_id) REFERENCES packages(id),
+    CONSTRAINT unique_team_owner_package_deleted UNIQUE (team_id, package_id, deleted_at)
+);
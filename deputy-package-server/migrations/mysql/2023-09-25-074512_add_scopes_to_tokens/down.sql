@@ -0,0 +1,5 @@
+ALTER TABLE tokens
+    DROP COLUMN scopes,
+    DROP COLUMN package_patterns,
+    DROP COLUMN expires_at,
+    DROP COLUMN last_used_at;
@@ -0,0 +1,5 @@
+ALTER TABLE tokens
+    ADD COLUMN scopes TEXT NOT NULL DEFAULT '' AFTER roles,
+    ADD COLUMN package_patterns TEXT NOT NULL DEFAULT '' AFTER scopes,
+    ADD COLUMN expires_at TIMESTAMP NULL DEFAULT NULL AFTER package_patterns,
+    ADD COLUMN last_used_at TIMESTAMP NULL DEFAULT NULL AFTER expires_at;
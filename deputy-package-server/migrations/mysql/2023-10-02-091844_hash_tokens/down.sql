@@ -0,0 +1,7 @@
+-- Hashed tokens can not be restored, every token has to be recreated after reverting.
+DROP INDEX tokens_token_prefix_index ON tokens;
+ALTER TABLE tokens
+    ADD COLUMN token TINYTEXT NOT NULL AFTER email,
+    DROP COLUMN token_prefix,
+    DROP COLUMN token_salt,
+    DROP COLUMN token_hash;
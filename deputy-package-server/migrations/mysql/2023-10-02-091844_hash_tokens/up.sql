@@ -0,0 +1,8 @@
+ALTER TABLE tokens
+    ADD COLUMN token_prefix VARCHAR(16) NOT NULL DEFAULT '' AFTER email,
+    ADD COLUMN token_salt VARCHAR(64) NOT NULL DEFAULT '' AFTER token_prefix,
+    ADD COLUMN token_hash VARCHAR(64) NOT NULL DEFAULT '' AFTER token_salt;
+UPDATE tokens SET token_prefix = LEFT(token, 12), token_salt = LEFT(SHA2(UUID(), 256), 32);
+UPDATE tokens SET token_hash = SHA2(CONCAT(token_salt, token), 256);
+ALTER TABLE tokens DROP COLUMN token;
+CREATE INDEX tokens_token_prefix_index ON tokens (token_prefix);
@@ -0,0 +1,17 @@
+CREATE TABLE audit_logs (
+    id BINARY(16) PRIMARY KEY,
+    actor_email VARCHAR(255) NOT NULL,
+    actor_id TINYTEXT NOT NULL,
+    token_id BINARY(16) NULL,
+    action VARCHAR(64) NOT NULL,
+    package_name VARCHAR(255) NULL,
+    package_version VARCHAR(255) NULL,
+    details TEXT NULL,
+    ip_address VARCHAR(64) NULL,
+    user_agent TEXT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    INDEX audit_logs_created_at_index (created_at),
+    INDEX audit_logs_actor_email_index (actor_email),
+    INDEX audit_logs_action_index (action),
+    INDEX audit_logs_package_name_index (package_name)
+);
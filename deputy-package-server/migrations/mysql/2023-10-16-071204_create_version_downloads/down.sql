@@ -0,0 +1,2 @@
+DROP TABLE version_downloads;
+ALTER TABLE packages DROP COLUMN downloads;
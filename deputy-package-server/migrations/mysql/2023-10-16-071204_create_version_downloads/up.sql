@@ -0,0 +1,10 @@
+ALTER TABLE packages ADD COLUMN downloads BIGINT UNSIGNED NOT NULL DEFAULT 0 AFTER visibility;
+
+CREATE TABLE version_downloads (
+    version_id BINARY(16) NOT NULL,
+    download_date DATE NOT NULL,
+    downloads BIGINT UNSIGNED NOT NULL DEFAULT 0,
+    PRIMARY KEY (version_id, download_date),
+    CONSTRAINT FK_VersionDownloads FOREIGN KEY (version_id)
+    REFERENCES versions(id) ON DELETE CASCADE
+);
@@ -0,0 +1,30 @@
+CREATE TABLE webhooks (
+    id BINARY(16) PRIMARY KEY,
+    package_id BINARY(16) NULL DEFAULT NULL,
+    url TEXT NOT NULL,
+    events TEXT NOT NULL,
+    secret VARCHAR(255) NOT NULL,
+    created_by VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMP NULL DEFAULT NULL,
+    CONSTRAINT FK_WebhookPackage FOREIGN KEY (package_id)
+    REFERENCES packages(id) ON DELETE CASCADE
+);
+
+CREATE TABLE webhook_deliveries (
+    id BINARY(16) PRIMARY KEY,
+    webhook_id BINARY(16) NOT NULL,
+    event VARCHAR(64) NOT NULL,
+    payload TEXT NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    attempts INT UNSIGNED NOT NULL DEFAULT 0,
+    response_status SMALLINT UNSIGNED NULL DEFAULT NULL,
+    last_error TEXT NULL DEFAULT NULL,
+    next_attempt_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    delivered_at TIMESTAMP NULL DEFAULT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    INDEX webhook_deliveries_status_next_attempt_at (status, next_attempt_at),
+    CONSTRAINT FK_WebhookDelivery FOREIGN KEY (webhook_id)
+    REFERENCES webhooks(id) ON DELETE CASCADE
+);
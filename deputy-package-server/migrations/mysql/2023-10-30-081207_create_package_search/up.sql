@@ -0,0 +1,26 @@
+CREATE TABLE package_search (
+    package_id BINARY(16) PRIMARY KEY,
+    document LONGTEXT NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    FULLTEXT INDEX package_search_document (document),
+    CONSTRAINT FK_PackageSearch FOREIGN KEY (package_id)
+    REFERENCES packages(id) ON DELETE CASCADE
+);
+
+-- Readmes are only stored as html, they are added to the documents on the next publish.
+INSERT INTO package_search (package_id, document)
+SELECT packages.id, CONCAT_WS(' ',
+    packages.name,
+    REPLACE(REPLACE(packages.name, '-', ' '), '_', ' '),
+    (SELECT GROUP_CONCAT(categories.name SEPARATOR ' ')
+        FROM package_categories
+        INNER JOIN categories ON categories.id = package_categories.category_id
+        WHERE package_categories.package_id = packages.id
+        AND package_categories.deleted_at IS NULL),
+    (SELECT versions.description
+        FROM versions
+        WHERE versions.package_id = packages.id
+        AND versions.deleted_at IS NULL
+        ORDER BY versions.created_at DESC
+        LIMIT 1))
+FROM packages;
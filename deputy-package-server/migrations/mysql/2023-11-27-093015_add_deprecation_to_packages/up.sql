@@ -0,0 +1,4 @@
+ALTER TABLE packages
+    ADD COLUMN deprecation_message TEXT NULL;
+ALTER TABLE packages
+    ADD COLUMN replacement_package TINYTEXT NULL;
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE webhook_deliveries
+    ADD COLUMN claimed_until TIMESTAMP NULL DEFAULT NULL;
\ No newline at end of file
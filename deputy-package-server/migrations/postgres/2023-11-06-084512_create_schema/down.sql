@@ -0,0 +1,15 @@
+DROP TABLE package_search;
+DROP TABLE webhook_deliveries;
+DROP TABLE webhooks;
+DROP TABLE version_downloads;
+DROP TABLE audit_logs;
+DROP TABLE team_owners;
+DROP TABLE team_members;
+DROP TABLE teams;
+DROP TABLE owners;
+DROP TABLE tokens;
+DROP TABLE package_categories;
+DROP TABLE categories;
+DROP TABLE versions;
+DROP TABLE packages;
+DROP FUNCTION set_updated_at;
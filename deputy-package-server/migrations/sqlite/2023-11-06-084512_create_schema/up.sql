@@ -0,0 +1,207 @@
+-- Initial SQLite schema, equal to the state of the MySQL migrations up to this point. SQLite has
+-- no full-text index on regular tables, the search documents are matched with LIKE.
+CREATE TABLE packages (
+    id BLOB PRIMARY KEY,
+    name TEXT NOT NULL,
+    package_type TEXT NOT NULL,
+    visibility TEXT NOT NULL DEFAULT 'public',
+    downloads BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMP NULL DEFAULT NULL
+);
+CREATE TRIGGER packages_updated_at AFTER UPDATE ON packages
+    FOR EACH ROW WHEN NEW.updated_at = OLD.updated_at
+    BEGIN
+        UPDATE packages SET updated_at = CURRENT_TIMESTAMP WHERE rowid = NEW.rowid;
+    END;
+
+CREATE TABLE versions (
+    id BLOB PRIMARY KEY,
+    package_id BLOB NOT NULL,
+    version TEXT NOT NULL,
+    description TEXT NOT NULL,
+    license TEXT NOT NULL,
+    is_yanked BOOLEAN NOT NULL DEFAULT FALSE,
+    readme_html TEXT NOT NULL,
+    package_size BIGINT NOT NULL,
+    checksum TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMP NULL DEFAULT NULL,
+    CONSTRAINT FK_PackageVersion FOREIGN KEY (package_id)
+    REFERENCES packages(id)
+);
+CREATE TRIGGER versions_updated_at AFTER UPDATE ON versions
+    FOR EACH ROW WHEN NEW.updated_at = OLD.updated_at
+    BEGIN
+        UPDATE versions SET updated_at = CURRENT_TIMESTAMP WHERE rowid = NEW.rowid;
+    END;
+
+CREATE TABLE categories (
+    id BLOB PRIMARY KEY,
+    name TEXT UNIQUE NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMP NULL DEFAULT NULL
+);
+CREATE TRIGGER categories_updated_at AFTER UPDATE ON categories
+    FOR EACH ROW WHEN NEW.updated_at = OLD.updated_at
+    BEGIN
+        UPDATE categories SET updated_at = CURRENT_TIMESTAMP WHERE rowid = NEW.rowid;
+    END;
+
+CREATE TABLE package_categories (
+    package_id BLOB NOT NULL,
+    category_id BLOB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMP NULL DEFAULT NULL,
+    PRIMARY KEY (package_id, category_id),
+    CONSTRAINT FK_Category FOREIGN KEY (category_id) REFERENCES categories(id),
+    CONSTRAINT FK_Package FOREIGN KEY (package_id) REFERENCES packages(id)
+);
+CREATE TRIGGER package_categories_updated_at AFTER UPDATE ON package_categories
+    FOR EACH ROW WHEN NEW.updated_at = OLD.updated_at
+    BEGIN
+        UPDATE package_categories SET updated_at = CURRENT_TIMESTAMP WHERE rowid = NEW.rowid;
+    END;
+
+CREATE TABLE tokens (
+    id BLOB PRIMARY KEY,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL,
+    token_prefix VARCHAR(16) NOT NULL DEFAULT '',
+    token_salt VARCHAR(64) NOT NULL DEFAULT '',
+    token_hash VARCHAR(64) NOT NULL DEFAULT '',
+    user_id TEXT NOT NULL,
+    roles TEXT NOT NULL DEFAULT '',
+    scopes TEXT NOT NULL DEFAULT '',
+    package_patterns TEXT NOT NULL DEFAULT '',
+    expires_at TIMESTAMP NULL DEFAULT NULL,
+    last_used_at TIMESTAMP NULL DEFAULT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:01'
+);
+CREATE INDEX tokens_token_prefix_index ON tokens (token_prefix);
+
+CREATE TABLE owners (
+    id BLOB PRIMARY KEY,
+    email TEXT NOT NULL,
+    package_id BLOB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:01',
+    CONSTRAINT FK_Package_Owners FOREIGN KEY (package_id) REFERENCES packages(id),
+    CONSTRAINT unique_owner_email_package_deleted UNIQUE (email, package_id, deleted_at)
+);
+
+CREATE TABLE teams (
+    id BLOB PRIMARY KEY,
+    name TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:01',
+    CONSTRAINT unique_team_name_deleted UNIQUE (name, deleted_at)
+);
+
+CREATE TABLE team_members (
+    id BLOB PRIMARY KEY,
+    team_id BLOB NOT NULL,
+    email TEXT NOT NULL,
+    is_admin BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:01',
+    CONSTRAINT FK_Team_Members FOREIGN KEY (team_id) REFERENCES teams(id),
+    CONSTRAINT unique_member_email_team_deleted UNIQUE (email, team_id, deleted_at)
+);
+
+CREATE TABLE team_owners (
+    id BLOB PRIMARY KEY,
+    team_id BLOB NOT NULL,
+    package_id BLOB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:01',
+    CONSTRAINT FK_Team_Owners_Team FOREIGN KEY (team_id) REFERENCES teams(id),
+    CONSTRAINT FK_Team_Owners_Package FOREIGN KEY (package_id) REFERENCES packages(id),
+    CONSTRAINT unique_team_owner_package_deleted UNIQUE (team_id, package_id, deleted_at)
+);
+
+CREATE TABLE audit_logs (
+    id BLOB PRIMARY KEY,
+    actor_email VARCHAR(255) NOT NULL,
+    actor_id TEXT NOT NULL,
+    token_id BLOB NULL,
+    action VARCHAR(64) NOT NULL,
+    package_name VARCHAR(255) NULL,
+    package_version VARCHAR(255) NULL,
+    details TEXT NULL,
+    ip_address VARCHAR(64) NULL,
+    user_agent TEXT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX audit_logs_created_at_index ON audit_logs (created_at);
+CREATE INDEX audit_logs_actor_email_index ON audit_logs (actor_email);
+CREATE INDEX audit_logs_action_index ON audit_logs (action);
+CREATE INDEX audit_logs_package_name_index ON audit_logs (package_name);
+
+CREATE TABLE version_downloads (
+    version_id BLOB NOT NULL,
+    download_date DATE NOT NULL,
+    downloads BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (version_id, download_date),
+    CONSTRAINT FK_VersionDownloads FOREIGN KEY (version_id)
+    REFERENCES versions(id) ON DELETE CASCADE
+);
+
+CREATE TABLE webhooks (
+    id BLOB PRIMARY KEY,
+    package_id BLOB NULL DEFAULT NULL,
+    url TEXT NOT NULL,
+    events TEXT NOT NULL,
+    secret VARCHAR(255) NOT NULL,
+    created_by VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMP NULL DEFAULT NULL,
+    CONSTRAINT FK_WebhookPackage FOREIGN KEY (package_id)
+    REFERENCES packages(id) ON DELETE CASCADE
+);
+CREATE TRIGGER webhooks_updated_at AFTER UPDATE ON webhooks
+    FOR EACH ROW WHEN NEW.updated_at = OLD.updated_at
+    BEGIN
+        UPDATE webhooks SET updated_at = CURRENT_TIMESTAMP WHERE rowid = NEW.rowid;
+    END;
+
+CREATE TABLE webhook_deliveries (
+    id BLOB PRIMARY KEY,
+    webhook_id BLOB NOT NULL,
+    event VARCHAR(64) NOT NULL,
+    payload TEXT NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    response_status SMALLINT NULL DEFAULT NULL,
+    last_error TEXT NULL DEFAULT NULL,
+    next_attempt_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    delivered_at TIMESTAMP NULL DEFAULT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT FK_WebhookDelivery FOREIGN KEY (webhook_id)
+    REFERENCES webhooks(id) ON DELETE CASCADE
+);
+CREATE INDEX webhook_deliveries_status_next_attempt_at
+    ON webhook_deliveries (status, next_attempt_at);
+
+CREATE TABLE package_search (
+    package_id BLOB PRIMARY KEY,
+    document TEXT NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT FK_PackageSearch FOREIGN KEY (package_id)
+    REFERENCES packages(id) ON DELETE CASCADE
+);
+CREATE TRIGGER package_search_updated_at AFTER UPDATE ON package_search
+    FOR EACH ROW WHEN NEW.updated_at = OLD.updated_at
+    BEGIN
+        UPDATE package_search SET updated_at = CURRENT_TIMESTAMP WHERE rowid = NEW.rowid;
+    END;
@@ -0,0 +1,2 @@
+ALTER TABLE versions
+    DROP COLUMN is_unavailable;
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE versions
+    ADD COLUMN is_unavailable BOOLEAN NOT NULL DEFAULT FALSE;
\ No newline at end of file
@@ -0,0 +1,4 @@
+ALTER TABLE packages
+    DROP COLUMN replacement_package;
+ALTER TABLE packages
+    DROP COLUMN deprecation_message;
\ No newline at end of file
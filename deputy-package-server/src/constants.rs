@@ -9,7 +9,29 @@ pub const fn default_limit() -> u32 {
     20
 }
 
+pub const fn default_stats_days() -> u32 {
+    90
+}
+
+pub fn default_admin_role() -> String {
+    "admin".to_string()
+}
+
+pub fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+pub const fn default_s3_request_timeout_seconds() -> u64 {
+    300
+}
+
 pub const PACKAGE_TOML: &str = "package.toml";
+pub const ENVIRONMENT_VARIABLE_PREFIX: &str = "DEPUTY_SERVER_";
+pub const ENVIRONMENT_VARIABLE_SEPARATOR: &str = "__";
+pub const DATABASE_URL_SCHEMES: [&str; 4] =
+    ["mysql://", "postgres://", "postgresql://", "sqlite://"];
+pub const TOKEN_PREFIX_LENGTH: usize = 12;
+pub const MAX_STATS_DAYS: u32 = 3650;
 
 pub const NAIVEDATETIME_DEFAULT_STRING: &str = "1970-01-01 00:00:01";
 pub const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
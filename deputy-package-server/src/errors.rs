@@ -1,4 +1,8 @@
-use actix_http::{body::BoxBody, StatusCode};
+use actix_http::{
+    body::BoxBody,
+    header::{HeaderValue, RETRY_AFTER},
+    StatusCode,
+};
 use actix_web::{error::ResponseError, HttpResponse};
 use anyhow::Error;
 use log::error;
@@ -25,20 +29,36 @@ pub enum PackageServerError {
     VersionParse,
     #[error("Package version on the server is either same or later: {0}")]
     VersionConflict(String),
+    #[error("Package name belongs to a deleted package")]
+    PackageNameDeleted,
     #[error("Failed to update version")]
     VersionUpdate,
+    #[error("Failed to delete the package")]
+    PackageDelete,
+    #[error("Failed to delete the package version")]
+    VersionDelete,
+    #[error("Days of the download statistics have to be between 1 and {0}")]
+    StatsDaysInvalid(u32),
     #[error("Failed to paginate packages")]
     Pagination,
     #[error("Actix mailbox full")]
     MailboxError,
     #[error("File not found")]
     FileNotFound,
+    #[error("Package version is unavailable, its archive failed the storage integrity check")]
+    VersionUnavailable,
     #[error("Not found")]
     DatabaseRecordNotFound,
     #[error("Keycloak validation failed")]
     KeycloakValidationFailed,
     #[error("Token missing")]
     TokenMissing,
+    #[error("Token expired")]
+    TokenExpired,
+    #[error("Token scope does not permit: {0}")]
+    TokenScopeDenied(String),
+    #[error("Requested token exceeds the scope of the authenticating token")]
+    TokenScopeExceeded,
     #[error("App state missing")]
     AppStateMissing,
     #[error("Database query failed")]
@@ -51,10 +71,66 @@ pub enum PackageServerError {
     OwnerRemove,
     #[error("Can not remove the last owner of a package")]
     OwnerRemoveLast,
+    #[error("Failed to create team")]
+    TeamCreate,
+    #[error("Team already exists")]
+    TeamExists,
+    #[error("Team not found")]
+    TeamNotFound,
+    #[error("Failed to get teams")]
+    TeamsList,
+    #[error("Failed to delete team")]
+    TeamDelete,
+    #[error("Team still owns packages, remove it from the package owners first")]
+    TeamOwnsPackages,
+    #[error("Failed to add member to team")]
+    TeamMemberAdd,
+    #[error("Failed to remove member from team")]
+    TeamMemberRemove,
+    #[error("Can not remove the last admin of a team")]
+    TeamMemberRemoveLastAdmin,
+    #[error("Team admin rights required")]
+    TeamAdminRequired,
     #[error("Failed to get path parameters")]
     PathParameters,
     #[error("Not authorized")]
     NotAuthorized,
+    #[error("Missing required role: {0}")]
+    MissingRole(String),
+    #[error("Access to the package denied")]
+    PackageAccessDenied,
+    #[error("Package exceeds the maximum allowed size of {0} bytes")]
+    PackageSizeLimitExceeded(u64),
+    #[error("Package size mismatch: {0}")]
+    PackageSizeMismatch(String),
+    #[error("Invalid package contents: {0}")]
+    PackageContentInvalid(String),
+    #[error("Unknown audit action: {0}")]
+    AuditActionParse(String),
+    #[error("Failed to get audit logs")]
+    AuditLogList,
+    #[error("Invalid webhook url: {0}")]
+    WebhookUrlInvalid(String),
+    #[error("Webhook not found")]
+    WebhookNotFound,
+    #[error("Failed to create webhook")]
+    WebhookCreate,
+    #[error("Failed to get webhooks")]
+    WebhookList,
+    #[error("Failed to delete webhook")]
+    WebhookDelete,
+    #[error("Failed to get webhook deliveries")]
+    WebhookDeliveryList,
+    #[error("Too many requests, retry after {0} seconds")]
+    RateLimited(u64),
+    #[error("Invalid deprecation: {0}")]
+    DeprecationInvalid(String),
+    #[error("Failed to update package deprecation")]
+    DeprecationUpdate,
+    #[error("Unknown package visibility: {0}")]
+    VisibilityParse(String),
+    #[error("Failed to update package visibility")]
+    VisibilityUpdate,
 }
 
 #[derive(Debug)]
@@ -78,11 +154,33 @@ impl ResponseError for ServerResponseError {
                 PackageServerError::PackageVersionRequirementValidation => StatusCode::BAD_REQUEST,
                 PackageServerError::PackageNameValidation => StatusCode::BAD_REQUEST,
                 PackageServerError::VersionConflict(_) => StatusCode::CONFLICT,
+                PackageServerError::PackageNameDeleted => StatusCode::CONFLICT,
                 PackageServerError::VersionUpdate => StatusCode::BAD_REQUEST,
                 PackageServerError::FileNotFound => StatusCode::NOT_FOUND,
+                PackageServerError::VersionUnavailable => StatusCode::GONE,
                 PackageServerError::DatabaseRecordNotFound => StatusCode::NOT_FOUND,
                 PackageServerError::TokenMissing => StatusCode::UNAUTHORIZED,
+                PackageServerError::TokenExpired => StatusCode::UNAUTHORIZED,
+                PackageServerError::TokenScopeDenied(_) => StatusCode::FORBIDDEN,
+                PackageServerError::TokenScopeExceeded => StatusCode::FORBIDDEN,
                 PackageServerError::NotAuthorized => StatusCode::UNAUTHORIZED,
+                PackageServerError::MissingRole(_) => StatusCode::FORBIDDEN,
+                PackageServerError::PackageAccessDenied => StatusCode::FORBIDDEN,
+                PackageServerError::TeamExists => StatusCode::CONFLICT,
+                PackageServerError::TeamNotFound => StatusCode::NOT_FOUND,
+                PackageServerError::TeamOwnsPackages => StatusCode::CONFLICT,
+                PackageServerError::TeamMemberRemoveLastAdmin => StatusCode::BAD_REQUEST,
+                PackageServerError::TeamAdminRequired => StatusCode::FORBIDDEN,
+                PackageServerError::PackageSizeLimitExceeded(_) => StatusCode::PAYLOAD_TOO_LARGE,
+                PackageServerError::PackageSizeMismatch(_) => StatusCode::BAD_REQUEST,
+                PackageServerError::PackageContentInvalid(_) => StatusCode::BAD_REQUEST,
+                PackageServerError::AuditActionParse(_) => StatusCode::BAD_REQUEST,
+                PackageServerError::WebhookUrlInvalid(_) => StatusCode::BAD_REQUEST,
+                PackageServerError::WebhookNotFound => StatusCode::NOT_FOUND,
+                PackageServerError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+                PackageServerError::DeprecationInvalid(_) => StatusCode::BAD_REQUEST,
+                PackageServerError::StatsDaysInvalid(_) => StatusCode::BAD_REQUEST,
+                PackageServerError::VisibilityParse(_) => StatusCode::BAD_REQUEST,
                 _ => StatusCode::INTERNAL_SERVER_ERROR,
             };
         }
@@ -90,7 +188,15 @@ impl ResponseError for ServerResponseError {
     }
 
     fn error_response(&self) -> HttpResponse<BoxBody> {
-        HttpResponse::with_body(self.status_code(), format!("{}", self.0)).map_into_boxed_body()
+        let mut response = HttpResponse::with_body(self.status_code(), format!("{}", self.0));
+        if let Some(PackageServerError::RateLimited(retry_after)) =
+            self.0.root_cause().downcast_ref::<PackageServerError>()
+        {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(*retry_after));
+        }
+        response.map_into_boxed_body()
     }
 }
 
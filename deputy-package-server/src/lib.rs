@@ -1,10 +1,15 @@
 use actix::{Actor, Addr};
+use configuration::{PackageSizeLimits, RoleRequirements};
+use deputy_library::archiver::ExtractionLimits;
+use services::{metrics::Metrics, storage::Storage};
+use std::sync::Arc;
 
 pub mod configuration;
 pub mod constants;
 mod errors;
 pub mod middleware;
 pub mod models;
+pub mod openapi;
 pub mod routes;
 pub mod schema;
 pub mod services;
@@ -19,5 +24,9 @@ where
     T: Actor,
 {
     pub database_address: Addr<T>,
-    pub package_folder: String,
+    pub storage: Arc<dyn Storage>,
+    pub extraction_limits: ExtractionLimits,
+    pub package_size_limits: PackageSizeLimits,
+    pub role_requirements: RoleRequirements,
+    pub metrics: Arc<Metrics>,
 }
@@ -1,33 +1,352 @@
 use actix::Actor;
 use actix_web::{
-    web::{delete, get, post, put, scope, Data},
+    web::{get, scope, Data, ServiceConfig},
     App, HttpServer,
 };
-use anyhow::{Ok, Result};
+use anyhow::{anyhow, Ok, Result};
+use chrono::NaiveDateTime;
+use clap::{ArgEnum, Args, Parser, Subcommand};
 use deputy_package_server::{
-    configuration::read_configuration,
-    middleware::authentication::{
-        jwt::AuthenticationMiddlewareFactory,
-        local_token::LocalTokenAuthenticationMiddlewareFactory,
-        owner::OwnerAuthenticationMiddlewareFactory,
-    },
+    configuration::{read_configuration, OrphanAction},
+    middleware::{authentication::jwt::decoding_key, metrics::MetricsMiddlewareFactory},
     routes::{
-        apitoken::{create_api_token, delete_api_token, get_all_api_tokens},
+        api::configure_api,
         basic::{status, version},
-        owner::{add_owner, delete_owner, get_all_owners},
-        package::{
-            add_package, download_file, download_package, get_all_categories, get_all_packages,
-            get_all_versions, get_package_version, yank_version,
-        },
+        documentation::{get_api_documentation, get_openapi_document},
+        health::{get_liveness, get_readiness},
+        metrics::get_metrics,
+    },
+    services::{
+        backup::{create_backup, restore_backup, verify_backup},
+        database::{Database, DatabaseConnectionManager},
+        health::HealthChecker,
+        metrics::Metrics,
+        rate_limit::RateLimiter,
+        scrub::StorageScrubber,
+        storage::storage_from_configuration,
+        webhook::WebhookDispatcher,
+    },
+    utilities::{
+        migration_states, pending_migrations, revert_migrations, run_migrations,
+        tls::server_configuration,
     },
-    services::database::Database,
     AppState,
 };
+use std::{path::Path, sync::Arc};
 
-async fn real_main() -> Result<()> {
-    env_logger::init();
-    let configuration = read_configuration(std::env::args().collect())?;
-    let database = Database::try_new(&configuration.database_url)
+#[derive(Parser)]
+#[clap(author, version, about, long_about = None)]
+#[clap(name = "deputy-package-server", args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[clap(subcommand)]
+    command: Option<Commands>,
+    #[clap(help = "Path of the configuration file, the same as `serve <CONFIGURATION_PATH>`")]
+    configuration_path: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    #[clap(about = "Run the package server, pending database migrations are run first")]
+    Serve(ServeOptions),
+    #[clap(about = "Inspect, run and revert database migrations")]
+    Migrate(MigrateOptions),
+    #[clap(about = "Validate the configuration file without starting the server")]
+    CheckConfig(CheckConfigOptions),
+    #[clap(
+        about = "Verify the stored archives against their checksums and find orphaned archives"
+    )]
+    Scrub(ScrubOptions),
+    #[clap(about = "Write the database and the stored archives into a single backup archive")]
+    Backup(BackupOptions),
+    #[clap(
+        about = "Restore a backup into an empty instance, or an incremental backup on top of the backups before it"
+    )]
+    Restore(RestoreOptions),
+}
+
+#[derive(Debug, Args)]
+struct ServeOptions {
+    configuration_path: String,
+    #[clap(
+        long,
+        help = "Refuse to start when database migrations are pending instead of running them"
+    )]
+    require_migrated: bool,
+}
+
+#[derive(Debug, Args)]
+struct MigrateOptions {
+    configuration_path: String,
+    #[clap(subcommand)]
+    subcommands: MigrateSubcommands,
+}
+
+#[derive(Subcommand, Debug)]
+enum MigrateSubcommands {
+    #[clap(about = "Run the pending migrations")]
+    Run {
+        #[clap(long, help = "Only list the migrations that would run")]
+        dry_run: bool,
+    },
+    #[clap(about = "List the applied and pending migrations")]
+    List,
+    #[clap(about = "Revert the most recently applied migrations")]
+    Revert {
+        #[clap(long, default_value = "1", help = "Number of migrations to revert")]
+        steps: usize,
+        #[clap(long, help = "Only list the migrations that would be reverted")]
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, Args)]
+struct CheckConfigOptions {
+    configuration_path: String,
+}
+
+#[derive(ArgEnum, Clone, Debug)]
+enum OrphanActionArg {
+    Report,
+    Quarantine,
+    Delete,
+}
+
+impl From<OrphanActionArg> for OrphanAction {
+    fn from(orphan_action: OrphanActionArg) -> Self {
+        match orphan_action {
+            OrphanActionArg::Report => OrphanAction::Report,
+            OrphanActionArg::Quarantine => OrphanAction::Quarantine,
+            OrphanActionArg::Delete => OrphanAction::Delete,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+struct ScrubOptions {
+    configuration_path: String,
+    #[clap(
+        arg_enum,
+        long,
+        help = "What to do with archives no version refers to, overrides scrub.orphan_action"
+    )]
+    orphans: Option<OrphanActionArg>,
+    #[clap(
+        long,
+        help = "Mark versions with a missing or corrupted archive unavailable and intact versions available again"
+    )]
+    mark_unavailable: bool,
+}
+
+#[derive(Debug, Args)]
+struct BackupOptions {
+    configuration_path: String,
+    backup_path: String,
+    #[clap(
+        long,
+        help = "Only back up the changes since this time (e.g. 2023-11-20T08:15:30), the creation time of the previous backup"
+    )]
+    since: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Args)]
+struct RestoreOptions {
+    configuration_path: String,
+    backup_path: String,
+    #[clap(long, help = "Only verify the backup against its manifest")]
+    dry_run: bool,
+}
+
+fn migrate(options: MigrateOptions) -> Result<()> {
+    let configuration = read_configuration(&options.configuration_path)?;
+    let mut connection = DatabaseConnectionManager::new(&configuration.database_url)
+        .establish()
+        .map_err(|error| anyhow!("Failed to connect to the database: {error}"))?;
+    let states = migration_states(&mut connection).map_err(|error| anyhow!("{error}"))?;
+
+    match options.subcommands {
+        MigrateSubcommands::List => {
+            for state in states {
+                let state_label = if state.is_applied {
+                    "applied"
+                } else {
+                    "pending"
+                };
+                println!("{state_label:<8} {}", state.name);
+            }
+        }
+        MigrateSubcommands::Run { dry_run } => {
+            let pending: Vec<String> = states
+                .into_iter()
+                .filter(|state| !state.is_applied)
+                .map(|state| state.name)
+                .collect();
+            if pending.is_empty() {
+                println!("No pending migrations");
+                return Ok(());
+            }
+            if !dry_run {
+                run_migrations(&mut connection).map_err(|error| anyhow!("{error}"))?;
+            }
+            let verb = if dry_run { "Would run" } else { "Ran" };
+            for name in pending {
+                println!("{verb} {name}");
+            }
+        }
+        MigrateSubcommands::Revert { steps, dry_run } => {
+            let reverted = if dry_run {
+                states
+                    .into_iter()
+                    .rev()
+                    .filter(|state| state.is_applied)
+                    .take(steps)
+                    .map(|state| state.name)
+                    .collect()
+            } else {
+                revert_migrations(&mut connection, steps).map_err(|error| anyhow!("{error}"))?
+            };
+            let verb = if dry_run { "Would revert" } else { "Reverted" };
+            for name in reverted {
+                println!("{verb} {name}");
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_config(options: CheckConfigOptions) -> Result<()> {
+    read_configuration(&options.configuration_path)?;
+    println!("Configuration {} is valid", options.configuration_path);
+    Ok(())
+}
+
+async fn scrub(options: ScrubOptions) -> Result<()> {
+    let configuration = read_configuration(&options.configuration_path)?;
+    let mut scrub_configuration = configuration.scrub.clone();
+    if let Some(orphan_action) = options.orphans {
+        scrub_configuration.orphan_action = orphan_action.into();
+    }
+    scrub_configuration.mark_unavailable |= options.mark_unavailable;
+    let database = Database::try_new_without_migrations(
+        &configuration.database_url,
+        &configuration.database_pool,
+    )?;
+    let pending = database.pending_migrations()?;
+    if !pending.is_empty() {
+        return Err(anyhow!(
+            "Refusing to scrub with pending database migrations, run `migrate` first: {}",
+            pending.join(", ")
+        ));
+    }
+    let database = database.start();
+    let orphan_label = match scrub_configuration.orphan_action {
+        OrphanAction::Report => "orphaned",
+        OrphanAction::Quarantine => "quarantined",
+        OrphanAction::Delete => "deleted",
+    };
+    let report = StorageScrubber::scrub(
+        database,
+        storage_from_configuration(&configuration)?,
+        scrub_configuration,
+    )
+    .await?;
+
+    for (label, keys) in [
+        ("missing", &report.missing),
+        ("corrupted", &report.corrupted),
+        (orphan_label, &report.orphaned),
+        ("unavailable", &report.marked_unavailable),
+        ("available", &report.marked_available),
+    ] {
+        for key in keys {
+            println!("{label:<11} {key}");
+        }
+    }
+    println!("Verified {} archives", report.verified);
+    if !report.is_clean() {
+        return Err(anyhow!(
+            "Storage scrub found {} missing, {} corrupted and {} orphaned archives",
+            report.missing.len(),
+            report.corrupted.len(),
+            report.orphaned.len()
+        ));
+    }
+    Ok(())
+}
+
+async fn backup(options: BackupOptions) -> Result<()> {
+    let configuration = read_configuration(&options.configuration_path)?;
+    let mut connection = DatabaseConnectionManager::new(&configuration.database_url)
+        .establish()
+        .map_err(|error| anyhow!("Failed to connect to the database: {error}"))?;
+    let storage = storage_from_configuration(&configuration)?;
+    let manifest = create_backup(
+        &mut connection,
+        storage.as_ref(),
+        Path::new(&options.backup_path),
+        options.since,
+    )
+    .await?;
+
+    for key in &manifest.unavailable_archives {
+        println!("Skipped the unavailable archive {key}");
+    }
+    println!(
+        "Backed up {} rows and {} archives to {}",
+        manifest.tables.values().sum::<usize>(),
+        manifest.archives.len(),
+        options.backup_path
+    );
+    println!(
+        "Take the next incremental backup with --since {}",
+        manifest.created_at.format("%Y-%m-%dT%H:%M:%S%.f")
+    );
+    Ok(())
+}
+
+async fn restore(options: RestoreOptions) -> Result<()> {
+    let backup_path = Path::new(&options.backup_path);
+    if options.dry_run {
+        let folder = tempfile::tempdir()?;
+        let manifest = verify_backup(backup_path, folder.path())?;
+        println!(
+            "Backup {} is intact, it has {} rows and {} archives",
+            options.backup_path,
+            manifest.tables.values().sum::<usize>(),
+            manifest.archives.len()
+        );
+        return Ok(());
+    }
+
+    let configuration = read_configuration(&options.configuration_path)?;
+    let mut connection = DatabaseConnectionManager::new(&configuration.database_url)
+        .establish()
+        .map_err(|error| anyhow!("Failed to connect to the database: {error}"))?;
+    run_migrations(&mut connection).map_err(|error| anyhow!("{error}"))?;
+    let storage = storage_from_configuration(&configuration)?;
+    let manifest = restore_backup(&mut connection, storage.as_ref(), backup_path).await?;
+
+    for (table, rows) in &manifest.tables {
+        println!("Restored {rows:>6} rows of {table}");
+    }
+    println!("Restored {} archives", manifest.archives.len());
+    Ok(())
+}
+
+async fn serve(options: ServeOptions) -> Result<()> {
+    let configuration = read_configuration(&options.configuration_path)?;
+    if options.require_migrated {
+        let mut connection = DatabaseConnectionManager::new(&configuration.database_url)
+            .establish()
+            .map_err(|error| anyhow!("Failed to connect to the database: {error}"))?;
+        let pending = pending_migrations(&mut connection).map_err(|error| anyhow!("{error}"))?;
+        if !pending.is_empty() {
+            return Err(anyhow!(
+                "Refusing to serve with pending database migrations, run `migrate` first: {}",
+                pending.join(", ")
+            ));
+        }
+    }
+    let database = Database::try_new(&configuration.database_url, &configuration.database_pool)
         .unwrap_or_else(|error| {
             panic!(
                 "Failed to create database connection to {} due to: {error}",
@@ -35,106 +354,105 @@ async fn real_main() -> Result<()> {
             )
         })
         .start();
+    WebhookDispatcher::new(database.clone(), configuration.webhooks.clone()).start();
+    let hostname = configuration.hostname.clone();
+    let tls_configuration = configuration
+        .tls
+        .as_ref()
+        .map(server_configuration)
+        .transpose()?;
 
     let app_state = AppState {
-        package_folder: configuration.package_folder,
+        storage: storage_from_configuration(&configuration)?,
         database_address: database,
+        extraction_limits: configuration.extraction_limits,
+        package_size_limits: configuration.package_size_limits.clone(),
+        role_requirements: configuration.role_requirements.clone(),
+        metrics: Arc::new(Metrics::default()),
     };
+    if configuration.scrub.enabled {
+        StorageScrubber::new(
+            app_state.database_address.clone(),
+            app_state.storage.clone(),
+            configuration.scrub.clone(),
+        )
+        .start();
+    }
+    let rate_limiter = Arc::new(RateLimiter::new(
+        configuration.rate_limits.clone(),
+        decoding_key(&configuration.keycloak.pem_content).ok(),
+    ));
+
+    let health_checker = HealthChecker::new(
+        configuration.health.clone(),
+        configuration.keycloak.pem_content.clone(),
+    );
 
-    HttpServer::new(move || {
-        let auth_middleware =
-            AuthenticationMiddlewareFactory(configuration.keycloak.pem_content.clone());
+    let server = HttpServer::new(move || {
+        let metrics_configuration = configuration.metrics.clone();
         let app_data = Data::new(app_state.clone());
         App::new()
             .app_data(app_data)
+            .wrap(MetricsMiddlewareFactory(app_state.metrics.clone()))
+            .configure(|service_config: &mut ServiceConfig| {
+                if metrics_configuration.enabled {
+                    service_config
+                        .app_data(Data::new(metrics_configuration.clone()))
+                        .route(
+                            &metrics_configuration.path,
+                            get().to(get_metrics::<Database>),
+                        );
+                }
+            })
             .service(status)
             .service(version)
             .service(
-                scope("/api").service(
-                    scope("/v1")
-                        .service(
-                            scope("/package")
-                                .service(
-                                    scope("/{package_name}")
-                                        .route("", get().to(get_all_versions::<Database>))
-                                        .service(
-                                            scope("/owner")
-                                                .route("", get().to(get_all_owners::<Database>))
-                                                .service(
-                                                    scope("")
-                                                        .wrap(OwnerAuthenticationMiddlewareFactory)
-                                                        .route("", post().to(add_owner::<Database>))
-                                                        .route(
-                                                            "/{owner_email}",
-                                                            delete().to(delete_owner::<Database>)
-                                                        )
-                                                        .wrap(
-                                                            LocalTokenAuthenticationMiddlewareFactory
-                                                        )
-                                                )
-                                        )
-                                        .service(
-                                            scope("/{version}")
-                                                .route(
-                                                    "/download",
-                                                    get().to(download_package::<Database>),
-                                                )
-                                                .route(
-                                                    "/path/{tail:.*}",
-                                                    get().to(download_file::<Database>),
-                                                )
-                                                .route(
-                                                    "",
-                                                    get().to(get_package_version::<Database>),
-                                                )
-                                                .service(
-                                                    scope("")
-                                                    .route(
-                                                        "/yank/{set_yank}",
-                                                        put().to(yank_version::<Database>),
-                                                    )
-                                                    .wrap(OwnerAuthenticationMiddlewareFactory)
-                                                    .wrap(
-                                                            LocalTokenAuthenticationMiddlewareFactory
-                                                        )
-                                                )
-                                        ),
-                                )
-                                .route("", get().to(get_all_packages::<Database>))
-                                .service(
-                                    scope("")
-                                        .service(
-                                            scope("").route("", post().to(add_package::<Database>)),
-                                        )
-                                        .wrap(LocalTokenAuthenticationMiddlewareFactory),
-                                ),
-                        )
-                        .route("/category", get().to(get_all_categories::<Database>))
-                        .service(
-                            scope("/token")
-                                .service(
-                                    scope("")
-                                        .route("", post().to(create_api_token::<Database>))
-                                        .route("", get().to(get_all_api_tokens::<Database>))
-                                        .route(
-                                            "/{token_id}",
-                                            delete().to(delete_api_token::<Database>),
-                                        ),
-                                )
-                                .wrap(auth_middleware),
-                        ),
-                ),
+                scope("/health")
+                    .app_data(Data::new(health_checker.clone()))
+                    .route("/live", get().to(get_liveness::<Database>))
+                    .route("/ready", get().to(get_readiness::<Database>)),
             )
-    })
-    .bind(configuration.hostname)?
-    .run()
-    .await?;
+            .service(get_api_documentation)
+            .service(get_openapi_document)
+            .configure(configure_api(&configuration, rate_limiter.clone()))
+    });
+    let server = match tls_configuration {
+        Some(tls_configuration) => server.bind_rustls_021(&hostname, tls_configuration)?,
+        None => server.bind(&hostname)?,
+    };
+    server.run().await?;
     Ok(())
 }
 
+async fn real_main() -> Result<()> {
+    env_logger::init();
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Commands::Serve(options)) => serve(options).await,
+        Some(Commands::Migrate(options)) => migrate(options),
+        Some(Commands::CheckConfig(options)) => check_config(options),
+        Some(Commands::Scrub(options)) => scrub(options).await,
+        Some(Commands::Backup(options)) => backup(options).await,
+        Some(Commands::Restore(options)) => restore(options).await,
+        None => match cli.configuration_path {
+            Some(configuration_path) => {
+                serve(ServeOptions {
+                    configuration_path,
+                    require_migrated: false,
+                })
+                .await
+            }
+            None => Err(anyhow!(
+                "Configuration path argument missing, see `deputy-package-server --help`"
+            )),
+        },
+    }
+}
+
 #[actix_web::main]
 async fn main() {
     if let Err(error) = real_main().await {
-        panic!("Failed to start the app due to: {error}");
+        eprintln!("{error}");
+        std::process::exit(1);
     };
 }
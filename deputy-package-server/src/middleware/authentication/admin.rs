@@ -0,0 +1,77 @@
+use crate::{
+    errors::{PackageServerError, ServerResponseError},
+    middleware::authentication::local_token::UserToken,
+};
+use actix_http::HttpMessage;
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error,
+};
+use futures_util::future::LocalBoxFuture;
+use log::error;
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+pub struct AdminAuthenticationMiddlewareFactory(pub String);
+
+impl<S, B> Transform<S, ServiceRequest> for AdminAuthenticationMiddlewareFactory
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AdminAuthenticationMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AdminAuthenticationMiddleware {
+            service: Rc::new(service),
+            admin_role: self.0.clone(),
+        }))
+    }
+}
+
+pub struct AdminAuthenticationMiddleware<S> {
+    service: Rc<S>,
+    admin_role: String,
+}
+
+impl<S, B> Service<ServiceRequest> for AdminAuthenticationMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let admin_role = self.admin_role.clone();
+
+        Box::pin(async move {
+            let user_token = req
+                .extensions()
+                .get::<Rc<UserToken>>()
+                .cloned()
+                .ok_or_else(|| error!("Requester token not found"))
+                .map_err(|_| ServerResponseError(PackageServerError::TokenMissing.into()))?;
+            let is_admin = user_token.has_role(&admin_role) && !user_token.is_restricted();
+
+            if is_admin {
+                let res = service.call(req).await?;
+                Ok(res)
+            } else {
+                Err(ServerResponseError(PackageServerError::NotAuthorized.into()).into())
+            }
+        })
+    }
+}
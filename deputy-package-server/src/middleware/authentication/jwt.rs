@@ -17,7 +17,7 @@ use std::{
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RealmAccess {
-    roles: Vec<String>,
+    pub roles: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -29,14 +29,19 @@ pub struct Token {
     pub email: Option<String>,
 }
 
-impl Token {
-    pub async fn try_new(token: &str, pem: &str) -> Result<Self, Error> {
-        let pem_file = format!(
-            "-----BEGIN PUBLIC KEY-----
+/// Decoding key of the Keycloak realm public key, `pem` is the key without the PEM armor.
+pub fn decoding_key(pem: &str) -> jsonwebtoken::errors::Result<DecodingKey> {
+    let pem_file = format!(
+        "-----BEGIN PUBLIC KEY-----
     {pem}
     -----END PUBLIC KEY-----"
-        );
-        let decoding_key = DecodingKey::from_rsa_pem(pem_file.as_bytes()).unwrap();
+    );
+    DecodingKey::from_rsa_pem(pem_file.as_bytes())
+}
+
+impl Token {
+    pub async fn try_new(token: &str, pem: &str) -> Result<Self, Error> {
+        let decoding_key = decoding_key(pem).unwrap();
         jsonwebtoken::decode::<Token>(token, &decoding_key, &Validation::new(Algorithm::RS256))
             .map(|data| data.claims)
             .map_err(|e| {
@@ -51,6 +56,13 @@ pub struct User {
     pub id: String,
     pub name: Option<String>,
     pub email: Option<String>,
+    pub roles: Vec<String>,
+}
+
+impl User {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|user_role| user_role == role)
+    }
 }
 
 impl From<Token> for User {
@@ -59,6 +71,10 @@ impl From<Token> for User {
             id: token.sub,
             name: token.name,
             email: token.email,
+            roles: token
+                .realm_access
+                .map(|realm_access| realm_access.roles)
+                .unwrap_or_default(),
         }
     }
 }
@@ -1,6 +1,10 @@
 use crate::{
     errors::{PackageServerError, ServerResponseError},
-    services::database::{apitoken::GetTokenByToken, Database},
+    models::{apitoken::package_pattern_matches, helpers::uuid::Uuid},
+    services::database::{
+        apitoken::{GetTokenByToken, UpdateTokenLastUsed},
+        Database,
+    },
     AppState,
 };
 use actix_http::HttpMessage;
@@ -10,6 +14,8 @@ use actix_web::{
     web::Data,
     Error, FromRequest,
 };
+use chrono::NaiveDateTime;
+use deputy_library::rest::TokenScope;
 use futures_util::future::LocalBoxFuture;
 use log::error;
 use serde::{Deserialize, Serialize};
@@ -18,10 +24,58 @@ use std::{
     rc::Rc,
 };
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Scopes and packages of a token are unrestricted when empty.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct UserToken {
     pub id: String,
     pub email: String,
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub token_id: Option<Uuid>,
+    #[serde(default)]
+    pub scopes: Vec<TokenScope>,
+    #[serde(default)]
+    pub packages: Vec<String>,
+    #[serde(default)]
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+impl UserToken {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|user_role| user_role == role)
+    }
+
+    pub fn permits_scope(&self, scope: TokenScope) -> bool {
+        self.scopes.is_empty() || self.scopes.contains(&scope)
+    }
+
+    pub fn permits_package(&self, package_name: &str) -> bool {
+        self.packages.is_empty()
+            || self
+                .packages
+                .iter()
+                .any(|pattern| package_pattern_matches(pattern, &package_name.to_lowercase()))
+    }
+
+    pub fn is_restricted(&self) -> bool {
+        !self.scopes.is_empty() || !self.packages.is_empty()
+    }
+}
+
+pub fn authorize_token_package(
+    user_token: &UserToken,
+    package_name: &str,
+) -> Result<(), ServerResponseError> {
+    if user_token.permits_package(package_name) {
+        return Ok(());
+    }
+    error!(
+        "Token of {} is not permitted to access package {package_name}",
+        user_token.email
+    );
+    Err(ServerResponseError(
+        PackageServerError::TokenScopeDenied(format!("package {package_name}")).into(),
+    ))
 }
 
 #[derive(Clone)]
@@ -54,6 +108,62 @@ impl std::ops::Deref for UserTokenInfo {
     }
 }
 
+async fn resolve_user_token(
+    app_state: Data<AppState<Database>>,
+    auth_header: Option<HeaderValue>,
+) -> Result<UserToken, ServerResponseError> {
+    let token_string: HeaderValue = match auth_header {
+        Some(value) => Ok(value),
+        _ => Err(ServerResponseError(PackageServerError::TokenMissing.into())),
+    }?;
+    let token_string = token_string
+        .to_str()
+        .map_err(|e| {
+            error!("Failed to convert authorization header to string: {}", e);
+            ServerResponseError(PackageServerError::TokenMissing.into())
+        })?
+        .replace("Bearer ", "");
+    let token_option = app_state
+        .database_address
+        .send(GetTokenByToken(token_string))
+        .await
+        .map_err(|error| {
+            error!("Mailbox error for getting tokens: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Database failed: {error}");
+            ServerResponseError(PackageServerError::DatabaseQueryFailed.into())
+        })?
+        .ok_or_else(|| {
+            error!("Token not found");
+            ServerResponseError(PackageServerError::TokenMissing.into())
+        })?;
+    if token_option.is_expired() {
+        error!("Token {} has expired", token_option.id);
+        return Err(ServerResponseError(PackageServerError::TokenExpired.into()));
+    }
+    match app_state
+        .database_address
+        .send(UpdateTokenLastUsed(token_option.id))
+        .await
+    {
+        Ok(Err(error)) => error!("Failed to record token usage: {error}"),
+        Err(error) => error!("Mailbox error for recording token usage: {error}"),
+        _ => {}
+    }
+
+    Ok(UserToken {
+        roles: token_option.role_list(),
+        token_id: Some(token_option.id),
+        scopes: token_option.scope_list(),
+        packages: token_option.package_pattern_list(),
+        expires_at: token_option.expires_at,
+        id: token_option.user_id,
+        email: token_option.email,
+    })
+}
+
 pub struct LocalTokenAuthenticationMiddlewareFactory;
 
 impl<S, B> Transform<S, ServiceRequest> for LocalTokenAuthenticationMiddlewareFactory
@@ -71,12 +181,37 @@ where
     fn new_transform(&self, service: S) -> Self::Future {
         ready(Ok(LocalTokenAuhtenticationMiddleware {
             service: Rc::new(service),
+            optional: false,
+        }))
+    }
+}
+
+/// Authenticates the request only when a valid token is presented, anonymous requests pass through.
+pub struct OptionalLocalTokenAuthenticationMiddlewareFactory;
+
+impl<S, B> Transform<S, ServiceRequest> for OptionalLocalTokenAuthenticationMiddlewareFactory
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = LocalTokenAuhtenticationMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(LocalTokenAuhtenticationMiddleware {
+            service: Rc::new(service),
+            optional: true,
         }))
     }
 }
 
 pub struct LocalTokenAuhtenticationMiddleware<S> {
     service: Rc<S>,
+    optional: bool,
 }
 
 impl<S, B> Service<ServiceRequest> for LocalTokenAuhtenticationMiddleware<S>
@@ -93,47 +228,31 @@ where
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let service = self.service.clone();
+        let optional = self.optional;
+        let already_authenticated = req.extensions().contains::<Rc<UserToken>>();
         let auth_header = req.headers().get("Authorization").cloned();
         let app_state = req.app_data::<Data<AppState<Database>>>().cloned();
+        let package_name = req.match_info().get("package_name").map(str::to_string);
 
         Box::pin(async move {
-            let app_state = app_state.ok_or_else(|| {
-                error!("App state not found");
-                ServerResponseError(PackageServerError::AppStateMissing.into())
-            })?;
-            let token_string: HeaderValue = match auth_header {
-                Some(value) => Ok(value),
-                _ => Err(ServerResponseError(PackageServerError::TokenMissing.into())),
-            }?;
-            let token_string = token_string
-                .to_str()
-                .map_err(|e| {
-                    error!("Failed to convert authorization header to string: {}", e);
-                    ServerResponseError(PackageServerError::TokenMissing.into())
-                })?
-                .replace("Bearer ", "");
-            let token_option = app_state
-                .database_address
-                .send(GetTokenByToken(token_string))
-                .await
-                .map_err(|error| {
-                    error!("Mailbox error for getting tokens: {error}");
-                    ServerResponseError(PackageServerError::MailboxError.into())
-                })?
-                .map_err(|error| {
-                    error!("Database failed: {error}");
-                    ServerResponseError(PackageServerError::DatabaseQueryFailed.into())
-                })?
-                .ok_or_else(|| {
-                    error!("Token not found");
-                    ServerResponseError(PackageServerError::TokenMissing.into())
+            if !already_authenticated && (!optional || auth_header.is_some()) {
+                let app_state = app_state.ok_or_else(|| {
+                    error!("App state not found");
+                    ServerResponseError(PackageServerError::AppStateMissing.into())
                 })?;
-
-            req.extensions_mut()
-                .insert::<Rc<UserToken>>(Rc::new(UserToken {
-                    id: token_option.user_id,
-                    email: token_option.email,
-                }));
+                match resolve_user_token(app_state, auth_header).await {
+                    Ok(user_token) => {
+                        req.extensions_mut()
+                            .insert::<Rc<UserToken>>(Rc::new(user_token));
+                    }
+                    Err(_) if optional => {}
+                    Err(error) => return Err(error.into()),
+                }
+            }
+            let user_token = req.extensions().get::<Rc<UserToken>>().cloned();
+            if let (Some(user_token), Some(package_name)) = (user_token, package_name) {
+                authorize_token_package(&user_token, &package_name)?;
+            }
 
             let res = service.call(req).await?;
             Ok(res)
@@ -1,3 +1,5 @@
+pub mod admin;
 pub mod jwt;
 pub mod local_token;
 pub mod owner;
+pub mod role;
@@ -1,7 +1,7 @@
 use crate::{
     errors::{PackageServerError, ServerResponseError},
     middleware::authentication::local_token::UserToken,
-    services::database::{owner::GetOwners, Database},
+    services::database::{owner::IsPackageOwner, Database},
     AppState,
 };
 use actix_http::HttpMessage;
@@ -68,12 +68,6 @@ where
                 .get("package_name")
                 .ok_or_else(|| error!("Package name not found"))
                 .map_err(|_| ServerResponseError(PackageServerError::PathParameters.into()))?;
-            let owners = app_state
-                .database_address
-                .send(GetOwners(package_name.to_owned()))
-                .await
-                .map_err(|_| ServerResponseError(PackageServerError::OwnersList.into()))?
-                .map_err(|_| ServerResponseError(PackageServerError::DatabaseQueryFailed.into()))?;
             let requester_email = req
                 .extensions()
                 .get::<Rc<UserToken>>()
@@ -81,8 +75,17 @@ where
                 .map_err(|_| ServerResponseError(PackageServerError::TokenMissing.into()))?
                 .email
                 .clone();
+            let is_owner = app_state
+                .database_address
+                .send(IsPackageOwner {
+                    package_name: package_name.to_owned(),
+                    email: requester_email,
+                })
+                .await
+                .map_err(|_| ServerResponseError(PackageServerError::OwnersList.into()))?
+                .map_err(|_| ServerResponseError(PackageServerError::DatabaseQueryFailed.into()))?;
 
-            if owners.contains_email(&requester_email) {
+            if is_owner {
                 let res = service.call(req).await?;
                 Ok(res)
             } else {
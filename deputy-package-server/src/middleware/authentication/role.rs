@@ -0,0 +1,139 @@
+use crate::{
+    configuration::{Action, RoleRequirements},
+    errors::{PackageServerError, ServerResponseError},
+    middleware::authentication::local_token::{authorize_token_package, UserToken},
+};
+use actix_http::HttpMessage;
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error,
+};
+use futures_util::future::LocalBoxFuture;
+use log::error;
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+pub fn authorize_token_scope(
+    user_token: &UserToken,
+    action: Action,
+    package_name: Option<&str>,
+) -> Result<(), ServerResponseError> {
+    let scope = action.token_scope();
+    if !user_token.permits_scope(scope) {
+        error!(
+            "Token of {} is missing the scope {scope} for {action:?}",
+            user_token.email
+        );
+        return Err(ServerResponseError(
+            PackageServerError::TokenScopeDenied(scope.to_string()).into(),
+        ));
+    }
+    if let Some(package_name) = package_name {
+        authorize_token_package(user_token, package_name)?;
+    }
+    Ok(())
+}
+
+pub fn authorize_action(
+    role_requirements: &RoleRequirements,
+    action: Action,
+    user_token: &UserToken,
+    package_name: Option<&str>,
+) -> Result<(), ServerResponseError> {
+    authorize_token_scope(user_token, action, package_name)?;
+    if role_requirements.is_permitted(action, &user_token.roles) {
+        return Ok(());
+    }
+    let required_role = role_requirements
+        .required_role(action)
+        .cloned()
+        .unwrap_or_default();
+    error!(
+        "User {} is missing the role {required_role} for {action:?}",
+        user_token.email
+    );
+    Err(ServerResponseError(
+        PackageServerError::MissingRole(required_role).into(),
+    ))
+}
+
+pub struct RoleAuthenticationMiddlewareFactory {
+    role_requirements: RoleRequirements,
+    action: Action,
+}
+
+impl RoleAuthenticationMiddlewareFactory {
+    pub fn new(role_requirements: RoleRequirements, action: Action) -> Self {
+        Self {
+            role_requirements,
+            action,
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RoleAuthenticationMiddlewareFactory
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RoleAuthenticationMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RoleAuthenticationMiddleware {
+            service: Rc::new(service),
+            role_requirements: self.role_requirements.clone(),
+            action: self.action,
+        }))
+    }
+}
+
+pub struct RoleAuthenticationMiddleware<S> {
+    service: Rc<S>,
+    role_requirements: RoleRequirements,
+    action: Action,
+}
+
+impl<S, B> Service<ServiceRequest> for RoleAuthenticationMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let role_requirements = self.role_requirements.clone();
+        let action = self.action;
+        let package_name = req.match_info().get("package_name").map(str::to_string);
+
+        Box::pin(async move {
+            let user_token = req
+                .extensions()
+                .get::<Rc<UserToken>>()
+                .cloned()
+                .ok_or_else(|| error!("Requester token not found"))
+                .map_err(|_| ServerResponseError(PackageServerError::TokenMissing.into()))?;
+            authorize_action(
+                &role_requirements,
+                action,
+                &user_token,
+                package_name.as_deref(),
+            )?;
+
+            let res = service.call(req).await?;
+            Ok(res)
+        })
+    }
+}
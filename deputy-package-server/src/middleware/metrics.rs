@@ -0,0 +1,78 @@
+use crate::services::metrics::Metrics;
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error,
+};
+use futures_util::future::LocalBoxFuture;
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+    sync::Arc,
+    time::Instant,
+};
+
+/// Route label of requests that did not match any route.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+pub struct MetricsMiddlewareFactory(pub Arc<Metrics>);
+
+impl<S, B> Transform<S, ServiceRequest> for MetricsMiddlewareFactory
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = MetricsMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(MetricsMiddleware {
+            service: Rc::new(service),
+            metrics: self.0.clone(),
+        }))
+    }
+}
+
+pub struct MetricsMiddleware<S> {
+    service: Rc<S>,
+    metrics: Arc<Metrics>,
+}
+
+impl<S, B> Service<ServiceRequest> for MetricsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let metrics = self.metrics.clone();
+        let method = req.method().to_string();
+        let started = Instant::now();
+
+        Box::pin(async move {
+            let result = service.call(req).await;
+            // Handler errors are already turned into responses, an error here never reached a route
+            let (route, status) = match &result {
+                Ok(res) => (res.request().match_pattern(), res.status()),
+                Err(error) => (None, error.as_response_error().status_code()),
+            };
+            metrics.record_request(
+                &method,
+                route.as_deref().unwrap_or(UNMATCHED_ROUTE),
+                status.as_u16(),
+                started.elapsed(),
+            );
+            result
+        })
+    }
+}
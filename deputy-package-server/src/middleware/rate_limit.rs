@@ -0,0 +1,155 @@
+use crate::{
+    errors::{PackageServerError, ServerResponseError},
+    services::{
+        database::{apitoken::GetTokenByToken, Database},
+        rate_limit::{RateLimitCategory, RateLimiter},
+    },
+    AppState,
+};
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::AUTHORIZATION,
+    web::Data,
+    Error,
+};
+use futures_util::future::LocalBoxFuture;
+use log::{error, warn};
+use sha2::{Digest, Sha256};
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+    sync::Arc,
+};
+
+/// Client key of requests without a resolvable IP address, they share one budget.
+const UNKNOWN_CLIENT: &str = "unknown";
+
+pub struct RateLimitMiddlewareFactory(pub Arc<RateLimiter>);
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimitMiddlewareFactory
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RateLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service: Rc::new(service),
+            rate_limiter: self.0.clone(),
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
+    rate_limiter: Arc<RateLimiter>,
+}
+
+/// Identifies the client by the subject of a valid JWT, then by a known API token and last by its
+/// IP address. Tokens that are not known yet are charged to the IP address and their digest is
+/// returned so the token is looked up only once the request is admitted, random tokens neither
+/// get fresh budgets nor cost a database query before they are throttled.
+fn client_key(
+    rate_limiter: &RateLimiter,
+    bearer_token: Option<&str>,
+    client_ip: Option<&str>,
+) -> (String, Option<String>) {
+    let mut unknown_token_digest = None;
+    if let Some(bearer_token) = bearer_token {
+        if let Some(subject) = rate_limiter.jwt_subject(bearer_token) {
+            return (format!("subject:{subject}"), None);
+        }
+        let token_digest = format!("{:x}", Sha256::digest(bearer_token));
+        if rate_limiter.is_known_token(&token_digest) {
+            return (format!("token:{token_digest}"), None);
+        }
+        unknown_token_digest = Some(token_digest);
+    }
+    (
+        format!("ip:{}", client_ip.unwrap_or(UNKNOWN_CLIENT)),
+        unknown_token_digest,
+    )
+}
+
+/// Remembers a valid API token so that its later requests are charged to its own budget.
+async fn resolve_token(
+    rate_limiter: &RateLimiter,
+    app_state: Data<AppState<Database>>,
+    bearer_token: String,
+    token_digest: String,
+) {
+    match app_state
+        .database_address
+        .send(GetTokenByToken(bearer_token))
+        .await
+    {
+        Ok(Ok(Some(api_token))) if !api_token.is_expired() => {
+            rate_limiter.add_known_token(token_digest);
+        }
+        Ok(Err(error)) => error!("Failed to get the token of a request: {error}"),
+        Err(error) => error!("Mailbox error for getting the token of a request: {error}"),
+        _ => {}
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let rate_limiter = self.rate_limiter.clone();
+        let category = RateLimitCategory::of(req.method(), req.path());
+        let bearer_token = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|header| header.to_str().ok())
+            .map(|header| header.strip_prefix("Bearer ").unwrap_or(header).to_string());
+        let client_ip = {
+            let connection_info = req.connection_info();
+            match rate_limiter.configuration.trust_forwarded_headers {
+                true => connection_info.realip_remote_addr(),
+                false => connection_info.peer_addr(),
+            }
+            .map(str::to_string)
+        };
+        let app_state = req.app_data::<Data<AppState<Database>>>().cloned();
+
+        Box::pin(async move {
+            let (client_key, unknown_token_digest) =
+                client_key(&rate_limiter, bearer_token.as_deref(), client_ip.as_deref());
+            if let Err(retry_after) = rate_limiter.acquire(&client_key, category) {
+                let retry_after = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+                warn!(
+                    "Rate limited {category:?} request of {client_key}, retry after {retry_after}s"
+                );
+                return Err(ServerResponseError(
+                    PackageServerError::RateLimited(retry_after).into(),
+                )
+                .into());
+            }
+            if let (Some(app_state), Some(bearer_token), Some(token_digest)) =
+                (app_state, bearer_token, unknown_token_digest)
+            {
+                resolve_token(&rate_limiter, app_state, bearer_token, token_digest).await;
+            }
+
+            let res = service.call(req).await?;
+            Ok(res)
+        })
+    }
+}
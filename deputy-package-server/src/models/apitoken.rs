@@ -1,28 +1,91 @@
 use crate::{
-    constants::NAIVEDATETIME_DEFAULT_VALUE,
+    constants::{NAIVEDATETIME_DEFAULT_VALUE, TOKEN_PREFIX_LENGTH},
+    errors::PackageServerError,
+    middleware::authentication::local_token::UserToken,
     models::helpers::uuid::Uuid,
     schema::tokens::{self},
-    services::database::{All, Create, FilterExistingNotNull, SoftDeleteById},
+    services::database::{All, Create, FilterExistingNotNull, SoftDeleteById, UpdateById},
 };
 use base64::{engine::general_purpose, Engine as _};
-use chrono::NaiveDateTime;
+use chrono::{Duration, NaiveDateTime, Utc};
+use deputy_library::rest::{NewScopedTokenRest, TokenRestrictionsRest, TokenScope};
 use diesel::{helper_types::FindBy, insert_into, prelude::*};
 use rand::Rng;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-#[derive(Queryable, Selectable, Eq, PartialEq, Deserialize, Serialize, Clone, Debug)]
+#[derive(
+    AsChangeset,
+    Queryable,
+    Selectable,
+    Identifiable,
+    Insertable,
+    Eq,
+    PartialEq,
+    Deserialize,
+    Serialize,
+    Clone,
+    Debug,
+)]
 #[diesel(table_name = tokens)]
 pub struct ApiToken {
     pub id: Uuid,
     pub name: String,
     pub email: String,
-    pub token: String,
+    pub token_prefix: String,
+    pub token_salt: String,
+    pub token_hash: String,
     pub user_id: String,
+    pub roles: String,
+    pub scopes: String,
+    pub package_patterns: String,
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
     pub created_at: NaiveDateTime,
     pub deleted_at: NaiveDateTime,
 }
 
 impl ApiToken {
+    pub fn role_list(&self) -> Vec<String> {
+        self.roles
+            .split(',')
+            .filter(|role| !role.is_empty())
+            .map(|role| role.to_string())
+            .collect()
+    }
+
+    pub fn scope_list(&self) -> Vec<TokenScope> {
+        self.scopes
+            .split(',')
+            .filter_map(|scope| scope.parse().ok())
+            .collect()
+    }
+
+    pub fn package_pattern_list(&self) -> Vec<String> {
+        self.package_patterns
+            .split(',')
+            .filter(|pattern| !pattern.is_empty())
+            .map(|pattern| pattern.to_string())
+            .collect()
+    }
+
+    pub fn matches_token(&self, token: &str) -> bool {
+        let token_hash = hash_token(&self.token_salt, token);
+        token_hash.len() == self.token_hash.len()
+            && token_hash
+                .bytes()
+                .zip(self.token_hash.bytes())
+                .fold(0, |difference, (left, right)| difference | (left ^ right))
+                == 0
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .map(|expires_at| expires_at <= Utc::now().naive_utc())
+            .unwrap_or(false)
+    }
+
     fn all_with_deleted() -> All<tokens::table, Self> {
         tokens::table.select(Self::as_select())
     }
@@ -38,14 +101,14 @@ impl ApiToken {
         Self::all().filter(tokens::id.eq(id))
     }
 
-    pub fn by_token(
-        token: String,
+    pub fn by_token_prefix(
+        token: &str,
     ) -> FindBy<
         FilterExistingNotNull<All<tokens::table, Self>, tokens::deleted_at>,
-        tokens::token,
+        tokens::token_prefix,
         String,
     > {
-        Self::all().filter(tokens::token.eq(token))
+        Self::all().filter(tokens::token_prefix.eq(token_prefix(token)))
     }
 
     pub fn by_user_id(
@@ -62,19 +125,42 @@ impl ApiToken {
         diesel::update(tokens::table.filter(tokens::id.eq(self.id)))
             .set(tokens::deleted_at.eq(diesel::dsl::now))
     }
+
+    pub fn update_last_used(
+        id: Uuid,
+    ) -> UpdateById<
+        tokens::id,
+        tokens::table,
+        diesel::dsl::Eq<tokens::last_used_at, diesel::dsl::now>,
+    > {
+        diesel::update(tokens::table.filter(tokens::id.eq(id)))
+            .set(tokens::last_used_at.eq(diesel::dsl::now))
+    }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ApiTokenRest {
     pub id: Uuid,
     pub name: String,
+    pub prefix: String,
+    pub roles: Vec<String>,
+    pub scopes: Vec<TokenScope>,
+    pub packages: Vec<String>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
     pub created_at: NaiveDateTime,
 }
 
 impl From<ApiToken> for ApiTokenRest {
     fn from(api_token: ApiToken) -> Self {
         Self {
+            roles: api_token.role_list(),
+            scopes: api_token.scope_list(),
+            packages: api_token.package_pattern_list(),
+            expires_at: api_token.expires_at,
+            last_used_at: api_token.last_used_at,
+            prefix: api_token.token_prefix,
             id: api_token.id,
             name: api_token.name,
             created_at: api_token.created_at,
@@ -82,7 +168,7 @@ impl From<ApiToken> for ApiTokenRest {
     }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct FullApiTokenRest {
     pub id: Uuid,
@@ -90,17 +176,26 @@ pub struct FullApiTokenRest {
     pub email: String,
     pub token: String,
     pub user_id: String,
+    pub roles: Vec<String>,
+    pub scopes: Vec<TokenScope>,
+    pub packages: Vec<String>,
+    pub expires_at: Option<NaiveDateTime>,
     pub created_at: NaiveDateTime,
     pub deleted_at: NaiveDateTime,
 }
 
-impl From<ApiToken> for FullApiTokenRest {
-    fn from(api_token: ApiToken) -> Self {
+/// The full token is only known when it is created, the database keeps just its hash.
+impl From<(ApiToken, String)> for FullApiTokenRest {
+    fn from((api_token, token): (ApiToken, String)) -> Self {
         Self {
+            roles: api_token.role_list(),
+            scopes: api_token.scope_list(),
+            packages: api_token.package_pattern_list(),
+            expires_at: api_token.expires_at,
             id: api_token.id,
             name: api_token.name,
             email: api_token.email,
-            token: api_token.token,
+            token,
             user_id: api_token.user_id,
             created_at: api_token.created_at,
             deleted_at: api_token.deleted_at,
@@ -115,25 +210,88 @@ pub struct NewApiToken {
     pub id: Uuid,
     pub name: String,
     pub email: String,
-    pub token: String,
+    pub token_prefix: String,
+    pub token_salt: String,
+    pub token_hash: String,
     pub user_id: String,
+    pub roles: String,
+    pub scopes: String,
+    pub package_patterns: String,
+    pub expires_at: Option<NaiveDateTime>,
 }
 
 impl NewApiToken {
     pub fn create_insert(&self) -> Create<&Self, tokens::table> {
         insert_into(tokens::table).values(self)
     }
+
+    /// Creates a token on behalf of an authenticated token, the new token can only narrow down
+    /// the scopes, packages and lifetime of its parent.
+    pub fn derived_from(
+        parent: &UserToken,
+        new_token: NewScopedTokenRest,
+        token: &str,
+    ) -> Result<Self, PackageServerError> {
+        let NewScopedTokenRest { name, restrictions } = new_token;
+        let scopes = match restrictions.scopes.is_empty() {
+            true => parent.scopes.clone(),
+            false => restrictions.scopes,
+        };
+        if !scopes.iter().all(|scope| parent.permits_scope(*scope)) {
+            return Err(PackageServerError::TokenScopeExceeded);
+        }
+        let packages = match restrictions.packages.is_empty() {
+            true => parent.packages.clone(),
+            false => restrictions
+                .packages
+                .iter()
+                .map(|pattern| pattern.to_lowercase())
+                .collect(),
+        };
+        let packages_permitted = parent.packages.is_empty()
+            || packages.iter().all(|pattern| {
+                parent.packages.contains(pattern)
+                    || (!is_package_pattern(pattern) && parent.permits_package(pattern))
+            });
+        if !packages_permitted {
+            return Err(PackageServerError::TokenScopeExceeded);
+        }
+        let expires_at = match (
+            restrictions.expires_in_days.map(expiry_from_days),
+            parent.expires_at,
+        ) {
+            (Some(requested), Some(parent_expiry)) => Some(requested.min(parent_expiry)),
+            (requested, parent_expiry) => requested.or(parent_expiry),
+        };
+
+        let token_salt = generate_salt();
+        Ok(Self {
+            id: Uuid::random(),
+            name,
+            email: parent.email.clone(),
+            token_prefix: token_prefix(token),
+            token_hash: hash_token(&token_salt, token),
+            token_salt,
+            user_id: parent.id.clone(),
+            roles: parent.roles.join(","),
+            scopes: join_scopes(&scopes),
+            package_patterns: packages.join(","),
+            expires_at,
+        })
+    }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct NewApiTokenRest {
     pub name: String,
     pub email: String,
+    #[serde(flatten)]
+    pub restrictions: TokenRestrictionsRest,
 }
 
 impl NewApiTokenRest {
-    fn generate_token() -> String {
+    pub fn generate_token() -> String {
         let token_byte_length = 128;
         let random_bytes = rand::thread_rng()
             .sample_iter(&rand::distributions::Alphanumeric)
@@ -142,13 +300,135 @@ impl NewApiTokenRest {
         general_purpose::STANDARD.encode(random_bytes)
     }
 
-    pub fn create_new_token(self, user_id: String) -> NewApiToken {
+    pub fn create_new_token(self, user_id: String, roles: &[String], token: &str) -> NewApiToken {
+        let expires_at = self.restrictions.expires_in_days.map(expiry_from_days);
+        let token_salt = generate_salt();
         NewApiToken {
             id: Uuid::random(),
             name: self.name,
             email: self.email,
-            token: Self::generate_token(),
+            token_prefix: token_prefix(token),
+            token_hash: hash_token(&token_salt, token),
+            token_salt,
             user_id,
+            roles: roles.join(","),
+            scopes: join_scopes(&self.restrictions.scopes),
+            package_patterns: self.restrictions.packages.join(","),
+            expires_at,
+        }
+    }
+}
+
+pub fn token_prefix(token: &str) -> String {
+    token.chars().take(TOKEN_PREFIX_LENGTH).collect()
+}
+
+fn generate_salt() -> String {
+    let salt_bytes = rand::thread_rng().gen::<[u8; 16]>();
+    salt_bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Hex encoded SHA-256 of the salt followed by the token, the token migration computes the
+/// same value with `SHA2(CONCAT(token_salt, token), 256)`.
+pub fn hash_token(salt: &str, token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(token.as_bytes());
+    let hash_bytes = hasher.finalize();
+    format!("{hash_bytes:x}")
+}
+
+pub fn expiry_from_days(days: u32) -> NaiveDateTime {
+    Utc::now().naive_utc() + Duration::days(days.into())
+}
+
+pub fn join_scopes(scopes: &[TokenScope]) -> String {
+    scopes
+        .iter()
+        .map(|scope| scope.as_str())
+        .collect::<Vec<&str>>()
+        .join(",")
+}
+
+/// Matches a package name against a pattern where `*` matches any sequence of characters and
+/// `?` matches a single character.
+pub fn package_pattern_matches(pattern: &str, package_name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<char>>();
+    let name = package_name.chars().collect::<Vec<char>>();
+    let (mut pattern_index, mut name_index) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while name_index < name.len() {
+        match pattern.get(pattern_index) {
+            Some('*') => {
+                backtrack = Some((pattern_index, name_index));
+                pattern_index += 1;
+            }
+            Some(character) if *character == '?' || *character == name[name_index] => {
+                pattern_index += 1;
+                name_index += 1;
+            }
+            _ => match backtrack {
+                Some((star_index, star_name_index)) => {
+                    pattern_index = star_index + 1;
+                    name_index = star_name_index + 1;
+                    backtrack = Some((star_index, star_name_index + 1));
+                }
+                None => return false,
+            },
         }
     }
+    pattern[pattern_index..]
+        .iter()
+        .all(|character| *character == '*')
+}
+
+pub fn is_package_pattern(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hash_token, package_pattern_matches, token_prefix, NewApiTokenRest};
+
+    #[test]
+    fn package_patterns_match_names() {
+        assert!(package_pattern_matches("my-package", "my-package"));
+        assert!(package_pattern_matches("my-*", "my-package"));
+        assert!(package_pattern_matches("*-package", "my-package"));
+        assert!(package_pattern_matches("my-?ackage", "my-package"));
+        assert!(package_pattern_matches("*", "my-package"));
+        assert!(!package_pattern_matches("my-*", "other-package"));
+        assert!(!package_pattern_matches("my-package", "my-package-2"));
+        assert!(!package_pattern_matches("my-?", "my-package"));
+    }
+
+    #[test]
+    fn token_hash_matches_mysql_sha2() {
+        assert_eq!(
+            hash_token("salt", "token"),
+            "6c71317dc482e04bde8aac8d2120657e5a2b7d22e266be2dbe630e58931d608a"
+        );
+    }
+
+    #[test]
+    fn new_token_is_stored_hashed() {
+        let token = NewApiTokenRest::generate_token();
+        let new_api_token = NewApiTokenRest {
+            name: "ci".to_string(),
+            email: "test-email".to_string(),
+            restrictions: Default::default(),
+        }
+        .create_new_token("test-id".to_string(), &[], &token);
+
+        assert_eq!(new_api_token.token_prefix, token_prefix(&token));
+        assert_ne!(new_api_token.token_hash, token);
+        assert_eq!(
+            new_api_token.token_hash,
+            hash_token(&new_api_token.token_salt, &token)
+        );
+    }
 }
@@ -0,0 +1,286 @@
+use crate::constants::{default_limit, default_page};
+use crate::middleware::authentication::local_token::UserToken;
+use crate::models::helpers::uuid::Uuid;
+use crate::{
+    schema::audit_logs,
+    services::database::{Create, MultiBackend},
+};
+use anyhow::{anyhow, Result};
+use chrono::NaiveDateTime;
+use diesel::{
+    insert_into, AsChangeset, ExpressionMethods, Identifiable, Insertable, QueryDsl, Queryable,
+    Selectable,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{self, Formatter},
+    str::FromStr,
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    PublishPackage,
+    PublishVersion,
+    YankVersion,
+    UnyankVersion,
+    AddOwner,
+    RemoveOwner,
+    AddTeamOwner,
+    RemoveTeamOwner,
+    CreateToken,
+    DeleteToken,
+    DeletePackage,
+    DeleteVersion,
+    DeprecatePackage,
+    UndeprecatePackage,
+    UpdateVisibility,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &str {
+        match self {
+            AuditAction::PublishPackage => "publish_package",
+            AuditAction::PublishVersion => "publish_version",
+            AuditAction::YankVersion => "yank_version",
+            AuditAction::UnyankVersion => "unyank_version",
+            AuditAction::AddOwner => "add_owner",
+            AuditAction::RemoveOwner => "remove_owner",
+            AuditAction::AddTeamOwner => "add_team_owner",
+            AuditAction::RemoveTeamOwner => "remove_team_owner",
+            AuditAction::CreateToken => "create_token",
+            AuditAction::DeleteToken => "delete_token",
+            AuditAction::DeletePackage => "delete_package",
+            AuditAction::DeleteVersion => "delete_version",
+            AuditAction::DeprecatePackage => "deprecate_package",
+            AuditAction::UndeprecatePackage => "undeprecate_package",
+            AuditAction::UpdateVisibility => "update_visibility",
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "publish_package" => Ok(AuditAction::PublishPackage),
+            "publish_version" => Ok(AuditAction::PublishVersion),
+            "yank_version" => Ok(AuditAction::YankVersion),
+            "unyank_version" => Ok(AuditAction::UnyankVersion),
+            "add_owner" => Ok(AuditAction::AddOwner),
+            "remove_owner" => Ok(AuditAction::RemoveOwner),
+            "add_team_owner" => Ok(AuditAction::AddTeamOwner),
+            "remove_team_owner" => Ok(AuditAction::RemoveTeamOwner),
+            "create_token" => Ok(AuditAction::CreateToken),
+            "delete_token" => Ok(AuditAction::DeleteToken),
+            "delete_package" => Ok(AuditAction::DeletePackage),
+            "delete_version" => Ok(AuditAction::DeleteVersion),
+            "deprecate_package" => Ok(AuditAction::DeprecatePackage),
+            "undeprecate_package" => Ok(AuditAction::UndeprecatePackage),
+            "update_visibility" => Ok(AuditAction::UpdateVisibility),
+            _ => Err(anyhow!("Unknown audit action: {value}")),
+        }
+    }
+}
+
+/// Audit log entries are append-only, there are no update or delete queries for them.
+#[derive(
+    AsChangeset,
+    Queryable,
+    Selectable,
+    Identifiable,
+    Insertable,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    Deserialize,
+    Serialize,
+    JsonSchema,
+)]
+#[diesel(table_name = audit_logs)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLog {
+    pub id: Uuid,
+    pub actor_email: String,
+    pub actor_id: String,
+    pub token_id: Option<Uuid>,
+    pub action: String,
+    pub package_name: Option<String>,
+    pub package_version: Option<String>,
+    pub details: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl AuditLog {
+    pub fn filtered(filter: AuditLogFilter) -> audit_logs::BoxedQuery<'static, MultiBackend> {
+        let mut query = audit_logs::table.into_boxed();
+        if let Some(actor) = filter.actor {
+            query = query.filter(audit_logs::actor_email.eq(actor));
+        }
+        if let Some(action) = filter.action {
+            query = query.filter(audit_logs::action.eq(action.to_string()));
+        }
+        if let Some(package_name) = filter.package_name {
+            query = query.filter(audit_logs::package_name.eq(package_name.to_lowercase()));
+        }
+        if let Some(since) = filter.since {
+            query = query.filter(audit_logs::created_at.ge(since));
+        }
+        if let Some(until) = filter.until {
+            query = query.filter(audit_logs::created_at.lt(until));
+        }
+        query
+    }
+}
+
+#[derive(Insertable, Clone, Debug, Deserialize, Serialize)]
+#[diesel(table_name = audit_logs)]
+#[serde(rename_all = "camelCase")]
+pub struct NewAuditLog {
+    pub id: Uuid,
+    pub actor_email: String,
+    pub actor_id: String,
+    pub token_id: Option<Uuid>,
+    pub action: String,
+    pub package_name: Option<String>,
+    pub package_version: Option<String>,
+    pub details: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl NewAuditLog {
+    pub fn new(
+        action: AuditAction,
+        actor_email: String,
+        actor_id: String,
+        token_id: Option<Uuid>,
+    ) -> Self {
+        Self {
+            id: Uuid::random(),
+            actor_email,
+            actor_id,
+            token_id,
+            action: action.to_string(),
+            package_name: None,
+            package_version: None,
+            details: None,
+            ip_address: None,
+            user_agent: None,
+        }
+    }
+
+    pub fn by_token(action: AuditAction, user_token: &UserToken) -> Self {
+        Self::new(
+            action,
+            user_token.email.clone(),
+            user_token.id.clone(),
+            user_token.token_id,
+        )
+    }
+
+    pub fn package(mut self, package_name: &str, package_version: Option<&str>) -> Self {
+        self.package_name = Some(package_name.to_lowercase());
+        self.package_version = package_version.map(str::to_string);
+        self
+    }
+
+    pub fn details(mut self, details: String) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn create_insert(&self) -> Create<&Self, audit_logs::table> {
+        insert_into(audit_logs::table).values(self)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AuditLogFilter {
+    pub actor: Option<String>,
+    pub action: Option<AuditAction>,
+    pub package_name: Option<String>,
+    pub since: Option<NaiveDateTime>,
+    pub until: Option<NaiveDateTime>,
+}
+
+impl AuditLogFilter {
+    pub fn matches(&self, audit_log: &AuditLog) -> bool {
+        if let Some(actor) = &self.actor {
+            if &audit_log.actor_email != actor {
+                return false;
+            }
+        }
+        if let Some(action) = self.action {
+            if audit_log.action != action.as_str() {
+                return false;
+            }
+        }
+        if let Some(package_name) = &self.package_name {
+            if audit_log.package_name.as_deref() != Some(package_name.to_lowercase().as_str()) {
+                return false;
+            }
+        }
+        if let Some(since) = self.since {
+            if audit_log.created_at < since {
+                return false;
+            }
+        }
+        if let Some(until) = self.until {
+            if audit_log.created_at >= until {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogQuery {
+    /// Page of the results, starting from 1
+    #[serde(default = "default_page")]
+    pub page: u32,
+    /// Number of results per page
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+    /// Email of the user who acted
+    pub actor: Option<String>,
+    /// Audited action
+    pub action: Option<String>,
+    /// Name of the package
+    pub package: Option<String>,
+    /// Earliest time of the entries, e.g. 2024-01-31T00:00:00
+    pub since: Option<NaiveDateTime>,
+    /// Time before which the entries were created
+    pub until: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogsWithPages {
+    pub audit_logs: Vec<AuditLog>,
+    pub total_pages: i64,
+    pub total_audit_logs: i64,
+}
+
+impl From<(Vec<AuditLog>, i64, i64)> for AuditLogsWithPages {
+    fn from((audit_logs, total_pages, total_audit_logs): (Vec<AuditLog>, i64, i64)) -> Self {
+        Self {
+            audit_logs,
+            total_pages,
+            total_audit_logs,
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use crate::models::helpers::uuid::Uuid;
+use crate::models::Version;
+use crate::schema::{version_downloads, versions};
+use crate::services::database::{DatabaseConnection, HardDeleteById, MultiBackend};
+use chrono::{NaiveDate, Utc};
+use deputy_library::rest::{DailyDownloadsRest, DownloadStatsRest, VersionDownloadsRest};
+use diesel::{
+    dsl::DuplicatedKeys, insert_into, query_dsl::methods::ExecuteDsl, AsChangeset,
+    ExpressionMethods, Identifiable, Insertable, QueryDsl, QueryResult, Queryable, RunQueryDsl,
+    Selectable,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Downloads are counted per version per day, the package wide total is kept on the package row
+/// so that search results can be sorted by it.
+#[derive(
+    AsChangeset,
+    Queryable,
+    Selectable,
+    Identifiable,
+    Insertable,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    Deserialize,
+    Serialize,
+)]
+#[diesel(table_name = version_downloads)]
+#[diesel(primary_key(version_id, download_date))]
+#[serde(rename_all = "camelCase")]
+pub struct VersionDownload {
+    pub version_id: Uuid,
+    pub download_date: NaiveDate,
+    pub downloads: i64,
+}
+
+impl VersionDownload {
+    pub fn today(version_id: Uuid) -> Self {
+        Self {
+            version_id,
+            download_date: Utc::now().date_naive(),
+            downloads: 1,
+        }
+    }
+
+    pub fn by_version_ids(
+        version_ids: Vec<Uuid>,
+    ) -> version_downloads::BoxedQuery<'static, MultiBackend> {
+        version_downloads::table
+            .filter(version_downloads::version_id.eq_any(version_ids))
+            .into_boxed()
+    }
+
+    pub fn hard_delete_by_version_id(
+        version_id: Uuid,
+    ) -> HardDeleteById<version_downloads::version_id, version_downloads::table> {
+        diesel::delete(
+            version_downloads::table.filter(version_downloads::version_id.eq(version_id)),
+        )
+    }
+
+    pub fn hard_delete_by_package_id(
+        package_id: Uuid,
+    ) -> impl RunQueryDsl<DatabaseConnection> + ExecuteDsl<DatabaseConnection> {
+        diesel::delete(
+            version_downloads::table.filter(
+                version_downloads::version_id.eq_any(
+                    versions::table
+                        .filter(versions::package_id.eq(package_id))
+                        .select(versions::id),
+                ),
+            ),
+        )
+    }
+
+    pub fn create_or_increment(&self, connection: &mut DatabaseConnection) -> QueryResult<usize> {
+        let insert = insert_into(version_downloads::table).values(self);
+        let increment =
+            version_downloads::downloads.eq(version_downloads::downloads + self.downloads);
+        let primary_key = (
+            version_downloads::version_id,
+            version_downloads::download_date,
+        );
+        match connection {
+            DatabaseConnection::Mysql(connection) => insert
+                .on_conflict(DuplicatedKeys)
+                .do_update()
+                .set(increment)
+                .execute(connection),
+            DatabaseConnection::Postgresql(connection) => insert
+                .on_conflict(primary_key)
+                .do_update()
+                .set(increment)
+                .execute(connection),
+            DatabaseConnection::Sqlite(connection) => insert
+                .on_conflict(primary_key)
+                .do_update()
+                .set(increment)
+                .execute(connection),
+        }
+    }
+}
+
+pub fn download_stats(
+    package_name: String,
+    versions: &[Version],
+    version_downloads: &[VersionDownload],
+    since: NaiveDate,
+) -> DownloadStatsRest {
+    let mut daily = BTreeMap::<NaiveDate, u64>::new();
+    let versions = versions
+        .iter()
+        .map(|version| {
+            let downloads = version_downloads
+                .iter()
+                .filter(|version_download| version_download.version_id == version.id)
+                .map(|version_download| version_download.downloads as u64)
+                .sum();
+            VersionDownloadsRest {
+                version: version.version.clone(),
+                downloads,
+            }
+        })
+        .collect::<Vec<_>>();
+    for version_download in version_downloads
+        .iter()
+        .filter(|version_download| version_download.download_date >= since)
+    {
+        *daily.entry(version_download.download_date).or_default() +=
+            version_download.downloads as u64;
+    }
+
+    DownloadStatsRest {
+        package_name,
+        total_downloads: versions.iter().map(|version| version.downloads).sum(),
+        versions,
+        daily: daily
+            .into_iter()
+            .map(|(date, downloads)| DailyDownloadsRest { date, downloads })
+            .collect(),
+    }
+}
@@ -0,0 +1,63 @@
+use crate::models::NewAuditLog;
+use crate::services::database::audit::CreateAuditLog;
+use crate::AppState;
+use actix::{Actor, Handler};
+use actix_web::{
+    dev::Payload, http::header::USER_AGENT, web::Data, Error, FromRequest, HttpRequest,
+};
+use log::error;
+use std::future::{ready, Ready};
+
+#[derive(Clone, Debug, Default)]
+pub struct RequestMetadata {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl FromRequest for RequestMetadata {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let ip_address = req
+            .connection_info()
+            .realip_remote_addr()
+            .map(str::to_string);
+        let user_agent = req
+            .headers()
+            .get(USER_AGENT)
+            .and_then(|user_agent| user_agent.to_str().ok())
+            .map(str::to_string);
+        ready(Ok(Self {
+            ip_address,
+            user_agent,
+        }))
+    }
+}
+
+/// Failing to write the audit log does not fail the request as the mutation itself has already
+/// been committed.
+pub async fn record_audit_log<T>(
+    new_audit_log: NewAuditLog,
+    request_metadata: &RequestMetadata,
+    app_state: &Data<AppState<T>>,
+) where
+    T: Actor + Handler<CreateAuditLog>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateAuditLog>,
+{
+    let new_audit_log = NewAuditLog {
+        ip_address: request_metadata.ip_address.clone(),
+        user_agent: request_metadata.user_agent.clone(),
+        ..new_audit_log
+    };
+    let action = new_audit_log.action.clone();
+    match app_state
+        .database_address
+        .send(CreateAuditLog(new_audit_log))
+        .await
+    {
+        Ok(Err(error)) => error!("Failed to record audit log for {action}: {error}"),
+        Err(error) => error!("Failed to record audit log for {action}: {error}"),
+        _ => {}
+    }
+}
@@ -1,3 +1,6 @@
+pub mod audit;
 pub mod pagination;
 pub mod uuid;
 pub mod versioning;
+pub mod visibility;
+pub mod webhook;
@@ -1,9 +1,10 @@
 use crate::constants::default_limit;
-use diesel::mysql::Mysql;
+use crate::models::{FullTextMatch, FullTextRelevance};
+use crate::services::database::{DatabaseConnection, MultiBackend};
 use diesel::query_builder::{AstPass, QueryFragment};
 use diesel::{
-    query_builder::Query, query_dsl::LoadQuery, sql_types::BigInt, MysqlConnection, QueryId,
-    QueryResult, RunQueryDsl,
+    query_builder::Query, query_dsl::LoadQuery, sql_types::BigInt, QueryId, QueryResult,
+    RunQueryDsl,
 };
 
 pub trait Paginate: Sized {
@@ -17,16 +18,20 @@ impl<T> Paginate for T {
             per_page: default_limit() as i64,
             page,
             offset: (page - 1) * default_limit() as i64,
+            order_by: "name ASC",
+            relevance: None,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, QueryId)]
+#[derive(Debug, Clone, QueryId)]
 pub struct Paginated<T> {
     pub query: T,
     page: i64,
     per_page: i64,
     offset: i64,
+    order_by: &'static str,
+    relevance: Option<FullTextRelevance>,
 }
 
 impl<T> Paginated<T> {
@@ -38,12 +43,25 @@ impl<T> Paginated<T> {
         }
     }
 
+    pub fn order_by(self, order_by: &'static str) -> Self {
+        Paginated { order_by, ..self }
+    }
+
+    /// Orders package search results by the full-text relevance of their search document first,
+    /// `order_by` only breaks the ties.
+    pub fn order_by_relevance(self, search_term: String) -> Self {
+        Paginated {
+            relevance: Some(FullTextMatch::new(search_term).relevance()),
+            ..self
+        }
+    }
+
     pub fn load_and_count_pages<'a, U>(
         self,
-        conn: &mut MysqlConnection,
+        conn: &mut DatabaseConnection,
     ) -> QueryResult<(Vec<U>, i64, i64)>
     where
-        Self: LoadQuery<'a, MysqlConnection, (U, i64)>,
+        Self: LoadQuery<'a, DatabaseConnection, (U, i64)>,
     {
         let per_page = self.per_page;
         let results = self.load::<(U, i64)>(conn)?;
@@ -58,16 +76,23 @@ impl<T: Query> Query for Paginated<T> {
     type SqlType = (T::SqlType, BigInt);
 }
 
-impl<T> RunQueryDsl<MysqlConnection> for Paginated<T> {}
+impl<T> RunQueryDsl<DatabaseConnection> for Paginated<T> {}
 
-impl<T> QueryFragment<Mysql> for Paginated<T>
+impl<T> QueryFragment<MultiBackend> for Paginated<T>
 where
-    T: QueryFragment<Mysql>,
+    T: QueryFragment<MultiBackend>,
 {
-    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Mysql>) -> QueryResult<()> {
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, MultiBackend>) -> QueryResult<()> {
         out.push_sql("SELECT *, COUNT(*) OVER () FROM (");
         self.query.walk_ast(out.reborrow())?;
-        out.push_sql(") t ORDER BY name ASC LIMIT ");
+        out.push_sql(") t ORDER BY ");
+        if let Some(relevance) = &self.relevance {
+            out.push_sql("COALESCE((SELECT ");
+            relevance.walk_ast(out.reborrow())?;
+            out.push_sql(" FROM package_search WHERE package_search.package_id = t.id), 0) DESC, ");
+        }
+        out.push_sql(self.order_by);
+        out.push_sql(" LIMIT ");
         out.push_bind_param::<BigInt, _>(&self.per_page)?;
         out.push_sql(" OFFSET ");
         out.push_bind_param::<BigInt, _>(&self.offset)?;
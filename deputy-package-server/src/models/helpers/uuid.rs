@@ -1,19 +1,12 @@
 use diesel::{
-    backend::Backend,
-    deserialize,
-    deserialize::FromSql,
-    mysql::Mysql,
-    serialize,
-    serialize::ToSql,
-    serialize::{IsNull, Output},
-    sql_types::Binary,
-    AsExpression, FromSqlRow,
+    backend::Backend, deserialize, deserialize::FromSql, serialize, serialize::Output,
+    serialize::ToSql, sql_types::Binary, AsExpression, FromSqlRow,
 };
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
 use serde::{Deserialize, Serialize};
 use std::{
     fmt,
     fmt::{Display, Formatter},
-    io::Write,
 };
 
 #[derive(Debug, Clone, Copy, FromSqlRow, AsExpression, Hash, Eq, PartialEq)]
@@ -64,14 +57,32 @@ impl<'de> Deserialize<'de> for Uuid {
     }
 }
 
+impl JsonSchema for Uuid {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        uuid::Uuid::schema_name()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        uuid::Uuid::json_schema(generator)
+    }
+}
+
 impl Display for Uuid {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.0)
     }
 }
 
-impl FromSql<Binary, Mysql> for Uuid {
-    fn from_sql(bytes: <Mysql as Backend>::RawValue<'_>) -> deserialize::Result<Self> {
+impl<DB> FromSql<Binary, DB> for Uuid
+where
+    DB: Backend,
+    Vec<u8>: FromSql<Binary, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
         let value = <Vec<u8>>::from_sql(bytes)?;
         uuid::Uuid::from_slice(&value)
             .map(Uuid)
@@ -79,10 +90,12 @@ impl FromSql<Binary, Mysql> for Uuid {
     }
 }
 
-impl ToSql<Binary, Mysql> for Uuid {
-    fn to_sql(&self, out: &mut Output<Mysql>) -> serialize::Result {
-        out.write_all(self.0.as_bytes())
-            .map(|_| IsNull::No)
-            .map_err(Into::into)
+impl<DB> ToSql<Binary, DB> for Uuid
+where
+    DB: Backend,
+    [u8]: ToSql<Binary, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        self.0.as_bytes().as_slice().to_sql(out)
     }
 }
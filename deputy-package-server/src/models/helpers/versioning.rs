@@ -1,6 +1,8 @@
 use crate::errors::{PackageServerError, ServerResponseError};
-use crate::models::Version;
-use crate::services::database::package::{GetPackageByNameAndVersion, GetVersionsByPackageName};
+use crate::models::{PublishedVersions, Version};
+use crate::services::database::package::{
+    GetPackageByNameAndVersion, GetPublishedVersions, GetVersionsByPackageName,
+};
 use crate::AppState;
 use actix::{Actor, Handler};
 use actix_web::{web::Data, Error, HttpResponse, Result as ActixWebResult};
@@ -53,6 +55,30 @@ where
         })
 }
 
+pub async fn get_published_versions<T>(
+    name: String,
+    app_state: Data<AppState<T>>,
+) -> ActixWebResult<PublishedVersions, ServerResponseError>
+where
+    T: Actor + Handler<GetPublishedVersions>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPublishedVersions>,
+{
+    app_state
+        .database_address
+        .send(GetPublishedVersions(name))
+        .await
+        .map_err(|error| {
+            error!("Failed to get package: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to get package: {error}");
+            ServerResponseError(PackageServerError::DatabaseRecordNotFound.into())
+        })
+}
+
+/// Checks the version against every version published under the package name, soft deleted
+/// versions included as their archives are kept.
 pub fn validate_version(
     uploadable_version: &str,
     package_versions: Vec<Version>,
@@ -0,0 +1,86 @@
+use crate::configuration::{Action, RoleRequirements};
+use crate::errors::{PackageServerError, ServerResponseError};
+use crate::middleware::authentication::local_token::UserTokenInfo;
+use crate::models::PackageViewer;
+use crate::services::database::{owner::IsPackageOwner, package::GetPackageByName};
+use crate::AppState;
+use actix::{Actor, Handler};
+use actix_web::{web::Data, Result as ActixWebResult};
+use deputy_library::package::Visibility;
+use log::error;
+
+pub fn package_viewer(
+    user_info: &Option<UserTokenInfo>,
+    role_requirements: &RoleRequirements,
+) -> PackageViewer {
+    match user_info {
+        Some(user_info) if user_info.permits_scope(Action::ReadPrivate.token_scope()) => {
+            PackageViewer {
+                email: Some(user_info.email.clone()),
+                can_read_authenticated: role_requirements
+                    .is_permitted(Action::ReadPrivate, &user_info.roles),
+            }
+        }
+        _ => PackageViewer::default(),
+    }
+}
+
+pub async fn authorize_package_read<T>(
+    name: String,
+    viewer: &PackageViewer,
+    app_state: Data<AppState<T>>,
+) -> ActixWebResult<(), ServerResponseError>
+where
+    T: Actor + Handler<GetPackageByName> + Handler<IsPackageOwner>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackageByName>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, IsPackageOwner>,
+{
+    let package = app_state
+        .database_address
+        .send(GetPackageByName(name.clone()))
+        .await
+        .map_err(|error| {
+            error!("Failed to get package: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to get package: {error}");
+            ServerResponseError(PackageServerError::DatabaseQueryFailed.into())
+        })?;
+    let package = match package {
+        Some(package) if package.visibility() != Visibility::Public => package,
+        _ => return Ok(()),
+    };
+    let email = match &viewer.email {
+        Some(email) => email.clone(),
+        None => {
+            error!("Anonymous request for non-public package {name}");
+            return Err(ServerResponseError(
+                PackageServerError::NotAuthorized.into(),
+            ));
+        }
+    };
+
+    let is_owner = app_state
+        .database_address
+        .send(IsPackageOwner {
+            package_name: name.clone(),
+            email,
+        })
+        .await
+        .map_err(|error| {
+            error!("Failed to get owners: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to get owners: {error}");
+            ServerResponseError(PackageServerError::OwnersList.into())
+        })?;
+    if !viewer.can_read(&package, is_owner) {
+        error!("Access to package {name} denied");
+        return Err(ServerResponseError(
+            PackageServerError::PackageAccessDenied.into(),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use crate::services::database::webhook::EnqueueWebhookEvent;
+use crate::AppState;
+use actix::{Actor, Handler};
+use actix_web::web::Data;
+use deputy_library::rest::WebhookPayloadRest;
+use log::error;
+
+/// Queues the event for the webhook dispatcher, a failure to do so is logged and does not fail the
+/// request that triggered the event.
+pub async fn enqueue_webhook_event<T>(payload: WebhookPayloadRest, app_state: &Data<AppState<T>>)
+where
+    T: Actor + Handler<EnqueueWebhookEvent>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, EnqueueWebhookEvent>,
+{
+    let event = payload.event;
+    match app_state
+        .database_address
+        .send(EnqueueWebhookEvent(payload))
+        .await
+    {
+        Ok(Err(error)) => error!("Failed to enqueue webhook event {event}: {error}"),
+        Err(error) => error!("Failed to enqueue webhook event {event}: {error}"),
+        _ => {}
+    }
+}
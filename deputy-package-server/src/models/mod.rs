@@ -1,7 +1,17 @@
 pub mod apitoken;
+mod audit;
+mod download;
 pub(crate) mod helpers;
 mod owner;
 mod package;
+mod search;
+mod team;
+mod webhook;
 
+pub use audit::*;
+pub use download::*;
 pub use owner::*;
 pub use package::*;
+pub use search::*;
+pub use team::*;
+pub use webhook::*;
@@ -1,18 +1,20 @@
 use crate::constants::NAIVEDATETIME_DEFAULT_VALUE;
 use crate::models::helpers::uuid::Uuid;
-use crate::services::database::{FilterExistingNotNull, SoftDeleteById};
+use crate::services::database::{FilterExistingNotNull, HardDeleteById, SoftDeleteById};
 use crate::{
     schema::owners,
     services::database::{All, Create},
 };
 use chrono::NaiveDateTime;
 use diesel::{
-    helper_types::FindBy, insert_into, ExpressionMethods, Identifiable, Insertable, QueryDsl,
-    Queryable, Selectable, SelectableHelper,
+    helper_types::FindBy, insert_into, AsChangeset, ExpressionMethods, Identifiable, Insertable,
+    QueryDsl, Queryable, Selectable, SelectableHelper,
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(
+    AsChangeset,
     Queryable,
     Identifiable,
     Selectable,
@@ -23,6 +25,7 @@ use serde::{Deserialize, Serialize};
     PartialEq,
     Deserialize,
     Serialize,
+    JsonSchema,
 )]
 #[diesel(table_name = owners)]
 #[serde(rename_all = "camelCase")]
@@ -35,9 +38,10 @@ pub struct Owner {
     pub deleted_at: NaiveDateTime,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct OwnerQuery {
+    /// Email of the new owner
     pub email: String,
 }
 
@@ -81,6 +85,12 @@ impl Owner {
         diesel::update(owners::table.filter(owners::id.eq(self.id)))
             .set(owners::deleted_at.eq(diesel::dsl::now))
     }
+
+    pub fn hard_delete_by_package_id(
+        package_id: Uuid,
+    ) -> HardDeleteById<owners::package_id, owners::table> {
+        diesel::delete(owners::table.filter(owners::package_id.eq(package_id)))
+    }
 }
 
 #[derive(
@@ -107,7 +117,7 @@ impl NewOwner {
         insert_into(owners::table).values(self)
     }
 }
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Owners(pub Vec<Owner>);
 
@@ -1,22 +1,28 @@
 use crate::models::helpers::uuid::Uuid;
-use crate::services::database::{
-    FilterByNames, SearchLikeName, SearchLikeNameAndIds, SearchLikeNameAndType,
-    SearchLikeNameAndTypeAndIds,
-};
+use crate::models::{PackageSearchDocument, SearchDocumentMatches};
+use crate::services::database::{DatabaseConnection, FilterByNames, MultiBackend};
 use crate::{
     schema::{categories, package_categories, packages, versions},
-    services::database::{All, Create, FilterByIds, FilterExisting, SelectById, UpdateById},
+    services::database::{
+        All, Create, FilterByIds, FilterExisting, HardDeleteById, SelectById, SoftDeleteById,
+        UpdateById,
+    },
 };
 use chrono::NaiveDateTime;
-use deputy_library::package::PackageMetadata;
-use deputy_library::rest::{PackageWithVersionsRest, VersionRest};
-use diesel::helper_types::FindBy;
+use deputy_library::package::{PackageMetadata, Visibility};
+use deputy_library::rest::{
+    DeprecationRest, PackageWithVersionsRest, SearchFacetsRest, VersionRest,
+};
+use diesel::helper_types::{AsSelect, EqAny, FindBy, Like, Or, SqlTypeOf};
 use diesel::insert_into;
-use diesel::mysql::Mysql;
 use diesel::prelude::*;
+use diesel::query_dsl::methods::ExecuteDsl;
+use diesel::sql_types::Bool;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(
+    AsChangeset,
     Queryable,
     QueryableByName,
     Identifiable,
@@ -28,6 +34,7 @@ use serde::{Deserialize, Serialize};
     PartialEq,
     Deserialize,
     Serialize,
+    JsonSchema,
 )]
 #[diesel(table_name = categories)]
 #[serde(rename_all = "camelCase")]
@@ -116,11 +123,22 @@ impl NewPackageCategory {
 }
 
 #[derive(
-    Associations, Clone, Queryable, QueryableByName, Selectable, Debug, Deserialize, Serialize,
+    AsChangeset,
+    Associations,
+    Clone,
+    Queryable,
+    QueryableByName,
+    Selectable,
+    Identifiable,
+    Insertable,
+    Debug,
+    Deserialize,
+    Serialize,
 )]
 #[diesel(belongs_to(Package, foreign_key = package_id))]
 #[diesel(belongs_to(Category, foreign_key = category_id))]
 #[diesel(table_name = package_categories)]
+#[diesel(primary_key(package_id, category_id))]
 #[serde(rename_all = "camelCase")]
 pub struct PackageCategory {
     pub package_id: Uuid,
@@ -162,6 +180,17 @@ impl PackageCategory {
         Self::all().filter(package_categories::category_id.eq_any(category_ids))
     }
 
+    pub fn by_package_ids(
+        package_ids: Vec<Uuid>,
+    ) -> FilterByIds<
+        package_categories::table,
+        package_categories::package_id,
+        package_categories::deleted_at,
+        Self,
+    > {
+        Self::all().filter(package_categories::package_id.eq_any(package_ids))
+    }
+
     pub fn by_package_and_category_id(
         package_id: Uuid,
         category_id: Uuid,
@@ -179,6 +208,27 @@ impl PackageCategory {
             .filter(package_categories::package_id.eq(package_id))
             .filter(package_categories::category_id.eq(category_id))
     }
+
+    pub fn soft_delete_by_package_id(
+        package_id: Uuid,
+    ) -> SoftDeleteById<
+        package_categories::package_id,
+        package_categories::deleted_at,
+        package_categories::table,
+    > {
+        diesel::update(
+            package_categories::table.filter(package_categories::package_id.eq(package_id)),
+        )
+        .set(package_categories::deleted_at.eq(diesel::dsl::now))
+    }
+
+    pub fn hard_delete_by_package_id(
+        package_id: Uuid,
+    ) -> HardDeleteById<package_categories::package_id, package_categories::table> {
+        diesel::delete(
+            package_categories::table.filter(package_categories::package_id.eq(package_id)),
+        )
+    }
 }
 
 #[derive(
@@ -189,9 +239,11 @@ impl PackageCategory {
     QueryableByName,
     Selectable,
     Identifiable,
+    Insertable,
     Debug,
     Deserialize,
     Serialize,
+    JsonSchema,
 )]
 #[diesel(belongs_to(Package, foreign_key = package_id))]
 #[diesel(table_name = versions)]
@@ -204,11 +256,13 @@ pub struct Version {
     pub license: String,
     pub is_yanked: bool,
     pub readme_html: String,
-    pub package_size: u64,
+    pub package_size: i64,
     pub checksum: String,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub deleted_at: Option<NaiveDateTime>,
+    /// Set by the storage scrub when the archive of the version is missing or corrupted.
+    pub is_unavailable: bool,
 }
 
 impl Version {
@@ -227,14 +281,54 @@ impl Version {
         Self::all_with_deleted().filter(versions::deleted_at.is_null())
     }
 
+    /// The versions whose archives should be in the storage along with the name of their package.
+    /// Soft deleted versions keep their archives, when a version was published again after being
+    /// deleted only the latest row is returned as both share the same archive.
+    pub fn load_stored(connection: &mut DatabaseConnection) -> QueryResult<Vec<(String, Self)>> {
+        let mut stored_versions: Vec<(String, Self)> = versions::table
+            .inner_join(packages::table)
+            .order((
+                packages::name.asc(),
+                versions::version.asc(),
+                versions::created_at.desc(),
+            ))
+            .select((packages::name, Self::as_select()))
+            .load(connection)?;
+        stored_versions.dedup_by(|(name, version), (previous_name, previous_version)| {
+            name == previous_name && version.version == previous_version.version
+        });
+        stored_versions.sort_by(|(name, version), (other_name, other_version)| {
+            (name, version.created_at).cmp(&(other_name, other_version.created_at))
+        });
+        Ok(stored_versions)
+    }
+
     pub fn create_update(&self, id: Uuid) -> UpdateById<versions::id, versions::table, &Self> {
         diesel::update(versions::table)
             .filter(versions::id.eq(id))
             .set(self)
     }
+
+    pub fn soft_delete(
+        &self,
+    ) -> SoftDeleteById<versions::id, versions::deleted_at, versions::table> {
+        diesel::update(versions::table.filter(versions::id.eq(self.id)))
+            .set(versions::deleted_at.eq(diesel::dsl::now))
+    }
+
+    pub fn hard_delete(&self) -> HardDeleteById<versions::id, versions::table> {
+        diesel::delete(versions::table.filter(versions::id.eq(self.id)))
+    }
+
+    pub fn hard_delete_by_package_id(
+        package_id: Uuid,
+    ) -> HardDeleteById<versions::package_id, versions::table> {
+        diesel::delete(versions::table.filter(versions::package_id.eq(package_id)))
+    }
 }
 
 #[derive(
+    AsChangeset,
     Queryable,
     QueryableByName,
     Identifiable,
@@ -248,14 +342,19 @@ impl Version {
     Serialize,
 )]
 #[diesel(table_name = packages)]
+#[diesel(treat_none_as_null = true)]
 #[serde(rename_all = "camelCase")]
 pub struct Package {
     pub id: Uuid,
     pub name: String,
     pub package_type: String,
+    pub visibility: String,
+    pub downloads: i64,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub deleted_at: Option<NaiveDateTime>,
+    pub deprecation_message: Option<String>,
+    pub replacement_package: Option<String>,
 }
 
 impl Package {
@@ -267,52 +366,14 @@ impl Package {
         Self::all_with_deleted().filter(packages::deleted_at.is_null())
     }
 
-    pub fn search_name(
-        search_term: String,
-    ) -> SearchLikeName<packages::table, packages::name, packages::deleted_at, Self> {
-        Self::all().filter(packages::name.like(format!("%{}%", search_term)))
-    }
-
-    pub fn search_name_with_type(
-        search_term: String,
-        package_type: String,
-    ) -> SearchLikeNameAndType<
-        packages::table,
-        packages::name,
-        packages::package_type,
-        packages::deleted_at,
-        Self,
-    > {
-        Self::search_name(search_term).filter(packages::package_type.eq(package_type))
-    }
-
-    pub fn search_name_with_categories(
-        search_term: String,
-        package_ids_by_categories: Vec<Uuid>,
-    ) -> SearchLikeNameAndIds<
-        packages::table,
-        packages::name,
-        packages::id,
-        packages::deleted_at,
-        Self,
-    > {
-        Self::search_name(search_term).filter(packages::id.eq_any(package_ids_by_categories))
-    }
-
-    pub fn search_name_with_type_and_categories(
-        search_term: String,
-        package_type: String,
-        package_ids_by_categories: Vec<Uuid>,
-    ) -> SearchLikeNameAndTypeAndIds<
-        packages::table,
-        packages::name,
-        packages::package_type,
-        packages::id,
-        packages::deleted_at,
-        Self,
-    > {
-        Self::search_name_with_type(search_term, package_type)
-            .filter(packages::id.eq_any(package_ids_by_categories))
+    /// Packages found by the search filter, ordering and pagination are left to the caller.
+    pub fn search(filter: &PackageSearchFilter) -> BoxedPackageQuery {
+        filter
+            .conditions()
+            .into_iter()
+            .fold(Self::all().into_boxed(), |query, condition| {
+                query.filter(condition)
+            })
     }
 
     pub fn by_id(
@@ -332,20 +393,209 @@ impl Package {
         Self::all().filter(packages::name.eq(name))
     }
 
-    pub fn versions(&self) -> versions::BoxedQuery<'_, Mysql> {
-        Version::belonging_to(self).into_boxed()
+    pub fn by_name_with_deleted(
+        name: String,
+    ) -> FindBy<All<packages::table, Self>, packages::name, String> {
+        Self::all_with_deleted().filter(packages::name.eq(name))
+    }
+
+    pub fn versions(&self) -> versions::BoxedQuery<'_, MultiBackend> {
+        Version::belonging_to(self)
+            .filter(versions::deleted_at.is_null())
+            .into_boxed()
     }
 
     pub fn exact_version(
         &self,
         version: String,
-    ) -> FindBy<versions::BoxedQuery<'_, Mysql>, versions::version, String> {
+    ) -> FindBy<versions::BoxedQuery<'_, MultiBackend>, versions::version, String> {
         self.versions().filter(versions::version.eq(version))
     }
+
+    pub fn versions_with_deleted(&self) -> versions::BoxedQuery<'_, MultiBackend> {
+        Version::belonging_to(self).into_boxed()
+    }
+
+    pub fn exact_version_with_deleted(
+        &self,
+        version: String,
+    ) -> FindBy<versions::BoxedQuery<'_, MultiBackend>, versions::version, String> {
+        self.versions_with_deleted()
+            .filter(versions::version.eq(version))
+    }
+
+    pub fn visibility(&self) -> Visibility {
+        parse_visibility(&self.visibility)
+    }
+
+    pub fn deprecation(&self) -> Option<DeprecationRest> {
+        self.deprecation_message
+            .clone()
+            .map(|message| DeprecationRest {
+                message,
+                replacement: self.replacement_package.clone(),
+            })
+    }
+
+    pub fn update_visibility(
+        &self,
+        visibility: String,
+    ) -> UpdateById<packages::id, packages::table, diesel::dsl::Eq<packages::visibility, String>>
+    {
+        diesel::update(packages::table)
+            .filter(packages::id.eq(self.id))
+            .set(packages::visibility.eq(visibility))
+    }
+
+    /// Clears the deprecation when none is given.
+    pub fn update_deprecation(
+        &self,
+        deprecation: Option<DeprecationRest>,
+    ) -> impl RunQueryDsl<DatabaseConnection> + ExecuteDsl<DatabaseConnection> {
+        let (message, replacement) = match deprecation {
+            Some(deprecation) => (Some(deprecation.message), deprecation.replacement),
+            None => (None, None),
+        };
+        diesel::update(packages::table)
+            .filter(packages::id.eq(self.id))
+            .set((
+                packages::deprecation_message.eq(message),
+                packages::replacement_package.eq(replacement),
+            ))
+    }
+
+    pub fn touch(
+        &self,
+    ) -> UpdateById<
+        packages::id,
+        packages::table,
+        diesel::dsl::Eq<packages::updated_at, diesel::dsl::now>,
+    > {
+        diesel::update(packages::table)
+            .filter(packages::id.eq(self.id))
+            .set(packages::updated_at.eq(diesel::dsl::now))
+    }
+
+    pub fn increment_downloads(
+        &self,
+    ) -> impl RunQueryDsl<DatabaseConnection> + ExecuteDsl<DatabaseConnection> {
+        diesel::update(packages::table.filter(packages::id.eq(self.id)))
+            .set(packages::downloads.eq(packages::downloads + 1))
+    }
+
+    pub fn soft_delete(
+        &self,
+    ) -> SoftDeleteById<packages::id, packages::deleted_at, packages::table> {
+        diesel::update(packages::table.filter(packages::id.eq(self.id)))
+            .set(packages::deleted_at.eq(diesel::dsl::now))
+    }
+
+    pub fn hard_delete(&self) -> HardDeleteById<packages::id, packages::table> {
+        diesel::delete(packages::table.filter(packages::id.eq(self.id)))
+    }
+}
+
+/// Unknown visibility values are treated as the most restrictive one so a corrupted row never
+/// exposes a package.
+pub fn parse_visibility(visibility: &str) -> Visibility {
+    visibility.parse().unwrap_or(Visibility::OwnersOnly)
+}
+
+pub type BoxedPackageQuery =
+    packages::BoxedQuery<'static, MultiBackend, SqlTypeOf<AsSelect<Package, MultiBackend>>>;
+
+pub type PackageFilter<QS> = Box<dyn BoxableExpression<QS, MultiBackend, SqlType = Bool>>;
+
+/// Conditions of a package search. They apply to any query that includes the packages table so
+/// the search results and the facet counts are taken over the same packages.
+#[derive(Clone, Debug, Default)]
+pub struct PackageSearchFilter {
+    /// Matches the package name or the search document, an empty term matches every package.
+    pub search_term: String,
+    pub package_type: Option<String>,
+    pub package_ids_by_categories: Option<Vec<Uuid>>,
+    pub viewer: PackageViewer,
+    pub owned_package_ids: Vec<Uuid>,
+}
+
+impl PackageSearchFilter {
+    pub fn conditions<QS>(&self) -> Vec<PackageFilter<QS>>
+    where
+        packages::id: SelectableExpression<QS>,
+        packages::package_type: SelectableExpression<QS>,
+        packages::visibility: SelectableExpression<QS>,
+        Or<Like<packages::name, String>, EqAny<packages::id, SearchDocumentMatches>>:
+            SelectableExpression<QS>,
+    {
+        let mut conditions: Vec<PackageFilter<QS>> = vec![Box::new(
+            packages::visibility
+                .eq_any(self.viewer.readable_visibilities())
+                .or(packages::id.eq_any(self.owned_package_ids.clone())),
+        )];
+        if !self.search_term.is_empty() {
+            conditions.push(Box::new(
+                packages::name
+                    .like(format!("%{}%", self.search_term.to_lowercase()))
+                    .or(packages::id
+                        .eq_any(PackageSearchDocument::matching(self.search_term.clone()))),
+            ));
+        }
+        if let Some(package_type) = &self.package_type {
+            conditions.push(Box::new(packages::package_type.eq(package_type.clone())));
+        }
+        if let Some(package_ids_by_categories) = &self.package_ids_by_categories {
+            conditions.push(Box::new(
+                packages::id.eq_any(package_ids_by_categories.clone()),
+            ));
+        }
+        conditions
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PackageViewer {
+    pub email: Option<String>,
+    pub can_read_authenticated: bool,
+}
+
+impl PackageViewer {
+    pub fn readable_visibilities(&self) -> Vec<String> {
+        let mut visibilities = vec![Visibility::Public.to_string()];
+        if self.email.is_some() && self.can_read_authenticated {
+            visibilities.push(Visibility::Authenticated.to_string());
+        }
+        visibilities
+    }
+
+    pub fn can_read(&self, package: &Package, is_owner: bool) -> bool {
+        is_owner
+            || self
+                .readable_visibilities()
+                .contains(&package.visibility().to_string())
+    }
 }
 
 pub struct PackageVersion(pub Package, pub Version);
 
+/// Every version published under a package name, soft deleted ones included. Soft deletes keep the
+/// archives, so a deleted package name or version can not be published again.
+#[derive(Clone, Debug, Default)]
+pub struct PublishedVersions {
+    /// Set when packages of the name exist but all of them are soft deleted.
+    pub is_package_deleted: bool,
+    pub versions: Vec<Version>,
+}
+
+impl PublishedVersions {
+    pub fn live_versions(&self) -> Vec<Version> {
+        self.versions
+            .iter()
+            .filter(|version| version.deleted_at.is_none())
+            .cloned()
+            .collect()
+    }
+}
+
 #[derive(
     Queryable, Selectable, Insertable, Clone, Debug, Eq, PartialEq, Deserialize, Serialize,
 )]
@@ -355,6 +605,7 @@ pub struct NewPackage {
     pub id: Uuid,
     pub name: String,
     pub package_type: String,
+    pub visibility: String,
 }
 
 impl NewPackage {
@@ -375,7 +626,7 @@ pub struct NewVersion {
     pub license: String,
     pub is_yanked: bool,
     pub readme_html: String,
-    pub package_size: u64,
+    pub package_size: i64,
     pub checksum: String,
     pub package_id: Uuid,
 }
@@ -386,14 +637,18 @@ impl NewVersion {
     }
 }
 
-pub struct NewPackageVersion(pub NewPackage, pub NewVersion);
+pub struct NewPackageVersion(pub NewPackage, pub NewVersion, pub PackageSearchDocument);
 
 impl From<(PackageMetadata, String)> for NewPackageVersion {
     fn from((package_metadata, readme_html): (PackageMetadata, String)) -> Self {
+        let package_id = Uuid::random();
+        let search_document =
+            PackageSearchDocument::new(package_id, &package_metadata, &readme_html);
         let package = NewPackage {
-            id: Uuid::random().to_owned(),
+            id: package_id,
             name: package_metadata.name,
             package_type: package_metadata.package_type.to_string(),
+            visibility: package_metadata.visibility.to_string(),
         };
         let version = NewVersion {
             id: Uuid::random().to_owned(),
@@ -402,12 +657,12 @@ impl From<(PackageMetadata, String)> for NewPackageVersion {
             license: package_metadata.license,
             is_yanked: false,
             readme_html,
-            package_size: package_metadata.package_size,
+            package_size: package_metadata.package_size as i64,
             checksum: package_metadata.checksum,
             package_id: package.id,
         };
 
-        NewPackageVersion(package, version)
+        NewPackageVersion(package, version, search_document)
     }
 }
 
@@ -420,8 +675,9 @@ impl From<Version> for VersionRest {
             description: version.description,
             license: version.license,
             is_yanked: version.is_yanked,
+            is_unavailable: version.is_unavailable,
             readme_html: version.readme_html,
-            package_size: version.package_size,
+            package_size: version.package_size as u64,
             checksum: version.checksum,
             created_at: version.created_at,
             updated_at: version.updated_at,
@@ -438,26 +694,33 @@ impl From<String> for NewCategory {
     }
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PackageWithVersions {
     pub id: Uuid,
     pub name: String,
     pub package_type: String,
+    pub visibility: String,
+    pub downloads: u64,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub versions: Vec<Version>,
+    pub deprecation: Option<DeprecationRest>,
 }
 
 impl From<(Package, Vec<Version>)> for PackageWithVersions {
     fn from((package, versions): (Package, Vec<Version>)) -> Self {
+        let deprecation = package.deprecation();
         Self {
             id: package.id,
             name: package.name,
             package_type: package.package_type,
+            visibility: package.visibility,
+            downloads: package.downloads as u64,
             created_at: package.created_at,
             updated_at: package.updated_at,
             versions,
+            deprecation,
         }
     }
 }
@@ -468,6 +731,8 @@ impl From<PackageWithVersions> for PackageWithVersionsRest {
             id: package.id.into(),
             name: package.name,
             package_type: package.package_type,
+            visibility: parse_visibility(&package.visibility),
+            downloads: package.downloads,
             created_at: package.created_at,
             updated_at: package.updated_at,
             versions: package
@@ -475,16 +740,24 @@ impl From<PackageWithVersions> for PackageWithVersionsRest {
                 .into_iter()
                 .map(VersionRest::from)
                 .collect(),
+            deprecation: package.deprecation,
         }
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PackagesWithVersionsAndPages {
     pub packages: Vec<PackageWithVersions>,
     pub total_pages: i64,
     pub total_packages: i64,
+    pub facets: SearchFacetsRest,
+}
+
+impl PackagesWithVersionsAndPages {
+    pub fn with_facets(self, facets: SearchFacetsRest) -> Self {
+        Self { facets, ..self }
+    }
 }
 
 impl From<(Vec<PackageWithVersions>, i64, i64)> for PackagesWithVersionsAndPages {
@@ -493,6 +766,7 @@ impl From<(Vec<PackageWithVersions>, i64, i64)> for PackagesWithVersionsAndPages
             packages,
             total_pages,
             total_packages,
+            facets: SearchFacetsRest::default(),
         }
     }
 }
@@ -0,0 +1,327 @@
+use crate::models::helpers::uuid::Uuid;
+use crate::models::Package;
+use crate::schema::package_search;
+use crate::services::database::{DatabaseConnection, HardDeleteById, MultiBackend};
+use deputy_library::{package::PackageMetadata, rest::SearchFacetsRest};
+use diesel::{
+    dsl::{DuplicatedKeys, Filter, Select},
+    expression::{is_aggregate, AppearsOnTable, Expression, SelectableExpression, ValidGrouping},
+    insert_into,
+    mysql::Mysql,
+    pg::Pg,
+    query_builder::{AstPass, QueryFragment},
+    sql_types::{Bool, Text},
+    sqlite::Sqlite,
+    AsChangeset, ExpressionMethods, Identifiable, Insertable, QueryDsl, QueryId, QueryResult,
+    Queryable, RunQueryDsl, Selectable,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+const POSTGRESQL_DOCUMENT_VECTOR: &str = "to_tsvector('english', package_search.document)";
+/// `plainto_tsquery` requires every word of the term, the words are joined with OR instead to
+/// match the natural language mode of MySQL.
+const POSTGRESQL_QUERY_START: &str = "replace(plainto_tsquery('english', ";
+const POSTGRESQL_QUERY_END: &str = ")::text, ' & ', ' | ')::tsquery";
+
+pub type SearchDocumentMatches =
+    Filter<Select<package_search::table, package_search::package_id>, FullTextMatch>;
+
+/// Full-text match of the search document. MySQL and PostgreSQL use the full-text index of the
+/// document while SQLite matches the documents that contain any of the words of the search term.
+#[derive(Debug, Clone, QueryId)]
+pub struct FullTextMatch {
+    search_term: String,
+    word_patterns: Vec<String>,
+}
+
+impl FullTextMatch {
+    pub fn new(search_term: String) -> Self {
+        let word_patterns = search_term
+            .split_whitespace()
+            .map(|word| {
+                let escaped_word = word
+                    .replace('\\', "\\\\")
+                    .replace('%', "\\%")
+                    .replace('_', "\\_");
+                format!("%{escaped_word}%")
+            })
+            .collect();
+        Self {
+            search_term,
+            word_patterns,
+        }
+    }
+
+    /// Relevance score of the search document, a higher score is a better match.
+    pub fn relevance(self) -> FullTextRelevance {
+        FullTextRelevance(self)
+    }
+
+    fn walk_sqlite_words<'b>(
+        &'b self,
+        mut out: AstPass<'_, 'b, Sqlite>,
+        separator: &str,
+    ) -> QueryResult<()> {
+        if self.word_patterns.is_empty() {
+            out.push_sql("0");
+            return Ok(());
+        }
+        out.push_sql("(");
+        for (index, word_pattern) in self.word_patterns.iter().enumerate() {
+            if index > 0 {
+                out.push_sql(separator);
+            }
+            out.push_sql("(package_search.document LIKE ");
+            out.push_bind_param::<Text, _>(word_pattern)?;
+            out.push_sql(" ESCAPE '\\')");
+        }
+        out.push_sql(")");
+        Ok(())
+    }
+}
+
+impl Expression for FullTextMatch {
+    type SqlType = Bool;
+}
+
+impl<QS> AppearsOnTable<QS> for FullTextMatch where package_search::document: AppearsOnTable<QS> {}
+
+impl<QS> SelectableExpression<QS> for FullTextMatch where
+    package_search::document: SelectableExpression<QS>
+{
+}
+
+impl ValidGrouping<()> for FullTextMatch {
+    type IsAggregate = is_aggregate::Never;
+}
+
+impl QueryFragment<Mysql> for FullTextMatch {
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Mysql>) -> QueryResult<()> {
+        out.push_sql("MATCH(package_search.document) AGAINST (");
+        out.push_bind_param::<Text, _>(&self.search_term)?;
+        out.push_sql(" IN NATURAL LANGUAGE MODE)");
+        Ok(())
+    }
+}
+
+impl QueryFragment<Pg> for FullTextMatch {
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        out.push_sql(POSTGRESQL_DOCUMENT_VECTOR);
+        out.push_sql(" @@ ");
+        out.push_sql(POSTGRESQL_QUERY_START);
+        out.push_bind_param::<Text, _>(&self.search_term)?;
+        out.push_sql(POSTGRESQL_QUERY_END);
+        Ok(())
+    }
+}
+
+impl QueryFragment<Sqlite> for FullTextMatch {
+    fn walk_ast<'b>(&'b self, out: AstPass<'_, 'b, Sqlite>) -> QueryResult<()> {
+        self.walk_sqlite_words(out, " OR ")
+    }
+}
+
+impl QueryFragment<MultiBackend> for FullTextMatch {
+    fn walk_ast<'b>(&'b self, out: AstPass<'_, 'b, MultiBackend>) -> QueryResult<()> {
+        MultiBackend::walk_variant_ast(self, out)
+    }
+}
+
+/// On SQLite the score is the number of words of the search term found in the document.
+#[derive(Debug, Clone, QueryId)]
+pub struct FullTextRelevance(FullTextMatch);
+
+impl QueryFragment<Mysql> for FullTextRelevance {
+    fn walk_ast<'b>(&'b self, out: AstPass<'_, 'b, Mysql>) -> QueryResult<()> {
+        self.0.walk_ast(out)
+    }
+}
+
+impl QueryFragment<Pg> for FullTextRelevance {
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        out.push_sql("ts_rank(");
+        out.push_sql(POSTGRESQL_DOCUMENT_VECTOR);
+        out.push_sql(", ");
+        out.push_sql(POSTGRESQL_QUERY_START);
+        out.push_bind_param::<Text, _>(&self.0.search_term)?;
+        out.push_sql(POSTGRESQL_QUERY_END);
+        out.push_sql(")");
+        Ok(())
+    }
+}
+
+impl QueryFragment<Sqlite> for FullTextRelevance {
+    fn walk_ast<'b>(&'b self, out: AstPass<'_, 'b, Sqlite>) -> QueryResult<()> {
+        self.0.walk_sqlite_words(out, " + ")
+    }
+}
+
+impl QueryFragment<MultiBackend> for FullTextRelevance {
+    fn walk_ast<'b>(&'b self, out: AstPass<'_, 'b, MultiBackend>) -> QueryResult<()> {
+        MultiBackend::walk_variant_ast(self, out)
+    }
+}
+
+/// The text that is indexed for full-text search, it is rewritten on every publish so it always
+/// describes the latest version of the package.
+#[derive(
+    AsChangeset,
+    Queryable,
+    Selectable,
+    Identifiable,
+    Insertable,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    Deserialize,
+    Serialize,
+)]
+#[diesel(table_name = package_search)]
+#[diesel(primary_key(package_id))]
+pub struct PackageSearchDocument {
+    pub package_id: Uuid,
+    pub document: String,
+}
+
+impl PackageSearchDocument {
+    pub fn new(package_id: Uuid, package_metadata: &PackageMetadata, readme_html: &str) -> Self {
+        let mut parts = vec![
+            package_metadata.name.clone(),
+            package_metadata.name.replace(['-', '_'], " "),
+        ];
+        parts.extend(package_metadata.keywords.clone().unwrap_or_default());
+        parts.extend(package_metadata.categories.clone().unwrap_or_default());
+        parts.push(package_metadata.description.clone());
+        parts.push(html_to_text(readme_html));
+
+        Self {
+            package_id,
+            document: parts.join(" "),
+        }
+    }
+
+    pub fn create_or_update(&self, connection: &mut DatabaseConnection) -> QueryResult<usize> {
+        let insert = insert_into(package_search::table).values(self);
+        let document = package_search::document.eq(&self.document);
+        match connection {
+            DatabaseConnection::Mysql(connection) => insert
+                .on_conflict(DuplicatedKeys)
+                .do_update()
+                .set(document)
+                .execute(connection),
+            DatabaseConnection::Postgresql(connection) => insert
+                .on_conflict(package_search::package_id)
+                .do_update()
+                .set(document)
+                .execute(connection),
+            DatabaseConnection::Sqlite(connection) => insert
+                .on_conflict(package_search::package_id)
+                .do_update()
+                .set(document)
+                .execute(connection),
+        }
+    }
+
+    pub fn hard_delete_by_package_id(
+        package_id: Uuid,
+    ) -> HardDeleteById<package_search::package_id, package_search::table> {
+        diesel::delete(package_search::table.filter(package_search::package_id.eq(package_id)))
+    }
+
+    /// Ids of the packages whose search document matches the search term. The query is not boxed
+    /// as a subquery has to be valid SQL for each of the backends.
+    pub fn matching(search_term: String) -> SearchDocumentMatches {
+        package_search::table
+            .select(package_search::package_id)
+            .filter(FullTextMatch::new(search_term))
+    }
+}
+
+fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut inside_tag = false;
+    for character in html.chars() {
+        match character {
+            '<' => {
+                inside_tag = true;
+                text.push(' ');
+            }
+            '>' if inside_tag => inside_tag = false,
+            _ if !inside_tag => text.push(character),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Counts the packages per type and per category, `package_categories` pairs a package id with
+/// a category name and pairs of packages that are not in `packages` are ignored.
+pub fn search_facets(
+    packages: &[Package],
+    package_categories: &[(Uuid, String)],
+) -> SearchFacetsRest {
+    let mut facets = SearchFacetsRest::default();
+    let mut package_ids = HashSet::new();
+    for package in packages {
+        package_ids.insert(package.id);
+        *facets
+            .package_types
+            .entry(package.package_type.clone())
+            .or_default() += 1;
+    }
+    for (package_id, category_name) in package_categories {
+        if package_ids.contains(package_id) {
+            *facets.categories.entry(category_name.clone()).or_default() += 1;
+        }
+    }
+    facets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    fn package(package_type: &str) -> Package {
+        Package {
+            id: Uuid::random(),
+            name: "package".to_string(),
+            package_type: package_type.to_string(),
+            visibility: "public".to_string(),
+            downloads: 0,
+            created_at: NaiveDateTime::MIN,
+            updated_at: NaiveDateTime::MIN,
+            deleted_at: None,
+            deprecation_message: None,
+            replacement_package: None,
+        }
+    }
+
+    #[test]
+    fn readme_html_is_converted_to_text() {
+        assert_eq!(
+            html_to_text("<h1>SQL injection</h1>\n<p>Lab with <code>sqlmap</code></p>"),
+            "SQL injection Lab with sqlmap"
+        );
+    }
+
+    #[test]
+    fn facets_count_types_and_categories_of_matching_packages() {
+        let packages = vec![package("VM"), package("VM"), package("Feature")];
+        let package_categories = vec![
+            (packages[0].id, "web".to_string()),
+            (packages[1].id, "web".to_string()),
+            (packages[2].id, "network".to_string()),
+            (Uuid::random(), "network".to_string()),
+        ];
+
+        let facets = search_facets(&packages, &package_categories);
+
+        assert_eq!(facets.package_types.get("VM"), Some(&2));
+        assert_eq!(facets.package_types.get("Feature"), Some(&1));
+        assert_eq!(facets.categories.get("web"), Some(&2));
+        assert_eq!(facets.categories.get("network"), Some(&1));
+    }
+}
@@ -0,0 +1,344 @@
+use crate::constants::NAIVEDATETIME_DEFAULT_VALUE;
+use crate::models::helpers::uuid::Uuid;
+use crate::services::database::{
+    FilterExistingNotNull, FilterExistingNotNullByIds, HardDeleteById, SoftDeleteById,
+};
+use crate::{
+    schema::{team_members, team_owners, teams},
+    services::database::{All, Create},
+};
+use chrono::NaiveDateTime;
+use diesel::{
+    helper_types::FindBy, insert_into, AsChangeset, ExpressionMethods, Identifiable, Insertable,
+    QueryDsl, Queryable, Selectable, SelectableHelper,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    AsChangeset,
+    Queryable,
+    Identifiable,
+    Selectable,
+    Insertable,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    Deserialize,
+    Serialize,
+    JsonSchema,
+)]
+#[diesel(table_name = teams)]
+#[serde(rename_all = "camelCase")]
+pub struct Team {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub deleted_at: NaiveDateTime,
+}
+
+impl Team {
+    fn all_with_deleted() -> All<teams::table, Self> {
+        teams::table.select(Self::as_select())
+    }
+
+    pub fn all() -> FilterExistingNotNull<All<teams::table, Self>, teams::deleted_at> {
+        Self::all_with_deleted().filter(teams::deleted_at.eq(*NAIVEDATETIME_DEFAULT_VALUE))
+    }
+
+    pub fn by_id(
+        id: Uuid,
+    ) -> FindBy<FilterExistingNotNull<All<teams::table, Self>, teams::deleted_at>, teams::id, Uuid>
+    {
+        Self::all().filter(teams::id.eq(id))
+    }
+
+    pub fn by_ids(
+        ids: Vec<Uuid>,
+    ) -> FilterExistingNotNullByIds<teams::table, teams::id, teams::deleted_at, Self> {
+        Self::all().filter(teams::id.eq_any(ids))
+    }
+
+    pub fn by_name(
+        name: String,
+    ) -> FindBy<
+        FilterExistingNotNull<All<teams::table, Self>, teams::deleted_at>,
+        teams::name,
+        String,
+    > {
+        Self::all().filter(teams::name.eq(name))
+    }
+
+    pub fn soft_delete(&self) -> SoftDeleteById<teams::id, teams::deleted_at, teams::table> {
+        diesel::update(teams::table.filter(teams::id.eq(self.id)))
+            .set(teams::deleted_at.eq(diesel::dsl::now))
+    }
+}
+
+#[derive(
+    Queryable, Selectable, Insertable, Clone, Debug, Eq, PartialEq, Deserialize, Serialize,
+)]
+#[diesel(table_name = teams)]
+#[serde(rename_all = "camelCase")]
+pub struct NewTeam {
+    pub id: Uuid,
+    pub name: String,
+}
+
+impl NewTeam {
+    pub fn new(name: String) -> Self {
+        Self {
+            id: Uuid::random(),
+            name: name.to_lowercase(),
+        }
+    }
+
+    pub fn create_insert(&self) -> Create<&Self, teams::table> {
+        insert_into(teams::table).values(self)
+    }
+}
+
+#[derive(
+    AsChangeset,
+    Queryable,
+    Identifiable,
+    Selectable,
+    Insertable,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    Deserialize,
+    Serialize,
+    JsonSchema,
+)]
+#[diesel(table_name = team_members)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamMember {
+    pub id: Uuid,
+    pub team_id: Uuid,
+    pub email: String,
+    pub is_admin: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub deleted_at: NaiveDateTime,
+}
+
+impl TeamMember {
+    fn all_with_deleted() -> All<team_members::table, Self> {
+        team_members::table.select(Self::as_select())
+    }
+
+    pub fn all() -> FilterExistingNotNull<All<team_members::table, Self>, team_members::deleted_at>
+    {
+        Self::all_with_deleted().filter(team_members::deleted_at.eq(*NAIVEDATETIME_DEFAULT_VALUE))
+    }
+
+    pub fn by_team_id(
+        team_id: Uuid,
+    ) -> FindBy<
+        FilterExistingNotNull<All<team_members::table, Self>, team_members::deleted_at>,
+        team_members::team_id,
+        Uuid,
+    > {
+        Self::all().filter(team_members::team_id.eq(team_id))
+    }
+
+    pub fn by_email(
+        email: String,
+    ) -> FindBy<
+        FilterExistingNotNull<All<team_members::table, Self>, team_members::deleted_at>,
+        team_members::email,
+        String,
+    > {
+        Self::all().filter(team_members::email.eq(email))
+    }
+
+    pub fn soft_delete(
+        &self,
+    ) -> SoftDeleteById<team_members::id, team_members::deleted_at, team_members::table> {
+        diesel::update(team_members::table.filter(team_members::id.eq(self.id)))
+            .set(team_members::deleted_at.eq(diesel::dsl::now))
+    }
+}
+
+#[derive(
+    Queryable, Selectable, Insertable, Clone, Debug, Eq, PartialEq, Deserialize, Serialize,
+)]
+#[diesel(table_name = team_members)]
+#[serde(rename_all = "camelCase")]
+pub struct NewTeamMember {
+    pub id: Uuid,
+    pub team_id: Uuid,
+    pub email: String,
+    pub is_admin: bool,
+}
+
+impl NewTeamMember {
+    pub fn new(team_id: Uuid, email: String, is_admin: bool) -> Self {
+        Self {
+            id: Uuid::random(),
+            team_id,
+            email: email.to_ascii_lowercase(),
+            is_admin,
+        }
+    }
+
+    pub fn create_insert(&self) -> Create<&Self, team_members::table> {
+        insert_into(team_members::table).values(self)
+    }
+}
+
+#[derive(
+    AsChangeset,
+    Queryable,
+    Identifiable,
+    Selectable,
+    Insertable,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    Deserialize,
+    Serialize,
+)]
+#[diesel(table_name = team_owners)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamOwner {
+    pub id: Uuid,
+    pub team_id: Uuid,
+    pub package_id: Uuid,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub deleted_at: NaiveDateTime,
+}
+
+impl TeamOwner {
+    fn all_with_deleted() -> All<team_owners::table, Self> {
+        team_owners::table.select(Self::as_select())
+    }
+
+    pub fn all() -> FilterExistingNotNull<All<team_owners::table, Self>, team_owners::deleted_at> {
+        Self::all_with_deleted().filter(team_owners::deleted_at.eq(*NAIVEDATETIME_DEFAULT_VALUE))
+    }
+
+    pub fn by_package_id(
+        package_id: Uuid,
+    ) -> FindBy<
+        FilterExistingNotNull<All<team_owners::table, Self>, team_owners::deleted_at>,
+        team_owners::package_id,
+        Uuid,
+    > {
+        Self::all().filter(team_owners::package_id.eq(package_id))
+    }
+
+    pub fn by_team_id(
+        team_id: Uuid,
+    ) -> FindBy<
+        FilterExistingNotNull<All<team_owners::table, Self>, team_owners::deleted_at>,
+        team_owners::team_id,
+        Uuid,
+    > {
+        Self::all().filter(team_owners::team_id.eq(team_id))
+    }
+
+    pub fn by_team_ids(
+        team_ids: Vec<Uuid>,
+    ) -> FilterExistingNotNullByIds<
+        team_owners::table,
+        team_owners::team_id,
+        team_owners::deleted_at,
+        Self,
+    > {
+        Self::all().filter(team_owners::team_id.eq_any(team_ids))
+    }
+
+    pub fn soft_delete(
+        &self,
+    ) -> SoftDeleteById<team_owners::id, team_owners::deleted_at, team_owners::table> {
+        diesel::update(team_owners::table.filter(team_owners::id.eq(self.id)))
+            .set(team_owners::deleted_at.eq(diesel::dsl::now))
+    }
+
+    pub fn hard_delete_by_package_id(
+        package_id: Uuid,
+    ) -> HardDeleteById<team_owners::package_id, team_owners::table> {
+        diesel::delete(team_owners::table.filter(team_owners::package_id.eq(package_id)))
+    }
+}
+
+#[derive(
+    Queryable, Selectable, Insertable, Clone, Debug, Eq, PartialEq, Deserialize, Serialize,
+)]
+#[diesel(table_name = team_owners)]
+#[serde(rename_all = "camelCase")]
+pub struct NewTeamOwner {
+    pub id: Uuid,
+    pub team_id: Uuid,
+    pub package_id: Uuid,
+}
+
+impl NewTeamOwner {
+    pub fn new(team_id: Uuid, package_id: Uuid) -> Self {
+        Self {
+            id: Uuid::random(),
+            team_id,
+            package_id,
+        }
+    }
+
+    pub fn create_insert(&self) -> Create<&Self, team_owners::table> {
+        insert_into(team_owners::table).values(self)
+    }
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamQuery {
+    /// Name of the new team
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamMemberQuery {
+    /// Email of the new member
+    pub email: String,
+    /// Allow the member to manage the team
+    #[serde(default)]
+    pub admin: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamWithMembers {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    pub members: Vec<TeamMember>,
+}
+
+impl From<(Team, Vec<TeamMember>)> for TeamWithMembers {
+    fn from((team, members): (Team, Vec<TeamMember>)) -> Self {
+        Self {
+            id: team.id,
+            name: team.name,
+            created_at: team.created_at,
+            members,
+        }
+    }
+}
+
+impl TeamWithMembers {
+    pub fn is_member(&self, email: &str) -> bool {
+        self.members.iter().any(|member| member.email == email)
+    }
+
+    pub fn is_admin(&self, email: &str) -> bool {
+        self.members
+            .iter()
+            .any(|member| member.email == email && member.is_admin)
+    }
+}
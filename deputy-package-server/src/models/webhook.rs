@@ -0,0 +1,414 @@
+use crate::models::helpers::uuid::Uuid;
+use crate::schema::{webhook_deliveries, webhooks};
+use crate::services::database::{
+    Create, DatabaseConnection, HardDeleteById, MultiBackend, SoftDeleteById, UpdateById,
+};
+use chrono::{Duration, NaiveDateTime, Utc};
+use deputy_library::rest::{
+    NewWebhookRest, WebhookDeliveryRest, WebhookEvent, WebhookPayloadRest, WebhookRest,
+};
+use diesel::{insert_into, prelude::*, query_dsl::methods::ExecuteDsl};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fmt::{self, Formatter};
+
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Deputy-Signature";
+pub const WEBHOOK_EVENT_HEADER: &str = "X-Deputy-Event";
+pub const WEBHOOK_DELIVERY_HEADER: &str = "X-Deputy-Delivery";
+const MAX_RETRY_DELAY_SECONDS: i64 = 60 * 60;
+const BASE_RETRY_DELAY_SECONDS: i64 = 30;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+}
+
+impl fmt::Display for DeliveryStatus {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(
+    AsChangeset,
+    Queryable,
+    Selectable,
+    Identifiable,
+    Insertable,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    Deserialize,
+    Serialize,
+)]
+#[diesel(table_name = webhooks)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub package_id: Option<Uuid>,
+    pub url: String,
+    pub events: String,
+    pub secret: String,
+    pub created_by: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
+}
+
+impl Webhook {
+    fn all() -> webhooks::BoxedQuery<'static, MultiBackend> {
+        webhooks::table
+            .filter(webhooks::deleted_at.is_null())
+            .into_boxed()
+    }
+
+    /// Global webhooks when no package is given, otherwise the webhooks of that package.
+    pub fn by_package_id(package_id: Option<Uuid>) -> webhooks::BoxedQuery<'static, MultiBackend> {
+        match package_id {
+            Some(package_id) => Self::all().filter(webhooks::package_id.eq(package_id)),
+            None => Self::all().filter(webhooks::package_id.is_null()),
+        }
+    }
+
+    pub fn by_id(
+        id: Uuid,
+        package_id: Option<Uuid>,
+    ) -> webhooks::BoxedQuery<'static, MultiBackend> {
+        Self::by_package_id(package_id).filter(webhooks::id.eq(id))
+    }
+
+    /// Every webhook that is called for events of the package, including the global ones.
+    pub fn subscribed_to_package(package_id: Uuid) -> webhooks::BoxedQuery<'static, MultiBackend> {
+        Self::all().filter(
+            webhooks::package_id
+                .is_null()
+                .or(webhooks::package_id.eq(package_id)),
+        )
+    }
+
+    pub fn event_list(&self) -> Vec<WebhookEvent> {
+        self.events
+            .split(',')
+            .filter_map(|event| event.parse().ok())
+            .collect()
+    }
+
+    pub fn subscribes_to(&self, event: WebhookEvent) -> bool {
+        let events = self.event_list();
+        events.is_empty() || events.contains(&event)
+    }
+
+    pub fn into_rest(self, package_name: Option<String>) -> WebhookRest {
+        WebhookRest {
+            id: self.id.into(),
+            package_name,
+            url: self.url.clone(),
+            events: self.event_list(),
+            created_by: self.created_by,
+            secret: None,
+            created_at: self.created_at,
+        }
+    }
+
+    pub fn soft_delete(
+        &self,
+    ) -> SoftDeleteById<webhooks::id, webhooks::deleted_at, webhooks::table> {
+        diesel::update(webhooks::table.filter(webhooks::id.eq(self.id)))
+            .set(webhooks::deleted_at.eq(diesel::dsl::now))
+    }
+
+    pub fn hard_delete_by_package_id(
+        package_id: Uuid,
+    ) -> HardDeleteById<webhooks::package_id, webhooks::table> {
+        diesel::delete(webhooks::table.filter(webhooks::package_id.eq(package_id)))
+    }
+}
+
+#[derive(Insertable, Clone, Debug, Eq, PartialEq)]
+#[diesel(table_name = webhooks)]
+pub struct NewWebhook {
+    pub id: Uuid,
+    pub package_id: Option<Uuid>,
+    pub url: String,
+    pub events: String,
+    pub secret: String,
+    pub created_by: String,
+}
+
+impl NewWebhook {
+    pub fn new(package_id: Option<Uuid>, new_webhook: NewWebhookRest, created_by: String) -> Self {
+        Self {
+            id: Uuid::random(),
+            package_id,
+            url: new_webhook.url,
+            events: join_events(&new_webhook.events),
+            secret: new_webhook.secret.unwrap_or_else(generate_secret),
+            created_by,
+        }
+    }
+
+    pub fn create_insert(&self) -> Create<&Self, webhooks::table> {
+        insert_into(webhooks::table).values(self)
+    }
+}
+
+#[derive(
+    AsChangeset,
+    Queryable,
+    Selectable,
+    Identifiable,
+    Insertable,
+    Associations,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    Deserialize,
+    Serialize,
+)]
+#[diesel(belongs_to(Webhook, foreign_key = webhook_id))]
+#[diesel(table_name = webhook_deliveries)]
+#[diesel(treat_none_as_null = true)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event: String,
+    pub payload: String,
+    pub status: String,
+    pub attempts: i32,
+    pub response_status: Option<i16>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: NaiveDateTime,
+    pub delivered_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub claimed_until: Option<NaiveDateTime>,
+}
+
+impl WebhookDelivery {
+    pub fn by_webhook_id(
+        webhook_id: Uuid,
+    ) -> webhook_deliveries::BoxedQuery<'static, MultiBackend> {
+        webhook_deliveries::table
+            .filter(webhook_deliveries::webhook_id.eq(webhook_id))
+            .order(webhook_deliveries::created_at.desc())
+            .into_boxed()
+    }
+
+    pub fn hard_delete_by_package_id(
+        package_id: Uuid,
+    ) -> impl RunQueryDsl<DatabaseConnection> + ExecuteDsl<DatabaseConnection> {
+        diesel::delete(
+            webhook_deliveries::table.filter(
+                webhook_deliveries::webhook_id.eq_any(
+                    webhooks::table
+                        .filter(webhooks::package_id.eq(package_id))
+                        .select(webhooks::id),
+                ),
+            ),
+        )
+    }
+
+    pub fn create_update(
+        &self,
+    ) -> UpdateById<webhook_deliveries::id, webhook_deliveries::table, &Self> {
+        diesel::update(webhook_deliveries::table)
+            .filter(webhook_deliveries::id.eq(self.id))
+            .set(self)
+    }
+
+    /// Claims the delivery until `claimed_until` unless it was already claimed by another replica.
+    /// The claim is taken by a single conditional update, the delivery is claimed when exactly
+    /// one row was updated.
+    pub fn claim(
+        &self,
+        claimed_until: NaiveDateTime,
+    ) -> impl RunQueryDsl<DatabaseConnection> + ExecuteDsl<DatabaseConnection> {
+        diesel::update(webhook_deliveries::table)
+            .filter(webhook_deliveries::id.eq(self.id))
+            .filter(webhook_deliveries::status.eq(DeliveryStatus::Pending.as_str()))
+            .filter(
+                webhook_deliveries::claimed_until
+                    .is_null()
+                    .or(webhook_deliveries::claimed_until.le(Utc::now().naive_utc())),
+            )
+            .set(webhook_deliveries::claimed_until.eq(claimed_until))
+    }
+
+    /// Records the outcome of a delivery attempt, a non-2xx response counts as a failure. Failed
+    /// attempts are retried with an exponential backoff until `max_attempts` is reached.
+    pub fn record_attempt(&mut self, outcome: Result<u16, String>, max_attempts: u32) {
+        let now = Utc::now().naive_utc();
+        self.attempts += 1;
+        self.claimed_until = None;
+        match outcome {
+            Ok(status) if (200..300).contains(&status) => {
+                self.status = DeliveryStatus::Delivered.to_string();
+                self.response_status = Some(status as i16);
+                self.last_error = None;
+                self.delivered_at = Some(now);
+                return;
+            }
+            Ok(status) => {
+                self.response_status = Some(status as i16);
+                self.last_error = Some(format!("Webhook responded with status {status}"));
+            }
+            Err(error) => {
+                self.response_status = None;
+                self.last_error = Some(error);
+            }
+        }
+        if self.attempts >= max_attempts as i32 {
+            self.status = DeliveryStatus::Failed.to_string();
+        } else {
+            self.next_attempt_at = now + retry_delay(self.attempts as u32);
+        }
+    }
+}
+
+impl From<WebhookDelivery> for WebhookDeliveryRest {
+    fn from(delivery: WebhookDelivery) -> Self {
+        Self {
+            id: delivery.id.into(),
+            webhook_id: delivery.webhook_id.into(),
+            event: delivery.event.parse().unwrap_or(WebhookEvent::Publish),
+            status: delivery.status,
+            attempts: delivery.attempts as u32,
+            response_status: delivery.response_status.map(|status| status as u16),
+            last_error: delivery.last_error,
+            next_attempt_at: delivery.next_attempt_at,
+            delivered_at: delivery.delivered_at,
+            created_at: delivery.created_at,
+        }
+    }
+}
+
+#[derive(Insertable, Clone, Debug, Eq, PartialEq)]
+#[diesel(table_name = webhook_deliveries)]
+pub struct NewWebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event: String,
+    pub payload: String,
+}
+
+impl NewWebhookDelivery {
+    pub fn new(webhook_id: Uuid, payload: &WebhookPayloadRest) -> serde_json::Result<Self> {
+        Ok(Self {
+            id: Uuid::random(),
+            webhook_id,
+            event: payload.event.to_string(),
+            payload: serde_json::to_string(payload)?,
+        })
+    }
+
+    pub fn create_insert(&self) -> Create<&Self, webhook_deliveries::table> {
+        insert_into(webhook_deliveries::table).values(self)
+    }
+}
+
+pub fn join_events(events: &[WebhookEvent]) -> String {
+    events
+        .iter()
+        .map(|event| event.as_str())
+        .collect::<Vec<&str>>()
+        .join(",")
+}
+
+fn generate_secret() -> String {
+    let secret_bytes = rand::thread_rng().gen::<[u8; 32]>();
+    secret_bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Value of the signature header, the hex encoded HMAC-SHA256 of the request body keyed with the
+/// webhook secret.
+pub fn sign_payload(secret: &str, payload: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(payload.as_bytes());
+    let signature: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("sha256={signature}")
+}
+
+pub fn retry_delay(attempts: u32) -> Duration {
+    let exponent = attempts.saturating_sub(1).min(16);
+    Duration::seconds((BASE_RETRY_DELAY_SECONDS << exponent).min(MAX_RETRY_DELAY_SECONDS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending_delivery() -> WebhookDelivery {
+        WebhookDelivery {
+            id: Uuid::random(),
+            webhook_id: Uuid::random(),
+            event: WebhookEvent::Publish.to_string(),
+            payload: "{}".to_string(),
+            status: DeliveryStatus::Pending.to_string(),
+            attempts: 0,
+            response_status: None,
+            last_error: None,
+            next_attempt_at: Utc::now().naive_utc(),
+            delivered_at: None,
+            created_at: Utc::now().naive_utc(),
+            claimed_until: None,
+        }
+    }
+
+    #[test]
+    fn payload_signature_matches_hmac_sha256() {
+        assert_eq!(
+            sign_payload("Jefe", "what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn retry_delay_backs_off_exponentially() {
+        assert_eq!(retry_delay(1), Duration::seconds(30));
+        assert_eq!(retry_delay(2), Duration::seconds(60));
+        assert_eq!(retry_delay(4), Duration::seconds(240));
+        assert_eq!(retry_delay(20), Duration::seconds(MAX_RETRY_DELAY_SECONDS));
+    }
+
+    #[test]
+    fn failed_delivery_is_retried_until_max_attempts() {
+        let mut delivery = pending_delivery();
+        delivery.record_attempt(Ok(500), 2);
+        assert_eq!(delivery.status, "pending");
+        assert_eq!(delivery.response_status, Some(500));
+        assert!(delivery.next_attempt_at > delivery.created_at);
+
+        delivery.record_attempt(Err("connection refused".to_string()), 2);
+        assert_eq!(delivery.status, "failed");
+        assert_eq!(delivery.attempts, 2);
+
+        let mut delivery = pending_delivery();
+        delivery.record_attempt(Ok(204), 2);
+        assert_eq!(delivery.status, "delivered");
+        assert!(delivery.delivered_at.is_some());
+    }
+}
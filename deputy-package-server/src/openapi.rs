@@ -0,0 +1,801 @@
+use crate::{
+    configuration::Action,
+    middleware::authentication::{jwt::UserInfo, local_token::UserTokenInfo},
+    models::helpers::audit::RequestMetadata,
+    routes::{
+        admin::{delete_package, delete_version, get_audit_logs},
+        apitoken::{
+            create_api_token, create_scoped_api_token, delete_api_token, get_all_api_tokens,
+        },
+        owner::{
+            add_owner, add_team_owner, delete_owner, delete_team_owner, get_all_owners,
+            get_team_owners,
+        },
+        package::{
+            add_package, deprecate_package, download_file, download_package, get_all_categories,
+            get_all_packages, get_all_versions, get_deprecation, get_download_stats,
+            get_package_version, undeprecate_package, update_visibility, yank_version,
+        },
+        team::{
+            add_team_member, create_team, delete_team, get_team, get_user_teams, remove_team_member,
+        },
+        webhook::{create_webhook, delete_webhook, get_webhook_deliveries, get_webhooks},
+    },
+    services::database::Database,
+};
+use actix_files::NamedFile;
+use actix_web::{
+    guard,
+    http::Method,
+    web::{self, Data, Json, Path, Payload, Query},
+    FromRequest, Handler, HttpResponse, Resource, Responder, Route,
+};
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    schema::Schema,
+    JsonSchema,
+};
+use serde_json::{json, Map, Value};
+use std::rc::Rc;
+
+pub const API_PREFIX: &str = "/api/v1";
+const PACKAGE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// How a request is authenticated, API tokens and Keycloak tokens are both sent as
+/// `Authorization: Bearer <token>`. [`crate::routes::api::configure_api`] wraps every route with
+/// the middleware of its authentication.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Authentication {
+    Public,
+    OptionalApiToken,
+    ApiToken,
+    /// API token of an owner of the package whose roles permit the action.
+    PackageOwner(Action),
+    /// API token of a user with the admin role.
+    Admin,
+    Keycloak,
+}
+
+type SchemaFunction = fn(&mut SchemaGenerator) -> Schema;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Content {
+    Text,
+    Binary,
+    Json(SchemaFunction),
+}
+
+/// Response of a handler, documented from the return type of the handler.
+pub trait ApiResponse {
+    /// `None` when the type does not tell the content, the operation has to set it.
+    fn content() -> Option<Content>;
+}
+
+impl<T: JsonSchema> ApiResponse for Json<T> {
+    fn content() -> Option<Content> {
+        Some(Content::Json(SchemaGenerator::subschema_for::<T>))
+    }
+}
+
+impl ApiResponse for String {
+    fn content() -> Option<Content> {
+        Some(Content::Text)
+    }
+}
+
+impl ApiResponse for NamedFile {
+    fn content() -> Option<Content> {
+        Some(Content::Binary)
+    }
+}
+
+impl ApiResponse for HttpResponse {
+    fn content() -> Option<Content> {
+        None
+    }
+}
+
+impl<T: ApiResponse, E> ApiResponse for Result<T, E> {
+    fn content() -> Option<Content> {
+        T::content()
+    }
+}
+
+/// Extractor of a handler argument, the request body and the query parameters are documented
+/// from the extractors of the handler.
+pub trait ApiArgument {
+    fn request_body() -> Option<Content> {
+        None
+    }
+
+    fn query() -> Option<SchemaFunction> {
+        None
+    }
+}
+
+impl<T: JsonSchema> ApiArgument for Json<T> {
+    fn request_body() -> Option<Content> {
+        Some(Content::Json(SchemaGenerator::subschema_for::<T>))
+    }
+}
+
+impl<T: JsonSchema> ApiArgument for Query<T> {
+    fn query() -> Option<SchemaFunction> {
+        Some(T::json_schema)
+    }
+}
+
+impl ApiArgument for Payload {
+    fn request_body() -> Option<Content> {
+        Some(Content::Binary)
+    }
+}
+
+impl<T> ApiArgument for Path<T> {}
+impl<T: ?Sized> ApiArgument for Data<T> {}
+impl<T> ApiArgument for Option<T> {}
+impl ApiArgument for RequestMetadata {}
+impl ApiArgument for UserInfo {}
+impl ApiArgument for UserTokenInfo {}
+
+pub trait ApiArguments {
+    fn request_body() -> Option<Content>;
+    fn query() -> Option<SchemaFunction>;
+}
+
+macro_rules! api_arguments {
+    ($($argument:ident),*) => {
+        impl<$($argument: ApiArgument),*> ApiArguments for ($($argument,)*) {
+            fn request_body() -> Option<Content> {
+                None$(.or_else($argument::request_body))*
+            }
+
+            fn query() -> Option<SchemaFunction> {
+                None$(.or_else($argument::query))*
+            }
+        }
+    };
+}
+
+api_arguments!();
+api_arguments!(A);
+api_arguments!(A, B);
+api_arguments!(A, B, C);
+api_arguments!(A, B, C, D);
+api_arguments!(A, B, C, D, E);
+api_arguments!(A, B, C, D, E, F);
+api_arguments!(A, B, C, D, E, F, G);
+api_arguments!(A, B, C, D, E, F, G, H);
+
+/// One route of the API, `path` is relative to [`API_PREFIX`] and written the way it is
+/// registered with actix. The request body, query parameters and response are documented from
+/// the handler of the route.
+#[derive(Clone)]
+pub struct Operation {
+    pub method: Method,
+    pub path: &'static str,
+    pub operation_id: &'static str,
+    pub tag: &'static str,
+    pub summary: &'static str,
+    pub authentication: Authentication,
+    query: Option<SchemaFunction>,
+    request_body: Option<Content>,
+    response: Option<Content>,
+    route: Rc<dyn Fn() -> Route>,
+}
+
+impl Operation {
+    fn new<F, Args>(
+        method: Method,
+        path: &'static str,
+        operation_id: &'static str,
+        tag: &'static str,
+        summary: &'static str,
+        handler: F,
+    ) -> Self
+    where
+        F: Handler<Args>,
+        F::Output: Responder + ApiResponse + 'static,
+        Args: FromRequest + ApiArguments + 'static,
+    {
+        Self {
+            method,
+            path,
+            operation_id,
+            tag,
+            summary,
+            authentication: Authentication::Public,
+            query: Args::query(),
+            request_body: Args::request_body(),
+            response: F::Output::content(),
+            route: Rc::new(move || web::route().to(handler.clone())),
+        }
+    }
+
+    /// Sets the response of handlers answering with a plain [`HttpResponse`].
+    fn response(mut self, response: Content) -> Self {
+        self.response = Some(response);
+        self
+    }
+
+    fn authentication(mut self, authentication: Authentication) -> Self {
+        self.authentication = authentication;
+        self
+    }
+
+    /// Resource of the route, guarded by the method so that the other methods of the same path
+    /// fall through to their own resources.
+    pub fn resource(&self) -> Resource {
+        web::resource(self.path)
+            .guard(guard::Method(self.method.clone()))
+            .route((self.route)())
+    }
+
+    /// Path in the OpenAPI form, regex constraints like `{tail:.*}` are dropped.
+    pub fn openapi_path(&self) -> String {
+        let path = self
+            .path
+            .split('/')
+            .map(|segment| match segment.split_once(':') {
+                Some((name, _)) if segment.starts_with('{') => format!("{name}}}"),
+                _ => segment.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        format!("{API_PREFIX}{path}")
+    }
+
+    pub fn path_parameters(&self) -> Vec<&'static str> {
+        self.path
+            .split('/')
+            .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+            .map(|parameter| parameter.split(':').next().unwrap_or(parameter))
+            .collect()
+    }
+
+    fn to_json(&self, generator: &mut SchemaGenerator) -> Value {
+        let mut parameters: Vec<Value> = self
+            .path_parameters()
+            .into_iter()
+            .map(|name| {
+                json!({
+                    "name": name,
+                    "in": "path",
+                    "required": true,
+                    "description": path_parameter_description(name),
+                    "schema": string_schema(),
+                })
+            })
+            .collect();
+        if let Some(query) = self.query {
+            parameters.extend(query_parameters(query(generator)));
+        }
+
+        let mut response = json!({ "description": "Successful response" });
+        if let Some(content) = self.response {
+            response["content"] = content_json(content, generator);
+        }
+        let mut operation = json!({
+            "operationId": self.operation_id,
+            "tags": [self.tag],
+            "summary": self.summary,
+            "responses": {
+                "200": response,
+                "default": { "$ref": "#/components/responses/Error" },
+            },
+        });
+        if !parameters.is_empty() {
+            operation["parameters"] = Value::Array(parameters);
+        }
+        if let Some(request_body) = self.request_body {
+            operation["requestBody"] = json!({
+                "required": true,
+                "content": content_json(request_body, generator),
+            });
+        }
+        match self.authentication {
+            Authentication::Public => {}
+            Authentication::OptionalApiToken => {
+                operation["security"] = json!([{}, { "apiToken": [] }]);
+            }
+            Authentication::ApiToken => operation["security"] = json!([{ "apiToken": [] }]),
+            Authentication::PackageOwner(action) => {
+                operation["security"] = json!([{ "apiToken": [] }]);
+                operation["description"] = Value::String(format!(
+                    "Only for the owners of the package whose roles permit `{}`",
+                    action_name(action)
+                ));
+            }
+            Authentication::Admin => {
+                operation["security"] = json!([{ "apiToken": [] }]);
+                operation["description"] = json!("Only for users with the admin role");
+            }
+            Authentication::Keycloak => operation["security"] = json!([{ "keycloak": [] }]),
+        }
+        operation
+    }
+}
+
+fn action_name(action: Action) -> String {
+    serde_json::to_value(action)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_else(|| format!("{action:?}"))
+}
+
+/// Query parameters from the properties of the query struct, the doc comment of a field becomes
+/// the description of its parameter.
+fn query_parameters(schema: Schema) -> Vec<Value> {
+    let schema = serde_json::to_value(schema).unwrap_or_default();
+    let required = schema["required"].as_array().cloned().unwrap_or_default();
+    schema["properties"]
+        .as_object()
+        .map(|properties| {
+            properties
+                .iter()
+                .map(|(name, property)| {
+                    let mut property = property.clone();
+                    let description = property
+                        .as_object_mut()
+                        .and_then(|property| property.remove("description"))
+                        .unwrap_or_else(|| json!(""));
+                    json!({
+                        "name": name,
+                        "in": "query",
+                        "required": required.contains(&json!(name)),
+                        "description": description,
+                        "schema": property,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn path_parameter_description(name: &str) -> &'static str {
+    match name {
+        "package_name" => "Name of the package",
+        "version" => "Version of the package",
+        "tail" => "Path of the file inside the package",
+        "set_yank" => "`true` to yank the version, `false` to restore it",
+        "visibility" => "`public`, `authenticated` or `owners_only`",
+        "webhook_id" => "Id of the webhook",
+        "owner_email" => "Email of the owner",
+        "team_name" => "Name of the team",
+        "email" => "Email of the team member",
+        "token_id" => "Id of the API token",
+        _ => "",
+    }
+}
+
+fn content_json(content: Content, generator: &mut SchemaGenerator) -> Value {
+    match content {
+        Content::Text => json!({ "text/plain": { "schema": string_schema() } }),
+        Content::Binary => json!({
+            "application/octet-stream": { "schema": { "type": "string", "format": "binary" } }
+        }),
+        Content::Json(schema) => json!({ "application/json": { "schema": schema(generator) } }),
+    }
+}
+
+fn string_schema() -> Value {
+    json!({ "type": "string" })
+}
+
+/// Every route under [`API_PREFIX`], [`crate::routes::api::configure_api`] registers the routes
+/// in this order so the static segments have to come before the path parameters they would
+/// otherwise match.
+pub fn operations() -> Vec<Operation> {
+    use Authentication::*;
+    use Content::*;
+
+    vec![
+        Operation::new(
+            Method::GET,
+            "/package",
+            "get_all_packages",
+            "package",
+            "Search packages",
+            get_all_packages::<Database>,
+        )
+        .authentication(OptionalApiToken),
+        Operation::new(
+            Method::POST,
+            "/package",
+            "add_package",
+            "package",
+            "Publish a package version",
+            add_package::<Database>,
+        )
+        .response(Text)
+        .authentication(ApiToken),
+        Operation::new(
+            Method::GET,
+            "/package/{package_name}",
+            "get_all_versions",
+            "package",
+            "List the versions of a package",
+            get_all_versions::<Database>,
+        )
+        .authentication(OptionalApiToken),
+        Operation::new(
+            Method::GET,
+            "/package/{package_name}/stats",
+            "get_download_stats",
+            "package",
+            "Download statistics of a package",
+            get_download_stats::<Database>,
+        )
+        .authentication(OptionalApiToken),
+        Operation::new(
+            Method::GET,
+            "/package/{package_name}/webhook",
+            "get_package_webhooks",
+            "webhook",
+            "List the webhooks of a package",
+            get_webhooks::<Database>,
+        )
+        .authentication(PackageOwner(Action::ManageWebhooks)),
+        Operation::new(
+            Method::POST,
+            "/package/{package_name}/webhook",
+            "create_package_webhook",
+            "webhook",
+            "Add a webhook to a package",
+            create_webhook::<Database>,
+        )
+        .authentication(PackageOwner(Action::ManageWebhooks)),
+        Operation::new(
+            Method::DELETE,
+            "/package/{package_name}/webhook/{webhook_id}",
+            "delete_package_webhook",
+            "webhook",
+            "Remove a webhook of a package",
+            delete_webhook::<Database>,
+        )
+        .authentication(PackageOwner(Action::ManageWebhooks)),
+        Operation::new(
+            Method::GET,
+            "/package/{package_name}/webhook/{webhook_id}/delivery",
+            "get_package_webhook_deliveries",
+            "webhook",
+            "List the deliveries of a package webhook",
+            get_webhook_deliveries::<Database>,
+        )
+        .authentication(PackageOwner(Action::ManageWebhooks)),
+        Operation::new(
+            Method::GET,
+            "/package/{package_name}/owner",
+            "get_all_owners",
+            "owner",
+            "List the owners of a package",
+            get_all_owners::<Database>,
+        )
+        .authentication(OptionalApiToken),
+        Operation::new(
+            Method::GET,
+            "/package/{package_name}/owner/team",
+            "get_team_owners",
+            "owner",
+            "List the teams owning a package",
+            get_team_owners::<Database>,
+        )
+        .authentication(OptionalApiToken),
+        Operation::new(
+            Method::POST,
+            "/package/{package_name}/owner",
+            "add_owner",
+            "owner",
+            "Add an owner to a package",
+            add_owner::<Database>,
+        )
+        .authentication(PackageOwner(Action::ManageOwners)),
+        Operation::new(
+            Method::DELETE,
+            "/package/{package_name}/owner/{owner_email}",
+            "delete_owner",
+            "owner",
+            "Remove an owner from a package",
+            delete_owner::<Database>,
+        )
+        .authentication(PackageOwner(Action::ManageOwners)),
+        Operation::new(
+            Method::POST,
+            "/package/{package_name}/owner/team/{team_name}",
+            "add_team_owner",
+            "owner",
+            "Add a team as an owner of a package",
+            add_team_owner::<Database>,
+        )
+        .authentication(PackageOwner(Action::ManageOwners)),
+        Operation::new(
+            Method::DELETE,
+            "/package/{package_name}/owner/team/{team_name}",
+            "delete_team_owner",
+            "owner",
+            "Remove a team from the owners of a package",
+            delete_team_owner::<Database>,
+        )
+        .authentication(PackageOwner(Action::ManageOwners)),
+        Operation::new(
+            Method::GET,
+            "/package/{package_name}/deprecation",
+            "get_deprecation",
+            "package",
+            "Get the deprecation of a package, `null` when the package is not deprecated",
+            get_deprecation::<Database>,
+        )
+        .authentication(OptionalApiToken),
+        Operation::new(
+            Method::PUT,
+            "/package/{package_name}/deprecation",
+            "deprecate_package",
+            "package",
+            "Deprecate a package, its versions can still be downloaded",
+            deprecate_package::<Database>,
+        )
+        .authentication(PackageOwner(Action::DeprecatePackage)),
+        Operation::new(
+            Method::DELETE,
+            "/package/{package_name}/deprecation",
+            "undeprecate_package",
+            "package",
+            "Lift the deprecation of a package",
+            undeprecate_package::<Database>,
+        )
+        .authentication(PackageOwner(Action::DeprecatePackage)),
+        Operation::new(
+            Method::PUT,
+            "/package/{package_name}/visibility/{visibility}",
+            "update_package_visibility",
+            "package",
+            "Change the visibility of a package, publishing a version never widens it",
+            update_visibility::<Database>,
+        )
+        .authentication(PackageOwner(Action::ManageVisibility)),
+        Operation::new(
+            Method::GET,
+            "/package/{package_name}/{version}/download",
+            "download_package",
+            "package",
+            "Download the archive of a package version",
+            download_package::<Database>,
+        )
+        .authentication(OptionalApiToken),
+        Operation::new(
+            Method::GET,
+            "/package/{package_name}/{version}/path/{tail:.*}",
+            "download_file",
+            "package",
+            "Download a single file of a package version",
+            download_file::<Database>,
+        )
+        .response(Binary)
+        .authentication(OptionalApiToken),
+        Operation::new(
+            Method::GET,
+            "/package/{package_name}/{version}",
+            "get_package_version",
+            "package",
+            "Get a package version",
+            get_package_version::<Database>,
+        )
+        .authentication(OptionalApiToken),
+        Operation::new(
+            Method::PUT,
+            "/package/{package_name}/{version}/yank/{set_yank}",
+            "yank_version",
+            "package",
+            "Yank or restore a package version",
+            yank_version::<Database>,
+        )
+        .authentication(PackageOwner(Action::YankVersion)),
+        Operation::new(
+            Method::GET,
+            "/category",
+            "get_all_categories",
+            "package",
+            "List the package categories",
+            get_all_categories::<Database>,
+        ),
+        Operation::new(
+            Method::POST,
+            "/team",
+            "create_team",
+            "team",
+            "Create a team",
+            create_team::<Database>,
+        )
+        .authentication(ApiToken),
+        Operation::new(
+            Method::GET,
+            "/team",
+            "get_user_teams",
+            "team",
+            "List the teams of the user",
+            get_user_teams::<Database>,
+        )
+        .authentication(ApiToken),
+        Operation::new(
+            Method::GET,
+            "/team/{team_name}",
+            "get_team",
+            "team",
+            "Get a team",
+            get_team::<Database>,
+        )
+        .authentication(ApiToken),
+        Operation::new(
+            Method::DELETE,
+            "/team/{team_name}",
+            "delete_team",
+            "team",
+            "Delete a team",
+            delete_team::<Database>,
+        )
+        .authentication(ApiToken),
+        Operation::new(
+            Method::POST,
+            "/team/{team_name}/member",
+            "add_team_member",
+            "team",
+            "Add a member to a team",
+            add_team_member::<Database>,
+        )
+        .authentication(ApiToken),
+        Operation::new(
+            Method::DELETE,
+            "/team/{team_name}/member/{email}",
+            "remove_team_member",
+            "team",
+            "Remove a member from a team",
+            remove_team_member::<Database>,
+        )
+        .authentication(ApiToken),
+        Operation::new(
+            Method::POST,
+            "/token/scoped",
+            "create_scoped_api_token",
+            "token",
+            "Create an API token with a subset of the permissions of the current token",
+            create_scoped_api_token::<Database>,
+        )
+        .authentication(ApiToken),
+        Operation::new(
+            Method::POST,
+            "/token",
+            "create_api_token",
+            "token",
+            "Create an API token",
+            create_api_token::<Database>,
+        )
+        .authentication(Keycloak),
+        Operation::new(
+            Method::GET,
+            "/token",
+            "get_all_api_tokens",
+            "token",
+            "List the API tokens of the user",
+            get_all_api_tokens::<Database>,
+        )
+        .authentication(Keycloak),
+        Operation::new(
+            Method::DELETE,
+            "/token/{token_id}",
+            "delete_api_token",
+            "token",
+            "Delete an API token",
+            delete_api_token::<Database>,
+        )
+        .authentication(Keycloak),
+        Operation::new(
+            Method::GET,
+            "/admin/audit-log",
+            "get_audit_logs",
+            "admin",
+            "Search the audit log",
+            get_audit_logs::<Database>,
+        )
+        .authentication(Admin),
+        Operation::new(
+            Method::GET,
+            "/admin/webhook",
+            "get_webhooks",
+            "admin",
+            "List the webhooks called for every package",
+            get_webhooks::<Database>,
+        )
+        .authentication(Admin),
+        Operation::new(
+            Method::POST,
+            "/admin/webhook",
+            "create_webhook",
+            "admin",
+            "Add a webhook called for every package",
+            create_webhook::<Database>,
+        )
+        .authentication(Admin),
+        Operation::new(
+            Method::DELETE,
+            "/admin/webhook/{webhook_id}",
+            "delete_webhook",
+            "admin",
+            "Remove a webhook called for every package",
+            delete_webhook::<Database>,
+        )
+        .authentication(Admin),
+        Operation::new(
+            Method::GET,
+            "/admin/webhook/{webhook_id}/delivery",
+            "get_webhook_deliveries",
+            "admin",
+            "List the deliveries of a webhook called for every package",
+            get_webhook_deliveries::<Database>,
+        )
+        .authentication(Admin),
+        Operation::new(
+            Method::DELETE,
+            "/admin/package/{package_name}",
+            "delete_package",
+            "admin",
+            "Delete a package with all of its versions",
+            delete_package::<Database>,
+        )
+        .authentication(Admin),
+        Operation::new(
+            Method::DELETE,
+            "/admin/package/{package_name}/{version}",
+            "delete_version",
+            "admin",
+            "Delete a package version",
+            delete_version::<Database>,
+        )
+        .authentication(Admin),
+    ]
+}
+
+/// OpenAPI 3 document of every route under [`API_PREFIX`], the schemas are derived from the
+/// types the handlers take and return.
+pub fn document() -> Value {
+    let mut generator = SchemaSettings::openapi3().into_generator();
+    let mut paths = Map::new();
+    for operation in operations() {
+        let path = paths
+            .entry(operation.openapi_path())
+            .or_insert_with(|| json!({}));
+        path[operation.method.as_str().to_lowercase()] = operation.to_json(&mut generator);
+    }
+    let error_content = content_json(Content::Text, &mut generator);
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Deputy package server API",
+            "version": PACKAGE_VERSION,
+        },
+        "paths": paths,
+        "components": {
+            "schemas": generator.definitions(),
+            "responses": {
+                "Error": {
+                    "description": "Error message, rate limited requests are answered with \
+                        `429 Too Many Requests` and a `Retry-After` header",
+                    "content": error_content,
+                },
+            },
+            "securitySchemes": {
+                "apiToken": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "API token created with `POST /api/v1/token`",
+                },
+                "keycloak": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "bearerFormat": "JWT",
+                    "description": "Access token issued by Keycloak",
+                },
+            },
+        },
+    })
+}
@@ -0,0 +1,190 @@
+use crate::middleware::authentication::local_token::UserTokenInfo;
+use crate::models::helpers::audit::{record_audit_log, RequestMetadata};
+use crate::models::{AuditAction, AuditLogFilter, AuditLogQuery, AuditLogsWithPages, NewAuditLog};
+use crate::services::database::audit::{CreateAuditLog, GetAuditLogs};
+use crate::services::database::package::{DeletePackage, DeleteVersion};
+use crate::services::storage::package_key;
+use crate::{
+    errors::{PackageServerError, ServerResponseError},
+    AppState,
+};
+use actix::{Actor, Handler};
+use actix_web::{
+    web::{Data, Json, Path, Query},
+    Error,
+};
+use anyhow::Result;
+use log::{debug, error};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::str::FromStr;
+
+#[derive(Deserialize, Debug, Default, JsonSchema)]
+pub struct DeleteQuery {
+    /// Remove the data for good instead of marking it deleted
+    #[serde(default)]
+    pub hard: bool,
+}
+
+fn map_delete_error(error: anyhow::Error, fallback: PackageServerError) -> ServerResponseError {
+    match error.downcast_ref::<PackageServerError>() {
+        Some(PackageServerError::DatabaseRecordNotFound) => ServerResponseError(error),
+        _ => ServerResponseError(fallback.into()),
+    }
+}
+
+pub async fn delete_package<T>(
+    path_variables: Path<String>,
+    app_state: Data<AppState<T>>,
+    query: Query<DeleteQuery>,
+    user_info: UserTokenInfo,
+    request_metadata: RequestMetadata,
+) -> Result<Json<String>, Error>
+where
+    T: Actor + Handler<DeletePackage> + Handler<CreateAuditLog>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, DeletePackage>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateAuditLog>,
+{
+    let package_name = path_variables.into_inner();
+    let deleted_package = app_state
+        .database_address
+        .send(DeletePackage {
+            name: package_name.clone(),
+            hard_delete: query.hard,
+        })
+        .await
+        .map_err(|error| {
+            error!("Failed to delete package: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to delete package: {error}");
+            map_delete_error(error, PackageServerError::PackageDelete)
+        })?;
+
+    if query.hard {
+        app_state
+            .storage
+            .delete_package(&package_name)
+            .await
+            .map_err(|error| {
+                error!("Failed to remove package files: {error}");
+                ServerResponseError(PackageServerError::PackageDelete.into())
+            })?;
+    }
+    debug!(
+        "Package {package_name} was deleted (hard delete: {hard_delete})",
+        hard_delete = query.hard
+    );
+    record_audit_log(
+        NewAuditLog::by_token(AuditAction::DeletePackage, &user_info)
+            .package(&package_name, None)
+            .details(format!("hard delete: {}", query.hard)),
+        &request_metadata,
+        &app_state,
+    )
+    .await;
+    Ok(Json(deleted_package))
+}
+
+pub async fn delete_version<T>(
+    path_variables: Path<(String, String)>,
+    app_state: Data<AppState<T>>,
+    query: Query<DeleteQuery>,
+    user_info: UserTokenInfo,
+    request_metadata: RequestMetadata,
+) -> Result<Json<String>, Error>
+where
+    T: Actor + Handler<DeleteVersion> + Handler<CreateAuditLog>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, DeleteVersion>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateAuditLog>,
+{
+    let (package_name, package_version) = path_variables.into_inner();
+    let deleted_version = app_state
+        .database_address
+        .send(DeleteVersion {
+            name: package_name.clone(),
+            version: package_version.clone(),
+            hard_delete: query.hard,
+        })
+        .await
+        .map_err(|error| {
+            error!("Failed to delete version: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to delete version: {error}");
+            map_delete_error(error, PackageServerError::VersionDelete)
+        })?;
+
+    if query.hard {
+        app_state
+            .storage
+            .delete(&package_key(&package_name, &package_version))
+            .await
+            .map_err(|error| {
+                error!("Failed to remove package file: {error}");
+                ServerResponseError(PackageServerError::VersionDelete.into())
+            })?;
+    }
+    debug!(
+        "Version {package_version} of package {package_name} was deleted (hard delete: {hard_delete})",
+        hard_delete = query.hard
+    );
+    record_audit_log(
+        NewAuditLog::by_token(AuditAction::DeleteVersion, &user_info)
+            .package(&package_name, Some(&package_version))
+            .details(format!("hard delete: {}", query.hard)),
+        &request_metadata,
+        &app_state,
+    )
+    .await;
+    Ok(Json(deleted_version))
+}
+
+pub async fn get_audit_logs<T>(
+    app_state: Data<AppState<T>>,
+    query: Query<AuditLogQuery>,
+) -> Result<Json<AuditLogsWithPages>, Error>
+where
+    T: Actor + Handler<GetAuditLogs>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetAuditLogs>,
+{
+    let query = query.into_inner();
+    let action = query
+        .action
+        .as_deref()
+        .map(AuditAction::from_str)
+        .transpose()
+        .map_err(|error| {
+            error!("Failed to parse audit action: {error}");
+            ServerResponseError(
+                PackageServerError::AuditActionParse(query.action.clone().unwrap_or_default())
+                    .into(),
+            )
+        })?;
+    let audit_logs = app_state
+        .database_address
+        .send(GetAuditLogs {
+            filter: AuditLogFilter {
+                actor: query.actor,
+                action,
+                package_name: query.package,
+                since: query.since,
+                until: query.until,
+            },
+            page: query.page as i64,
+            per_page: query.limit as i64,
+        })
+        .await
+        .map_err(|error| {
+            error!("Failed to get audit logs: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to get audit logs: {error}");
+            ServerResponseError(PackageServerError::AuditLogList.into())
+        })?;
+
+    Ok(Json(audit_logs))
+}
@@ -0,0 +1,74 @@
+use crate::{
+    configuration::Configuration,
+    middleware::{
+        authentication::{
+            admin::AdminAuthenticationMiddlewareFactory,
+            jwt::AuthenticationMiddlewareFactory,
+            local_token::{
+                LocalTokenAuthenticationMiddlewareFactory,
+                OptionalLocalTokenAuthenticationMiddlewareFactory,
+            },
+            owner::OwnerAuthenticationMiddlewareFactory,
+            role::RoleAuthenticationMiddlewareFactory,
+        },
+        rate_limit::RateLimitMiddlewareFactory,
+    },
+    openapi::{operations, Authentication, API_PREFIX},
+    services::rate_limit::RateLimiter,
+};
+use actix_web::{
+    middleware::Condition,
+    web::{scope, ServiceConfig},
+};
+use std::sync::Arc;
+
+/// Every `/api` route of the server, the routes are registered from the documented
+/// [`crate::openapi::operations`] and wrapped with the middleware of their authentication.
+pub fn configure_api(
+    configuration: &Configuration,
+    rate_limiter: Arc<RateLimiter>,
+) -> impl FnOnce(&mut ServiceConfig) {
+    let pem_content = configuration.keycloak.pem_content.clone();
+    let role_requirements = configuration.role_requirements.clone();
+    let admin_role = configuration.keycloak.admin_role.clone();
+    let rate_limits_enabled = configuration.rate_limits.enabled;
+
+    move |service_config: &mut ServiceConfig| {
+        let api_scope = scope(API_PREFIX)
+            .configure(|api_config| {
+                for operation in operations() {
+                    let resource = operation.resource();
+                    match operation.authentication {
+                        Authentication::Public => api_config.service(resource),
+                        Authentication::OptionalApiToken => api_config.service(
+                            resource.wrap(OptionalLocalTokenAuthenticationMiddlewareFactory),
+                        ),
+                        Authentication::ApiToken => api_config
+                            .service(resource.wrap(LocalTokenAuthenticationMiddlewareFactory)),
+                        Authentication::PackageOwner(action) => api_config.service(
+                            resource
+                                .wrap(OwnerAuthenticationMiddlewareFactory)
+                                .wrap(RoleAuthenticationMiddlewareFactory::new(
+                                    role_requirements.clone(),
+                                    action,
+                                ))
+                                .wrap(LocalTokenAuthenticationMiddlewareFactory),
+                        ),
+                        Authentication::Admin => api_config.service(
+                            resource
+                                .wrap(AdminAuthenticationMiddlewareFactory(admin_role.clone()))
+                                .wrap(LocalTokenAuthenticationMiddlewareFactory),
+                        ),
+                        Authentication::Keycloak => api_config.service(
+                            resource.wrap(AuthenticationMiddlewareFactory(pem_content.clone())),
+                        ),
+                    };
+                }
+            })
+            .wrap(Condition::new(
+                rate_limits_enabled,
+                RateLimitMiddlewareFactory(rate_limiter),
+            ));
+        service_config.service(api_scope);
+    }
+}
@@ -1,8 +1,15 @@
 use crate::{
     errors::{PackageServerError, ServerResponseError},
-    middleware::authentication::jwt::UserInfo,
-    models::apitoken::{ApiTokenRest, FullApiTokenRest, NewApiTokenRest},
-    services::database::apitoken::{CreateApiToken, DeleteApiToken, GetApiTokens},
+    middleware::authentication::{jwt::UserInfo, local_token::UserTokenInfo},
+    models::{
+        apitoken::{ApiTokenRest, FullApiTokenRest, NewApiToken, NewApiTokenRest},
+        helpers::audit::{record_audit_log, RequestMetadata},
+        AuditAction, NewAuditLog,
+    },
+    services::database::{
+        apitoken::{CreateApiToken, DeleteApiToken, GetApiTokens},
+        audit::CreateAuditLog,
+    },
     AppState,
 };
 use actix::{Actor, Handler};
@@ -11,6 +18,7 @@ use actix_web::{
     Error,
 };
 use anyhow::Result;
+use deputy_library::rest::NewScopedTokenRest;
 use log::error;
 
 pub async fn get_all_api_tokens<T>(
@@ -46,14 +54,18 @@ pub async fn create_api_token<T>(
     app_state: Data<AppState<T>>,
     new_api_token: Json<NewApiTokenRest>,
     user_info: UserInfo,
+    request_metadata: RequestMetadata,
 ) -> Result<Json<FullApiTokenRest>, Error>
 where
-    T: Actor + Handler<CreateApiToken>,
+    T: Actor + Handler<CreateApiToken> + Handler<CreateAuditLog>,
     <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateApiToken>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateAuditLog>,
 {
-    let new_api_token = new_api_token
-        .into_inner()
-        .create_new_token(user_info.id.clone());
+    let token = NewApiTokenRest::generate_token();
+    let new_api_token =
+        new_api_token
+            .into_inner()
+            .create_new_token(user_info.id.clone(), &user_info.roles, &token);
 
     let full_api_token = app_state
         .database_address
@@ -67,17 +79,71 @@ where
             error!("Failed to create token: {error}");
             ServerResponseError(PackageServerError::DatabaseRecordNotFound.into())
         })?;
-    Ok(Json(full_api_token.into()))
+    record_audit_log(
+        NewAuditLog::new(
+            AuditAction::CreateToken,
+            user_info.email.clone().unwrap_or_default(),
+            user_info.id.clone(),
+            None,
+        )
+        .details(full_api_token.name.clone()),
+        &request_metadata,
+        &app_state,
+    )
+    .await;
+    Ok(Json((full_api_token, token).into()))
+}
+
+pub async fn create_scoped_api_token<T>(
+    app_state: Data<AppState<T>>,
+    new_api_token: Json<NewScopedTokenRest>,
+    user_info: UserTokenInfo,
+    request_metadata: RequestMetadata,
+) -> Result<Json<FullApiTokenRest>, Error>
+where
+    T: Actor + Handler<CreateApiToken> + Handler<CreateAuditLog>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateApiToken>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateAuditLog>,
+{
+    let token = NewApiTokenRest::generate_token();
+    let new_api_token = NewApiToken::derived_from(&user_info, new_api_token.into_inner(), &token)
+        .map_err(|error| {
+        error!("Failed to create scoped token: {error}");
+        ServerResponseError(error.into())
+    })?;
+
+    let full_api_token = app_state
+        .database_address
+        .send(CreateApiToken(new_api_token))
+        .await
+        .map_err(|error| {
+            error!("Failed to create token: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to create token: {error}");
+            ServerResponseError(PackageServerError::DatabaseRecordNotFound.into())
+        })?;
+    record_audit_log(
+        NewAuditLog::by_token(AuditAction::CreateToken, &user_info)
+            .details(full_api_token.name.clone()),
+        &request_metadata,
+        &app_state,
+    )
+    .await;
+    Ok(Json((full_api_token, token).into()))
 }
 
 pub async fn delete_api_token<T>(
     app_state: Data<AppState<T>>,
     path_variables: Path<String>,
     user_info: UserInfo,
+    request_metadata: RequestMetadata,
 ) -> Result<String, Error>
 where
-    T: Actor + Handler<DeleteApiToken>,
+    T: Actor + Handler<DeleteApiToken> + Handler<CreateAuditLog>,
     <T as Actor>::Context: actix::dev::ToEnvelope<T, DeleteApiToken>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateAuditLog>,
 {
     let token_id = path_variables.into_inner();
     let user_id = user_info.id.clone();
@@ -93,5 +159,17 @@ where
             error!("Failed to delete token: {error}");
             ServerResponseError(PackageServerError::DatabaseRecordNotFound.into())
         })?;
+    record_audit_log(
+        NewAuditLog::new(
+            AuditAction::DeleteToken,
+            user_info.email.clone().unwrap_or_default(),
+            user_info.id.clone(),
+            None,
+        )
+        .details(deleted_token_id.clone()),
+        &request_metadata,
+        &app_state,
+    )
+    .await;
     Ok(deleted_token_id)
 }
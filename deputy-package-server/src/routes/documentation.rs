@@ -0,0 +1,36 @@
+use crate::openapi::document;
+use actix_web::{get, http::header::ContentType, HttpResponse};
+
+const API_DOCUMENTATION_PAGE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <title>Deputy package server API</title>
+    <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+  </head>
+  <body>
+    <div id="swagger-ui"></div>
+    <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js" crossorigin></script>
+    <script>
+      window.onload = () => {
+        window.ui = SwaggerUIBundle({
+          url: "/api-docs/openapi.json",
+          dom_id: "#swagger-ui",
+        });
+      };
+    </script>
+  </body>
+</html>
+"##;
+
+#[get("api-docs")]
+pub async fn get_api_documentation() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(API_DOCUMENTATION_PAGE)
+}
+
+#[get("api-docs/openapi.json")]
+pub async fn get_openapi_document() -> HttpResponse {
+    HttpResponse::Ok().json(document())
+}
@@ -0,0 +1,48 @@
+use crate::{
+    services::{
+        database::{CheckDatabaseHealth, GetPoolState},
+        health::HealthChecker,
+    },
+    AppState,
+};
+use actix::{Actor, Handler};
+use actix_web::{web::Data, HttpResponse};
+use deputy_library::rest::{HealthRest, HealthStatus};
+use log::warn;
+
+fn health_response(health: HealthRest) -> HttpResponse {
+    match health.status {
+        HealthStatus::Down => HttpResponse::ServiceUnavailable().json(health),
+        HealthStatus::Up | HealthStatus::Degraded => HttpResponse::Ok().json(health),
+    }
+}
+
+pub async fn get_liveness<T>(
+    app_state: Data<AppState<T>>,
+    health_checker: Data<HealthChecker>,
+) -> HttpResponse
+where
+    T: Actor + Handler<GetPoolState>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPoolState>,
+{
+    let health = health_checker.liveness(&app_state).await;
+    if health.status == HealthStatus::Down {
+        warn!("Liveness check failed: {:?}", health.checks);
+    }
+    health_response(health)
+}
+
+pub async fn get_readiness<T>(
+    app_state: Data<AppState<T>>,
+    health_checker: Data<HealthChecker>,
+) -> HttpResponse
+where
+    T: Actor + Handler<CheckDatabaseHealth>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CheckDatabaseHealth>,
+{
+    let health = health_checker.readiness(&app_state).await;
+    if health.status == HealthStatus::Down {
+        warn!("Readiness check failed: {:?}", health.checks);
+    }
+    health_response(health)
+}
@@ -0,0 +1,65 @@
+use crate::{
+    configuration::MetricsConfiguration,
+    errors::{PackageServerError, ServerResponseError},
+    services::{database::GetPoolState, metrics::DatabaseMetrics},
+    AppState,
+};
+use actix::{Actor, Handler};
+use actix_web::{http::header::AUTHORIZATION, web::Data, Error, HttpRequest, HttpResponse};
+use log::error;
+use sha2::{Digest, Sha256};
+use std::time::Instant;
+
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+pub async fn get_metrics<T>(
+    request: HttpRequest,
+    app_state: Data<AppState<T>>,
+    metrics_configuration: Data<MetricsConfiguration>,
+) -> Result<HttpResponse, Error>
+where
+    T: Actor + Handler<GetPoolState>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPoolState>,
+{
+    if let Some(bearer_token) = &metrics_configuration.bearer_token {
+        // Comparing digests keeps the comparison time independent of the token
+        let is_authorized = request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|header| header.to_str().ok())
+            .and_then(|header| header.strip_prefix("Bearer "))
+            .is_some_and(|token| Sha256::digest(token) == Sha256::digest(bearer_token));
+        if !is_authorized {
+            error!("Metrics requested without a valid bearer token");
+            return Err(ServerResponseError(PackageServerError::NotAuthorized.into()).into());
+        }
+    }
+
+    let mailbox_started = Instant::now();
+    let database_metrics = match app_state.database_address.send(GetPoolState).await {
+        Ok(pool_state) => Some(DatabaseMetrics {
+            mailbox_latency: mailbox_started.elapsed(),
+            connections: pool_state.connections,
+            idle_connections: pool_state.idle_connections,
+            max_connections: pool_state.max_connections,
+        }),
+        Err(error) => {
+            error!("Failed to get the database pool state: {error}");
+            None
+        }
+    };
+    let storage_used_bytes = app_state
+        .storage
+        .used_bytes()
+        .await
+        .unwrap_or_else(|error| {
+            error!("Failed to get the storage usage: {error}");
+            None
+        });
+
+    Ok(HttpResponse::Ok().content_type(METRICS_CONTENT_TYPE).body(
+        app_state
+            .metrics
+            .render(database_metrics, storage_used_bytes),
+    ))
+}
@@ -1,4 +1,11 @@
+pub mod admin;
+pub mod api;
 pub mod apitoken;
 pub mod basic;
+pub mod documentation;
+pub mod health;
+pub mod metrics;
 pub mod owner;
 pub mod package;
+pub mod team;
+pub mod webhook;
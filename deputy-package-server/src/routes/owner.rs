@@ -1,6 +1,17 @@
-use crate::models::Owner;
-use crate::models::{OwnerQuery, Owners};
-use crate::services::database::owner::{AddOwner, DeleteOwner, GetOwners};
+use crate::middleware::authentication::local_token::UserTokenInfo;
+use crate::models::helpers::{
+    audit::{record_audit_log, RequestMetadata},
+    visibility::{authorize_package_read, package_viewer},
+    webhook::enqueue_webhook_event,
+};
+use crate::models::{AuditAction, NewAuditLog, Owner};
+use crate::models::{OwnerQuery, Owners, Team};
+use crate::services::database::audit::CreateAuditLog;
+use crate::services::database::owner::{
+    AddOwner, AddTeamOwner, DeleteOwner, DeleteTeamOwner, GetOwners, GetTeamOwners, IsPackageOwner,
+};
+use crate::services::database::package::GetPackageByName;
+use crate::services::database::webhook::EnqueueWebhookEvent;
 use crate::{
     errors::{PackageServerError, ServerResponseError},
     AppState,
@@ -11,16 +22,21 @@ use actix_web::{
     Error,
 };
 use anyhow::Result;
+use deputy_library::rest::{WebhookEvent, WebhookPayloadRest};
 use log::{debug, error};
 
 pub async fn add_owner<T>(
     path_variables: Path<String>,
     app_state: Data<AppState<T>>,
     query: Query<OwnerQuery>,
+    user_info: UserTokenInfo,
+    request_metadata: RequestMetadata,
 ) -> Result<Json<Owner>, Error>
 where
-    T: Actor + Handler<AddOwner>,
+    T: Actor + Handler<AddOwner> + Handler<CreateAuditLog> + Handler<EnqueueWebhookEvent>,
     <T as Actor>::Context: actix::dev::ToEnvelope<T, AddOwner>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateAuditLog>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, EnqueueWebhookEvent>,
 {
     let package_name = path_variables.into_inner();
     let owner = app_state
@@ -42,18 +58,41 @@ where
         "Added owner: {owner_email} to package: {package_name}",
         owner_email = query.email
     );
+    record_audit_log(
+        NewAuditLog::by_token(AuditAction::AddOwner, &user_info)
+            .package(&package_name, None)
+            .details(owner.email.clone()),
+        &request_metadata,
+        &app_state,
+    )
+    .await;
+    enqueue_webhook_event(
+        WebhookPayloadRest::new(
+            WebhookEvent::OwnerAdd,
+            &package_name,
+            user_info.email.clone(),
+        )
+        .details(owner.email.clone()),
+        &app_state,
+    )
+    .await;
     Ok(Json(owner))
 }
 
 pub async fn get_all_owners<T>(
     path_variables: Path<String>,
     app_state: Data<AppState<T>>,
+    user_info: Option<UserTokenInfo>,
 ) -> Result<Json<Owners>, Error>
 where
-    T: Actor + Handler<GetOwners>,
+    T: Actor + Handler<GetOwners> + Handler<GetPackageByName> + Handler<IsPackageOwner>,
     <T as Actor>::Context: actix::dev::ToEnvelope<T, GetOwners>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackageByName>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, IsPackageOwner>,
 {
     let package_name = path_variables.into_inner();
+    let viewer = package_viewer(&user_info, &app_state.role_requirements);
+    authorize_package_read(package_name.clone(), &viewer, app_state.clone()).await?;
     let owners = app_state
         .database_address
         .send(GetOwners(package_name))
@@ -73,10 +112,14 @@ where
 pub async fn delete_owner<T>(
     path_variables: Path<(String, String)>,
     app_state: Data<AppState<T>>,
+    user_info: UserTokenInfo,
+    request_metadata: RequestMetadata,
 ) -> Result<Json<String>, Error>
 where
-    T: Actor + Handler<DeleteOwner>,
+    T: Actor + Handler<DeleteOwner> + Handler<CreateAuditLog> + Handler<EnqueueWebhookEvent>,
     <T as Actor>::Context: actix::dev::ToEnvelope<T, DeleteOwner>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateAuditLog>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, EnqueueWebhookEvent>,
 {
     let (package_name, owner_email) = path_variables.into_inner();
 
@@ -98,5 +141,169 @@ where
         })?;
 
     debug!("Deleted owner: {owner_email} from package: {package_name}");
+    record_audit_log(
+        NewAuditLog::by_token(AuditAction::RemoveOwner, &user_info)
+            .package(&package_name, None)
+            .details(owner_email.clone()),
+        &request_metadata,
+        &app_state,
+    )
+    .await;
+    enqueue_webhook_event(
+        WebhookPayloadRest::new(
+            WebhookEvent::OwnerRemove,
+            &package_name,
+            user_info.email.clone(),
+        )
+        .details(owner_email.clone()),
+        &app_state,
+    )
+    .await;
     Ok(Json(owner_email))
 }
+
+pub async fn add_team_owner<T>(
+    path_variables: Path<(String, String)>,
+    app_state: Data<AppState<T>>,
+    user_info: UserTokenInfo,
+    request_metadata: RequestMetadata,
+) -> Result<Json<Team>, Error>
+where
+    T: Actor + Handler<AddTeamOwner> + Handler<CreateAuditLog> + Handler<EnqueueWebhookEvent>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, AddTeamOwner>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateAuditLog>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, EnqueueWebhookEvent>,
+{
+    let (package_name, team_name) = path_variables.into_inner();
+    let team = app_state
+        .database_address
+        .send(AddTeamOwner {
+            package_name: package_name.clone(),
+            team_name,
+        })
+        .await
+        .map_err(|error| {
+            error!("Failed to add team owner: {error}");
+            ServerResponseError(PackageServerError::OwnerAdd.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to add team owner: {error}");
+            match error.downcast_ref::<PackageServerError>() {
+                Some(PackageServerError::TeamNotFound) => ServerResponseError(error),
+                _ => ServerResponseError(PackageServerError::OwnerAdd.into()),
+            }
+        })?;
+    debug!(
+        "Added team owner: {team_name} to package: {package_name}",
+        team_name = team.name
+    );
+    record_audit_log(
+        NewAuditLog::by_token(AuditAction::AddTeamOwner, &user_info)
+            .package(&package_name, None)
+            .details(team.name.clone()),
+        &request_metadata,
+        &app_state,
+    )
+    .await;
+    enqueue_webhook_event(
+        WebhookPayloadRest::new(
+            WebhookEvent::OwnerAdd,
+            &package_name,
+            user_info.email.clone(),
+        )
+        .details(team.name.clone()),
+        &app_state,
+    )
+    .await;
+    Ok(Json(team))
+}
+
+pub async fn get_team_owners<T>(
+    path_variables: Path<String>,
+    app_state: Data<AppState<T>>,
+    user_info: Option<UserTokenInfo>,
+) -> Result<Json<Vec<Team>>, Error>
+where
+    T: Actor + Handler<GetTeamOwners> + Handler<GetPackageByName> + Handler<IsPackageOwner>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetTeamOwners>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackageByName>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, IsPackageOwner>,
+{
+    let package_name = path_variables.into_inner();
+    let viewer = package_viewer(&user_info, &app_state.role_requirements);
+    authorize_package_read(package_name.clone(), &viewer, app_state.clone()).await?;
+    let teams = app_state
+        .database_address
+        .send(GetTeamOwners(package_name))
+        .await
+        .map_err(|error| {
+            error!("Failed to get team owners: {error}");
+            ServerResponseError(PackageServerError::OwnersList.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to get team owners: {error}");
+            ServerResponseError(PackageServerError::OwnersList.into())
+        })?;
+
+    Ok(Json(teams))
+}
+
+pub async fn delete_team_owner<T>(
+    path_variables: Path<(String, String)>,
+    app_state: Data<AppState<T>>,
+    user_info: UserTokenInfo,
+    request_metadata: RequestMetadata,
+) -> Result<Json<String>, Error>
+where
+    T: Actor + Handler<DeleteTeamOwner> + Handler<CreateAuditLog> + Handler<EnqueueWebhookEvent>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, DeleteTeamOwner>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateAuditLog>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, EnqueueWebhookEvent>,
+{
+    let (package_name, team_name) = path_variables.into_inner();
+
+    let team_name = app_state
+        .database_address
+        .send(DeleteTeamOwner {
+            package_name: package_name.clone(),
+            team_name,
+        })
+        .await
+        .map_err(|error| {
+            error!("Failed to delete team owner: {error}");
+            ServerResponseError(PackageServerError::OwnerRemove.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to delete team owner: {error}");
+            if error.to_string().contains("last owner") {
+                return ServerResponseError(PackageServerError::OwnerRemoveLast.into());
+            }
+            match error.downcast_ref::<PackageServerError>() {
+                Some(
+                    PackageServerError::TeamNotFound | PackageServerError::DatabaseRecordNotFound,
+                ) => ServerResponseError(error),
+                _ => ServerResponseError(PackageServerError::OwnerRemove.into()),
+            }
+        })?;
+
+    debug!("Deleted team owner: {team_name} from package: {package_name}");
+    record_audit_log(
+        NewAuditLog::by_token(AuditAction::RemoveTeamOwner, &user_info)
+            .package(&package_name, None)
+            .details(team_name.clone()),
+        &request_metadata,
+        &app_state,
+    )
+    .await;
+    enqueue_webhook_event(
+        WebhookPayloadRest::new(
+            WebhookEvent::OwnerRemove,
+            &package_name,
+            user_info.email.clone(),
+        )
+        .details(team_name.clone()),
+        &app_state,
+    )
+    .await;
+    Ok(Json(team_name))
+}
@@ -1,38 +1,55 @@
-use crate::middleware::authentication::local_token::UserTokenInfo;
-use crate::models::helpers::versioning::{
-    get_package_by_name_and_version, get_packages_by_name, validate_version,
+use crate::configuration::Action;
+use crate::middleware::authentication::{local_token::UserTokenInfo, role::authorize_action};
+use crate::models::helpers::{
+    audit::{record_audit_log, RequestMetadata},
+    versioning::{
+        get_package_by_name_and_version, get_packages_by_name, get_published_versions,
+        validate_version,
+    },
+    visibility::{authorize_package_read, package_viewer},
+    webhook::enqueue_webhook_event,
 };
+use crate::services::database::audit::CreateAuditLog;
+use crate::services::database::download::{GetDownloadStats, RecordDownload};
+use crate::services::database::owner::IsPackageOwner;
 use crate::services::database::package::{
-    CreateCategory, CreatePackage, GetAllCategories, GetPackageByNameAndVersion, GetPackages,
-    GetVersionsByPackageName, UpdateVersionMsg,
+    CreateCategory, CreatePackage, GetAllCategories, GetPackageByName, GetPackageByNameAndVersion,
+    GetPackages, GetPublishedVersions, GetVersionsByPackageName, UpdatePackageDeprecation,
+    UpdatePackageVisibility, UpdateVersionMsg,
 };
+use crate::services::database::webhook::EnqueueWebhookEvent;
+use crate::services::storage::{package_key, StoredFile};
 use crate::{
-    constants::{default_limit, default_page},
+    constants::{default_limit, default_page, default_stats_days, MAX_STATS_DAYS},
     errors::{PackageServerError, ServerResponseError},
-    models::{Category, PackagesWithVersionsAndPages},
+    models::{AuditAction, Category, NewAuditLog, PackagesWithVersionsAndPages},
     AppState,
 };
 use actix::{Actor, Handler};
 use actix_files::NamedFile;
 use actix_http::error::PayloadError;
 use actix_web::{
-    web::{Bytes, Data, Json, Path, Payload, Query},
+    web::{block, Bytes, Data, Json, Path, Payload, Query},
     Error, HttpResponse,
 };
 use anyhow::Result;
 use async_stream::try_stream;
+use chrono::{Duration, Utc};
 use deputy_library::{
     archiver::ArchiveStreamer,
-    package::{Package, PackageFile, PackageMetadata},
-    rest::VersionRest,
+    package::{Package, PackageFile, PackageMetadata, PackageSizeError, Visibility},
+    rest::{
+        DeprecationRest, DownloadStatsRest, PackageSort, VersionRest, WebhookEvent,
+        WebhookPayloadRest,
+    },
     validation::{validate_name, validate_version_semantic},
 };
 use futures::{Stream, StreamExt};
 use log::{debug, error};
+use schemars::JsonSchema;
 use semver::{Version, VersionReq};
 use serde::Deserialize;
 use serde_with::{formats::CommaSeparator, StringWithSeparator};
-use std::path::PathBuf;
 
 async fn drain_stream(
     stream: impl Stream<Item = Result<Bytes, PayloadError>> + Unpin + 'static,
@@ -44,60 +61,260 @@ async fn drain_stream(
     Ok(())
 }
 
+async fn fetch_package_file<T>(
+    package_name: &str,
+    package_version: &str,
+    app_state: &AppState<T>,
+) -> Result<StoredFile, Error>
+where
+    T: Actor,
+{
+    app_state
+        .storage
+        .fetch(&package_key(package_name, package_version))
+        .await
+        .map_err(|error| {
+            error!("Failed to fetch the package: {error}");
+            ServerResponseError(PackageServerError::FileNotFound.into())
+        })?
+        .ok_or_else(|| {
+            error!("Package {package_name} version {package_version} not found from the storage");
+            ServerResponseError(PackageServerError::FileNotFound.into()).into()
+        })
+}
+
+/// Versions marked unavailable by the storage scrub are not served, a version without a database
+/// row is left for the storage lookup to reject.
+async fn ensure_version_available<T>(
+    package_name: &str,
+    package_version: &str,
+    app_state: &AppState<T>,
+) -> Result<(), Error>
+where
+    T: Actor + Handler<GetPackageByNameAndVersion>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackageByNameAndVersion>,
+{
+    if let Ok(Ok(version)) = app_state
+        .database_address
+        .send(GetPackageByNameAndVersion {
+            name: package_name.to_string(),
+            version: package_version.to_string(),
+        })
+        .await
+    {
+        if version.is_unavailable {
+            error!("Package {package_name} version {package_version} is marked unavailable");
+            return Err(ServerResponseError(PackageServerError::VersionUnavailable.into()).into());
+        }
+    }
+    Ok(())
+}
+
 pub async fn add_package<T>(
     body: Payload,
     app_state: Data<AppState<T>>,
     user_info: UserTokenInfo,
+    request_metadata: RequestMetadata,
 ) -> Result<HttpResponse, Error>
 where
     T: Actor
+        + Handler<CreateAuditLog>
+        + Handler<EnqueueWebhookEvent>
         + Handler<CreatePackage>
-        + Handler<GetVersionsByPackageName>
+        + Handler<GetPublishedVersions>
         + Handler<GetPackageByNameAndVersion>
         + Handler<GetPackages>
-        + Handler<CreateCategory>,
+        + Handler<CreateCategory>
+        + Handler<GetAllCategories>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateAuditLog>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, EnqueueWebhookEvent>,
     <T as Actor>::Context: actix::dev::ToEnvelope<T, CreatePackage>,
-    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetVersionsByPackageName>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPublishedVersions>,
     <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackageByNameAndVersion>,
     <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackages>,
     <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateCategory>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetAllCategories>,
 {
     let (package_metadata, body) = PackageMetadata::from_stream(body).await.map_err(|error| {
         error!("Failed to parse package metadata: {error}");
         ServerResponseError(PackageServerError::MetadataParse.into())
     })?;
 
-    let versions: Vec<crate::models::Version> =
-        get_packages_by_name(package_metadata.clone().name, app_state.clone()).await?;
-    if let Err(error) = validate_version(package_metadata.version.as_str(), versions) {
+    let published_versions =
+        get_published_versions(package_metadata.clone().name, app_state.clone()).await?;
+    if published_versions.is_package_deleted {
+        error!(
+            "Package {} was deleted, its name can not be published again",
+            package_metadata.name
+        );
+        drain_stream(body).await?;
+        return Err(ServerResponseError(PackageServerError::PackageNameDeleted.into()).into());
+    }
+    let (publish_action, publish_audit_action) = match published_versions.live_versions().is_empty()
+    {
+        true => (Action::PublishPackage, AuditAction::PublishPackage),
+        false => (Action::PublishVersion, AuditAction::PublishVersion),
+    };
+    if let Err(error) = authorize_action(
+        &app_state.role_requirements,
+        publish_action,
+        &user_info,
+        Some(&package_metadata.name),
+    ) {
+        drain_stream(body).await?;
+        return Err(error.into());
+    }
+    if let Err(error) = validate_version(
+        package_metadata.version.as_str(),
+        published_versions.versions,
+    ) {
         drain_stream(body).await?;
         return Err(error);
     }
+    if let Some(categories) = &package_metadata.categories {
+        let is_permitted = app_state
+            .role_requirements
+            .is_permitted(Action::ManageCategories, &user_info.roles);
+        if !is_permitted {
+            let existing_categories = app_state
+                .database_address
+                .send(GetAllCategories)
+                .await
+                .map_err(|error| {
+                    error!("Failed to get categories: {error}");
+                    ServerResponseError(PackageServerError::MailboxError.into())
+                })?
+                .map_err(|error| {
+                    error!("Failed to get categories: {error}");
+                    ServerResponseError(PackageServerError::DatabaseRecordNotFound.into())
+                })?;
+            let has_new_categories = categories.iter().any(|category| {
+                !existing_categories
+                    .iter()
+                    .any(|existing| existing.name == category.to_lowercase())
+            });
+            if has_new_categories {
+                if let Err(error) = authorize_action(
+                    &app_state.role_requirements,
+                    Action::ManageCategories,
+                    &user_info,
+                    Some(&package_metadata.name),
+                ) {
+                    drain_stream(body).await?;
+                    return Err(error.into());
+                }
+            }
+        }
+    }
 
-    let archive_file: PackageFile = PackageFile::from_stream(body).await.map_err(|error| {
-        error!("Failed to save the file: {error}");
-        ServerResponseError(PackageServerError::FileSave.into())
-    })?;
+    let max_package_size = app_state
+        .package_size_limits
+        .max_size(&package_metadata.package_type);
+    if let Some(max_package_size) = max_package_size {
+        if package_metadata.package_size > max_package_size {
+            error!(
+                "Package {} declared size {} exceeds the limit of {max_package_size}",
+                package_metadata.name, package_metadata.package_size
+            );
+            drain_stream(body).await?;
+            return Err(ServerResponseError(
+                PackageServerError::PackageSizeLimitExceeded(max_package_size).into(),
+            )
+            .into());
+        }
+    }
+
+    let archive_file: PackageFile = PackageFile::from_stream(body, max_package_size)
+        .await
+        .map_err(|error| {
+            error!("Failed to save the file: {error}");
+            match error.downcast_ref::<PackageSizeError>() {
+                Some(PackageSizeError::LimitExceeded(max_size)) => ServerResponseError(
+                    PackageServerError::PackageSizeLimitExceeded(*max_size).into(),
+                ),
+                Some(size_error) => ServerResponseError(
+                    PackageServerError::PackageSizeMismatch(size_error.to_string()).into(),
+                ),
+                None => ServerResponseError(PackageServerError::FileSave.into()),
+            }
+        })?;
+    let received_size = archive_file
+        .metadata()
+        .map_err(|error| {
+            error!("Failed to read the size of the file: {error}");
+            ServerResponseError(PackageServerError::FileSave.into())
+        })?
+        .len();
+    if received_size != package_metadata.package_size {
+        error!(
+            "Package {} metadata declares {} bytes but {received_size} bytes were received",
+            package_metadata.name, package_metadata.package_size
+        );
+        return Err(ServerResponseError(
+            PackageServerError::PackageSizeMismatch(format!(
+                "metadata declares {} bytes but {received_size} bytes were received",
+                package_metadata.package_size
+            ))
+            .into(),
+        )
+        .into());
+    }
 
     let mut package = Package::new(package_metadata.clone(), archive_file);
-    package.validate_checksum().map_err(|error| {
+    let extraction_limits = app_state.extraction_limits;
+    let package = block(move || {
+        package.validate_checksum().map_err(|error| {
+            error!("Failed to validate the package: {error}");
+            ServerResponseError(PackageServerError::PackageValidation.into())
+        })?;
+        package
+            .validate_contents(&extraction_limits)
+            .map_err(|error| {
+                error!("Failed to validate the package contents: {error}");
+                ServerResponseError(
+                    PackageServerError::PackageContentInvalid(error.to_string()).into(),
+                )
+            })?;
+        Ok::<Package, ServerResponseError>(package)
+    })
+    .await
+    .map_err(|error| {
         error!("Failed to validate the package: {error}");
         ServerResponseError(PackageServerError::PackageValidation.into())
-    })?;
-    package.save(&app_state.package_folder).map_err(|error| {
+    })??;
+    let archive_path = package.archive_path().map_err(|error| {
         error!("Failed to save the package: {error}");
         ServerResponseError(PackageServerError::PackageSave.into())
     })?;
+    app_state
+        .storage
+        .put(
+            &package_key(&package.metadata.name, &package.metadata.version),
+            archive_path,
+        )
+        .await
+        .map_err(|error| {
+            error!("Failed to save the package: {error}");
+            ServerResponseError(PackageServerError::PackageSave.into())
+        })?;
 
     let package_metadata = package.metadata.clone();
     let readme_html = package_metadata
-        .readme_html(app_state.package_folder.clone().into())
+        .readme_html(archive_path.to_path_buf(), &app_state.extraction_limits)
         .await
         .map_err(|error| {
             error!("Failed to generate the readme html: {error}");
             ServerResponseError(PackageServerError::PackageSave.into())
         })?
         .unwrap_or_default();
+    let publish_audit_log = NewAuditLog::by_token(publish_audit_action, &user_info)
+        .package(&package_metadata.name, Some(&package_metadata.version));
+    let publish_payload = WebhookPayloadRest::new(
+        WebhookEvent::Publish,
+        &package_metadata.name,
+        user_info.email.clone(),
+    )
+    .version(&package_metadata.version);
     let response = app_state
         .database_address
         .send(CreatePackage(
@@ -111,7 +328,12 @@ where
         })?
         .map_err(|error| {
             error!("Failed to add package: {error}");
-            ServerResponseError(PackageServerError::PackageSave.into())
+            match error.downcast_ref::<PackageServerError>() {
+                Some(
+                    PackageServerError::PackageNameDeleted | PackageServerError::VersionConflict(_),
+                ) => ServerResponseError(error),
+                _ => ServerResponseError(PackageServerError::PackageSave.into()),
+            }
         })?;
     let optional_categories = package.metadata.categories;
     if let Some(categories) = optional_categories {
@@ -130,15 +352,27 @@ where
                 })?;
         }
     }
+    app_state.metrics.record_upload(received_size);
+    record_audit_log(publish_audit_log, &request_metadata, &app_state).await;
+    enqueue_webhook_event(publish_payload, &app_state).await;
     Ok(HttpResponse::Ok().body("OK"))
 }
 
 pub async fn download_package<T>(
     path_variables: Path<(String, String)>,
     app_state: Data<AppState<T>>,
+    user_info: Option<UserTokenInfo>,
 ) -> Result<NamedFile, Error>
 where
-    T: Actor,
+    T: Actor
+        + Handler<GetPackageByName>
+        + Handler<GetPackageByNameAndVersion>
+        + Handler<IsPackageOwner>
+        + Handler<RecordDownload>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackageByName>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackageByNameAndVersion>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, IsPackageOwner>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, RecordDownload>,
 {
     let package_name = &path_variables.0;
     let package_version = &path_variables.1;
@@ -151,35 +385,124 @@ where
         error!("Failed to validate the package version: {error}");
         ServerResponseError(PackageServerError::PackageVersionValidation.into())
     })?;
+    let viewer = package_viewer(&user_info, &app_state.role_requirements);
+    authorize_package_read(package_name.to_string(), &viewer, app_state.clone()).await?;
+    ensure_version_available(package_name, package_version, &app_state).await?;
 
-    let package_path = PathBuf::from(app_state.package_folder.clone())
-        .join(Package::normalize_file_path(package_name, package_version));
-    NamedFile::open(package_path).map_err(|error| {
+    let stored_file = fetch_package_file(package_name, package_version, &app_state).await?;
+    let package_file = NamedFile::open(stored_file.path()).map_err(|error| {
         error!("Failed to open the package: {error}");
         Error::from(error)
-    })
+    })?;
+    if let Ok(metadata) = package_file.file().metadata() {
+        app_state.metrics.record_download(metadata.len());
+    }
+
+    match app_state
+        .database_address
+        .send(RecordDownload {
+            name: package_name.to_string(),
+            version: package_version.to_string(),
+        })
+        .await
+    {
+        Ok(Err(error)) => error!("Failed to record download of {package_name}: {error}"),
+        Err(error) => error!("Failed to record download of {package_name}: {error}"),
+        _ => {}
+    }
+
+    Ok(package_file)
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+pub struct DownloadStatsQuery {
+    /// Number of days covered by the daily series, from 1 to 3650
+    #[serde(default = "default_stats_days")]
+    days: u32,
+    /// Only count the downloads of this version
+    #[serde(default)]
+    version: Option<String>,
+}
+
+pub async fn get_download_stats<T>(
+    path_variable: Path<String>,
+    app_state: Data<AppState<T>>,
+    query: Query<DownloadStatsQuery>,
+    user_info: Option<UserTokenInfo>,
+) -> Result<Json<DownloadStatsRest>, Error>
+where
+    T: Actor + Handler<GetPackageByName> + Handler<IsPackageOwner> + Handler<GetDownloadStats>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackageByName>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, IsPackageOwner>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetDownloadStats>,
+{
+    let package_name = path_variable.into_inner();
+    validate_name(package_name.clone()).map_err(|error| {
+        error!("Failed to validate the package name: {error}");
+        ServerResponseError(PackageServerError::PackageNameValidation.into())
+    })?;
+    let viewer = package_viewer(&user_info, &app_state.role_requirements);
+    authorize_package_read(package_name.clone(), &viewer, app_state.clone()).await?;
+
+    let query = query.into_inner();
+    if !(1..=MAX_STATS_DAYS).contains(&query.days) {
+        error!("Download statistics requested for {} days", query.days);
+        return Err(ServerResponseError(
+            PackageServerError::StatsDaysInvalid(MAX_STATS_DAYS).into(),
+        )
+        .into());
+    }
+    let since = Utc::now().date_naive() - Duration::days((query.days - 1).into());
+    let download_stats = app_state
+        .database_address
+        .send(GetDownloadStats {
+            name: package_name,
+            version: query.version,
+            since,
+        })
+        .await
+        .map_err(|error| {
+            error!("Failed to get download stats: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to get download stats: {error}");
+            ServerResponseError(PackageServerError::DatabaseRecordNotFound.into())
+        })?;
+
+    Ok(Json(download_stats))
 }
 
 #[serde_with::serde_as]
 #[serde_with::skip_serializing_none]
-#[derive(Deserialize, Debug, Default)]
+#[derive(Deserialize, Debug, Default, JsonSchema)]
 pub struct SearchQuery {
+    /// Page of the results, starting from 1
     #[serde(default = "default_page")]
     page: u32,
+    /// Number of results per page
     #[serde(default = "default_limit")]
     limit: u32,
+    /// Full text search over the package names, descriptions and other metadata
     #[serde(default)]
     search_term: Option<String>,
+    /// Package type
     #[serde(rename = "type", default)]
     type_param: Option<String>,
+    /// Comma separated category names
     #[serde_as(as = "Option<StringWithSeparator::<CommaSeparator, String>>")]
     #[serde(rename = "categories", default)]
+    #[schemars(with = "Option<String>")]
     category_param: Option<Vec<String>>,
+    /// Order of the results
+    #[serde(default)]
+    sort: PackageSort,
 }
 
 pub async fn get_all_packages<T>(
     app_state: Data<AppState<T>>,
     query: Query<SearchQuery>,
+    user_info: Option<UserTokenInfo>,
 ) -> Result<Json<PackagesWithVersionsAndPages>, Error>
 where
     T: Actor + Handler<GetPackages>,
@@ -196,6 +519,8 @@ where
             categories: optional_package_categories.clone(),
             page: query.page as i64,
             per_page: query.limit as i64,
+            viewer: package_viewer(&user_info, &app_state.role_requirements),
+            sort: query.sort,
         })
         .await
         .map_err(|error| {
@@ -210,8 +535,9 @@ where
     Ok(Json(packages_with_versions_and_pages))
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, JsonSchema)]
 pub struct VersionQuery {
+    /// Semantic version requirement the versions have to match
     pub version_requirement: Option<String>,
 }
 
@@ -219,10 +545,16 @@ pub async fn get_all_versions<T>(
     path_variable: Path<String>,
     app_state: Data<AppState<T>>,
     query: Query<VersionQuery>,
+    user_info: Option<UserTokenInfo>,
 ) -> Result<Json<Vec<VersionRest>>, Error>
 where
-    T: Actor + Handler<GetVersionsByPackageName>,
+    T: Actor
+        + Handler<GetVersionsByPackageName>
+        + Handler<GetPackageByName>
+        + Handler<IsPackageOwner>,
     <T as Actor>::Context: actix::dev::ToEnvelope<T, GetVersionsByPackageName>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackageByName>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, IsPackageOwner>,
 {
     let package_name = path_variable.into_inner();
     validate_name(package_name.to_string()).map_err(|error| {
@@ -242,6 +574,8 @@ where
         },
         None => None,
     };
+    let viewer = package_viewer(&user_info, &app_state.role_requirements);
+    authorize_package_read(package_name.to_string(), &viewer, app_state.clone()).await?;
 
     let packages: Vec<VersionRest> = get_packages_by_name(package_name.to_string(), app_state)
         .await?
@@ -249,7 +583,10 @@ where
         .filter_map(|package| match &version_requirement {
             Some(version_requirement) => {
                 if let Ok(version) = Version::parse(&package.version) {
-                    if version_requirement.matches(&version) && !package.is_yanked {
+                    if version_requirement.matches(&version)
+                        && !package.is_yanked
+                        && !package.is_unavailable
+                    {
                         return Some(package);
                     }
                 }
@@ -265,33 +602,48 @@ where
 pub async fn download_file<T>(
     path_variables: Path<(String, String, String)>,
     app_state: Data<AppState<T>>,
+    user_info: Option<UserTokenInfo>,
 ) -> Result<HttpResponse, Error>
 where
-    T: Actor,
+    T: Actor
+        + Handler<GetPackageByName>
+        + Handler<GetPackageByNameAndVersion>
+        + Handler<IsPackageOwner>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackageByName>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackageByNameAndVersion>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, IsPackageOwner>,
 {
     let package_name = &path_variables.clone().0;
     let package_version = &path_variables.1;
     let file_path_in_package = path_variables.clone().2;
+    let viewer = package_viewer(&user_info, &app_state.role_requirements);
+    authorize_package_read(package_name.to_string(), &viewer, app_state.clone()).await?;
+    ensure_version_available(package_name, package_version, &app_state).await?;
 
-    let package_path = PathBuf::from(&app_state.package_folder)
-        .join(Package::normalize_file_path(package_name, package_version));
+    let stored_file = fetch_package_file(package_name, package_version, &app_state).await?;
 
     let stream = try_stream! {
-        let mut archive = ArchiveStreamer::prepare_archive(package_path).map_err(|error| {
+        let mut archive = ArchiveStreamer::prepare_archive(stored_file.path().to_path_buf()).map_err(|error| {
             error!("Failed to open the package: {error}");
             ServerResponseError(PackageServerError::FileNotFound.into())
         })?;
-        let mut archive_stream = ArchiveStreamer::try_new(&mut archive, file_path_in_package.into())
-            .map_err(|error| {
-                error!("Failed to open the package: {error}");
-                ServerResponseError(PackageServerError::FileNotFound.into())
-            })?
-            .ok_or_else(|| {
-                error!("File not found from the archive");
-                ServerResponseError(PackageServerError::FileNotFound.into())
-            })?;
+        let mut archive_stream = ArchiveStreamer::try_new(
+            &mut archive,
+            file_path_in_package.into(),
+            &app_state.extraction_limits,
+        )
+        .map_err(|error| {
+            error!("Failed to open the package: {error}");
+            ServerResponseError(PackageServerError::FileNotFound.into())
+        })?
+        .ok_or_else(|| {
+            error!("File not found from the archive");
+            ServerResponseError(PackageServerError::FileNotFound.into())
+        })?;
         while let Some(row) = archive_stream.next().await {
-            yield row?;
+            let row = row?;
+            app_state.metrics.record_download(row.len() as u64);
+            yield row;
         }
     };
     let _: &dyn Stream<Item = Result<_, Error>> = &stream;
@@ -302,13 +654,21 @@ where
 pub async fn get_package_version<T>(
     path_variables: Path<(String, String)>,
     app_state: Data<AppState<T>>,
+    user_info: Option<UserTokenInfo>,
 ) -> Result<Json<VersionRest>, Error>
 where
-    T: Actor + Handler<GetPackageByNameAndVersion>,
+    T: Actor
+        + Handler<GetPackageByNameAndVersion>
+        + Handler<GetPackageByName>
+        + Handler<IsPackageOwner>,
     <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackageByNameAndVersion>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackageByName>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, IsPackageOwner>,
 {
     let package_name = &path_variables.0;
     let package_version = &path_variables.1;
+    let viewer = package_viewer(&user_info, &app_state.role_requirements);
+    authorize_package_read(package_name.to_string(), &viewer, app_state.clone()).await?;
 
     let package_version = get_package_by_name_and_version(
         package_name.to_string(),
@@ -324,10 +684,17 @@ pub async fn yank_version<T>(
     path_variables: Path<(String, String, String)>,
     app_state: Data<AppState<T>>,
     user_info: UserTokenInfo,
+    request_metadata: RequestMetadata,
 ) -> Result<Json<crate::models::Version>, Error>
 where
-    T: Actor + Handler<UpdateVersionMsg> + Handler<GetPackageByNameAndVersion>,
+    T: Actor
+        + Handler<UpdateVersionMsg>
+        + Handler<GetPackageByNameAndVersion>
+        + Handler<CreateAuditLog>
+        + Handler<EnqueueWebhookEvent>,
     <T as Actor>::Context: actix::dev::ToEnvelope<T, UpdateVersionMsg>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateAuditLog>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, EnqueueWebhookEvent>,
     <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackageByNameAndVersion>,
 {
     let package_name = &path_variables.0;
@@ -364,9 +731,247 @@ where
         "Package {package_name} was yanked by {owner_email}",
         owner_email = user_info.email
     );
+    app_state.metrics.record_yank(response.is_yanked);
+    let (yank_audit_action, yank_event) = match response.is_yanked {
+        true => (AuditAction::YankVersion, WebhookEvent::Yank),
+        false => (AuditAction::UnyankVersion, WebhookEvent::Unyank),
+    };
+    record_audit_log(
+        NewAuditLog::by_token(yank_audit_action, &user_info)
+            .package(package_name, Some(&response.version)),
+        &request_metadata,
+        &app_state,
+    )
+    .await;
+    enqueue_webhook_event(
+        WebhookPayloadRest::new(yank_event, package_name, user_info.email.clone())
+            .version(&response.version),
+        &app_state,
+    )
+    .await;
     Ok(Json(response))
 }
 
+async fn get_existing_package<T>(
+    package_name: &str,
+    app_state: &Data<AppState<T>>,
+) -> Result<crate::models::Package, Error>
+where
+    T: Actor + Handler<GetPackageByName>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackageByName>,
+{
+    app_state
+        .database_address
+        .send(GetPackageByName(package_name.to_lowercase()))
+        .await
+        .map_err(|error| {
+            error!("Failed to get package: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to get package: {error}");
+            ServerResponseError(PackageServerError::DatabaseQueryFailed.into())
+        })?
+        .ok_or_else(|| {
+            error!("Package {package_name} not found");
+            ServerResponseError(PackageServerError::DatabaseRecordNotFound.into()).into()
+        })
+}
+
+pub async fn get_deprecation<T>(
+    path_variable: Path<String>,
+    app_state: Data<AppState<T>>,
+    user_info: Option<UserTokenInfo>,
+) -> Result<Json<Option<DeprecationRest>>, Error>
+where
+    T: Actor + Handler<GetPackageByName> + Handler<IsPackageOwner>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackageByName>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, IsPackageOwner>,
+{
+    let package_name = path_variable.into_inner();
+    let viewer = package_viewer(&user_info, &app_state.role_requirements);
+    authorize_package_read(package_name.to_string(), &viewer, app_state.clone()).await?;
+    let package = get_existing_package(&package_name, &app_state).await?;
+    Ok(Json(package.deprecation()))
+}
+
+/// The replacement has to be another existing package, the deprecation is returned as stored.
+pub async fn deprecate_package<T>(
+    path_variable: Path<String>,
+    app_state: Data<AppState<T>>,
+    deprecation: Json<DeprecationRest>,
+    user_info: UserTokenInfo,
+    request_metadata: RequestMetadata,
+) -> Result<Json<Option<DeprecationRest>>, Error>
+where
+    T: Actor
+        + Handler<GetPackageByName>
+        + Handler<UpdatePackageDeprecation>
+        + Handler<CreateAuditLog>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackageByName>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, UpdatePackageDeprecation>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateAuditLog>,
+{
+    let package_name = path_variable.into_inner();
+    let package = get_existing_package(&package_name, &app_state).await?;
+    let mut deprecation = deprecation.into_inner();
+    deprecation.message = deprecation.message.trim().to_string();
+    if deprecation.message.is_empty() {
+        return Err(ServerResponseError(
+            PackageServerError::DeprecationInvalid("The message is empty".to_string()).into(),
+        )
+        .into());
+    }
+    if let Some(replacement) = &mut deprecation.replacement {
+        *replacement = replacement.to_lowercase();
+        if *replacement == package.name {
+            return Err(ServerResponseError(
+                PackageServerError::DeprecationInvalid(
+                    "A package can not replace itself".to_string(),
+                )
+                .into(),
+            )
+            .into());
+        }
+        if get_existing_package(replacement, &app_state).await.is_err() {
+            return Err(ServerResponseError(
+                PackageServerError::DeprecationInvalid(format!(
+                    "Replacement package {replacement} does not exist"
+                ))
+                .into(),
+            )
+            .into());
+        }
+    }
+    update_deprecation(
+        package,
+        Some(deprecation),
+        app_state,
+        user_info,
+        request_metadata,
+    )
+    .await
+}
+
+pub async fn undeprecate_package<T>(
+    path_variable: Path<String>,
+    app_state: Data<AppState<T>>,
+    user_info: UserTokenInfo,
+    request_metadata: RequestMetadata,
+) -> Result<Json<Option<DeprecationRest>>, Error>
+where
+    T: Actor
+        + Handler<GetPackageByName>
+        + Handler<UpdatePackageDeprecation>
+        + Handler<CreateAuditLog>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackageByName>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, UpdatePackageDeprecation>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateAuditLog>,
+{
+    let package = get_existing_package(&path_variable.into_inner(), &app_state).await?;
+    update_deprecation(package, None, app_state, user_info, request_metadata).await
+}
+
+async fn update_deprecation<T>(
+    package: crate::models::Package,
+    deprecation: Option<DeprecationRest>,
+    app_state: Data<AppState<T>>,
+    user_info: UserTokenInfo,
+    request_metadata: RequestMetadata,
+) -> Result<Json<Option<DeprecationRest>>, Error>
+where
+    T: Actor + Handler<UpdatePackageDeprecation> + Handler<CreateAuditLog>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, UpdatePackageDeprecation>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateAuditLog>,
+{
+    let package = app_state
+        .database_address
+        .send(UpdatePackageDeprecation {
+            package,
+            deprecation,
+        })
+        .await
+        .map_err(|error| {
+            error!("Failed to update package deprecation: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to update package deprecation: {error}");
+            ServerResponseError(PackageServerError::DeprecationUpdate.into())
+        })?;
+    debug!(
+        "Package {package_name} deprecation was updated by {owner_email}",
+        package_name = package.name,
+        owner_email = user_info.email
+    );
+    let deprecation = package.deprecation();
+    let audit_log = match &deprecation {
+        Some(deprecation) => NewAuditLog::by_token(AuditAction::DeprecatePackage, &user_info)
+            .details(deprecation.to_string()),
+        None => NewAuditLog::by_token(AuditAction::UndeprecatePackage, &user_info),
+    };
+    record_audit_log(
+        audit_log.package(&package.name, None),
+        &request_metadata,
+        &app_state,
+    )
+    .await;
+    Ok(Json(deprecation))
+}
+
+/// Publishing only ever narrows the visibility of a package, widening it takes this route.
+pub async fn update_visibility<T>(
+    path_variables: Path<(String, String)>,
+    app_state: Data<AppState<T>>,
+    user_info: UserTokenInfo,
+    request_metadata: RequestMetadata,
+) -> Result<Json<Visibility>, Error>
+where
+    T: Actor
+        + Handler<GetPackageByName>
+        + Handler<UpdatePackageVisibility>
+        + Handler<CreateAuditLog>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetPackageByName>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, UpdatePackageVisibility>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateAuditLog>,
+{
+    let (package_name, visibility) = path_variables.into_inner();
+    let visibility: Visibility = visibility.parse().map_err(|error| {
+        error!("Failed to parse the visibility: {error}");
+        ServerResponseError(PackageServerError::VisibilityParse(visibility).into())
+    })?;
+    let package = get_existing_package(&package_name, &app_state).await?;
+    let package = app_state
+        .database_address
+        .send(UpdatePackageVisibility {
+            package,
+            visibility,
+        })
+        .await
+        .map_err(|error| {
+            error!("Failed to update package visibility: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to update package visibility: {error}");
+            ServerResponseError(PackageServerError::VisibilityUpdate.into())
+        })?;
+    debug!(
+        "Package {package_name} visibility was set to {visibility} by {owner_email}",
+        package_name = package.name,
+        owner_email = user_info.email
+    );
+    record_audit_log(
+        NewAuditLog::by_token(AuditAction::UpdateVisibility, &user_info)
+            .package(&package.name, None)
+            .details(visibility.to_string()),
+        &request_metadata,
+        &app_state,
+    )
+    .await;
+    Ok(Json(package.visibility()))
+}
+
 pub async fn get_all_categories<T>(
     app_state: Data<AppState<T>>,
 ) -> Result<Json<Vec<Category>>, Error>
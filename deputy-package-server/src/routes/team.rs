@@ -0,0 +1,242 @@
+use crate::configuration::Action;
+use crate::middleware::authentication::{local_token::UserTokenInfo, role::authorize_token_scope};
+use crate::models::{TeamMember, TeamMemberQuery, TeamQuery, TeamWithMembers};
+use crate::services::database::team::{
+    AddTeamMember, CreateTeam, DeleteTeam, GetTeam, GetTeamsByMember, RemoveTeamMember,
+};
+use crate::{
+    errors::{PackageServerError, ServerResponseError},
+    AppState,
+};
+use actix::{Actor, Handler};
+use actix_web::{
+    web::{Data, Json, Path, Query},
+    Error,
+};
+use anyhow::Result;
+use log::{debug, error};
+
+fn map_team_error(error: anyhow::Error, fallback: PackageServerError) -> ServerResponseError {
+    match error.downcast_ref::<PackageServerError>() {
+        Some(
+            PackageServerError::TeamExists
+            | PackageServerError::TeamNotFound
+            | PackageServerError::TeamOwnsPackages
+            | PackageServerError::TeamMemberRemoveLastAdmin
+            | PackageServerError::DatabaseRecordNotFound,
+        ) => ServerResponseError(error),
+        _ => ServerResponseError(fallback.into()),
+    }
+}
+
+async fn get_team_with_members<T>(
+    team_name: String,
+    app_state: &Data<AppState<T>>,
+) -> Result<TeamWithMembers, ServerResponseError>
+where
+    T: Actor + Handler<GetTeam>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetTeam>,
+{
+    app_state
+        .database_address
+        .send(GetTeam(team_name.clone()))
+        .await
+        .map_err(|error| {
+            error!("Failed to get team: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to get team: {error}");
+            ServerResponseError(PackageServerError::TeamsList.into())
+        })?
+        .ok_or_else(|| {
+            error!("Team {team_name} not found");
+            ServerResponseError(PackageServerError::TeamNotFound.into())
+        })
+}
+
+async fn require_team_admin<T>(
+    team_name: String,
+    user_info: &UserTokenInfo,
+    app_state: &Data<AppState<T>>,
+) -> Result<(), ServerResponseError>
+where
+    T: Actor + Handler<GetTeam>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetTeam>,
+{
+    authorize_token_scope(user_info, Action::ManageOwners, None)?;
+    let team = get_team_with_members(team_name, app_state).await?;
+    if !team.is_admin(&user_info.email) {
+        error!(
+            "{email} is not an admin of team {team_name}",
+            email = user_info.email,
+            team_name = team.name
+        );
+        return Err(ServerResponseError(
+            PackageServerError::TeamAdminRequired.into(),
+        ));
+    }
+    Ok(())
+}
+
+pub async fn create_team<T>(
+    app_state: Data<AppState<T>>,
+    query: Query<TeamQuery>,
+    user_info: UserTokenInfo,
+) -> Result<Json<TeamWithMembers>, Error>
+where
+    T: Actor + Handler<CreateTeam>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateTeam>,
+{
+    authorize_token_scope(&user_info, Action::ManageOwners, None)?;
+    let team = app_state
+        .database_address
+        .send(CreateTeam {
+            name: query.name.clone(),
+            admin_email: user_info.email.clone(),
+        })
+        .await
+        .map_err(|error| {
+            error!("Failed to create team: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to create team: {error}");
+            map_team_error(error, PackageServerError::TeamCreate)
+        })?;
+    debug!("Created team: {team_name}", team_name = team.name);
+    Ok(Json(team))
+}
+
+pub async fn get_user_teams<T>(
+    app_state: Data<AppState<T>>,
+    user_info: UserTokenInfo,
+) -> Result<Json<Vec<TeamWithMembers>>, Error>
+where
+    T: Actor + Handler<GetTeamsByMember>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetTeamsByMember>,
+{
+    let teams = app_state
+        .database_address
+        .send(GetTeamsByMember(user_info.email.clone()))
+        .await
+        .map_err(|error| {
+            error!("Failed to get teams: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to get teams: {error}");
+            ServerResponseError(PackageServerError::TeamsList.into())
+        })?;
+    Ok(Json(teams))
+}
+
+pub async fn get_team<T>(
+    path_variables: Path<String>,
+    app_state: Data<AppState<T>>,
+) -> Result<Json<TeamWithMembers>, Error>
+where
+    T: Actor + Handler<GetTeam>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetTeam>,
+{
+    let team = get_team_with_members(path_variables.into_inner(), &app_state).await?;
+    Ok(Json(team))
+}
+
+pub async fn delete_team<T>(
+    path_variables: Path<String>,
+    app_state: Data<AppState<T>>,
+    user_info: UserTokenInfo,
+) -> Result<Json<String>, Error>
+where
+    T: Actor + Handler<GetTeam> + Handler<DeleteTeam>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetTeam>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, DeleteTeam>,
+{
+    let team_name = path_variables.into_inner();
+    require_team_admin(team_name.clone(), &user_info, &app_state).await?;
+
+    let team_name = app_state
+        .database_address
+        .send(DeleteTeam(team_name))
+        .await
+        .map_err(|error| {
+            error!("Failed to delete team: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to delete team: {error}");
+            map_team_error(error, PackageServerError::TeamDelete)
+        })?;
+    debug!("Deleted team: {team_name}");
+    Ok(Json(team_name))
+}
+
+pub async fn add_team_member<T>(
+    path_variables: Path<String>,
+    app_state: Data<AppState<T>>,
+    query: Query<TeamMemberQuery>,
+    user_info: UserTokenInfo,
+) -> Result<Json<TeamMember>, Error>
+where
+    T: Actor + Handler<GetTeam> + Handler<AddTeamMember>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetTeam>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, AddTeamMember>,
+{
+    let team_name = path_variables.into_inner();
+    require_team_admin(team_name.clone(), &user_info, &app_state).await?;
+
+    let member = app_state
+        .database_address
+        .send(AddTeamMember {
+            team_name: team_name.clone(),
+            email: query.email.clone(),
+            is_admin: query.admin,
+        })
+        .await
+        .map_err(|error| {
+            error!("Failed to add team member: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to add team member: {error}");
+            map_team_error(error, PackageServerError::TeamMemberAdd)
+        })?;
+    debug!(
+        "Added member: {email} to team: {team_name}",
+        email = member.email
+    );
+    Ok(Json(member))
+}
+
+pub async fn remove_team_member<T>(
+    path_variables: Path<(String, String)>,
+    app_state: Data<AppState<T>>,
+    user_info: UserTokenInfo,
+) -> Result<Json<String>, Error>
+where
+    T: Actor + Handler<GetTeam> + Handler<RemoveTeamMember>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetTeam>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, RemoveTeamMember>,
+{
+    let (team_name, email) = path_variables.into_inner();
+    require_team_admin(team_name.clone(), &user_info, &app_state).await?;
+
+    let email = app_state
+        .database_address
+        .send(RemoveTeamMember {
+            team_name: team_name.clone(),
+            email,
+        })
+        .await
+        .map_err(|error| {
+            error!("Failed to remove team member: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to remove team member: {error}");
+            map_team_error(error, PackageServerError::TeamMemberRemove)
+        })?;
+    debug!("Removed member: {email} from team: {team_name}");
+    Ok(Json(email))
+}
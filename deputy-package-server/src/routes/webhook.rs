@@ -0,0 +1,176 @@
+use crate::middleware::authentication::local_token::UserTokenInfo;
+use crate::models::helpers::uuid::Uuid;
+use crate::services::database::webhook::{
+    CreateWebhook, DeleteWebhook, GetWebhookDeliveries, GetWebhooks,
+};
+use crate::{
+    errors::{PackageServerError, ServerResponseError},
+    AppState,
+};
+use actix::{Actor, Handler};
+use actix_web::{
+    http::Uri,
+    web::{Data, Json, Path},
+    Error,
+};
+use anyhow::Result;
+use deputy_library::rest::{NewWebhookRest, WebhookDeliveryRest, WebhookRest};
+use log::{debug, error};
+use serde::Deserialize;
+
+/// Package webhooks are managed under the package, global webhooks under the admin scope where
+/// the path has no package name.
+#[derive(Deserialize, Debug)]
+pub struct WebhookPath {
+    pub package_name: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WebhookIdPath {
+    pub package_name: Option<String>,
+    pub webhook_id: Uuid,
+}
+
+fn validate_webhook_url(url: &str) -> Result<(), PackageServerError> {
+    let uri = url
+        .parse::<Uri>()
+        .map_err(|error| PackageServerError::WebhookUrlInvalid(error.to_string()))?;
+    match (uri.scheme_str(), uri.host()) {
+        (Some("http" | "https"), Some(_)) => Ok(()),
+        _ => Err(PackageServerError::WebhookUrlInvalid(format!(
+            "{url} is not an absolute http(s) url"
+        ))),
+    }
+}
+
+fn map_webhook_error(error: anyhow::Error, fallback: PackageServerError) -> ServerResponseError {
+    match error.downcast_ref::<PackageServerError>() {
+        Some(PackageServerError::WebhookNotFound) => ServerResponseError(error),
+        _ => ServerResponseError(fallback.into()),
+    }
+}
+
+pub async fn create_webhook<T>(
+    path_variables: Path<WebhookPath>,
+    app_state: Data<AppState<T>>,
+    new_webhook: Json<NewWebhookRest>,
+    user_info: UserTokenInfo,
+) -> Result<Json<WebhookRest>, Error>
+where
+    T: Actor + Handler<CreateWebhook>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, CreateWebhook>,
+{
+    let new_webhook = new_webhook.into_inner();
+    validate_webhook_url(&new_webhook.url).map_err(|error| {
+        error!("Failed to validate webhook url: {error}");
+        ServerResponseError(error.into())
+    })?;
+    let webhook = app_state
+        .database_address
+        .send(CreateWebhook {
+            package_name: path_variables.into_inner().package_name,
+            new_webhook,
+            created_by: user_info.email.clone(),
+        })
+        .await
+        .map_err(|error| {
+            error!("Failed to create webhook: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to create webhook: {error}");
+            ServerResponseError(PackageServerError::WebhookCreate.into())
+        })?;
+    debug!("Created webhook {} for {}", webhook.id, webhook.url);
+
+    Ok(Json(webhook))
+}
+
+pub async fn get_webhooks<T>(
+    path_variables: Path<WebhookPath>,
+    app_state: Data<AppState<T>>,
+) -> Result<Json<Vec<WebhookRest>>, Error>
+where
+    T: Actor + Handler<GetWebhooks>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetWebhooks>,
+{
+    let webhooks = app_state
+        .database_address
+        .send(GetWebhooks {
+            package_name: path_variables.into_inner().package_name,
+        })
+        .await
+        .map_err(|error| {
+            error!("Failed to get webhooks: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to get webhooks: {error}");
+            ServerResponseError(PackageServerError::WebhookList.into())
+        })?;
+
+    Ok(Json(webhooks))
+}
+
+pub async fn delete_webhook<T>(
+    path_variables: Path<WebhookIdPath>,
+    app_state: Data<AppState<T>>,
+) -> Result<Json<String>, Error>
+where
+    T: Actor + Handler<DeleteWebhook>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, DeleteWebhook>,
+{
+    let WebhookIdPath {
+        package_name,
+        webhook_id,
+    } = path_variables.into_inner();
+    app_state
+        .database_address
+        .send(DeleteWebhook {
+            id: webhook_id,
+            package_name,
+        })
+        .await
+        .map_err(|error| {
+            error!("Failed to delete webhook: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to delete webhook: {error}");
+            map_webhook_error(error, PackageServerError::WebhookDelete)
+        })?;
+    debug!("Deleted webhook {webhook_id}");
+
+    Ok(Json(webhook_id.to_string()))
+}
+
+pub async fn get_webhook_deliveries<T>(
+    path_variables: Path<WebhookIdPath>,
+    app_state: Data<AppState<T>>,
+) -> Result<Json<Vec<WebhookDeliveryRest>>, Error>
+where
+    T: Actor + Handler<GetWebhookDeliveries>,
+    <T as Actor>::Context: actix::dev::ToEnvelope<T, GetWebhookDeliveries>,
+{
+    let WebhookIdPath {
+        package_name,
+        webhook_id,
+    } = path_variables.into_inner();
+    let deliveries = app_state
+        .database_address
+        .send(GetWebhookDeliveries {
+            webhook_id,
+            package_name,
+        })
+        .await
+        .map_err(|error| {
+            error!("Failed to get webhook deliveries: {error}");
+            ServerResponseError(PackageServerError::MailboxError.into())
+        })?
+        .map_err(|error| {
+            error!("Failed to get webhook deliveries: {error}");
+            map_webhook_error(error, PackageServerError::WebhookDeliveryList)
+        })?;
+
+    Ok(Json(deliveries))
+}
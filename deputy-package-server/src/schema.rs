@@ -1,5 +1,28 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    audit_logs (id) {
+        #[max_length = 16]
+        id -> Binary,
+        #[max_length = 255]
+        actor_email -> Varchar,
+        actor_id -> Tinytext,
+        #[max_length = 16]
+        token_id -> Nullable<Binary>,
+        #[max_length = 64]
+        action -> Varchar,
+        #[max_length = 255]
+        package_name -> Nullable<Varchar>,
+        #[max_length = 255]
+        package_version -> Nullable<Varchar>,
+        details -> Nullable<Text>,
+        #[max_length = 64]
+        ip_address -> Nullable<Varchar>,
+        user_agent -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     categories (id) {
         #[max_length = 16]
@@ -36,15 +59,67 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    package_search (package_id) {
+        #[max_length = 16]
+        package_id -> Binary,
+        document -> Longtext,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     packages (id) {
         #[max_length = 16]
         id -> Binary,
         name -> Tinytext,
         package_type -> Tinytext,
+        visibility -> Tinytext,
+        downloads -> Bigint,
         created_at -> Timestamp,
         updated_at -> Timestamp,
         deleted_at -> Nullable<Timestamp>,
+        deprecation_message -> Nullable<Text>,
+        replacement_package -> Nullable<Tinytext>,
+    }
+}
+
+diesel::table! {
+    team_members (id) {
+        #[max_length = 16]
+        id -> Binary,
+        #[max_length = 16]
+        team_id -> Binary,
+        email -> Tinytext,
+        is_admin -> Bool,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    team_owners (id) {
+        #[max_length = 16]
+        id -> Binary,
+        #[max_length = 16]
+        team_id -> Binary,
+        #[max_length = 16]
+        package_id -> Binary,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    teams (id) {
+        #[max_length = 16]
+        id -> Binary,
+        name -> Tinytext,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Timestamp,
     }
 }
 
@@ -54,8 +129,18 @@ diesel::table! {
         id -> Binary,
         name -> Tinytext,
         email -> Tinytext,
-        token -> Tinytext,
+        #[max_length = 16]
+        token_prefix -> Varchar,
+        #[max_length = 64]
+        token_salt -> Varchar,
+        #[max_length = 64]
+        token_hash -> Varchar,
         user_id -> Tinytext,
+        roles -> Text,
+        scopes -> Text,
+        package_patterns -> Text,
+        expires_at -> Nullable<Timestamp>,
+        last_used_at -> Nullable<Timestamp>,
         created_at -> Timestamp,
         deleted_at -> Timestamp,
     }
@@ -72,24 +157,88 @@ diesel::table! {
         license -> Text,
         is_yanked -> Bool,
         readme_html -> Longtext,
-        package_size -> Unsigned<Bigint>,
+        package_size -> Bigint,
         checksum -> Text,
         created_at -> Timestamp,
         updated_at -> Timestamp,
         deleted_at -> Nullable<Timestamp>,
+        is_unavailable -> Bool,
+    }
+}
+
+diesel::table! {
+    version_downloads (version_id, download_date) {
+        #[max_length = 16]
+        version_id -> Binary,
+        download_date -> Date,
+        downloads -> Bigint,
+    }
+}
+
+diesel::table! {
+    webhook_deliveries (id) {
+        #[max_length = 16]
+        id -> Binary,
+        #[max_length = 16]
+        webhook_id -> Binary,
+        #[max_length = 64]
+        event -> Varchar,
+        payload -> Text,
+        #[max_length = 16]
+        status -> Varchar,
+        attempts -> Integer,
+        response_status -> Nullable<Smallint>,
+        last_error -> Nullable<Text>,
+        next_attempt_at -> Timestamp,
+        delivered_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        claimed_until -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    webhooks (id) {
+        #[max_length = 16]
+        id -> Binary,
+        #[max_length = 16]
+        package_id -> Nullable<Binary>,
+        url -> Text,
+        events -> Text,
+        #[max_length = 255]
+        secret -> Varchar,
+        #[max_length = 255]
+        created_by -> Varchar,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
     }
 }
 
 diesel::joinable!(owners -> packages (package_id));
 diesel::joinable!(package_categories -> categories (category_id));
 diesel::joinable!(package_categories -> packages (package_id));
+diesel::joinable!(package_search -> packages (package_id));
+diesel::joinable!(team_members -> teams (team_id));
+diesel::joinable!(team_owners -> packages (package_id));
+diesel::joinable!(team_owners -> teams (team_id));
+diesel::joinable!(version_downloads -> versions (version_id));
 diesel::joinable!(versions -> packages (package_id));
+diesel::joinable!(webhook_deliveries -> webhooks (webhook_id));
+diesel::joinable!(webhooks -> packages (package_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    audit_logs,
     categories,
     owners,
     package_categories,
+    package_search,
     packages,
+    team_members,
+    team_owners,
+    teams,
     tokens,
+    version_downloads,
     versions,
+    webhook_deliveries,
+    webhooks,
 );
@@ -0,0 +1,428 @@
+use crate::models::{
+    apitoken::ApiToken, helpers::uuid::Uuid, AuditLog, Category, Owner, Package, PackageCategory,
+    PackageSearchDocument, Team, TeamMember, TeamOwner, Version, VersionDownload, Webhook,
+    WebhookDelivery,
+};
+use crate::schema::{
+    audit_logs, categories, owners, package_categories, package_search, packages, team_members,
+    team_owners, teams, tokens, version_downloads, versions, webhook_deliveries, webhooks,
+};
+use crate::services::{
+    database::DatabaseConnection,
+    scrub::file_checksum,
+    storage::{package_key, Storage},
+};
+use crate::utilities::migration_states;
+use anyhow::{anyhow, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{
+    connection::SimpleConnection, dsl::now, insert_into, BoolExpressionMethods, Connection,
+    ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl, SelectableHelper, Table,
+};
+use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fs::{read, File},
+    io::{Read, Write},
+    path::Path,
+};
+use tar::{Archive, Builder, Header};
+use tempfile::NamedTempFile;
+
+/// Bumped whenever the layout of the backup archive changes.
+pub const BACKUP_FORMAT_VERSION: u32 = 2;
+const MANIFEST_ENTRY: &str = "manifest.json";
+
+fn table_entry(table: &str) -> String {
+    format!("tables/{table}.json")
+}
+
+fn keys_entry(table: &str) -> String {
+    format!("keys/{table}.json")
+}
+
+fn archive_entry(key: &str) -> String {
+    format!("archives/{key}")
+}
+
+/// Declares `BackupTables` with the rows of every table and `BackupKeys` with the primary keys
+/// of every row. The tables are listed in the order they are restored in, so a row only refers to
+/// rows of the tables before it. The expression after a table selects the rows that changed at or
+/// after `since` for an incremental backup.
+macro_rules! backup_tables {
+    ($($table:ident: $model:ty, $key:ty, |$since:ident| $changed:expr;)*) => {
+        #[derive(Debug, Default, Clone)]
+        pub struct BackupTables {
+            $(pub $table: Vec<$model>,)*
+        }
+
+        impl BackupTables {
+            pub fn load(
+                connection: &mut DatabaseConnection,
+                since: Option<NaiveDateTime>,
+            ) -> QueryResult<Self> {
+                Ok(Self {
+                    $($table: {
+                        let mut query = $table::table.select(<$model>::as_select()).into_boxed();
+                        if let Some($since) = since {
+                            query = query.filter($changed);
+                        }
+                        query.load(connection)?
+                    },)*
+                })
+            }
+
+            pub fn row_counts(&self) -> BTreeMap<String, usize> {
+                BTreeMap::from([$((stringify!($table).to_string(), self.$table.len()),)*])
+            }
+
+            fn entries(&self) -> Result<Vec<(String, Vec<u8>)>> {
+                Ok(vec![$(
+                    (table_entry(stringify!($table)), serde_json::to_vec(&self.$table)?),
+                )*])
+            }
+
+            fn read_entries(folder: &Path) -> Result<Self> {
+                Ok(Self {
+                    $($table: serde_json::from_slice(
+                        &read(folder.join(table_entry(stringify!($table))))?,
+                    )?,)*
+                })
+            }
+
+            fn non_empty_tables(connection: &mut DatabaseConnection) -> QueryResult<Vec<&'static str>> {
+                let mut non_empty_tables = Vec::new();
+                $(if $table::table.count().get_result::<i64>(connection)? > 0 {
+                    non_empty_tables.push(stringify!($table));
+                })*
+                Ok(non_empty_tables)
+            }
+
+            /// Updates the rows that already exist and inserts the rest.
+            fn restore(&self, connection: &mut DatabaseConnection) -> QueryResult<()> {
+                $(for row in &self.$table {
+                    if diesel::update(row).set(row).execute(connection)? == 0 {
+                        insert_into($table::table).values(row).execute(connection)?;
+                    }
+                })*
+                Ok(())
+            }
+        }
+
+        /// Primary keys of every row at the time of an incremental backup, the rows missing from
+        /// them were deleted since the backups taken before it.
+        #[derive(Debug, Default, Clone)]
+        pub struct BackupKeys {
+            $(pub $table: Vec<$key>,)*
+        }
+
+        impl BackupKeys {
+            pub fn load(connection: &mut DatabaseConnection) -> QueryResult<Self> {
+                Ok(Self {
+                    $($table: $table::table
+                        .select($table::table.primary_key())
+                        .load(connection)?,)*
+                })
+            }
+
+            fn entries(&self) -> Result<Vec<(String, Vec<u8>)>> {
+                Ok(vec![$(
+                    (keys_entry(stringify!($table)), serde_json::to_vec(&self.$table)?),
+                )*])
+            }
+
+            fn read_entries(folder: &Path) -> Result<Self> {
+                Ok(Self {
+                    $($table: serde_json::from_slice(
+                        &read(folder.join(keys_entry(stringify!($table))))?,
+                    )?,)*
+                })
+            }
+
+            /// Deletes the rows whose keys are missing, in the reverse of the restore order so
+            /// the rows referring to a deleted row are deleted before it.
+            fn delete_missing(&self, connection: &mut DatabaseConnection) -> QueryResult<()> {
+                type Deletion<'a> =
+                    Box<dyn FnOnce(&mut DatabaseConnection) -> QueryResult<()> + 'a>;
+                let deletions: Vec<Deletion> = vec![$(
+                    Box::new(|connection: &mut DatabaseConnection| {
+                        let kept_keys: HashSet<&$key> = self.$table.iter().collect();
+                        let keys: Vec<$key> = $table::table
+                            .select($table::table.primary_key())
+                            .load(connection)?;
+                        for key in keys.into_iter().filter(|key| !kept_keys.contains(key)) {
+                            diesel::delete($table::table.find(key)).execute(connection)?;
+                        }
+                        Ok(())
+                    }),
+                )*];
+                for deletion in deletions.into_iter().rev() {
+                    deletion(connection)?;
+                }
+                Ok(())
+            }
+        }
+    };
+}
+
+backup_tables! {
+    categories: Category, Uuid, |since| categories::updated_at.ge(since);
+    packages: Package, Uuid, |since| packages::updated_at.ge(since);
+    package_categories: PackageCategory, (Uuid, Uuid),
+        |since| package_categories::updated_at.ge(since);
+    versions: Version, Uuid, |since| versions::updated_at.ge(since);
+    version_downloads: VersionDownload, (Uuid, NaiveDate),
+        |since| version_downloads::download_date.ge(since.date());
+    package_search: PackageSearchDocument, Uuid, |since| package_search::updated_at.ge(since);
+    owners: Owner, Uuid, |since| owners::updated_at.ge(since).or(owners::deleted_at.ge(since));
+    teams: Team, Uuid, |since| teams::updated_at.ge(since).or(teams::deleted_at.ge(since));
+    team_members: TeamMember, Uuid,
+        |since| team_members::updated_at.ge(since).or(team_members::deleted_at.ge(since));
+    team_owners: TeamOwner, Uuid,
+        |since| team_owners::updated_at.ge(since).or(team_owners::deleted_at.ge(since));
+    tokens: ApiToken, Uuid, |since| tokens::created_at
+        .ge(since)
+        .or(tokens::last_used_at.ge(since))
+        .or(tokens::deleted_at.ge(since));
+    webhooks: Webhook, Uuid, |since| webhooks::updated_at.ge(since);
+    webhook_deliveries: WebhookDelivery, Uuid, |since| webhook_deliveries::created_at
+        .ge(since)
+        .or(webhook_deliveries::next_attempt_at.ge(since))
+        .or(webhook_deliveries::delivered_at.ge(since));
+    audit_logs: AuditLog, Uuid, |since| audit_logs::created_at.ge(since);
+}
+
+/// Describes the content of a backup archive, every other entry of the archive is listed in
+/// `checksums` with its SHA-256 checksum.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub server_version: String,
+    /// The latest migration of the database, a backup is only restored into the same schema.
+    pub schema_version: String,
+    /// Database time of the snapshot, the `since` of the next incremental backup.
+    pub created_at: NaiveDateTime,
+    /// Set for an incremental backup, which only has the rows changed since then, the keys of
+    /// every row and the archives of the versions published since then.
+    pub since: Option<NaiveDateTime>,
+    pub tables: BTreeMap<String, usize>,
+    pub archives: Vec<String>,
+    /// Versions marked unavailable by the storage scrub, their archives are left out.
+    pub unavailable_archives: Vec<String>,
+    pub checksums: BTreeMap<String, String>,
+}
+
+fn schema_version(connection: &mut DatabaseConnection) -> Result<String> {
+    let states = migration_states(connection).map_err(|error| anyhow!("{error}"))?;
+    if let Some(pending) = states.iter().find(|state| !state.is_applied) {
+        return Err(anyhow!(
+            "Database migration {} is pending, run `migrate` first",
+            pending.name
+        ));
+    }
+    states
+        .last()
+        .map(|state| state.version.clone())
+        .ok_or_else(|| anyhow!("Database has no migrations applied"))
+}
+
+fn append_bytes<W: Write>(builder: &mut Builder<W>, path: &str, content: &[u8]) -> Result<()> {
+    let mut header = Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    builder.append_data(&mut header, path, content)?;
+    Ok(())
+}
+
+fn bytes_checksum(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// Writes the rows of every table and the archives of the stored versions to `backup_path`. The
+/// rows are read in a single transaction and every archive is verified against the checksum of
+/// its version, so the rows and the archives of a backup always match.
+pub async fn create_backup(
+    connection: &mut DatabaseConnection,
+    storage: &dyn Storage,
+    backup_path: &Path,
+    since: Option<NaiveDateTime>,
+) -> Result<BackupManifest> {
+    let schema_version = schema_version(connection)?;
+    let (created_at, tables, keys, stored_versions) = connection.transaction(|connection| {
+        if let DatabaseConnection::Postgresql(connection) = connection {
+            connection.batch_execute("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ")?;
+        }
+        let created_at = diesel::select(now).get_result::<NaiveDateTime>(connection)?;
+        let tables = BackupTables::load(connection, since)?;
+        let keys = match since {
+            Some(_) => Some(BackupKeys::load(connection)?),
+            None => None,
+        };
+        let stored_versions = Version::load_stored(connection)?;
+        QueryResult::Ok((created_at, tables, keys, stored_versions))
+    })?;
+
+    let backup_folder = match backup_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let temporary_file = NamedTempFile::new_in(backup_folder)?;
+    let mut builder = Builder::new(GzEncoder::new(&temporary_file, Compression::default()));
+    let mut manifest = BackupManifest {
+        format_version: BACKUP_FORMAT_VERSION,
+        server_version: env!("CARGO_PKG_VERSION").to_string(),
+        schema_version,
+        created_at,
+        since,
+        tables: tables.row_counts(),
+        archives: Vec::new(),
+        unavailable_archives: Vec::new(),
+        checksums: BTreeMap::new(),
+    };
+    let key_entries = match &keys {
+        Some(keys) => keys.entries()?,
+        None => Vec::new(),
+    };
+    for (path, content) in tables.entries()?.into_iter().chain(key_entries) {
+        append_bytes(&mut builder, &path, &content)?;
+        manifest.checksums.insert(path, bytes_checksum(&content));
+    }
+
+    for (package_name, version) in stored_versions {
+        if since.is_some_and(|since| version.created_at < since) {
+            continue;
+        }
+        let key = package_key(&package_name, &version.version);
+        if version.is_unavailable {
+            manifest.unavailable_archives.push(key);
+            continue;
+        }
+        let stored_file = storage
+            .fetch(&key)
+            .await?
+            .ok_or_else(|| anyhow!("Archive {key} is missing from the storage, run `scrub`"))?;
+        let checksum = file_checksum(stored_file.path().to_path_buf()).await?;
+        if checksum != version.checksum {
+            return Err(anyhow!(
+                "Archive {key} does not match the checksum of its version, run `scrub`"
+            ));
+        }
+        let path = archive_entry(&key);
+        builder.append_path_with_name(stored_file.path(), &path)?;
+        manifest.checksums.insert(path, checksum);
+        manifest.archives.push(key);
+    }
+
+    append_bytes(
+        &mut builder,
+        MANIFEST_ENTRY,
+        &serde_json::to_vec_pretty(&manifest)?,
+    )?;
+    builder.into_inner()?.finish()?;
+    temporary_file.persist(backup_path)?;
+    Ok(manifest)
+}
+
+/// Unpacks the backup into `folder` and checks every entry against the manifest, the manifest is
+/// returned only when the backup is complete and unaltered.
+pub fn verify_backup(backup_path: &Path, folder: &Path) -> Result<BackupManifest> {
+    let mut archive = Archive::new(MultiGzDecoder::new(File::open(backup_path)?));
+    let mut manifest: Option<BackupManifest> = None;
+    let mut unpacked_paths = BTreeSet::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry
+            .path()?
+            .to_str()
+            .ok_or_else(|| anyhow!("Backup has an entry with a non UTF-8 path"))?
+            .to_string();
+        if path == MANIFEST_ENTRY {
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content)?;
+            manifest = Some(serde_json::from_slice(&content)?);
+        } else if entry.unpack_in(folder)? {
+            unpacked_paths.insert(path);
+        } else {
+            return Err(anyhow!("Backup entry {path} points outside of the backup"));
+        }
+    }
+
+    let manifest = manifest.ok_or_else(|| anyhow!("Backup has no {MANIFEST_ENTRY}"))?;
+    if manifest.format_version != BACKUP_FORMAT_VERSION {
+        return Err(anyhow!(
+            "Backup format version {} is not supported, expected {BACKUP_FORMAT_VERSION}",
+            manifest.format_version
+        ));
+    }
+    for path in &unpacked_paths {
+        if !manifest.checksums.contains_key(path) {
+            return Err(anyhow!("Backup entry {path} is not in the manifest"));
+        }
+    }
+    for (path, checksum) in &manifest.checksums {
+        if !unpacked_paths.contains(path) {
+            return Err(anyhow!("Backup entry {path} is missing"));
+        }
+        if &bytes_checksum(&read(folder.join(path))?) != checksum {
+            return Err(anyhow!("Backup entry {path} does not match its checksum"));
+        }
+    }
+    Ok(manifest)
+}
+
+/// Restores a verified backup. A full backup is only restored into an instance without any rows
+/// or archives, an incremental backup is restored on top of the backups taken before it and
+/// deletes the rows deleted since them.
+pub async fn restore_backup(
+    connection: &mut DatabaseConnection,
+    storage: &dyn Storage,
+    backup_path: &Path,
+) -> Result<BackupManifest> {
+    let folder = tempfile::tempdir()?;
+    let manifest = verify_backup(backup_path, folder.path())?;
+    let schema_version = schema_version(connection)?;
+    if manifest.schema_version != schema_version {
+        return Err(anyhow!(
+            "Backup was taken at schema version {} but the database is at {schema_version}",
+            manifest.schema_version
+        ));
+    }
+    let tables = BackupTables::read_entries(folder.path())?;
+    let keys = match manifest.since {
+        Some(_) => Some(BackupKeys::read_entries(folder.path())?),
+        None => None,
+    };
+
+    if manifest.since.is_none() {
+        let non_empty_tables = BackupTables::non_empty_tables(connection)?;
+        if !non_empty_tables.is_empty() {
+            return Err(anyhow!(
+                "Refusing to restore a full backup into a database that is not empty, these tables have rows: {}",
+                non_empty_tables.join(", ")
+            ));
+        }
+        if !storage.keys().await?.is_empty() {
+            return Err(anyhow!(
+                "Refusing to restore a full backup into a storage that is not empty"
+            ));
+        }
+    }
+
+    // Archives go first as on publish, a failed restore leaves orphans and never versions
+    // without archives
+    for key in &manifest.archives {
+        storage
+            .put(key, &folder.path().join(archive_entry(key)))
+            .await?;
+    }
+    connection.write_transaction(|connection| {
+        if let Some(keys) = &keys {
+            keys.delete_missing(connection)?;
+        }
+        tables.restore(connection)
+    })?;
+    Ok(manifest)
+}
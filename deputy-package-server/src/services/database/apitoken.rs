@@ -1,7 +1,9 @@
 use super::Database;
-use crate::models::apitoken::{ApiToken, NewApiToken};
+use crate::models::{
+    apitoken::{ApiToken, NewApiToken},
+    helpers::uuid::Uuid,
+};
 use actix::{Handler, Message, ResponseActFuture, WrapFuture};
-use actix_web::web::block;
 use anyhow::{Ok, Result};
 use diesel::RunQueryDsl;
 
@@ -14,17 +16,17 @@ impl Handler<CreateApiToken> for Database {
 
     fn handle(&mut self, msg: CreateApiToken, _ctx: &mut Self::Context) -> Self::Result {
         let new_api_token = msg.0;
-        let connection_result = self.get_connection();
+        let database = self.clone();
 
         Box::pin(
             async move {
-                let mut connection = connection_result?;
-                let api_token = block(move || {
-                    new_api_token.create_insert().execute(&mut connection)?;
-                    let api_token = ApiToken::by_id(new_api_token.id).first(&mut connection)?;
-                    Ok(api_token)
-                })
-                .await??;
+                let api_token = database
+                    .run(move |mut connection| {
+                        new_api_token.create_insert().execute(&mut connection)?;
+                        let api_token = ApiToken::by_id(new_api_token.id).first(&mut connection)?;
+                        Ok(api_token)
+                    })
+                    .await?;
                 Ok(api_token)
             }
             .into_actor(self),
@@ -42,17 +44,17 @@ impl Handler<GetApiTokens> for Database {
     type Result = ResponseActFuture<Self, Result<Vec<ApiToken>>>;
 
     fn handle(&mut self, get_api_tokens: GetApiTokens, _ctx: &mut Self::Context) -> Self::Result {
-        let connection_result = self.get_connection();
+        let database = self.clone();
 
         Box::pin(
             async move {
-                let mut connection = connection_result?;
-                let api_tokens = block(move || {
-                    let api_tokens =
-                        ApiToken::by_user_id(get_api_tokens.user_id).load(&mut connection)?;
-                    Ok(api_tokens)
-                })
-                .await??;
+                let api_tokens = database
+                    .run(move |mut connection| {
+                        let api_tokens =
+                            ApiToken::by_user_id(get_api_tokens.user_id).load(&mut connection)?;
+                        Ok(api_tokens)
+                    })
+                    .await?;
                 Ok(api_tokens)
             }
             .into_actor(self),
@@ -73,21 +75,20 @@ impl Handler<GetTokenByToken> for Database {
         _ctx: &mut Self::Context,
     ) -> Self::Result {
         let token = get_api_tokens.0;
-        let connection_result = self.get_connection();
+        let database = self.clone();
 
         Box::pin(
             async move {
-                let mut connection = connection_result?;
-                let api_token = block(move || {
-                    let api_tokens = ApiToken::by_token(token).load(&mut connection)?;
-                    let api_token = api_tokens
-                        .into_iter()
-                        .collect::<Vec<ApiToken>>()
-                        .first()
-                        .cloned();
-                    Ok(api_token)
-                })
-                .await??;
+                let api_token = database
+                    .run(move |mut connection| {
+                        let api_tokens: Vec<ApiToken> =
+                            ApiToken::by_token_prefix(&token).load(&mut connection)?;
+                        let api_token = api_tokens
+                            .into_iter()
+                            .find(|api_token| api_token.matches_token(&token));
+                        Ok(api_token)
+                    })
+                    .await?;
                 Ok(api_token)
             }
             .into_actor(self),
@@ -95,6 +96,32 @@ impl Handler<GetTokenByToken> for Database {
     }
 }
 
+#[derive(Message)]
+#[rtype(result = "Result<()>")]
+pub struct UpdateTokenLastUsed(pub Uuid);
+
+impl Handler<UpdateTokenLastUsed> for Database {
+    type Result = ResponseActFuture<Self, Result<()>>;
+
+    fn handle(&mut self, msg: UpdateTokenLastUsed, _ctx: &mut Self::Context) -> Self::Result {
+        let UpdateTokenLastUsed(id) = msg;
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                database
+                    .run(move |mut connection| {
+                        ApiToken::update_last_used(id).execute(&mut connection)?;
+                        Ok(())
+                    })
+                    .await?;
+                Ok(())
+            }
+            .into_actor(self),
+        )
+    }
+}
+
 #[derive(Message)]
 #[rtype(result = "Result<String>")]
 pub struct DeleteApiToken {
@@ -110,26 +137,28 @@ impl Handler<DeleteApiToken> for Database {
         delete_token_info: DeleteApiToken,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        let connection_result = self.get_connection();
+        let database = self.clone();
 
         Box::pin(
             async move {
-                let mut connection = connection_result?;
-                let id = block(move || {
-                    let api_tokens =
-                        ApiToken::by_user_id(delete_token_info.user_id).load(&mut connection)?;
-                    let delete_api_token = api_tokens
-                        .into_iter()
-                        .filter(|api_token| api_token.id.to_string() == delete_token_info.token_id)
-                        .collect::<Vec<ApiToken>>()
-                        .first()
-                        .cloned()
-                        .ok_or(anyhow::anyhow!("Token not found"))?;
-                    delete_api_token.soft_delete().execute(&mut connection)?;
-
-                    Ok(delete_token_info.token_id)
-                })
-                .await??;
+                let id = database
+                    .run(move |mut connection| {
+                        let api_tokens = ApiToken::by_user_id(delete_token_info.user_id)
+                            .load(&mut connection)?;
+                        let delete_api_token = api_tokens
+                            .into_iter()
+                            .filter(|api_token| {
+                                api_token.id.to_string() == delete_token_info.token_id
+                            })
+                            .collect::<Vec<ApiToken>>()
+                            .first()
+                            .cloned()
+                            .ok_or(anyhow::anyhow!("Token not found"))?;
+                        delete_api_token.soft_delete().execute(&mut connection)?;
+
+                        Ok(delete_token_info.token_id)
+                    })
+                    .await?;
 
                 Ok(id)
             }
@@ -0,0 +1,66 @@
+use super::Database;
+use crate::models::{
+    helpers::pagination::*, AuditLog, AuditLogFilter, AuditLogsWithPages, NewAuditLog,
+};
+use actix::{Handler, Message, ResponseActFuture, WrapFuture};
+use anyhow::{Ok, Result};
+use diesel::RunQueryDsl;
+
+#[derive(Message)]
+#[rtype(result = "Result<()>")]
+pub struct CreateAuditLog(pub NewAuditLog);
+
+impl Handler<CreateAuditLog> for Database {
+    type Result = ResponseActFuture<Self, Result<()>>;
+
+    fn handle(&mut self, msg: CreateAuditLog, _ctx: &mut Self::Context) -> Self::Result {
+        let new_audit_log = msg.0;
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                database
+                    .run(move |mut connection| {
+                        new_audit_log.create_insert().execute(&mut connection)?;
+                        Ok(())
+                    })
+                    .await?;
+                Ok(())
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<AuditLogsWithPages>")]
+pub struct GetAuditLogs {
+    pub filter: AuditLogFilter,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+impl Handler<GetAuditLogs> for Database {
+    type Result = ResponseActFuture<Self, Result<AuditLogsWithPages>>;
+
+    fn handle(&mut self, msg: GetAuditLogs, _ctx: &mut Self::Context) -> Self::Result {
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let audit_logs = database
+                    .run(move |mut connection| {
+                        let audit_logs: (Vec<AuditLog>, i64, i64) = AuditLog::filtered(msg.filter)
+                            .paginate(msg.page)
+                            .per_page(msg.per_page)
+                            .order_by("created_at DESC")
+                            .load_and_count_pages(&mut connection)?;
+                        Ok(AuditLogsWithPages::from(audit_logs))
+                    })
+                    .await?;
+                Ok(audit_logs)
+            }
+            .into_actor(self),
+        )
+    }
+}
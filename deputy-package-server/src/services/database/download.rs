@@ -0,0 +1,88 @@
+use super::Database;
+use crate::models::{download_stats, Package, Version, VersionDownload};
+use actix::{Handler, Message, ResponseActFuture, WrapFuture};
+use anyhow::{Ok, Result};
+use chrono::NaiveDate;
+use deputy_library::rest::DownloadStatsRest;
+use diesel::RunQueryDsl;
+
+#[derive(Message)]
+#[rtype(result = "Result<()>")]
+pub struct RecordDownload {
+    pub name: String,
+    pub version: String,
+}
+
+impl Handler<RecordDownload> for Database {
+    type Result = ResponseActFuture<Self, Result<()>>;
+
+    fn handle(&mut self, msg: RecordDownload, _ctx: &mut Self::Context) -> Self::Result {
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                database
+                    .run(move |mut connection| {
+                        connection.write_transaction(|transaction| {
+                            let package: Package =
+                                Package::by_name(msg.name.to_lowercase()).first(transaction)?;
+                            let version: Version =
+                                package.exact_version(msg.version).first(transaction)?;
+                            VersionDownload::today(version.id).create_or_increment(transaction)?;
+                            package.increment_downloads().execute(transaction)?;
+                            Ok(())
+                        })
+                    })
+                    .await?;
+                Ok(())
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<DownloadStatsRest>")]
+pub struct GetDownloadStats {
+    pub name: String,
+    pub version: Option<String>,
+    pub since: NaiveDate,
+}
+
+impl Handler<GetDownloadStats> for Database {
+    type Result = ResponseActFuture<Self, Result<DownloadStatsRest>>;
+
+    fn handle(&mut self, msg: GetDownloadStats, _ctx: &mut Self::Context) -> Self::Result {
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let download_stats = database
+                    .run(move |mut connection| {
+                        let package: Package =
+                            Package::by_name(msg.name.to_lowercase()).first(&mut connection)?;
+                        let mut versions: Vec<Version> =
+                            package.versions().load(&mut connection)?;
+                        if let Some(version) = msg.version {
+                            versions.retain(|package_version| package_version.version == version);
+                        }
+                        let version_downloads: Vec<VersionDownload> =
+                            VersionDownload::by_version_ids(
+                                versions.iter().map(|version| version.id).collect(),
+                            )
+                            .load(&mut connection)?;
+
+                        Ok(download_stats(
+                            package.name,
+                            &versions,
+                            &version_downloads,
+                            msg.since,
+                        ))
+                    })
+                    .await?;
+                Ok(download_stats)
+            }
+            .into_actor(self),
+        )
+    }
+}
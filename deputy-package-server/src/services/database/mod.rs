@@ -1,22 +1,31 @@
 pub(crate) mod apitoken;
+pub(crate) mod audit;
+pub(crate) mod download;
 pub(crate) mod owner;
 pub(crate) mod package;
+pub(crate) mod team;
+pub(crate) mod webhook;
 
+use crate::configuration::DatabasePoolConfiguration;
 use crate::models::helpers::uuid::Uuid;
-use crate::utilities::run_migrations;
-use actix::Actor;
+use crate::utilities::{pending_migrations, run_migrations};
+use actix::{Actor, Handler, Message, MessageResult, ResponseActFuture, WrapFuture};
+use actix_web::web::block;
 use anyhow::{anyhow, Result};
 use chrono::NaiveDateTime;
 use diesel::{
+    associations::HasTable,
+    connection::{AnsiTransactionManager, SimpleConnection, TransactionManager},
     dsl::now,
-    helper_types::{AsSelect, Eq, EqAny, Filter, IsNull, Like, Select, Update},
-    mysql::{Mysql, MysqlConnection},
-    query_builder::InsertStatement,
-    r2d2::{ConnectionManager, Pool, PooledConnection},
-    Insertable,
+    helper_types::{AsSelect, Eq, EqAny, Filter, IsNull, Select, Update},
+    query_builder::{DeleteStatement, InsertStatement, IntoUpdateTarget},
+    r2d2::{self, ManageConnection, Pool, PooledConnection, R2D2Connection},
+    sqlite::SqliteConnection,
+    Connection, ConnectionError, Insertable, MultiConnection, MysqlConnection, PgConnection,
 };
+use std::{future::Future, time::Duration};
 
-pub type All<Table, T> = Select<Table, AsSelect<T, Mysql>>;
+pub type All<Table, T> = Select<Table, AsSelect<T, MultiBackend>>;
 pub type FilterExisting<Target, DeletedAtColumn> = Filter<Target, IsNull<DeletedAtColumn>>;
 pub type FilterExistingNotNull<Target, DeletedAtColumn> =
     Filter<Target, Eq<DeletedAtColumn, NaiveDateTime>>;
@@ -27,23 +36,117 @@ pub type Create<Type, Table> = InsertStatement<Table, <Type as Insertable<Table>
 pub type UpdateById<Id, Table, T> = Update<ById<Id, Table>, T>;
 pub type FilterByIds<Table, Id, DeletedAtColumn, T> =
     Filter<FilterExisting<All<Table, T>, DeletedAtColumn>, EqAny<Id, Vec<Uuid>>>;
+pub type FilterExistingNotNullByIds<Table, Id, DeletedAtColumn, T> =
+    Filter<FilterExistingNotNull<All<Table, T>, DeletedAtColumn>, EqAny<Id, Vec<Uuid>>>;
 pub type FilterByNames<Table, Name, DeletedAtColumn, T> =
     Filter<FilterExisting<All<Table, T>, DeletedAtColumn>, EqAny<Name, Vec<String>>>;
 type UpdateDeletedAt<DeletedAtColumn> = Eq<DeletedAtColumn, now>;
 pub type SoftDelete<L, DeletedAtColumn> = Update<L, UpdateDeletedAt<DeletedAtColumn>>;
 pub type SoftDeleteById<Id, DeleteAtColumn, Table> = SoftDelete<ById<Id, Table>, DeleteAtColumn>;
-pub type SearchLikeName<Table, Name, DeletedAtColumn, T> =
-    Filter<FilterExisting<All<Table, T>, DeletedAtColumn>, Like<Name, String>>;
-pub type SearchLikeNameAndType<Table, Name, Type, DeletedAtColumn, T> =
-    Filter<SearchLikeName<Table, Name, DeletedAtColumn, T>, Eq<Type, String>>;
-pub type SearchLikeNameAndIds<Table, Name, Id, DeletedAtColumn, T> =
-    Filter<SearchLikeName<Table, Name, DeletedAtColumn, T>, EqAny<Id, Vec<Uuid>>>;
-pub type SearchLikeNameAndTypeAndIds<Table, Name, Type, Id, DeletedAtColumn, T> =
-    Filter<SearchLikeNameAndType<Table, Name, Type, DeletedAtColumn, T>, EqAny<Id, Vec<Uuid>>>;
+pub type HardDelete<Target> =
+    DeleteStatement<<Target as HasTable>::Table, <Target as IntoUpdateTarget>::WhereClause>;
+pub type HardDeleteById<Id, Table> = HardDelete<ById<Id, Table>>;
+
+/// A connection to one of the supported databases, the backend is chosen by the scheme of the
+/// database url: `mysql://`, `postgres://` (or `postgresql://`) and `sqlite://`.
+#[derive(MultiConnection)]
+pub enum DatabaseConnection {
+    Mysql(MysqlConnection),
+    Postgresql(PgConnection),
+    Sqlite(SqliteConnection),
+}
+
+impl DatabaseConnection {
+    /// Runs `f` in a transaction that writes. SQLite starts the transaction with
+    /// `BEGIN IMMEDIATE` to take the write lock up front, a deferred transaction that reads first
+    /// fails with `SQLITE_BUSY` instead of waiting when another connection writes in between.
+    pub fn write_transaction<T, E, F>(&mut self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut Self) -> Result<T, E>,
+        E: From<diesel::result::Error>,
+    {
+        let DatabaseConnection::Sqlite(connection) = self else {
+            return self.transaction(f);
+        };
+        AnsiTransactionManager::begin_transaction_sql(connection, "BEGIN IMMEDIATE")?;
+        match f(self) {
+            Ok(value) => {
+                <Self as Connection>::TransactionManager::commit_transaction(self)?;
+                Ok(value)
+            }
+            Err(error) => {
+                <Self as Connection>::TransactionManager::rollback_transaction(self)?;
+                Err(error)
+            }
+        }
+    }
+}
+
+/// Connection manager for the pool that establishes the connection by the database url scheme,
+/// unlike `establish` of `DatabaseConnection` that tries the backends one by one.
+#[derive(Debug, Clone)]
+pub struct DatabaseConnectionManager {
+    database_url: String,
+}
+
+impl DatabaseConnectionManager {
+    pub fn new(database_url: &str) -> Self {
+        Self {
+            database_url: database_url.to_string(),
+        }
+    }
+
+    /// Opens a single connection outside of the pool, used by the maintenance commands.
+    pub fn establish(&self) -> diesel::ConnectionResult<DatabaseConnection> {
+        let database_url = self.database_url.as_str();
+        if database_url.starts_with("mysql://") {
+            return Ok(DatabaseConnection::Mysql(MysqlConnection::establish(
+                database_url,
+            )?));
+        }
+        if database_url.starts_with("postgres://") || database_url.starts_with("postgresql://") {
+            return Ok(DatabaseConnection::Postgresql(PgConnection::establish(
+                database_url,
+            )?));
+        }
+        if let Some(database_path) = database_url.strip_prefix("sqlite://") {
+            let mut connection = SqliteConnection::establish(database_path)?;
+            connection
+                .batch_execute(SQLITE_CONNECTION_PRAGMAS)
+                .map_err(ConnectionError::CouldntSetupConfiguration)?;
+            return Ok(DatabaseConnection::Sqlite(connection));
+        }
+        Err(ConnectionError::InvalidConnectionUrl(format!(
+            "Unsupported database url scheme, expected one of mysql://, postgres:// or sqlite://: {database_url}"
+        )))
+    }
+}
+
+/// Foreign keys are off in SQLite by default and concurrent writers from the pool would fail
+/// immediately without a busy timeout.
+const SQLITE_CONNECTION_PRAGMAS: &str =
+    "PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 10000; PRAGMA journal_mode = WAL;";
+
+impl ManageConnection for DatabaseConnectionManager {
+    type Connection = DatabaseConnection;
+    type Error = r2d2::Error;
+
+    fn connect(&self) -> Result<DatabaseConnection, r2d2::Error> {
+        self.establish().map_err(r2d2::Error::ConnectionError)
+    }
+
+    fn is_valid(&self, connection: &mut DatabaseConnection) -> Result<(), r2d2::Error> {
+        connection.ping().map_err(r2d2::Error::QueryError)
+    }
+
+    fn has_broken(&self, connection: &mut DatabaseConnection) -> bool {
+        std::thread::panicking() || connection.is_broken()
+    }
+}
 
 #[derive(Clone)]
 pub struct Database {
-    connection_pool: Pool<ConnectionManager<MysqlConnection>>,
+    connection_pool: Pool<DatabaseConnectionManager>,
 }
 
 impl Actor for Database {
@@ -51,20 +154,117 @@ impl Actor for Database {
 }
 
 impl Database {
-    pub fn try_new(database_url: &str) -> Result<Self> {
-        let manager = ConnectionManager::<MysqlConnection>::new(database_url);
-        let connection_pool = Pool::builder()
-            .build(manager)
-            .map_err(|error| anyhow!("Failed to create database connection pool: {}", error))?;
-        let mut connection = connection_pool
+    /// Creates the pool and runs the pending migrations.
+    pub fn try_new(
+        database_url: &str,
+        pool_configuration: &DatabasePoolConfiguration,
+    ) -> Result<Self> {
+        let database = Self::try_new_without_migrations(database_url, pool_configuration)?;
+        let mut connection = database
+            .connection_pool
             .get()
             .map_err(|error| anyhow!("Failed to get database connection: {}", error))?;
         run_migrations(&mut connection)
             .map_err(|error| anyhow!("Failed to run database migrations: {}", error))?;
+        Ok(database)
+    }
+
+    /// Creates the pool without touching the schema, for the maintenance commands that have to
+    /// refuse to run on a database with [`Database::pending_migrations`].
+    pub fn try_new_without_migrations(
+        database_url: &str,
+        pool_configuration: &DatabasePoolConfiguration,
+    ) -> Result<Self> {
+        let manager = DatabaseConnectionManager::new(database_url);
+        let connection_pool = Pool::builder()
+            .max_size(pool_configuration.max_connections)
+            .min_idle(pool_configuration.min_idle_connections)
+            .connection_timeout(Duration::from_secs(
+                pool_configuration.connection_timeout_seconds,
+            ))
+            .idle_timeout(
+                pool_configuration
+                    .idle_timeout_seconds
+                    .map(Duration::from_secs),
+            )
+            .max_lifetime(
+                pool_configuration
+                    .max_lifetime_seconds
+                    .map(Duration::from_secs),
+            )
+            .build(manager)
+            .map_err(|error| anyhow!("Failed to create database connection pool: {}", error))?;
         Ok(Self { connection_pool })
     }
 
-    pub fn get_connection(&self) -> Result<PooledConnection<ConnectionManager<MysqlConnection>>> {
-        Ok(self.connection_pool.get()?)
+    pub fn pending_migrations(&self) -> Result<Vec<String>> {
+        let mut connection = self
+            .connection_pool
+            .get()
+            .map_err(|error| anyhow!("Failed to get database connection: {}", error))?;
+        pending_migrations(&mut connection).map_err(|error| anyhow!("{error}"))
+    }
+
+    /// Runs the query on the blocking thread pool with a pooled connection. Neither waiting for a
+    /// free connection nor the query itself blocks the arbiter, so the actor keeps handling other
+    /// messages while the query runs.
+    pub fn run<T, F>(&self, query: F) -> impl Future<Output = Result<T>>
+    where
+        F: FnOnce(PooledConnection<DatabaseConnectionManager>) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let connection_pool = self.connection_pool.clone();
+        async move { block(move || query(connection_pool.get()?)).await? }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PoolState {
+    pub connections: u32,
+    pub idle_connections: u32,
+    pub max_connections: u32,
+}
+
+/// Answered straight from the actor, the round trip of this message is the mailbox latency.
+#[derive(Message)]
+#[rtype(result = "PoolState")]
+pub struct GetPoolState;
+
+impl Handler<GetPoolState> for Database {
+    type Result = MessageResult<GetPoolState>;
+
+    fn handle(&mut self, _: GetPoolState, _ctx: &mut Self::Context) -> Self::Result {
+        let state = self.connection_pool.state();
+        MessageResult(PoolState {
+            connections: state.connections,
+            idle_connections: state.idle_connections,
+            max_connections: self.connection_pool.max_size(),
+        })
+    }
+}
+
+/// Checks that a pooled connection answers and returns the names of the migrations the database
+/// is missing.
+#[derive(Message)]
+#[rtype(result = "Result<Vec<String>>")]
+pub struct CheckDatabaseHealth;
+
+impl Handler<CheckDatabaseHealth> for Database {
+    type Result = ResponseActFuture<Self, Result<Vec<String>>>;
+
+    fn handle(&mut self, _: CheckDatabaseHealth, _ctx: &mut Self::Context) -> Self::Result {
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                database
+                    .run(|mut connection| {
+                        connection.ping()?;
+                        pending_migrations(&mut connection).map_err(|error| anyhow!("{error}"))
+                    })
+                    .await
+            }
+            .into_actor(self),
+        )
     }
 }
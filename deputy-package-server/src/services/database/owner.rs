@@ -1,9 +1,10 @@
-use super::Database;
-use crate::models::{NewOwner, Owner, Owners, Package};
+use super::{Database, DatabaseConnection};
+use crate::errors::PackageServerError;
+use crate::models::helpers::uuid::Uuid;
+use crate::models::{NewOwner, NewTeamOwner, Owner, Owners, Package, Team, TeamMember, TeamOwner};
 use actix::{Handler, Message, ResponseActFuture, WrapFuture};
-use actix_web::web::block;
 use anyhow::{anyhow, Ok, Result};
-use diesel::RunQueryDsl;
+use diesel::{OptionalExtension, RunQueryDsl};
 
 #[derive(Message)]
 #[rtype(result = "Result<Owner>")]
@@ -20,20 +21,21 @@ impl Handler<AddOwner> for Database {
             package_name,
             email,
         } = msg;
-        let connection_result = self.get_connection();
+        let database = self.clone();
 
         Box::pin(
             async move {
-                let mut connection = connection_result?;
-                let owner = block(move || {
-                    let package: Package = Package::by_name(package_name).first(&mut connection)?;
-                    let new_owner = NewOwner::new(email, package.id);
-                    new_owner.create_insert().execute(&mut connection)?;
-                    let owner = Owner::by_id(new_owner.id).first(&mut connection)?;
-
-                    Ok(owner)
-                })
-                .await??;
+                let owner = database
+                    .run(move |mut connection| {
+                        let package: Package =
+                            Package::by_name(package_name).first(&mut connection)?;
+                        let new_owner = NewOwner::new(email, package.id);
+                        new_owner.create_insert().execute(&mut connection)?;
+                        let owner = Owner::by_id(new_owner.id).first(&mut connection)?;
+
+                        Ok(owner)
+                    })
+                    .await?;
                 Ok(owner)
             }
             .into_actor(self),
@@ -49,19 +51,21 @@ impl Handler<GetOwners> for Database {
     type Result = ResponseActFuture<Self, Result<Owners>>;
 
     fn handle(&mut self, get_owners: GetOwners, _ctx: &mut Self::Context) -> Self::Result {
-        let connection_result = self.get_connection();
+        let database = self.clone();
         let GetOwners(package_name) = get_owners;
 
         Box::pin(
             async move {
-                let mut connection = connection_result?;
-                let owners = block(move || {
-                    let package: Package = Package::by_name(package_name).first(&mut connection)?;
-                    let owners = Owners(Owner::by_package_id(package.id).load(&mut connection)?);
-
-                    Ok(owners)
-                })
-                .await??;
+                let owners = database
+                    .run(move |mut connection| {
+                        let package: Package =
+                            Package::by_name(package_name).first(&mut connection)?;
+                        let owners =
+                            Owners(Owner::by_package_id(package.id).load(&mut connection)?);
+
+                        Ok(owners)
+                    })
+                    .await?;
                 Ok(owners)
             }
             .into_actor(self),
@@ -77,28 +81,225 @@ impl Handler<DeleteOwner> for Database {
     type Result = ResponseActFuture<Self, Result<String>>;
 
     fn handle(&mut self, delete_owners: DeleteOwner, _ctx: &mut Self::Context) -> Self::Result {
-        let connection_result = self.get_connection();
+        let database = self.clone();
         let DeleteOwner(package_name, owner_email) = delete_owners;
 
         Box::pin(
             async move {
-                let mut connection = connection_result?;
-                let owner_email = block(move || {
-                    let package: Package = Package::by_name(package_name).first(&mut connection)?;
-                    let owners = Owner::by_package_id(package.id).load(&mut connection)?;
-                    if owners.len() == 1 {
-                        return Err(anyhow!("Can not delete the last owner of a package"));
-                    }
-                    let owner: Owner =
-                        Owner::by_email(owner_email.clone()).first(&mut connection)?;
-                    owner.soft_delete().execute(&mut connection)?;
-
-                    Ok(owner_email)
-                })
-                .await??;
+                let owner_email = database
+                    .run(move |mut connection| {
+                        let package: Package =
+                            Package::by_name(package_name).first(&mut connection)?;
+                        if package_owner_count(&mut connection, package.id)? == 1 {
+                            return Err(anyhow!("Can not delete the last owner of a package"));
+                        }
+                        let owner: Owner =
+                            Owner::by_email(owner_email.clone()).first(&mut connection)?;
+                        owner.soft_delete().execute(&mut connection)?;
+
+                        Ok(owner_email)
+                    })
+                    .await?;
                 Ok(owner_email)
             }
             .into_actor(self),
         )
     }
 }
+
+/// Package ids owned by the email, either directly or through team membership.
+pub(crate) fn owned_package_ids(
+    connection: &mut DatabaseConnection,
+    email: &str,
+) -> Result<Vec<Uuid>> {
+    let mut package_ids = Owner::by_email(email.to_owned())
+        .load(connection)?
+        .iter()
+        .map(|owner| owner.package_id)
+        .collect::<Vec<Uuid>>();
+    let team_ids = TeamMember::by_email(email.to_owned())
+        .load(connection)?
+        .iter()
+        .map(|member| member.team_id)
+        .collect::<Vec<Uuid>>();
+    if !team_ids.is_empty() {
+        package_ids.extend(
+            TeamOwner::by_team_ids(team_ids)
+                .load(connection)?
+                .iter()
+                .map(|team_owner| team_owner.package_id),
+        );
+    }
+
+    Ok(package_ids)
+}
+
+pub(crate) fn is_package_owner(
+    connection: &mut DatabaseConnection,
+    package_id: Uuid,
+    email: &str,
+) -> Result<bool> {
+    Ok(owned_package_ids(connection, email)?.contains(&package_id))
+}
+
+fn package_owner_count(connection: &mut DatabaseConnection, package_id: Uuid) -> Result<usize> {
+    let owners: Vec<Owner> = Owner::by_package_id(package_id).load(connection)?;
+    let team_owners: Vec<TeamOwner> = TeamOwner::by_package_id(package_id).load(connection)?;
+    Ok(owners.len() + team_owners.len())
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<bool>")]
+pub struct IsPackageOwner {
+    pub package_name: String,
+    pub email: String,
+}
+
+impl Handler<IsPackageOwner> for Database {
+    type Result = ResponseActFuture<Self, Result<bool>>;
+
+    fn handle(&mut self, msg: IsPackageOwner, _ctx: &mut Self::Context) -> Self::Result {
+        let IsPackageOwner {
+            package_name,
+            email,
+        } = msg;
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let is_owner = database
+                    .run(move |mut connection| {
+                        let package: Package =
+                            Package::by_name(package_name).first(&mut connection)?;
+                        is_package_owner(&mut connection, package.id, &email)
+                    })
+                    .await?;
+                Ok(is_owner)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<Team>")]
+pub struct AddTeamOwner {
+    pub package_name: String,
+    pub team_name: String,
+}
+
+impl Handler<AddTeamOwner> for Database {
+    type Result = ResponseActFuture<Self, Result<Team>>;
+
+    fn handle(&mut self, msg: AddTeamOwner, _ctx: &mut Self::Context) -> Self::Result {
+        let AddTeamOwner {
+            package_name,
+            team_name,
+        } = msg;
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let team = database
+                    .run(move |mut connection| {
+                        let package: Package =
+                            Package::by_name(package_name).first(&mut connection)?;
+                        let team: Team = Team::by_name(team_name.to_lowercase())
+                            .first(&mut connection)
+                            .optional()?
+                            .ok_or(PackageServerError::TeamNotFound)?;
+                        NewTeamOwner::new(team.id, package.id)
+                            .create_insert()
+                            .execute(&mut connection)?;
+
+                        Ok(team)
+                    })
+                    .await?;
+                Ok(team)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<Vec<Team>>")]
+pub struct GetTeamOwners(pub String);
+
+impl Handler<GetTeamOwners> for Database {
+    type Result = ResponseActFuture<Self, Result<Vec<Team>>>;
+
+    fn handle(&mut self, msg: GetTeamOwners, _ctx: &mut Self::Context) -> Self::Result {
+        let database = self.clone();
+        let GetTeamOwners(package_name) = msg;
+
+        Box::pin(
+            async move {
+                let teams = database
+                    .run(move |mut connection| {
+                        let package: Package =
+                            Package::by_name(package_name).first(&mut connection)?;
+                        let team_ids = TeamOwner::by_package_id(package.id)
+                            .load(&mut connection)?
+                            .iter()
+                            .map(|team_owner| team_owner.team_id)
+                            .collect::<Vec<Uuid>>();
+                        let teams = Team::by_ids(team_ids).load(&mut connection)?;
+
+                        Ok(teams)
+                    })
+                    .await?;
+                Ok(teams)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<String>")]
+pub struct DeleteTeamOwner {
+    pub package_name: String,
+    pub team_name: String,
+}
+
+impl Handler<DeleteTeamOwner> for Database {
+    type Result = ResponseActFuture<Self, Result<String>>;
+
+    fn handle(&mut self, msg: DeleteTeamOwner, _ctx: &mut Self::Context) -> Self::Result {
+        let DeleteTeamOwner {
+            package_name,
+            team_name,
+        } = msg;
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let team_name = database
+                    .run(move |mut connection| {
+                        let package: Package =
+                            Package::by_name(package_name).first(&mut connection)?;
+                        if package_owner_count(&mut connection, package.id)? == 1 {
+                            return Err(anyhow!("Can not delete the last owner of a package"));
+                        }
+                        let team: Team = Team::by_name(team_name.to_lowercase())
+                            .first(&mut connection)
+                            .optional()?
+                            .ok_or(PackageServerError::TeamNotFound)?;
+                        let team_owners: Vec<TeamOwner> =
+                            TeamOwner::by_package_id(package.id).load(&mut connection)?;
+                        let team_owner = team_owners
+                            .into_iter()
+                            .find(|team_owner| team_owner.team_id == team.id)
+                            .ok_or(PackageServerError::DatabaseRecordNotFound)?;
+                        team_owner.soft_delete().execute(&mut connection)?;
+
+                        Ok(team.name)
+                    })
+                    .await?;
+                Ok(team_name)
+            }
+            .into_actor(self),
+        )
+    }
+}
@@ -1,14 +1,22 @@
+use super::owner::{is_package_owner, owned_package_ids};
 use super::Database;
+use crate::errors::PackageServerError;
 use crate::models::helpers::pagination::*;
 use crate::models::helpers::uuid::Uuid;
 use crate::models::{
-    Category, NewCategory, NewOwner, NewPackageCategory, NewPackageVersion, Owner, Owners, Package,
-    PackageCategory, PackageVersion, PackageWithVersions, PackagesWithVersionsAndPages, Version,
+    Category, NewCategory, NewOwner, NewPackageCategory, NewPackageVersion, Owner, Package,
+    PackageCategory, PackageSearchDocument, PackageSearchFilter, PackageVersion, PackageViewer,
+    PackageWithVersions, PackagesWithVersionsAndPages, PublishedVersions, TeamOwner, Version,
+    VersionDownload, Webhook, WebhookDelivery,
 };
+use crate::schema::{categories, package_categories, packages};
 use actix::{Handler, Message, ResponseActFuture, WrapFuture};
-use actix_web::web::block;
 use anyhow::{anyhow, Ok, Result};
-use diesel::{OptionalExtension, RunQueryDsl};
+use deputy_library::package::Visibility;
+use deputy_library::rest::{DeprecationRest, PackageSort, SearchFacetsRest};
+use diesel::dsl::count_star;
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+use std::collections::BTreeMap;
 
 #[derive(Message)]
 #[rtype(result = "Result<PackageVersion>")]
@@ -18,41 +26,74 @@ impl Handler<CreatePackage> for Database {
     type Result = ResponseActFuture<Self, Result<PackageVersion>>;
 
     fn handle(&mut self, msg: CreatePackage, _ctx: &mut Self::Context) -> Self::Result {
-        let NewPackageVersion(mut new_package, mut new_version) = msg.0;
+        let NewPackageVersion(mut new_package, mut new_version, mut search_document) = msg.0;
         new_package.name = new_package.name.to_lowercase();
         let requester_email = msg.1;
-        let connection_result = self.get_connection();
+        let database = self.clone();
 
         Box::pin(
             async move {
-                let mut connection = connection_result?;
-                let package = block(move || {
-                    let optional_package = Package::by_name(new_package.name.clone())
-                        .first(&mut connection)
-                        .optional()?;
-                    let existing_package = match optional_package {
-                        Some(package) => {
-                            let owners =
-                                Owners(Owner::by_package_id(package.id).load(&mut connection)?);
-                            if !owners.contains_email(&requester_email) {
-                                return Err(anyhow!("Requester is not an owner of this package"));
-                            }
-                            package
+                let package = database
+                    .run(move |mut connection| {
+                        let optional_package = Package::by_name(new_package.name.clone())
+                            .first(&mut connection)
+                            .optional()?;
+                        if optional_package.is_none()
+                            && Package::by_name_with_deleted(new_package.name.clone())
+                                .first(&mut connection)
+                                .optional()?
+                                .is_some()
+                        {
+                            return Err(PackageServerError::PackageNameDeleted.into());
                         }
-                        None => {
-                            new_package.create_insert().execute(&mut connection)?;
-                            let package = Package::by_id(new_package.id).first(&mut connection)?;
-                            let package_owner = NewOwner::new(requester_email, package.id);
-                            package_owner.create_insert().execute(&mut connection)?;
-                            package
+                        let existing_package = match optional_package {
+                            Some(package) => {
+                                if !is_package_owner(&mut connection, package.id, &requester_email)?
+                                {
+                                    return Err(anyhow!(
+                                        "Requester is not an owner of this package"
+                                    ));
+                                }
+                                package.touch().execute(&mut connection)?;
+                                if new_package.visibility != Visibility::Public.as_str()
+                                    && new_package.visibility != package.visibility
+                                {
+                                    package
+                                        .update_visibility(new_package.visibility.clone())
+                                        .execute(&mut connection)?;
+                                    Package::by_id(package.id).first(&mut connection)?
+                                } else {
+                                    package
+                                }
+                            }
+                            None => {
+                                new_package.create_insert().execute(&mut connection)?;
+                                let package =
+                                    Package::by_id(new_package.id).first(&mut connection)?;
+                                let package_owner = NewOwner::new(requester_email, package.id);
+                                package_owner.create_insert().execute(&mut connection)?;
+                                package
+                            }
+                        };
+                        if existing_package
+                            .exact_version_with_deleted(new_version.version.clone())
+                            .first::<Version>(&mut connection)
+                            .optional()?
+                            .is_some()
+                        {
+                            return Err(PackageServerError::VersionConflict(
+                                new_version.version.clone(),
+                            )
+                            .into());
                         }
-                    };
-                    new_version.package_id = existing_package.id;
-                    new_version.create_insert().execute(&mut connection)?;
-                    let version = Version::by_id(new_version.id).first(&mut connection)?;
-                    Ok(PackageVersion(existing_package, version))
-                })
-                .await??;
+                        new_version.package_id = existing_package.id;
+                        new_version.create_insert().execute(&mut connection)?;
+                        search_document.package_id = existing_package.id;
+                        search_document.create_or_update(&mut connection)?;
+                        let version = Version::by_id(new_version.id).first(&mut connection)?;
+                        Ok(PackageVersion(existing_package, version))
+                    })
+                    .await?;
                 Ok(package)
             }
             .into_actor(self),
@@ -68,89 +109,133 @@ pub struct GetPackages {
     pub categories: Option<Vec<String>>,
     pub page: i64,
     pub per_page: i64,
+    pub viewer: PackageViewer,
+    pub sort: PackageSort,
+}
+
+fn count_map(counts: Vec<(String, i64)>) -> BTreeMap<String, u64> {
+    counts
+        .into_iter()
+        .map(|(name, count)| (name, count as u64))
+        .collect()
+}
+
+fn package_order_by(sort: PackageSort) -> &'static str {
+    match sort {
+        PackageSort::Relevance | PackageSort::Name => "name ASC",
+        PackageSort::Updated => "updated_at DESC, name ASC",
+        PackageSort::Downloads => "downloads DESC, name ASC",
+    }
 }
 
 impl Handler<GetPackages> for Database {
     type Result = ResponseActFuture<Self, Result<PackagesWithVersionsAndPages>>;
 
     fn handle(&mut self, search_packages: GetPackages, _ctx: &mut Self::Context) -> Self::Result {
-        let connection_result = self.get_connection();
+        let database = self.clone();
 
         Box::pin(
             async move {
-                let mut connection = connection_result?;
-                let package = block(move || {
-                    let search_term = search_packages.search_term.unwrap_or_default();
-
-                    let query = match (
-                        search_packages.package_type.clone(),
-                        search_packages.categories,
-                    ) {
-                        (_, Some(search_categories)) => {
-                            let search_category_ids = Category::by_names(search_categories)
-                                .load(&mut connection)?
-                                .iter()
-                                .map(|category| category.id)
-                                .collect::<Vec<Uuid>>();
-
-                            let package_ids_by_categories =
-                                PackageCategory::by_category_ids(search_category_ids)
+                let package = database
+                    .run(move |mut connection| {
+                        let search_term = search_packages.search_term.unwrap_or_default();
+                        let owned_package_ids = match &search_packages.viewer.email {
+                            Some(email) => owned_package_ids(&mut connection, email)?,
+                            None => Vec::new(),
+                        };
+                        let package_type = search_packages
+                            .package_type
+                            .map(|package_type| package_type.to_lowercase());
+                        let package_ids_by_categories = match search_packages.categories {
+                            Some(search_categories) => {
+                                let search_category_ids = Category::by_names(search_categories)
                                     .load(&mut connection)?
                                     .iter()
-                                    .map(|package_category| package_category.package_id)
+                                    .map(|category| category.id)
                                     .collect::<Vec<Uuid>>();
 
-                            if let Some(search_package_type) = search_packages.package_type {
-                                Package::search_name_with_type_and_categories(
-                                    search_term,
-                                    search_package_type.to_lowercase(),
-                                    package_ids_by_categories,
-                                )
-                                .paginate(search_packages.page)
-                                .per_page(search_packages.per_page)
-                                .load_and_count_pages(&mut connection)?
-                            } else {
-                                Package::search_name_with_categories(
-                                    search_term,
-                                    package_ids_by_categories,
+                                Some(
+                                    PackageCategory::by_category_ids(search_category_ids)
+                                        .load(&mut connection)?
+                                        .iter()
+                                        .map(|package_category| package_category.package_id)
+                                        .collect::<Vec<Uuid>>(),
                                 )
-                                .paginate(search_packages.page)
-                                .per_page(search_packages.per_page)
-                                .load_and_count_pages(&mut connection)?
                             }
-                        }
-                        (Some(search_package_type), None) => Package::search_name_with_type(
-                            search_term,
-                            search_package_type.to_lowercase(),
-                        )
-                        .paginate(search_packages.page)
-                        .per_page(search_packages.per_page)
-                        .load_and_count_pages(&mut connection)?,
-                        _ => Package::search_name(search_term)
+                            None => None,
+                        };
+                        let search_filter = PackageSearchFilter {
+                            search_term: search_term.clone(),
+                            package_type,
+                            package_ids_by_categories,
+                            viewer: search_packages.viewer,
+                            owned_package_ids,
+                        };
+
+                        let mut paginated_query = Package::search(&search_filter)
                             .paginate(search_packages.page)
                             .per_page(search_packages.per_page)
-                            .load_and_count_pages(&mut connection)?,
-                    };
-
-                    let packages_with_versions_query_result = query
-                        .0
-                        .iter()
-                        .map(|package| {
-                            let package_versions = package.versions().load(&mut connection)?;
-                            Ok(PackageWithVersions::from((
-                                package.clone(),
-                                package_versions,
-                            )))
-                        })
-                        .collect::<Result<Vec<PackageWithVersions>>>()?;
-
-                    Ok(PackagesWithVersionsAndPages::from((
-                        packages_with_versions_query_result,
-                        query.1,
-                        query.2,
-                    )))
-                })
-                .await??;
+                            .order_by(package_order_by(search_packages.sort));
+                        if search_packages.sort == PackageSort::Relevance && !search_term.is_empty()
+                        {
+                            paginated_query =
+                                paginated_query.order_by_relevance(search_term.clone());
+                        }
+                        let query = paginated_query.load_and_count_pages(&mut connection)?;
+
+                        let package_type_counts: Vec<(String, i64)> = search_filter
+                            .conditions()
+                            .into_iter()
+                            .fold(
+                                packages::table
+                                    .filter(packages::deleted_at.is_null())
+                                    .group_by(packages::package_type)
+                                    .select((packages::package_type, count_star()))
+                                    .into_boxed(),
+                                |query, condition| query.filter(condition),
+                            )
+                            .load(&mut connection)?;
+                        let category_counts: Vec<(String, i64)> = search_filter
+                            .conditions()
+                            .into_iter()
+                            .fold(
+                                package_categories::table
+                                    .inner_join(categories::table)
+                                    .inner_join(packages::table)
+                                    .filter(package_categories::deleted_at.is_null())
+                                    .filter(categories::deleted_at.is_null())
+                                    .filter(packages::deleted_at.is_null())
+                                    .group_by(categories::name)
+                                    .select((categories::name, count_star()))
+                                    .into_boxed(),
+                                |query, condition| query.filter(condition),
+                            )
+                            .load(&mut connection)?;
+                        let facets = SearchFacetsRest {
+                            package_types: count_map(package_type_counts),
+                            categories: count_map(category_counts),
+                        };
+
+                        let packages_with_versions_query_result = query
+                            .0
+                            .iter()
+                            .map(|package| {
+                                let package_versions = package.versions().load(&mut connection)?;
+                                Ok(PackageWithVersions::from((
+                                    package.clone(),
+                                    package_versions,
+                                )))
+                            })
+                            .collect::<Result<Vec<PackageWithVersions>>>()?;
+
+                        Ok(PackagesWithVersionsAndPages::from((
+                            packages_with_versions_query_result,
+                            query.1,
+                            query.2,
+                        ))
+                        .with_facets(facets))
+                    })
+                    .await?;
                 Ok(package)
             }
             .into_actor(self),
@@ -173,20 +258,47 @@ impl Handler<GetPackageByNameAndVersion> for Database {
         query_params: GetPackageByNameAndVersion,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        let connection_result = self.get_connection();
+        let database = self.clone();
 
         Box::pin(
             async move {
-                let mut connection = connection_result?;
-                let package = block(move || {
-                    let package: Package = Package::by_name(query_params.name.to_lowercase())
-                        .first(&mut connection)?;
-                    let package_version: Version = package
-                        .exact_version(query_params.version)
-                        .first(&mut connection)?;
-                    Ok(package_version)
-                })
-                .await??;
+                let package = database
+                    .run(move |mut connection| {
+                        let package: Package = Package::by_name(query_params.name.to_lowercase())
+                            .first(&mut connection)?;
+                        let package_version: Version = package
+                            .exact_version(query_params.version)
+                            .first(&mut connection)?;
+                        Ok(package_version)
+                    })
+                    .await?;
+                Ok(package)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<Option<Package>>")]
+pub struct GetPackageByName(pub String);
+
+impl Handler<GetPackageByName> for Database {
+    type Result = ResponseActFuture<Self, Result<Option<Package>>>;
+
+    fn handle(&mut self, query_params: GetPackageByName, _ctx: &mut Self::Context) -> Self::Result {
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let package = database
+                    .run(move |mut connection| {
+                        let package = Package::by_name(query_params.0.to_lowercase())
+                            .first(&mut connection)
+                            .optional()?;
+                        Ok(package)
+                    })
+                    .await?;
                 Ok(package)
             }
             .into_actor(self),
@@ -206,23 +318,23 @@ impl Handler<GetVersionsByPackageName> for Database {
         query_params: GetVersionsByPackageName,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        let connection_result = self.get_connection();
+        let database = self.clone();
 
         Box::pin(
             async move {
-                let mut connection = connection_result?;
-                let package = block(move || {
-                    let package = Package::by_name(query_params.0.to_lowercase())
-                        .first(&mut connection)
-                        .optional()?;
-                    if let Some(package) = package {
-                        let package_versions: Vec<Version> =
-                            package.versions().load(&mut connection)?;
-                        return Ok(package_versions);
-                    }
-                    Ok(Vec::new())
-                })
-                .await??;
+                let package = database
+                    .run(move |mut connection| {
+                        let package = Package::by_name(query_params.0.to_lowercase())
+                            .first(&mut connection)
+                            .optional()?;
+                        if let Some(package) = package {
+                            let package_versions: Vec<Version> =
+                                package.versions().load(&mut connection)?;
+                            return Ok(package_versions);
+                        }
+                        Ok(Vec::new())
+                    })
+                    .await?;
                 Ok(package)
             }
             .into_actor(self),
@@ -230,6 +342,45 @@ impl Handler<GetVersionsByPackageName> for Database {
     }
 }
 
+#[derive(Message)]
+#[rtype(result = "Result<PublishedVersions>")]
+pub struct GetPublishedVersions(pub String);
+
+impl Handler<GetPublishedVersions> for Database {
+    type Result = ResponseActFuture<Self, Result<PublishedVersions>>;
+
+    fn handle(&mut self, msg: GetPublishedVersions, _ctx: &mut Self::Context) -> Self::Result {
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let published_versions = database
+                    .run(move |mut connection| {
+                        let packages: Vec<Package> =
+                            Package::by_name_with_deleted(msg.0.to_lowercase())
+                                .load(&mut connection)?;
+                        let mut published_versions = PublishedVersions {
+                            is_package_deleted: !packages.is_empty()
+                                && packages.iter().all(|package| package.deleted_at.is_some()),
+                            versions: Vec::new(),
+                        };
+                        for package in packages {
+                            published_versions.versions.extend(
+                                package
+                                    .versions_with_deleted()
+                                    .load::<Version>(&mut connection)?,
+                            );
+                        }
+                        Ok(published_versions)
+                    })
+                    .await?;
+                Ok(published_versions)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
 #[derive(Message)]
 #[rtype(result = "Result<Category>")]
 pub struct CreateCategory(pub NewCategory, pub Uuid);
@@ -241,40 +392,41 @@ impl Handler<CreateCategory> for Database {
         let mut new_category: NewCategory = msg.0;
         new_category.name = new_category.name.to_lowercase();
         let package_id: Uuid = msg.1;
-        let connection_result = self.get_connection();
+        let database = self.clone();
 
         Box::pin(
             async move {
-                let mut connection = connection_result?;
-                let category: Category = block(move || {
-                    let optional_category = Category::by_name(new_category.name.clone())
-                        .first(&mut connection)
-                        .optional()?;
-                    let existing_category = match optional_category {
-                        Some(cat) => cat,
-                        None => {
-                            new_category.create_insert().execute(&mut connection)?;
-                            Category::by_id(new_category.id).first(&mut connection)?
-                        }
-                    };
-                    let optional_package_category = PackageCategory::by_package_and_category_id(
-                        package_id,
-                        existing_category.id,
-                    )
-                    .first(&mut connection)
-                    .optional()?;
-                    if optional_package_category.is_none() {
-                        let new_package_category = NewPackageCategory {
-                            package_id,
-                            category_id: existing_category.id,
+                let category: Category = database
+                    .run(move |mut connection| {
+                        let optional_category = Category::by_name(new_category.name.clone())
+                            .first(&mut connection)
+                            .optional()?;
+                        let existing_category = match optional_category {
+                            Some(cat) => cat,
+                            None => {
+                                new_category.create_insert().execute(&mut connection)?;
+                                Category::by_id(new_category.id).first(&mut connection)?
+                            }
                         };
-                        new_package_category
-                            .create_insert()
-                            .execute(&mut connection)?;
-                    }
-                    Ok(existing_category)
-                })
-                .await??;
+                        let optional_package_category =
+                            PackageCategory::by_package_and_category_id(
+                                package_id,
+                                existing_category.id,
+                            )
+                            .first(&mut connection)
+                            .optional()?;
+                        if optional_package_category.is_none() {
+                            let new_package_category = NewPackageCategory {
+                                package_id,
+                                category_id: existing_category.id,
+                            };
+                            new_package_category
+                                .create_insert()
+                                .execute(&mut connection)?;
+                        }
+                        Ok(existing_category)
+                    })
+                    .await?;
                 Ok(category)
             }
             .into_actor(self),
@@ -290,16 +442,16 @@ impl Handler<GetAllCategories> for Database {
     type Result = ResponseActFuture<Self, Result<Vec<Category>>>;
 
     fn handle(&mut self, _: GetAllCategories, _ctx: &mut Self::Context) -> Self::Result {
-        let connection_result = self.get_connection();
+        let database = self.clone();
 
         Box::pin(
             async move {
-                let mut connection = connection_result?;
-                let categories = block(move || {
-                    let categories: Vec<Category> = Category::all().load(&mut connection)?;
-                    Ok(categories)
-                })
-                .await??;
+                let categories = database
+                    .run(move |mut connection| {
+                        let categories: Vec<Category> = Category::all().load(&mut connection)?;
+                        Ok(categories)
+                    })
+                    .await?;
                 Ok(categories)
             }
             .into_actor(self),
@@ -321,20 +473,20 @@ impl Handler<GetCategoriesForPackage> for Database {
         query_params: GetCategoriesForPackage,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        let connection_result = self.get_connection();
+        let database = self.clone();
 
         Box::pin(
             async move {
-                let mut connection = connection_result?;
-                let categories = block(move || {
-                    let package_categories =
-                        PackageCategory::by_package_id(query_params.id).load(&mut connection)?;
-                    let category_ids: Vec<Uuid> =
-                        package_categories.iter().map(|pc| pc.category_id).collect();
-                    let categories = Category::by_ids(category_ids).load(&mut connection)?;
-                    Ok(categories)
-                })
-                .await??;
+                let categories = database
+                    .run(move |mut connection| {
+                        let package_categories = PackageCategory::by_package_id(query_params.id)
+                            .load(&mut connection)?;
+                        let category_ids: Vec<Uuid> =
+                            package_categories.iter().map(|pc| pc.category_id).collect();
+                        let categories = Category::by_ids(category_ids).load(&mut connection)?;
+                        Ok(categories)
+                    })
+                    .await?;
                 Ok(categories)
             }
             .into_actor(self),
@@ -342,6 +494,30 @@ impl Handler<GetCategoriesForPackage> for Database {
     }
 }
 
+/// Every version of the existing packages along with the name of its package, the versions whose
+/// archives should be in the storage.
+#[derive(Message)]
+#[rtype(result = "Result<Vec<(String, Version)>>")]
+pub struct GetStoredVersions;
+
+impl Handler<GetStoredVersions> for Database {
+    type Result = ResponseActFuture<Self, Result<Vec<(String, Version)>>>;
+
+    fn handle(&mut self, _: GetStoredVersions, _ctx: &mut Self::Context) -> Self::Result {
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let versions = database
+                    .run(move |mut connection| Ok(Version::load_stored(&mut connection)?))
+                    .await?;
+                Ok(versions)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
 #[derive(Message)]
 #[rtype(result = "Result<Version>")]
 pub struct UpdateVersionMsg {
@@ -353,17 +529,222 @@ impl Handler<UpdateVersionMsg> for Database {
     type Result = ResponseActFuture<Self, Result<Version>>;
 
     fn handle(&mut self, msg: UpdateVersionMsg, _ctx: &mut Self::Context) -> Self::Result {
-        let connection_result = self.get_connection();
+        let database = self.clone();
 
         Box::pin(
             async move {
-                let mut connection = connection_result?;
-                let version = block(move || {
-                    msg.version.create_update(msg.id).execute(&mut connection)?;
-                    let version = Version::by_id(msg.id).first(&mut connection)?;
-                    Ok(version)
-                })
-                .await??;
+                let version = database
+                    .run(move |mut connection| {
+                        msg.version.create_update(msg.id).execute(&mut connection)?;
+                        let version = Version::by_id(msg.id).first(&mut connection)?;
+                        Ok(version)
+                    })
+                    .await?;
+                Ok(version)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<Package>")]
+pub struct UpdatePackageDeprecation {
+    pub package: Package,
+    pub deprecation: Option<DeprecationRest>,
+}
+
+impl Handler<UpdatePackageDeprecation> for Database {
+    type Result = ResponseActFuture<Self, Result<Package>>;
+
+    fn handle(&mut self, msg: UpdatePackageDeprecation, _ctx: &mut Self::Context) -> Self::Result {
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let package = database
+                    .run(move |mut connection| {
+                        msg.package
+                            .update_deprecation(msg.deprecation)
+                            .execute(&mut connection)?;
+                        let package = Package::by_id(msg.package.id).first(&mut connection)?;
+                        Ok(package)
+                    })
+                    .await?;
+                Ok(package)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<Package>")]
+pub struct UpdatePackageVisibility {
+    pub package: Package,
+    pub visibility: Visibility,
+}
+
+impl Handler<UpdatePackageVisibility> for Database {
+    type Result = ResponseActFuture<Self, Result<Package>>;
+
+    fn handle(&mut self, msg: UpdatePackageVisibility, _ctx: &mut Self::Context) -> Self::Result {
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let package = database
+                    .run(move |mut connection| {
+                        msg.package
+                            .update_visibility(msg.visibility.to_string())
+                            .execute(&mut connection)?;
+                        let package = Package::by_id(msg.package.id).first(&mut connection)?;
+                        Ok(package)
+                    })
+                    .await?;
+                Ok(package)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<String>")]
+pub struct DeletePackage {
+    pub name: String,
+    pub hard_delete: bool,
+}
+
+impl Handler<DeletePackage> for Database {
+    type Result = ResponseActFuture<Self, Result<String>>;
+
+    fn handle(&mut self, msg: DeletePackage, _ctx: &mut Self::Context) -> Self::Result {
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let package_name = database
+                    .run(move |mut connection| {
+                        let name = msg.name.to_lowercase();
+                        let packages: Vec<Package> = match msg.hard_delete {
+                            true => {
+                                Package::by_name_with_deleted(name.clone()).load(&mut connection)?
+                            }
+                            false => Package::by_name(name.clone()).load(&mut connection)?,
+                        };
+                        if packages.is_empty() {
+                            return Err(PackageServerError::DatabaseRecordNotFound.into());
+                        }
+                        connection.write_transaction(|connection| {
+                            for package in packages {
+                                if msg.hard_delete {
+                                    WebhookDelivery::hard_delete_by_package_id(package.id)
+                                        .execute(connection)?;
+                                    Webhook::hard_delete_by_package_id(package.id)
+                                        .execute(connection)?;
+                                    VersionDownload::hard_delete_by_package_id(package.id)
+                                        .execute(connection)?;
+                                    PackageSearchDocument::hard_delete_by_package_id(package.id)
+                                        .execute(connection)?;
+                                    PackageCategory::hard_delete_by_package_id(package.id)
+                                        .execute(connection)?;
+                                    Owner::hard_delete_by_package_id(package.id)
+                                        .execute(connection)?;
+                                    TeamOwner::hard_delete_by_package_id(package.id)
+                                        .execute(connection)?;
+                                    Version::hard_delete_by_package_id(package.id)
+                                        .execute(connection)?;
+                                    package.hard_delete().execute(connection)?;
+                                } else {
+                                    let versions: Vec<Version> =
+                                        package.versions().load(connection)?;
+                                    for version in versions {
+                                        version.soft_delete().execute(connection)?;
+                                    }
+                                    for owner in
+                                        Owner::by_package_id(package.id).load(connection)?
+                                    {
+                                        owner.soft_delete().execute(connection)?;
+                                    }
+                                    for team_owner in
+                                        TeamOwner::by_package_id(package.id).load(connection)?
+                                    {
+                                        team_owner.soft_delete().execute(connection)?;
+                                    }
+                                    PackageCategory::soft_delete_by_package_id(package.id)
+                                        .execute(connection)?;
+                                    package.soft_delete().execute(connection)?;
+                                }
+                            }
+                            Ok(name)
+                        })
+                    })
+                    .await?;
+                Ok(package_name)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<String>")]
+pub struct DeleteVersion {
+    pub name: String,
+    pub version: String,
+    pub hard_delete: bool,
+}
+
+impl Handler<DeleteVersion> for Database {
+    type Result = ResponseActFuture<Self, Result<String>>;
+
+    fn handle(&mut self, msg: DeleteVersion, _ctx: &mut Self::Context) -> Self::Result {
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let version = database
+                    .run(move |mut connection| {
+                        let name = msg.name.to_lowercase();
+                        let mut versions: Vec<Version> = Vec::new();
+                        if msg.hard_delete {
+                            for package in
+                                Package::by_name_with_deleted(name).load(&mut connection)?
+                            {
+                                versions.extend(
+                                    package
+                                        .exact_version_with_deleted(msg.version.clone())
+                                        .load::<Version>(&mut connection)?,
+                                );
+                            }
+                        } else if let Some(package) =
+                            Package::by_name(name).first(&mut connection).optional()?
+                        {
+                            versions.extend(
+                                package
+                                    .exact_version(msg.version.clone())
+                                    .load::<Version>(&mut connection)?,
+                            );
+                        }
+                        if versions.is_empty() {
+                            return Err(PackageServerError::DatabaseRecordNotFound.into());
+                        }
+                        connection.write_transaction(|connection| {
+                            for version in versions {
+                                match msg.hard_delete {
+                                    true => {
+                                        VersionDownload::hard_delete_by_version_id(version.id)
+                                            .execute(connection)?;
+                                        version.hard_delete().execute(connection)?
+                                    }
+                                    false => version.soft_delete().execute(connection)?,
+                                };
+                            }
+                            Ok(msg.version)
+                        })
+                    })
+                    .await?;
                 Ok(version)
             }
             .into_actor(self),
@@ -0,0 +1,254 @@
+use super::Database;
+use crate::errors::PackageServerError;
+use crate::models::helpers::uuid::Uuid;
+use crate::models::{NewTeam, NewTeamMember, Team, TeamMember, TeamOwner, TeamWithMembers};
+use actix::{Handler, Message, ResponseActFuture, WrapFuture};
+use anyhow::{anyhow, Ok, Result};
+use diesel::{OptionalExtension, RunQueryDsl};
+
+#[derive(Message)]
+#[rtype(result = "Result<TeamWithMembers>")]
+pub struct CreateTeam {
+    pub name: String,
+    pub admin_email: String,
+}
+
+impl Handler<CreateTeam> for Database {
+    type Result = ResponseActFuture<Self, Result<TeamWithMembers>>;
+
+    fn handle(&mut self, msg: CreateTeam, _ctx: &mut Self::Context) -> Self::Result {
+        let CreateTeam { name, admin_email } = msg;
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let team = database
+                    .run(move |mut connection| {
+                        let new_team = NewTeam::new(name);
+                        if Team::by_name(new_team.name.clone())
+                            .first(&mut connection)
+                            .optional()?
+                            .is_some()
+                        {
+                            return Err(PackageServerError::TeamExists.into());
+                        }
+                        connection.write_transaction(|connection| {
+                            new_team.create_insert().execute(connection)?;
+                            NewTeamMember::new(new_team.id, admin_email, true)
+                                .create_insert()
+                                .execute(connection)?;
+                            let team: Team = Team::by_id(new_team.id).first(connection)?;
+                            let members = TeamMember::by_team_id(team.id).load(connection)?;
+
+                            Ok(TeamWithMembers::from((team, members)))
+                        })
+                    })
+                    .await?;
+                Ok(team)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<Option<TeamWithMembers>>")]
+pub struct GetTeam(pub String);
+
+impl Handler<GetTeam> for Database {
+    type Result = ResponseActFuture<Self, Result<Option<TeamWithMembers>>>;
+
+    fn handle(&mut self, msg: GetTeam, _ctx: &mut Self::Context) -> Self::Result {
+        let GetTeam(name) = msg;
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let team = database
+                    .run(move |mut connection| {
+                        let team: Option<Team> = Team::by_name(name.to_lowercase())
+                            .first(&mut connection)
+                            .optional()?;
+                        match team {
+                            Some(team) => {
+                                let members =
+                                    TeamMember::by_team_id(team.id).load(&mut connection)?;
+                                Ok(Some(TeamWithMembers::from((team, members))))
+                            }
+                            None => Ok(None),
+                        }
+                    })
+                    .await?;
+                Ok(team)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<Vec<TeamWithMembers>>")]
+pub struct GetTeamsByMember(pub String);
+
+impl Handler<GetTeamsByMember> for Database {
+    type Result = ResponseActFuture<Self, Result<Vec<TeamWithMembers>>>;
+
+    fn handle(&mut self, msg: GetTeamsByMember, _ctx: &mut Self::Context) -> Self::Result {
+        let GetTeamsByMember(email) = msg;
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let teams = database
+                    .run(move |mut connection| {
+                        let team_ids = TeamMember::by_email(email)
+                            .load(&mut connection)?
+                            .iter()
+                            .map(|member| member.team_id)
+                            .collect::<Vec<Uuid>>();
+                        Team::by_ids(team_ids)
+                            .load(&mut connection)?
+                            .into_iter()
+                            .map(|team| {
+                                let members =
+                                    TeamMember::by_team_id(team.id).load(&mut connection)?;
+                                Ok(TeamWithMembers::from((team, members)))
+                            })
+                            .collect::<Result<Vec<TeamWithMembers>>>()
+                    })
+                    .await?;
+                Ok(teams)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<String>")]
+pub struct DeleteTeam(pub String);
+
+impl Handler<DeleteTeam> for Database {
+    type Result = ResponseActFuture<Self, Result<String>>;
+
+    fn handle(&mut self, msg: DeleteTeam, _ctx: &mut Self::Context) -> Self::Result {
+        let DeleteTeam(name) = msg;
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let team_name = database
+                    .run(move |mut connection| {
+                        let team: Team = Team::by_name(name.to_lowercase())
+                            .first(&mut connection)
+                            .optional()?
+                            .ok_or(PackageServerError::TeamNotFound)?;
+                        let team_owners: Vec<TeamOwner> =
+                            TeamOwner::by_team_id(team.id).load(&mut connection)?;
+                        if !team_owners.is_empty() {
+                            return Err(PackageServerError::TeamOwnsPackages.into());
+                        }
+                        connection.write_transaction(|connection| {
+                            for member in TeamMember::by_team_id(team.id).load(connection)? {
+                                member.soft_delete().execute(connection)?;
+                            }
+                            team.soft_delete().execute(connection)?;
+
+                            Ok(team.name)
+                        })
+                    })
+                    .await?;
+                Ok(team_name)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<TeamMember>")]
+pub struct AddTeamMember {
+    pub team_name: String,
+    pub email: String,
+    pub is_admin: bool,
+}
+
+impl Handler<AddTeamMember> for Database {
+    type Result = ResponseActFuture<Self, Result<TeamMember>>;
+
+    fn handle(&mut self, msg: AddTeamMember, _ctx: &mut Self::Context) -> Self::Result {
+        let AddTeamMember {
+            team_name,
+            email,
+            is_admin,
+        } = msg;
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let member = database
+                    .run(move |mut connection| {
+                        let team: Team = Team::by_name(team_name.to_lowercase())
+                            .first(&mut connection)
+                            .optional()?
+                            .ok_or(PackageServerError::TeamNotFound)?;
+                        let new_member = NewTeamMember::new(team.id, email, is_admin);
+                        new_member.create_insert().execute(&mut connection)?;
+                        let member = TeamMember::by_team_id(team.id)
+                            .load(&mut connection)?
+                            .into_iter()
+                            .find(|member| member.id == new_member.id)
+                            .ok_or_else(|| anyhow!("Failed to find the added team member"))?;
+
+                        Ok(member)
+                    })
+                    .await?;
+                Ok(member)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<String>")]
+pub struct RemoveTeamMember {
+    pub team_name: String,
+    pub email: String,
+}
+
+impl Handler<RemoveTeamMember> for Database {
+    type Result = ResponseActFuture<Self, Result<String>>;
+
+    fn handle(&mut self, msg: RemoveTeamMember, _ctx: &mut Self::Context) -> Self::Result {
+        let RemoveTeamMember { team_name, email } = msg;
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let email = database
+                    .run(move |mut connection| {
+                        let team: Team = Team::by_name(team_name.to_lowercase())
+                            .first(&mut connection)
+                            .optional()?
+                            .ok_or(PackageServerError::TeamNotFound)?;
+                        let members = TeamMember::by_team_id(team.id).load(&mut connection)?;
+                        let member = members
+                            .iter()
+                            .find(|member| member.email == email)
+                            .ok_or(PackageServerError::DatabaseRecordNotFound)?;
+                        let admin_count = members.iter().filter(|member| member.is_admin).count();
+                        if member.is_admin && admin_count == 1 {
+                            return Err(PackageServerError::TeamMemberRemoveLastAdmin.into());
+                        }
+                        member.soft_delete().execute(&mut connection)?;
+
+                        Ok(email)
+                    })
+                    .await?;
+                Ok(email)
+            }
+            .into_actor(self),
+        )
+    }
+}
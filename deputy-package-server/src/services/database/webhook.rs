@@ -0,0 +1,334 @@
+use super::{Database, DatabaseConnection};
+use crate::errors::PackageServerError;
+use crate::models::helpers::uuid::Uuid;
+use crate::models::{
+    DeliveryStatus, NewWebhook, NewWebhookDelivery, Package, Webhook, WebhookDelivery,
+};
+use crate::schema::{webhook_deliveries, webhooks};
+use actix::{Handler, Message, ResponseActFuture, WrapFuture};
+use anyhow::{Ok, Result};
+use chrono::NaiveDateTime;
+use deputy_library::rest::{NewWebhookRest, WebhookDeliveryRest, WebhookPayloadRest, WebhookRest};
+use diesel::{
+    BoolExpressionMethods, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl,
+    SelectableHelper,
+};
+
+fn package_by_name(
+    package_name: Option<&String>,
+    connection: &mut DatabaseConnection,
+) -> Result<Option<Package>> {
+    match package_name {
+        Some(package_name) => Ok(Some(
+            Package::by_name(package_name.to_owned()).first(connection)?,
+        )),
+        None => Ok(None),
+    }
+}
+
+fn webhook_in_scope(
+    id: Uuid,
+    package_name: Option<&String>,
+    connection: &mut DatabaseConnection,
+) -> Result<Webhook> {
+    let package_id = package_by_name(package_name, connection)?.map(|package| package.id);
+    Webhook::by_id(id, package_id)
+        .first(connection)
+        .optional()?
+        .ok_or_else(|| PackageServerError::WebhookNotFound.into())
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<WebhookRest>")]
+pub struct CreateWebhook {
+    pub package_name: Option<String>,
+    pub new_webhook: NewWebhookRest,
+    pub created_by: String,
+}
+
+impl Handler<CreateWebhook> for Database {
+    type Result = ResponseActFuture<Self, Result<WebhookRest>>;
+
+    fn handle(&mut self, msg: CreateWebhook, _ctx: &mut Self::Context) -> Self::Result {
+        let CreateWebhook {
+            package_name,
+            new_webhook,
+            created_by,
+        } = msg;
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let webhook = database
+                    .run(move |mut connection| {
+                        let package = package_by_name(package_name.as_ref(), &mut connection)?;
+                        let new_webhook = NewWebhook::new(
+                            package.as_ref().map(|package| package.id),
+                            new_webhook,
+                            created_by,
+                        );
+                        new_webhook.create_insert().execute(&mut connection)?;
+                        let webhook: Webhook =
+                            Webhook::by_id(new_webhook.id, new_webhook.package_id)
+                                .first(&mut connection)?;
+                        let secret = webhook.secret.clone();
+
+                        Ok(WebhookRest {
+                            secret: Some(secret),
+                            ..webhook.into_rest(package.map(|package| package.name))
+                        })
+                    })
+                    .await?;
+                Ok(webhook)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<Vec<WebhookRest>>")]
+pub struct GetWebhooks {
+    pub package_name: Option<String>,
+}
+
+impl Handler<GetWebhooks> for Database {
+    type Result = ResponseActFuture<Self, Result<Vec<WebhookRest>>>;
+
+    fn handle(&mut self, msg: GetWebhooks, _ctx: &mut Self::Context) -> Self::Result {
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let webhooks = database
+                    .run(move |mut connection| {
+                        let package = package_by_name(msg.package_name.as_ref(), &mut connection)?;
+                        let webhooks: Vec<Webhook> =
+                            Webhook::by_package_id(package.as_ref().map(|package| package.id))
+                                .order(webhooks::created_at.asc())
+                                .load(&mut connection)?;
+
+                        Ok(webhooks
+                            .into_iter()
+                            .map(|webhook| webhook.into_rest(msg.package_name.clone()))
+                            .collect::<Vec<_>>())
+                    })
+                    .await?;
+                Ok(webhooks)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<()>")]
+pub struct DeleteWebhook {
+    pub id: Uuid,
+    pub package_name: Option<String>,
+}
+
+impl Handler<DeleteWebhook> for Database {
+    type Result = ResponseActFuture<Self, Result<()>>;
+
+    fn handle(&mut self, msg: DeleteWebhook, _ctx: &mut Self::Context) -> Self::Result {
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                database
+                    .run(move |mut connection| {
+                        let webhook =
+                            webhook_in_scope(msg.id, msg.package_name.as_ref(), &mut connection)?;
+                        webhook.soft_delete().execute(&mut connection)?;
+                        Ok(())
+                    })
+                    .await?;
+                Ok(())
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<Vec<WebhookDeliveryRest>>")]
+pub struct GetWebhookDeliveries {
+    pub webhook_id: Uuid,
+    pub package_name: Option<String>,
+}
+
+impl Handler<GetWebhookDeliveries> for Database {
+    type Result = ResponseActFuture<Self, Result<Vec<WebhookDeliveryRest>>>;
+
+    fn handle(&mut self, msg: GetWebhookDeliveries, _ctx: &mut Self::Context) -> Self::Result {
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let deliveries = database
+                    .run(move |mut connection| {
+                        let webhook = webhook_in_scope(
+                            msg.webhook_id,
+                            msg.package_name.as_ref(),
+                            &mut connection,
+                        )?;
+                        let deliveries: Vec<WebhookDelivery> =
+                            WebhookDelivery::by_webhook_id(webhook.id).load(&mut connection)?;
+
+                        Ok(deliveries
+                            .into_iter()
+                            .map(WebhookDeliveryRest::from)
+                            .collect::<Vec<_>>())
+                    })
+                    .await?;
+                Ok(deliveries)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+/// Queues a delivery for every webhook of the package and every global webhook that is
+/// subscribed to the event, the deliveries are sent by the webhook dispatcher.
+#[derive(Message)]
+#[rtype(result = "Result<()>")]
+pub struct EnqueueWebhookEvent(pub WebhookPayloadRest);
+
+impl Handler<EnqueueWebhookEvent> for Database {
+    type Result = ResponseActFuture<Self, Result<()>>;
+
+    fn handle(&mut self, msg: EnqueueWebhookEvent, _ctx: &mut Self::Context) -> Self::Result {
+        let payload = msg.0;
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                database
+                    .run(move |mut connection| {
+                        connection.write_transaction(|transaction| {
+                            let package: Option<Package> =
+                                Package::by_name_with_deleted(payload.package_name.clone())
+                                    .first(transaction)
+                                    .optional()?;
+                            let webhooks: Vec<Webhook> = match package {
+                                Some(package) => {
+                                    Webhook::subscribed_to_package(package.id).load(transaction)?
+                                }
+                                None => Webhook::by_package_id(None).load(transaction)?,
+                            };
+                            for webhook in webhooks
+                                .iter()
+                                .filter(|webhook| webhook.subscribes_to(payload.event))
+                            {
+                                NewWebhookDelivery::new(webhook.id, &payload)?
+                                    .create_insert()
+                                    .execute(transaction)?;
+                            }
+                            Ok(())
+                        })
+                    })
+                    .await?;
+                Ok(())
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<Vec<(WebhookDelivery, Webhook)>>")]
+pub struct GetDueWebhookDeliveries(pub i64);
+
+impl Handler<GetDueWebhookDeliveries> for Database {
+    type Result = ResponseActFuture<Self, Result<Vec<(WebhookDelivery, Webhook)>>>;
+
+    fn handle(&mut self, msg: GetDueWebhookDeliveries, _ctx: &mut Self::Context) -> Self::Result {
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let deliveries = database
+                    .run(move |mut connection| {
+                        let deliveries = webhook_deliveries::table
+                            .inner_join(webhooks::table)
+                            .filter(webhook_deliveries::status.eq(DeliveryStatus::Pending.as_str()))
+                            .filter(webhook_deliveries::next_attempt_at.le(diesel::dsl::now))
+                            .filter(
+                                webhook_deliveries::claimed_until
+                                    .is_null()
+                                    .or(webhook_deliveries::claimed_until.le(diesel::dsl::now)),
+                            )
+                            .filter(webhooks::deleted_at.is_null())
+                            .order(webhook_deliveries::next_attempt_at.asc())
+                            .limit(msg.0)
+                            .select((WebhookDelivery::as_select(), Webhook::as_select()))
+                            .load(&mut connection)?;
+                        Ok(deliveries)
+                    })
+                    .await?;
+                Ok(deliveries)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+/// Answers whether the delivery was claimed, a delivery that another replica claimed first is
+/// left to that replica.
+#[derive(Message)]
+#[rtype(result = "Result<bool>")]
+pub struct ClaimWebhookDelivery {
+    pub delivery: WebhookDelivery,
+    pub claimed_until: NaiveDateTime,
+}
+
+impl Handler<ClaimWebhookDelivery> for Database {
+    type Result = ResponseActFuture<Self, Result<bool>>;
+
+    fn handle(&mut self, msg: ClaimWebhookDelivery, _ctx: &mut Self::Context) -> Self::Result {
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                let is_claimed = database
+                    .run(move |mut connection| {
+                        let updated_rows = msg
+                            .delivery
+                            .claim(msg.claimed_until)
+                            .execute(&mut connection)?;
+                        Ok(updated_rows == 1)
+                    })
+                    .await?;
+                Ok(is_claimed)
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<()>")]
+pub struct UpdateWebhookDelivery(pub WebhookDelivery);
+
+impl Handler<UpdateWebhookDelivery> for Database {
+    type Result = ResponseActFuture<Self, Result<()>>;
+
+    fn handle(&mut self, msg: UpdateWebhookDelivery, _ctx: &mut Self::Context) -> Self::Result {
+        let delivery = msg.0;
+        let database = self.clone();
+
+        Box::pin(
+            async move {
+                database
+                    .run(move |mut connection| {
+                        delivery.create_update().execute(&mut connection)?;
+                        Ok(())
+                    })
+                    .await?;
+                Ok(())
+            }
+            .into_actor(self),
+        )
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{
+    configuration::HealthConfiguration,
+    middleware::authentication::jwt::decoding_key,
+    services::database::{CheckDatabaseHealth, GetPoolState},
+    AppState,
+};
+use actix::{dev::ToEnvelope, Actor, Handler};
+use actix_rt::time::timeout;
+use deputy_library::rest::{ComponentHealthRest, HealthRest, HealthStatus};
+use std::{collections::BTreeMap, time::Duration};
+
+fn down(message: String) -> ComponentHealthRest {
+    ComponentHealthRest::new(HealthStatus::Down).message(message)
+}
+
+/// Checks the dependencies of the server for the liveness and readiness endpoints.
+#[derive(Debug, Clone)]
+pub struct HealthChecker {
+    configuration: HealthConfiguration,
+    jwt_pem_content: String,
+}
+
+impl HealthChecker {
+    pub fn new(configuration: HealthConfiguration, jwt_pem_content: String) -> Self {
+        Self {
+            configuration,
+            jwt_pem_content,
+        }
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.configuration.timeout_seconds)
+    }
+
+    /// The server is alive as long as the database actor handles its messages, the dependencies
+    /// are only checked for readiness so that an outage of them does not restart the server.
+    pub async fn liveness<T>(&self, app_state: &AppState<T>) -> HealthRest
+    where
+        T: Actor + Handler<GetPoolState>,
+        <T as Actor>::Context: ToEnvelope<T, GetPoolState>,
+    {
+        let database_actor = match timeout(
+            self.timeout(),
+            app_state.database_address.send(GetPoolState),
+        )
+        .await
+        {
+            Ok(Ok(_)) => ComponentHealthRest::new(HealthStatus::Up),
+            Ok(Err(error)) => down(format!("Database actor mailbox failed: {error}")),
+            Err(_) => down("Database actor did not answer in time".to_string()),
+        };
+        BTreeMap::from([("databaseActor".to_string(), database_actor)]).into()
+    }
+
+    pub async fn readiness<T>(&self, app_state: &AppState<T>) -> HealthRest
+    where
+        T: Actor + Handler<CheckDatabaseHealth>,
+        <T as Actor>::Context: ToEnvelope<T, CheckDatabaseHealth>,
+    {
+        let (database, storage) = futures::join!(
+            self.check_database(app_state),
+            self.check_storage(app_state)
+        );
+        BTreeMap::from([
+            ("database".to_string(), database),
+            ("storage".to_string(), storage),
+            ("jwtKey".to_string(), self.check_jwt_key()),
+        ])
+        .into()
+    }
+
+    async fn check_database<T>(&self, app_state: &AppState<T>) -> ComponentHealthRest
+    where
+        T: Actor + Handler<CheckDatabaseHealth>,
+        <T as Actor>::Context: ToEnvelope<T, CheckDatabaseHealth>,
+    {
+        match timeout(
+            self.timeout(),
+            app_state.database_address.send(CheckDatabaseHealth),
+        )
+        .await
+        {
+            Ok(Ok(Ok(pending_migrations))) if pending_migrations.is_empty() => {
+                ComponentHealthRest::new(HealthStatus::Up)
+            }
+            Ok(Ok(Ok(pending_migrations))) => ComponentHealthRest {
+                pending_migrations,
+                ..down("Database migrations are pending".to_string())
+            },
+            Ok(Ok(Err(error))) => down(format!("Database is not reachable: {error}")),
+            Ok(Err(error)) => down(format!("Database actor mailbox failed: {error}")),
+            Err(_) => down("Database did not answer in time".to_string()),
+        }
+    }
+
+    async fn check_storage<T>(&self, app_state: &AppState<T>) -> ComponentHealthRest
+    where
+        T: Actor,
+    {
+        let free_bytes = match timeout(self.timeout(), app_state.storage.check_health()).await {
+            Ok(Ok(free_bytes)) => free_bytes,
+            Ok(Err(error)) => return down(format!("Storage is not writable: {error}")),
+            Err(_) => return down("Storage did not answer in time".to_string()),
+        };
+        let component = match free_bytes {
+            Some(free_bytes) if free_bytes < self.configuration.minimum_free_bytes => {
+                down(format!(
+                    "Free space is below the minimum of {} bytes",
+                    self.configuration.minimum_free_bytes
+                ))
+            }
+            Some(free_bytes) if free_bytes < self.configuration.warning_free_bytes => {
+                ComponentHealthRest::new(HealthStatus::Degraded).message(format!(
+                    "Free space is below the warning threshold of {} bytes",
+                    self.configuration.warning_free_bytes
+                ))
+            }
+            _ => ComponentHealthRest::new(HealthStatus::Up),
+        };
+        ComponentHealthRest {
+            free_bytes,
+            ..component
+        }
+    }
+
+    fn check_jwt_key(&self) -> ComponentHealthRest {
+        match decoding_key(&self.jwt_pem_content) {
+            Ok(_) => ComponentHealthRest::new(HealthStatus::Up),
+            Err(error) => down(format!("Keycloak public key can not be loaded: {error}")),
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+/// Upper bounds in seconds of the request duration histogram buckets.
+const DURATION_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct RouteLabels {
+    method: String,
+    route: String,
+}
+
+#[derive(Debug, Default)]
+struct RouteStatistics {
+    responses_by_status: BTreeMap<u16, u64>,
+    bucket_counts: [u64; DURATION_BUCKETS.len()],
+    duration_sum: f64,
+    count: u64,
+}
+
+/// Snapshot of the database connection pool and how long a message waited in the database actor
+/// mailbox, taken when the metrics are scraped.
+#[derive(Debug, Clone, Copy)]
+pub struct DatabaseMetrics {
+    pub mailbox_latency: Duration,
+    pub connections: u32,
+    pub idle_connections: u32,
+    pub max_connections: u32,
+}
+
+/// Registry counters, rendered in the Prometheus text exposition format. Requests are labelled by
+/// the matched route pattern so that package names do not create new series.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    routes: Mutex<BTreeMap<RouteLabels, RouteStatistics>>,
+    uploaded_bytes: AtomicU64,
+    downloaded_bytes: AtomicU64,
+    published_versions: AtomicU64,
+    yanked_versions: AtomicU64,
+    unyanked_versions: AtomicU64,
+}
+
+impl Metrics {
+    pub fn record_request(&self, method: &str, route: &str, status: u16, duration: Duration) {
+        let mut routes = self
+            .routes
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        let statistics = routes
+            .entry(RouteLabels {
+                method: method.to_string(),
+                route: route.to_string(),
+            })
+            .or_default();
+        *statistics.responses_by_status.entry(status).or_default() += 1;
+        let seconds = duration.as_secs_f64();
+        for (bucket_count, upper_bound) in statistics.bucket_counts.iter_mut().zip(DURATION_BUCKETS)
+        {
+            if seconds <= upper_bound {
+                *bucket_count += 1;
+            }
+        }
+        statistics.duration_sum += seconds;
+        statistics.count += 1;
+    }
+
+    pub fn record_upload(&self, bytes: u64) {
+        self.uploaded_bytes.fetch_add(bytes, Ordering::Relaxed);
+        self.published_versions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_download(&self, bytes: u64) {
+        self.downloaded_bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn record_yank(&self, is_yanked: bool) {
+        match is_yanked {
+            true => self.yanked_versions.fetch_add(1, Ordering::Relaxed),
+            false => self.unyanked_versions.fetch_add(1, Ordering::Relaxed),
+        };
+    }
+
+    pub fn render(
+        &self,
+        database_metrics: Option<DatabaseMetrics>,
+        storage_used_bytes: Option<u64>,
+    ) -> String {
+        let mut output = String::new();
+        {
+            let routes = self
+                .routes
+                .lock()
+                .unwrap_or_else(|error| error.into_inner());
+            write_header(
+                &mut output,
+                "deputy_http_requests_total",
+                "counter",
+                "HTTP requests by method, route and response status.",
+            );
+            for (labels, statistics) in routes.iter() {
+                for (status, count) in &statistics.responses_by_status {
+                    let _ = writeln!(
+                        output,
+                        "deputy_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{status}\"}} {count}",
+                        escape_label(&labels.method),
+                        escape_label(&labels.route),
+                    );
+                }
+            }
+            write_header(
+                &mut output,
+                "deputy_http_request_duration_seconds",
+                "histogram",
+                "HTTP request duration by method and route.",
+            );
+            for (labels, statistics) in routes.iter() {
+                let label_prefix = format!(
+                    "method=\"{}\",route=\"{}\"",
+                    escape_label(&labels.method),
+                    escape_label(&labels.route)
+                );
+                for (bucket_count, upper_bound) in
+                    statistics.bucket_counts.iter().zip(DURATION_BUCKETS)
+                {
+                    let _ = writeln!(
+                        output,
+                        "deputy_http_request_duration_seconds_bucket{{{label_prefix},le=\"{upper_bound}\"}} {bucket_count}"
+                    );
+                }
+                let _ = writeln!(
+                    output,
+                    "deputy_http_request_duration_seconds_bucket{{{label_prefix},le=\"+Inf\"}} {}",
+                    statistics.count
+                );
+                let _ = writeln!(
+                    output,
+                    "deputy_http_request_duration_seconds_sum{{{label_prefix}}} {}",
+                    statistics.duration_sum
+                );
+                let _ = writeln!(
+                    output,
+                    "deputy_http_request_duration_seconds_count{{{label_prefix}}} {}",
+                    statistics.count
+                );
+            }
+        }
+
+        write_metric(
+            &mut output,
+            "deputy_uploaded_bytes_total",
+            "counter",
+            "Bytes of package archives received by publishing.",
+            self.uploaded_bytes.load(Ordering::Relaxed),
+        );
+        write_metric(
+            &mut output,
+            "deputy_downloaded_bytes_total",
+            "counter",
+            "Bytes of package archives and files served for download.",
+            self.downloaded_bytes.load(Ordering::Relaxed),
+        );
+        write_metric(
+            &mut output,
+            "deputy_published_versions_total",
+            "counter",
+            "Package versions published.",
+            self.published_versions.load(Ordering::Relaxed),
+        );
+        write_header(
+            &mut output,
+            "deputy_yank_changes_total",
+            "counter",
+            "Package versions yanked or unyanked.",
+        );
+        let _ = writeln!(
+            output,
+            "deputy_yank_changes_total{{action=\"yank\"}} {}",
+            self.yanked_versions.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(
+            output,
+            "deputy_yank_changes_total{{action=\"unyank\"}} {}",
+            self.unyanked_versions.load(Ordering::Relaxed)
+        );
+
+        if let Some(database_metrics) = database_metrics {
+            write_metric(
+                &mut output,
+                "deputy_database_mailbox_latency_seconds",
+                "gauge",
+                "Round trip of a message through the database actor mailbox.",
+                database_metrics.mailbox_latency.as_secs_f64(),
+            );
+            write_metric(
+                &mut output,
+                "deputy_database_pool_connections",
+                "gauge",
+                "Open database connections in the pool.",
+                database_metrics.connections,
+            );
+            write_metric(
+                &mut output,
+                "deputy_database_pool_idle_connections",
+                "gauge",
+                "Idle database connections in the pool.",
+                database_metrics.idle_connections,
+            );
+            write_metric(
+                &mut output,
+                "deputy_database_pool_max_connections",
+                "gauge",
+                "Maximum size of the database connection pool.",
+                database_metrics.max_connections,
+            );
+        }
+        if let Some(storage_used_bytes) = storage_used_bytes {
+            write_metric(
+                &mut output,
+                "deputy_storage_used_bytes",
+                "gauge",
+                "Bytes used by the stored package archives.",
+                storage_used_bytes,
+            );
+        }
+        output
+    }
+}
+
+fn write_header(output: &mut String, name: &str, metric_type: &str, help: &str) {
+    let _ = writeln!(output, "# HELP {name} {help}");
+    let _ = writeln!(output, "# TYPE {name} {metric_type}");
+}
+
+fn write_metric(
+    output: &mut String,
+    name: &str,
+    metric_type: &str,
+    help: &str,
+    value: impl std::fmt::Display,
+) {
+    write_header(output, name, metric_type, help);
+    let _ = writeln!(output, "{name} {value}");
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DatabaseMetrics, Metrics};
+    use std::time::Duration;
+
+    #[test]
+    fn renders_requests_per_route_and_counters() {
+        let metrics = Metrics::default();
+        metrics.record_request(
+            "GET",
+            "/api/v1/package/{package_name}",
+            200,
+            Duration::from_millis(20),
+        );
+        metrics.record_request(
+            "GET",
+            "/api/v1/package/{package_name}",
+            404,
+            Duration::from_secs(3),
+        );
+        metrics.record_upload(1024);
+        metrics.record_download(512);
+        metrics.record_yank(true);
+
+        let output = metrics.render(
+            Some(DatabaseMetrics {
+                mailbox_latency: Duration::from_millis(2),
+                connections: 3,
+                idle_connections: 2,
+                max_connections: 10,
+            }),
+            Some(4096),
+        );
+
+        assert!(output.contains(
+            "deputy_http_requests_total{method=\"GET\",route=\"/api/v1/package/{package_name}\",status=\"404\"} 1"
+        ));
+        assert!(output.contains(
+            "deputy_http_request_duration_seconds_bucket{method=\"GET\",route=\"/api/v1/package/{package_name}\",le=\"0.025\"} 1"
+        ));
+        assert!(output.contains(
+            "deputy_http_request_duration_seconds_count{method=\"GET\",route=\"/api/v1/package/{package_name}\"} 2"
+        ));
+        assert!(output.contains("deputy_uploaded_bytes_total 1024"));
+        assert!(output.contains("deputy_downloaded_bytes_total 512"));
+        assert!(output.contains("deputy_published_versions_total 1"));
+        assert!(output.contains("deputy_yank_changes_total{action=\"yank\"} 1"));
+        assert!(output.contains("deputy_database_pool_idle_connections 2"));
+        assert!(output.contains("deputy_storage_used_bytes 4096"));
+    }
+}
@@ -1 +1,8 @@
+pub mod backup;
 pub mod database;
+pub mod health;
+pub mod metrics;
+pub mod rate_limit;
+pub mod scrub;
+pub mod storage;
+pub mod webhook;
@@ -0,0 +1,143 @@
+use crate::{
+    configuration::{RateLimitBudget, RateLimitConfiguration},
+    middleware::authentication::jwt::Token,
+};
+use actix_web::http::Method;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// How often buckets that have refilled completely are dropped, a full bucket behaves the same as
+/// a missing one.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateLimitCategory {
+    Read,
+    Download,
+    Publish,
+}
+
+impl RateLimitCategory {
+    /// Category of an API request by its method and path, `path` includes the `/api/v1` prefix.
+    pub fn of(method: &Method, path: &str) -> Self {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match (method, segments.as_slice()) {
+            (&Method::POST, ["api", "v1", "package"]) => RateLimitCategory::Publish,
+            (&Method::GET, ["api", "v1", "package", _, _, "download"])
+            | (&Method::GET, ["api", "v1", "package", _, _, "path", ..]) => {
+                RateLimitCategory::Download
+            }
+            _ => RateLimitCategory::Read,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+#[derive(Debug)]
+struct RateLimiterState {
+    buckets: HashMap<(String, RateLimitCategory), Bucket>,
+    known_token_digests: HashSet<String>,
+    swept_at: Instant,
+}
+
+/// Token buckets per client and category, shared by every worker of the server.
+pub struct RateLimiter {
+    pub configuration: RateLimitConfiguration,
+    jwt_decoding_key: Option<DecodingKey>,
+    state: Mutex<RateLimiterState>,
+}
+
+impl RateLimiter {
+    /// Without `jwt_decoding_key` JWT users are told apart by their IP address only.
+    pub fn new(
+        configuration: RateLimitConfiguration,
+        jwt_decoding_key: Option<DecodingKey>,
+    ) -> Self {
+        Self {
+            configuration,
+            jwt_decoding_key,
+            state: Mutex::new(RateLimiterState {
+                buckets: HashMap::new(),
+                known_token_digests: HashSet::new(),
+                swept_at: Instant::now(),
+            }),
+        }
+    }
+
+    fn budget(&self, category: RateLimitCategory) -> RateLimitBudget {
+        match category {
+            RateLimitCategory::Read => self.configuration.read,
+            RateLimitCategory::Download => self.configuration.download,
+            RateLimitCategory::Publish => self.configuration.publish,
+        }
+    }
+
+    /// Takes a request from the bucket of the client, returns how long the client has to wait when
+    /// the bucket is empty.
+    pub fn acquire(&self, client_key: &str, category: RateLimitCategory) -> Result<(), Duration> {
+        let budget = self.budget(category);
+        let burst = f64::from(budget.burst);
+        let refill_per_second = f64::from(budget.requests_per_minute) / 60.0;
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap_or_else(|error| error.into_inner());
+        if now.duration_since(state.swept_at) >= SWEEP_INTERVAL {
+            state.buckets.retain(|(_, category), bucket| {
+                let budget = self.budget(*category);
+                let refilled = bucket.tokens
+                    + now.duration_since(bucket.updated_at).as_secs_f64()
+                        * f64::from(budget.requests_per_minute)
+                        / 60.0;
+                refilled < f64::from(budget.burst)
+            });
+            state.swept_at = now;
+        }
+
+        let bucket = state
+            .buckets
+            .entry((client_key.to_string(), category))
+            .or_insert(Bucket {
+                tokens: burst,
+                updated_at: now,
+            });
+        bucket.tokens = (bucket.tokens
+            + now.duration_since(bucket.updated_at).as_secs_f64() * refill_per_second)
+            .min(burst);
+        bucket.updated_at = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        Err(Duration::from_secs_f64(
+            (1.0 - bucket.tokens) / refill_per_second,
+        ))
+    }
+
+    /// Subject of a JWT with a valid signature.
+    pub fn jwt_subject(&self, token: &str) -> Option<String> {
+        let decoding_key = self.jwt_decoding_key.as_ref()?;
+        jsonwebtoken::decode::<Token>(token, decoding_key, &Validation::new(Algorithm::RS256))
+            .ok()
+            .map(|data| data.claims.sub)
+    }
+
+    /// API tokens are looked up from the database only once, afterwards the digest of a known
+    /// token identifies the client.
+    pub fn is_known_token(&self, token_digest: &str) -> bool {
+        let state = self.state.lock().unwrap_or_else(|error| error.into_inner());
+        state.known_token_digests.contains(token_digest)
+    }
+
+    pub fn add_known_token(&self, token_digest: String) {
+        let mut state = self.state.lock().unwrap_or_else(|error| error.into_inner());
+        state.known_token_digests.insert(token_digest);
+    }
+}
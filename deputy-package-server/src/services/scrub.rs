@@ -0,0 +1,169 @@
+use crate::configuration::{OrphanAction, ScrubConfiguration};
+use crate::services::database::{
+    package::{GetStoredVersions, UpdateVersionMsg},
+    Database,
+};
+use crate::services::storage::{package_key, Storage, QUARANTINE_PREFIX};
+use actix::{Actor, ActorFutureExt, Addr, AsyncContext, Context, WrapFuture};
+use actix_web::web::block;
+use anyhow::{anyhow, Result};
+use log::{error, info, warn};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeSet, fs::File, io::copy, path::PathBuf, sync::Arc, time::Duration};
+
+/// Outcome of a storage scrub, the archives are named by their storage keys.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ScrubReport {
+    pub verified: usize,
+    pub missing: Vec<String>,
+    pub corrupted: Vec<String>,
+    pub orphaned: Vec<String>,
+    pub marked_unavailable: Vec<String>,
+    pub marked_available: Vec<String>,
+}
+
+impl ScrubReport {
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.corrupted.is_empty() && self.orphaned.is_empty()
+    }
+}
+
+pub(crate) async fn file_checksum(path: PathBuf) -> Result<String> {
+    block(move || {
+        let mut file = File::open(path)?;
+        let mut hasher = Sha256::new();
+        copy(&mut file, &mut hasher)?;
+        Ok(format!("{:x}", hasher.finalize()))
+    })
+    .await?
+}
+
+/// Walks the storage and the versions, verifies every archive against the checksum of its version
+/// and handles the archives that no version refers to as configured.
+pub struct StorageScrubber {
+    database_address: Addr<Database>,
+    storage: Arc<dyn Storage>,
+    configuration: ScrubConfiguration,
+    scrubbing: bool,
+}
+
+impl StorageScrubber {
+    pub fn new(
+        database_address: Addr<Database>,
+        storage: Arc<dyn Storage>,
+        configuration: ScrubConfiguration,
+    ) -> Self {
+        Self {
+            database_address,
+            storage,
+            configuration,
+            scrubbing: false,
+        }
+    }
+
+    async fn quarantine(storage: &dyn Storage, key: &str) -> Result<()> {
+        let stored_file = storage
+            .fetch(key)
+            .await?
+            .ok_or_else(|| anyhow!("{key} disappeared from the storage"))?;
+        storage
+            .put(&format!("{QUARANTINE_PREFIX}/{key}"), stored_file.path())
+            .await?;
+        storage.delete(key).await
+    }
+
+    /// A package being published has its archive stored before its version is created, so an
+    /// archive without a version is only taken for an orphan once it is older than
+    /// `orphan_grace_seconds`.
+    pub async fn scrub(
+        database_address: Addr<Database>,
+        storage: Arc<dyn Storage>,
+        configuration: ScrubConfiguration,
+    ) -> Result<ScrubReport> {
+        let mut orphaned_keys: BTreeSet<String> = storage.keys().await?.into_iter().collect();
+        let versions = database_address.send(GetStoredVersions).await??;
+        let mut report = ScrubReport::default();
+
+        for (package_name, mut version) in versions {
+            let key = package_key(&package_name, &version.version);
+            orphaned_keys.remove(&key);
+            let is_intact = match storage.fetch(&key).await? {
+                None => {
+                    report.missing.push(key.clone());
+                    false
+                }
+                Some(stored_file) => {
+                    if file_checksum(stored_file.path().to_path_buf()).await? == version.checksum {
+                        report.verified += 1;
+                        true
+                    } else {
+                        report.corrupted.push(key.clone());
+                        false
+                    }
+                }
+            };
+            if configuration.mark_unavailable && version.is_unavailable == is_intact {
+                version.is_unavailable = !is_intact;
+                let id = version.id;
+                database_address
+                    .send(UpdateVersionMsg { id, version })
+                    .await??;
+                match is_intact {
+                    true => report.marked_available.push(key),
+                    false => report.marked_unavailable.push(key),
+                }
+            }
+        }
+
+        let orphan_grace = Duration::from_secs(configuration.orphan_grace_seconds);
+        for key in orphaned_keys {
+            let is_settled = match storage.modified_at(&key).await? {
+                Some(modified_at) => modified_at.elapsed().is_ok_and(|age| age >= orphan_grace),
+                None => false,
+            };
+            if !is_settled {
+                continue;
+            }
+            match configuration.orphan_action {
+                OrphanAction::Report => {}
+                OrphanAction::Quarantine => Self::quarantine(storage.as_ref(), &key).await?,
+                OrphanAction::Delete => storage.delete(&key).await?,
+            }
+            report.orphaned.push(key);
+        }
+        Ok(report)
+    }
+}
+
+impl Actor for StorageScrubber {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let interval = Duration::from_secs(self.configuration.interval_seconds);
+        ctx.run_interval(interval, |scrubber, ctx| {
+            if scrubber.scrubbing {
+                return;
+            }
+            scrubber.scrubbing = true;
+            let scrub = Self::scrub(
+                scrubber.database_address.clone(),
+                scrubber.storage.clone(),
+                scrubber.configuration.clone(),
+            );
+            ctx.spawn(scrub.into_actor(scrubber).map(|result, scrubber, _| {
+                match result {
+                    Ok(report) if report.is_clean() => {
+                        info!("Storage scrub verified {} archives", report.verified)
+                    }
+                    Ok(report) => warn!(
+                        "Storage scrub verified {} archives, missing: {:?}, corrupted: {:?}, \
+                        orphaned: {:?}",
+                        report.verified, report.missing, report.corrupted, report.orphaned
+                    ),
+                    Err(error) => error!("Failed to scrub the storage: {error}"),
+                }
+                scrubber.scrubbing = false;
+            }));
+        });
+    }
+}
@@ -0,0 +1,178 @@
+use super::{is_package_key, Storage, StorageFuture, StoredFile};
+use actix_web::web::block;
+use anyhow::{anyhow, Result};
+use fs4::available_space;
+use std::{
+    fs::{copy, create_dir_all, metadata, read_dir, remove_dir_all, remove_file},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+use tempfile::NamedTempFile;
+
+/// How long a walk of the package folder is reused by `used_bytes`, so frequent metrics scrapes
+/// do not walk the whole folder every time.
+const USED_BYTES_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Stores the archives as `<package_folder>/<name>/<version>` files.
+#[derive(Debug, Clone)]
+pub struct FilesystemStorage {
+    package_folder: PathBuf,
+    used_bytes: Arc<Mutex<Option<(Instant, u64)>>>,
+}
+
+impl FilesystemStorage {
+    pub fn new(package_folder: &str) -> Self {
+        Self {
+            package_folder: PathBuf::from(package_folder),
+            used_bytes: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    fn cached_used_bytes(&self) -> Option<u64> {
+        let used_bytes = self.used_bytes.lock().ok()?;
+        used_bytes
+            .filter(|(walked_at, _)| walked_at.elapsed() < USED_BYTES_REFRESH_INTERVAL)
+            .map(|(_, used_bytes)| used_bytes)
+    }
+}
+
+fn list_keys(package_folder: &Path) -> Result<Vec<String>> {
+    let mut keys = Vec::new();
+    if !package_folder.is_dir() {
+        return Ok(keys);
+    }
+    for package_entry in read_dir(package_folder)? {
+        let package_entry = package_entry?;
+        if !package_entry.file_type()?.is_dir() {
+            continue;
+        }
+        for version_entry in read_dir(package_entry.path())? {
+            let version_entry = version_entry?;
+            if !version_entry.file_type()?.is_file() {
+                continue;
+            }
+            let key = format!(
+                "{}/{}",
+                package_entry.file_name().to_string_lossy(),
+                version_entry.file_name().to_string_lossy()
+            );
+            if is_package_key(&key) {
+                keys.push(key);
+            }
+        }
+    }
+    keys.sort();
+    Ok(keys)
+}
+
+fn walk_used_bytes(package_folder: &Path) -> Result<u64> {
+    if !package_folder.is_dir() {
+        return Ok(0);
+    }
+    let mut used_bytes = 0;
+    for package_entry in read_dir(package_folder)? {
+        let package_path = package_entry?.path();
+        if !package_path.is_dir() {
+            continue;
+        }
+        for version_entry in read_dir(package_path)? {
+            let metadata = version_entry?.metadata()?;
+            if metadata.is_file() {
+                used_bytes += metadata.len();
+            }
+        }
+    }
+    Ok(used_bytes)
+}
+
+impl Storage for FilesystemStorage {
+    fn put<'a>(&'a self, key: &'a str, file_path: &'a Path) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            let target_path = self.package_folder.join(key);
+            let file_path = file_path.to_path_buf();
+            block(move || {
+                let target_folder = target_path
+                    .parent()
+                    .ok_or_else(|| anyhow!("Could not get parent path from {target_path:?}"))?;
+                create_dir_all(target_folder)?;
+                copy(file_path, &target_path)?;
+                Ok(())
+            })
+            .await?
+        })
+    }
+
+    fn fetch<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Option<StoredFile>> {
+        Box::pin(async move {
+            let path = self.package_folder.join(key);
+            Ok(path.is_file().then_some(StoredFile::Local(path)))
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            let path = self.package_folder.join(key);
+            if path.is_file() {
+                remove_file(path)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn delete_package<'a>(&'a self, package_name: &'a str) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            let path = self.package_folder.join(package_name.to_lowercase());
+            block(move || {
+                if path.is_dir() {
+                    remove_dir_all(path)?;
+                }
+                Ok(())
+            })
+            .await?
+        })
+    }
+
+    fn keys(&self) -> StorageFuture<'_, Vec<String>> {
+        Box::pin(async move {
+            let package_folder = self.package_folder.clone();
+            block(move || list_keys(&package_folder)).await?
+        })
+    }
+
+    fn modified_at<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Option<SystemTime>> {
+        Box::pin(async move {
+            let path = self.package_folder.join(key);
+            block(move || match path.is_file() {
+                true => Ok(Some(metadata(path)?.modified()?)),
+                false => Ok(None),
+            })
+            .await?
+        })
+    }
+
+    /// Reuses the last walk of the package folder for `USED_BYTES_REFRESH_INTERVAL`.
+    fn used_bytes(&self) -> StorageFuture<'_, Option<u64>> {
+        Box::pin(async move {
+            if let Some(used_bytes) = self.cached_used_bytes() {
+                return Ok(Some(used_bytes));
+            }
+            let package_folder = self.package_folder.clone();
+            let used_bytes = block(move || walk_used_bytes(&package_folder)).await??;
+            if let Ok(mut cached_used_bytes) = self.used_bytes.lock() {
+                *cached_used_bytes = Some((Instant::now(), used_bytes));
+            }
+            Ok(Some(used_bytes))
+        })
+    }
+
+    /// The probe file is a hidden file in the package folder, `used_bytes` only counts the files
+    /// in the package directories.
+    fn check_health(&self) -> StorageFuture<'_, Option<u64>> {
+        Box::pin(async move {
+            create_dir_all(&self.package_folder)?;
+            NamedTempFile::new_in(&self.package_folder)?.close()?;
+            Ok(Some(available_space(&self.package_folder)?))
+        })
+    }
+}
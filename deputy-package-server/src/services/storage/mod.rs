@@ -0,0 +1,91 @@
+mod filesystem;
+mod s3;
+
+pub use filesystem::FilesystemStorage;
+pub use s3::S3Storage;
+
+use crate::configuration::{Configuration, StorageConfiguration};
+use anyhow::Result;
+use futures::future::LocalBoxFuture;
+use std::{
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+use tempfile::NamedTempFile;
+
+pub type StorageFuture<'a, T> = LocalBoxFuture<'a, Result<T>>;
+
+/// Orphaned archives are moved under this prefix by the storage scrub, `keys` does not list them.
+pub const QUARANTINE_PREFIX: &str = ".quarantine";
+
+/// Keeps the package archives, every archive is stored under the key returned by `package_key`.
+pub trait Storage: Debug + Send + Sync {
+    /// Stores the archive at `file_path` under `key`, an existing archive is replaced.
+    fn put<'a>(&'a self, key: &'a str, file_path: &'a Path) -> StorageFuture<'a, ()>;
+
+    /// Makes the archive available as a local file, `None` when nothing is stored under `key`.
+    fn fetch<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Option<StoredFile>>;
+
+    /// Removes the archive stored under `key`, a missing archive is not an error.
+    fn delete<'a>(&'a self, key: &'a str) -> StorageFuture<'a, ()>;
+
+    /// Removes every archive of the package, a package without archives is not an error.
+    fn delete_package<'a>(&'a self, package_name: &'a str) -> StorageFuture<'a, ()>;
+
+    /// Keys of every stored archive, keys outside of the `<name>/<version>` layout are left out.
+    fn keys(&self) -> StorageFuture<'_, Vec<String>>;
+
+    /// When the archive under `key` was last written, `None` when nothing is stored under `key`.
+    fn modified_at<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Option<SystemTime>>;
+
+    /// Total size of the stored archives, `None` when the backend can not tell it cheaply.
+    fn used_bytes(&self) -> StorageFuture<'_, Option<u64>>;
+
+    /// Checks that the storage accepts writes and returns its free space, `None` when the backend
+    /// has no notion of free space.
+    fn check_health(&self) -> StorageFuture<'_, Option<u64>>;
+}
+
+/// A stored archive on the local disk, archives of remote backends are downloaded into a
+/// temporary file that is removed when this is dropped.
+#[derive(Debug)]
+pub enum StoredFile {
+    Local(PathBuf),
+    Temporary(NamedTempFile),
+}
+
+impl StoredFile {
+    pub fn path(&self) -> &Path {
+        match self {
+            StoredFile::Local(path) => path,
+            StoredFile::Temporary(file) => file.path(),
+        }
+    }
+}
+
+pub fn package_key(name: &str, version: &str) -> String {
+    format!("{}/{}", name.to_lowercase(), version.to_lowercase())
+}
+
+/// Whether `key` has the `<name>/<version>` layout of `package_key`, hidden names such as the
+/// quarantine and the health check probes are not package keys.
+pub fn is_package_key(key: &str) -> bool {
+    let parts: Vec<&str> = key.split('/').collect();
+    parts.len() == 2
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && !part.starts_with('.'))
+}
+
+pub fn storage_from_configuration(configuration: &Configuration) -> Result<Arc<dyn Storage>> {
+    Ok(match &configuration.storage {
+        StorageConfiguration::Filesystem => {
+            Arc::new(FilesystemStorage::new(&configuration.package_folder))
+        }
+        StorageConfiguration::S3(s3_configuration) => {
+            Arc::new(S3Storage::try_new(s3_configuration.clone())?)
+        }
+    })
+}
@@ -0,0 +1,246 @@
+use crate::configuration::WebhookConfiguration;
+use crate::models::{
+    sign_payload, Webhook, WebhookDelivery, WEBHOOK_DELIVERY_HEADER, WEBHOOK_EVENT_HEADER,
+    WEBHOOK_SIGNATURE_HEADER,
+};
+use crate::services::database::{
+    webhook::{ClaimWebhookDelivery, GetDueWebhookDeliveries, UpdateWebhookDelivery},
+    Database,
+};
+use actix::{Actor, ActorFutureExt, Addr, AsyncContext, Context, WrapFuture};
+use actix_web::http::Uri;
+use anyhow::{anyhow, Result};
+use awc::Client;
+use chrono::Utc;
+use log::{debug, error};
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+use tokio::net::lookup_host;
+
+/// A claimed delivery is left to its replica for the request timeout plus this margin, after
+/// that it is taken for abandoned and can be claimed again.
+const CLAIM_MARGIN_SECONDS: u64 = 30;
+
+/// Addresses that are reachable from the internet, webhooks are not delivered to loopback,
+/// private, link-local and other special purpose addresses unless private targets are allowed.
+pub fn is_public_address(address: IpAddr) -> bool {
+    match address {
+        IpAddr::V4(address) => {
+            let [first, second, ..] = address.octets();
+            !(address.is_private()
+                || address.is_loopback()
+                || address.is_link_local()
+                || address.is_unspecified()
+                || address.is_broadcast()
+                || address.is_multicast()
+                || address.is_documentation()
+                || first == 0
+                || (first == 100 && (64..128).contains(&second)))
+        }
+        IpAddr::V6(address) => match address.to_ipv4_mapped() {
+            Some(mapped_address) => is_public_address(IpAddr::V4(mapped_address)),
+            None => {
+                let first_segment = address.segments()[0];
+                !(address.is_loopback()
+                    || address.is_unspecified()
+                    || address.is_multicast()
+                    || (first_segment & 0xfe00) == 0xfc00
+                    || (first_segment & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+/// Resolves the host of the webhook url when the delivery is sent, rather than when the webhook
+/// is created, so a host that later resolves to an internal address is refused as well. The
+/// request is sent to the returned address so it can not be resolved differently in between.
+pub async fn resolve_target(url: &str, allow_private_targets: bool) -> Result<SocketAddr> {
+    let uri: Uri = url.parse()?;
+    let host = uri
+        .host()
+        .ok_or_else(|| anyhow!("Webhook url {url} has no host"))?
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let port = uri.port_u16().unwrap_or(match uri.scheme_str() {
+        Some("https") => 443,
+        _ => 80,
+    });
+    let addresses: Vec<SocketAddr> = lookup_host((host, port)).await?.collect();
+    if !allow_private_targets {
+        if let Some(address) = addresses
+            .iter()
+            .find(|address| !is_public_address(address.ip()))
+        {
+            return Err(anyhow!(
+                "Webhook host {host} resolves to the internal address {}",
+                address.ip()
+            ));
+        }
+    }
+    addresses
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("Webhook host {host} did not resolve to any address"))
+}
+
+/// Polls the delivery queue and sends every due webhook delivery, the outcome of each attempt is
+/// written back so that failed deliveries are picked up again after their backoff. Every replica
+/// polls the same queue, a delivery is claimed before it is sent so it is sent only once.
+pub struct WebhookDispatcher {
+    database_address: Addr<Database>,
+    configuration: WebhookConfiguration,
+    dispatching: bool,
+}
+
+impl WebhookDispatcher {
+    pub fn new(database_address: Addr<Database>, configuration: WebhookConfiguration) -> Self {
+        Self {
+            database_address,
+            configuration,
+            dispatching: false,
+        }
+    }
+
+    async fn send(
+        client: &Client,
+        webhook: &Webhook,
+        delivery: &WebhookDelivery,
+        allow_private_targets: bool,
+    ) -> Result<u16> {
+        let address = resolve_target(&webhook.url, allow_private_targets).await?;
+        let response = client
+            .post(&webhook.url)
+            .address(address)
+            .insert_header(("Content-Type", "application/json"))
+            .insert_header((WEBHOOK_EVENT_HEADER, delivery.event.as_str()))
+            .insert_header((WEBHOOK_DELIVERY_HEADER, delivery.id.to_string()))
+            .insert_header((
+                WEBHOOK_SIGNATURE_HEADER,
+                sign_payload(&webhook.secret, &delivery.payload),
+            ))
+            .send_body(delivery.payload.clone())
+            .await
+            .map_err(|error| anyhow!("{error}"))?;
+
+        Ok(response.status().as_u16())
+    }
+
+    async fn dispatch(
+        database_address: Addr<Database>,
+        configuration: WebhookConfiguration,
+    ) -> Result<()> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(configuration.request_timeout_seconds))
+            .finish();
+        let deliveries = database_address
+            .send(GetDueWebhookDeliveries(configuration.batch_size))
+            .await??;
+
+        let claim_duration = chrono::Duration::seconds(
+            (configuration.request_timeout_seconds + CLAIM_MARGIN_SECONDS) as i64,
+        );
+        for (mut delivery, webhook) in deliveries {
+            let is_claimed = database_address
+                .send(ClaimWebhookDelivery {
+                    delivery: delivery.clone(),
+                    claimed_until: Utc::now().naive_utc() + claim_duration,
+                })
+                .await??;
+            if !is_claimed {
+                debug!(
+                    "Webhook delivery {} was claimed by another replica",
+                    delivery.id
+                );
+                continue;
+            }
+            let outcome = Self::send(
+                &client,
+                &webhook,
+                &delivery,
+                configuration.allow_private_targets,
+            )
+            .await
+            .map_err(|error| error.to_string());
+            debug!(
+                "Webhook delivery {} to {} finished with {outcome:?}",
+                delivery.id, webhook.url
+            );
+            delivery.record_attempt(outcome, configuration.max_attempts);
+            database_address
+                .send(UpdateWebhookDelivery(delivery))
+                .await??;
+        }
+        Ok(())
+    }
+}
+
+impl Actor for WebhookDispatcher {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let poll_interval = Duration::from_secs(self.configuration.poll_interval_seconds);
+        ctx.run_interval(poll_interval, |dispatcher, ctx| {
+            if dispatcher.dispatching {
+                return;
+            }
+            dispatcher.dispatching = true;
+            let dispatch = Self::dispatch(
+                dispatcher.database_address.clone(),
+                dispatcher.configuration.clone(),
+            );
+            ctx.spawn(
+                dispatch
+                    .into_actor(dispatcher)
+                    .map(|result, dispatcher, _| {
+                        if let Err(error) = result {
+                            error!("Failed to dispatch webhook deliveries: {error}");
+                        }
+                        dispatcher.dispatching = false;
+                    }),
+            );
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn internal_addresses_are_not_public() {
+        for address in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_address(address.parse().unwrap()), "{address}");
+        }
+        for address in ["93.184.216.34", "2606:2800:220:1::1"] {
+            assert!(is_public_address(address.parse().unwrap()), "{address}");
+        }
+    }
+
+    #[actix_web::test]
+    async fn internal_targets_are_refused_unless_allowed() {
+        assert!(resolve_target("http://127.0.0.1:8080/hook", false)
+            .await
+            .is_err());
+        assert!(resolve_target("http://[::1]/hook", false).await.is_err());
+        assert_eq!(
+            resolve_target("http://127.0.0.1:8080/hook", true)
+                .await
+                .unwrap(),
+            "127.0.0.1:8080".parse().unwrap()
+        );
+    }
+}
@@ -1,18 +1,39 @@
+use crate::errors::PackageServerError;
+use crate::models::apitoken::{ApiToken, NewApiToken};
 use crate::models::helpers::uuid::Uuid;
+use crate::models::NewAuditLog;
 use crate::models::{
-    Category, NewCategory, NewOwner, NewPackageVersion, Owner, Owners, Package, PackageVersion,
-    PackageWithVersions, PackagesWithVersionsAndPages, Version,
+    download_stats, search_facets, AuditLog, AuditLogsWithPages, Category, DeliveryStatus,
+    NewCategory, NewOwner, NewPackageVersion, NewTeam, NewTeamMember, NewTeamOwner, NewWebhook,
+    NewWebhookDelivery, Owner, Owners, Package, PackageVersion, PackageWithVersions,
+    PackagesWithVersionsAndPages, PublishedVersions, Team, TeamMember, TeamOwner, TeamWithMembers,
+    Version, VersionDownload, Webhook, WebhookDelivery,
+};
+use crate::services::database::apitoken::CreateApiToken;
+use crate::services::database::audit::{CreateAuditLog, GetAuditLogs};
+use crate::services::database::download::{GetDownloadStats, RecordDownload};
+use crate::services::database::owner::{
+    AddOwner, AddTeamOwner, DeleteOwner, DeleteTeamOwner, GetOwners, GetTeamOwners, IsPackageOwner,
 };
-use crate::services::database::owner::{AddOwner, DeleteOwner, GetOwners};
 use crate::services::database::package::{
-    CreateCategory, CreatePackage, GetAllCategories, GetCategoriesForPackage,
-    GetPackageByNameAndVersion, GetPackages, GetVersionsByPackageName, UpdateVersionMsg,
+    CreateCategory, CreatePackage, DeletePackage, DeleteVersion, GetAllCategories,
+    GetCategoriesForPackage, GetPackageByName, GetPackageByNameAndVersion, GetPackages,
+    GetPublishedVersions, GetVersionsByPackageName, UpdatePackageDeprecation,
+    UpdatePackageVisibility, UpdateVersionMsg,
+};
+use crate::services::database::team::{
+    AddTeamMember, CreateTeam, DeleteTeam, GetTeam, GetTeamsByMember, RemoveTeamMember,
+};
+use crate::services::database::webhook::{
+    CreateWebhook, DeleteWebhook, EnqueueWebhookEvent, GetWebhookDeliveries, GetWebhooks,
 };
 use actix::Actor;
 use actix::ActorFutureExt;
 use actix::{Handler, ResponseActFuture, WrapFuture};
 use anyhow::{anyhow, Ok, Result};
-use chrono::NaiveDateTime;
+use chrono::{NaiveDateTime, Utc};
+use deputy_library::package::Visibility;
+use deputy_library::rest::{DownloadStatsRest, PackageSort, WebhookDeliveryRest, WebhookRest};
 use std::collections::HashMap;
 
 #[derive(Default, Clone, Debug)]
@@ -22,14 +43,97 @@ pub struct MockDatabase {
     categories: HashMap<Uuid, Category>,
     package_categories: HashMap<Uuid, Vec<Uuid>>,
     owners: HashMap<Uuid, Owner>,
+    teams: HashMap<Uuid, Team>,
+    team_members: HashMap<Uuid, TeamMember>,
+    team_owners: HashMap<Uuid, TeamOwner>,
+    tokens: HashMap<Uuid, ApiToken>,
+    audit_logs: Vec<AuditLog>,
+    version_downloads: Vec<VersionDownload>,
+    webhooks: HashMap<Uuid, Webhook>,
+    webhook_deliveries: Vec<WebhookDelivery>,
+    search_documents: HashMap<Uuid, String>,
 }
 
 impl Actor for MockDatabase {
     type Context = actix::Context<Self>;
 }
 
+impl MockDatabase {
+    /// Stand-in for the full-text relevance, the number of search term words in the document.
+    fn relevance(&self, package_id: Uuid, search_term: &str) -> usize {
+        let document = match self.search_documents.get(&package_id) {
+            Some(document) => document,
+            None => return 0,
+        };
+        search_term
+            .split_whitespace()
+            .map(|word| document.matches(word).count())
+            .sum()
+    }
+
+    fn owned_package_ids(&self, email: &str) -> Vec<Uuid> {
+        let team_ids = self
+            .team_members
+            .values()
+            .filter(|member| member.email == email)
+            .map(|member| member.team_id)
+            .collect::<Vec<_>>();
+        self.owners
+            .values()
+            .filter(|owner| owner.email == email)
+            .map(|owner| owner.package_id)
+            .chain(
+                self.team_owners
+                    .values()
+                    .filter(|team_owner| team_ids.contains(&team_owner.team_id))
+                    .map(|team_owner| team_owner.package_id),
+            )
+            .collect()
+    }
+
+    fn package_id_by_name(&self, package_name: &str) -> Result<Uuid> {
+        self.packages
+            .values()
+            .find(|package| package.name == package_name.to_lowercase())
+            .map(|package| package.id)
+            .ok_or(anyhow!("Mock Package not found"))
+    }
+
+    fn webhook_package_id(&self, package_name: Option<&String>) -> Result<Option<Uuid>> {
+        package_name
+            .map(|package_name| self.package_id_by_name(package_name))
+            .transpose()
+    }
+
+    fn webhook_in_scope(&self, id: Uuid, package_name: Option<&String>) -> Result<Webhook> {
+        let package_id = self.webhook_package_id(package_name)?;
+        self.webhooks
+            .get(&id)
+            .filter(|webhook| webhook.package_id == package_id)
+            .cloned()
+            .ok_or_else(|| PackageServerError::WebhookNotFound.into())
+    }
+
+    fn team_by_name(&self, team_name: &str) -> Option<Team> {
+        self.teams
+            .values()
+            .find(|team| team.name == team_name.to_lowercase())
+            .cloned()
+    }
+
+    fn team_with_members(&self, team: Team) -> TeamWithMembers {
+        let members = self
+            .team_members
+            .values()
+            .filter(|member| member.team_id == team.id)
+            .cloned()
+            .collect::<Vec<_>>();
+        TeamWithMembers::from((team, members))
+    }
+}
+
 impl From<NewPackageVersion> for PackageVersion {
-    fn from(NewPackageVersion(new_package, new_version): NewPackageVersion) -> Self {
+    fn from(NewPackageVersion(new_package, new_version, _): NewPackageVersion) -> Self {
         let package = Package {
             id: new_package.id,
             package_type: new_package.package_type,
@@ -37,6 +141,10 @@ impl From<NewPackageVersion> for PackageVersion {
             updated_at: NaiveDateTime::MAX,
             deleted_at: None,
             name: new_package.name.to_lowercase(),
+            visibility: new_package.visibility,
+            downloads: 0,
+            deprecation_message: None,
+            replacement_package: None,
         };
         let version = Version {
             id: Uuid::random().to_owned(),
@@ -51,6 +159,7 @@ impl From<NewPackageVersion> for PackageVersion {
             readme_html: new_version.readme_html,
             package_size: new_version.package_size,
             checksum: new_version.checksum,
+            is_unavailable: false,
         };
         Self(package, version)
     }
@@ -65,7 +174,9 @@ impl Handler<CreatePackage> for MockDatabase {
                 .into_actor(self)
                 .map(move |msg, mock_database, _| {
                     let new_package_version = msg.0;
-                    let PackageVersion(new_package, version) = new_package_version.into();
+                    let search_document = new_package_version.2.document.clone();
+                    let PackageVersion(mut new_package, version) = new_package_version.into();
+                    new_package.updated_at = Utc::now().naive_utc();
                     let requester_email = msg.1;
 
                     let optional_package = mock_database
@@ -75,20 +186,19 @@ impl Handler<CreatePackage> for MockDatabase {
 
                     match optional_package {
                         Some(package) => {
-                            let owners = mock_database
-                                .owners
-                                .values()
-                                .filter_map(|owner| {
-                                    if owner.package_id == package.id {
-                                        Some(owner.clone())
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .collect::<Vec<_>>();
-                            if !Owners(owners).contains_email(&requester_email) {
+                            if !mock_database
+                                .owned_package_ids(&requester_email)
+                                .contains(&package.id)
+                            {
                                 return Err(anyhow!("Requester is not an owner of this package"));
                             }
+                            let package_id = package.id;
+                            if let Some(package) = mock_database.packages.get_mut(&package_id) {
+                                package.updated_at = new_package.updated_at;
+                                if new_package.visibility != Visibility::Public.to_string() {
+                                    package.visibility = new_package.visibility.clone();
+                                }
+                            }
                         }
                         None => {
                             let new_owner = NewOwner::new(requester_email, new_package.id);
@@ -105,6 +215,9 @@ impl Handler<CreatePackage> for MockDatabase {
                         .entry(new_package.id)
                         .or_default()
                         .push(version.clone());
+                    mock_database
+                        .search_documents
+                        .insert(new_package.id, search_document.to_lowercase());
                     Ok(PackageVersion(new_package, version))
                 }),
         )
@@ -119,13 +232,22 @@ impl Handler<GetPackages> for MockDatabase {
             async move {}
                 .into_actor(self)
                 .map(move |_, mock_database, _| {
-                    let search_term = msg.search_term.unwrap_or_default();
+                    let search_term = msg.search_term.unwrap_or_default().to_lowercase();
 
                     let mut packages: Vec<Package> =
                         mock_database.packages.values().cloned().collect();
 
                     packages.retain(|package| {
                         package.name.to_lowercase().contains(search_term.as_str())
+                            || mock_database.relevance(package.id, &search_term) > 0
+                    });
+                    let owned_package_ids = match &msg.viewer.email {
+                        Some(email) => mock_database.owned_package_ids(email),
+                        None => Vec::new(),
+                    };
+                    packages.retain(|package| {
+                        msg.viewer
+                            .can_read(package, owned_package_ids.contains(&package.id))
                     });
 
                     packages = match (msg.package_type.clone(), msg.categories) {
@@ -175,6 +297,35 @@ impl Handler<GetPackages> for MockDatabase {
                         _ => packages,
                     };
 
+                    packages.sort_by(|a, b| a.name.cmp(&b.name));
+                    match msg.sort {
+                        PackageSort::Relevance if !search_term.is_empty() => {
+                            packages.sort_by_key(|package| {
+                                std::cmp::Reverse(mock_database.relevance(package.id, &search_term))
+                            })
+                        }
+                        PackageSort::Updated => {
+                            packages.sort_by_key(|package| std::cmp::Reverse(package.updated_at))
+                        }
+                        PackageSort::Downloads => {
+                            packages.sort_by_key(|package| std::cmp::Reverse(package.downloads))
+                        }
+                        _ => {}
+                    }
+                    let package_categories = mock_database
+                        .package_categories
+                        .iter()
+                        .flat_map(|(package_id, category_ids)| {
+                            category_ids.iter().filter_map(|category_id| {
+                                mock_database
+                                    .categories
+                                    .get(category_id)
+                                    .map(|category| (*package_id, category.name.clone()))
+                            })
+                        })
+                        .collect::<Vec<(Uuid, String)>>();
+                    let facets = search_facets(&packages, &package_categories);
+
                     let packages_with_versions: Vec<PackageWithVersions> = packages
                         .into_iter()
                         .map(|package| {
@@ -191,7 +342,8 @@ impl Handler<GetPackages> for MockDatabase {
                         packages_with_versions,
                         rand::random::<i64>(),
                         rand::random::<i64>(),
-                    ));
+                    ))
+                    .with_facets(facets);
 
                     Ok(packages_with_versions_and_pages)
                 }),
@@ -199,6 +351,24 @@ impl Handler<GetPackages> for MockDatabase {
     }
 }
 
+impl Handler<GetPackageByName> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<Option<Package>>>;
+
+    fn handle(&mut self, msg: GetPackageByName, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    Ok(mock_database
+                        .packages
+                        .values()
+                        .find(|package| package.name == msg.0)
+                        .cloned())
+                }),
+        )
+    }
+}
+
 impl Handler<GetPackageByNameAndVersion> for MockDatabase {
     type Result = ResponseActFuture<Self, Result<Version>>;
 
@@ -261,6 +431,31 @@ impl Handler<GetVersionsByPackageName> for MockDatabase {
     }
 }
 
+impl Handler<GetPublishedVersions> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<PublishedVersions>>;
+
+    fn handle(&mut self, msg: GetPublishedVersions, _ctx: &mut Self::Context) -> Self::Result {
+        let name = msg.0.to_lowercase();
+        Box::pin(
+            async move { name }
+                .into_actor(self)
+                .map(move |name, mock_database, _| {
+                    let versions = mock_database
+                        .packages
+                        .values()
+                        .find(|package| package.name == name)
+                        .and_then(|package| mock_database.package_versions.get(&package.id))
+                        .cloned()
+                        .unwrap_or_default();
+                    Ok(PublishedVersions {
+                        is_package_deleted: false,
+                        versions,
+                    })
+                }),
+        )
+    }
+}
+
 impl Handler<CreateCategory> for MockDatabase {
     type Result = ResponseActFuture<Self, Result<Category>>;
 
@@ -392,7 +587,12 @@ impl Handler<DeleteOwner> for MockDatabase {
                         })
                         .collect::<Vec<_>>();
 
-                    if owners.len() == 1 {
+                    let team_owner_count = mock_database
+                        .team_owners
+                        .values()
+                        .filter(|team_owner| team_owner.package_id == package.id)
+                        .count();
+                    if owners.len() + team_owner_count == 1 {
                         return Err(anyhow!("Can not delete the last owner of a package"));
                     }
 
@@ -435,6 +635,49 @@ impl Handler<UpdateVersionMsg> for MockDatabase {
     }
 }
 
+impl Handler<UpdatePackageDeprecation> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<Package>>;
+
+    fn handle(&mut self, msg: UpdatePackageDeprecation, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    let package = mock_database
+                        .packages
+                        .get_mut(&msg.package.id)
+                        .ok_or(anyhow::anyhow!("Package not found"))?;
+                    let (message, replacement) = match msg.deprecation {
+                        Some(deprecation) => (Some(deprecation.message), deprecation.replacement),
+                        None => (None, None),
+                    };
+                    package.deprecation_message = message;
+                    package.replacement_package = replacement;
+                    Ok(package.clone())
+                }),
+        )
+    }
+}
+
+impl Handler<UpdatePackageVisibility> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<Package>>;
+
+    fn handle(&mut self, msg: UpdatePackageVisibility, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    let package = mock_database
+                        .packages
+                        .get_mut(&msg.package.id)
+                        .ok_or(anyhow::anyhow!("Package not found"))?;
+                    package.visibility = msg.visibility.to_string();
+                    Ok(package.clone())
+                }),
+        )
+    }
+}
+
 impl Handler<GetCategoriesForPackage> for MockDatabase {
     type Result = ResponseActFuture<Self, Result<Vec<Category>>>;
 
@@ -482,3 +725,660 @@ impl Handler<GetAllCategories> for MockDatabase {
         )
     }
 }
+
+impl Handler<DeletePackage> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<String>>;
+
+    fn handle(&mut self, msg: DeletePackage, _ctx: &mut Self::Context) -> Self::Result {
+        let name = msg.name.to_lowercase();
+
+        Box::pin(
+            async move { name }
+                .into_actor(self)
+                .map(move |name, mock_database, _| {
+                    let package_id = mock_database
+                        .packages
+                        .values()
+                        .find(|package| package.name == name)
+                        .map(|package| package.id)
+                        .ok_or(PackageServerError::DatabaseRecordNotFound)?;
+
+                    mock_database.packages.remove(&package_id);
+                    mock_database.package_versions.remove(&package_id);
+                    mock_database.package_categories.remove(&package_id);
+                    mock_database
+                        .owners
+                        .retain(|_, owner| owner.package_id != package_id);
+                    mock_database
+                        .team_owners
+                        .retain(|_, team_owner| team_owner.package_id != package_id);
+                    Ok(name)
+                }),
+        )
+    }
+}
+
+impl Handler<DeleteVersion> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<String>>;
+
+    fn handle(&mut self, msg: DeleteVersion, _ctx: &mut Self::Context) -> Self::Result {
+        let name = msg.name.to_lowercase();
+        let version_value = msg.version;
+
+        Box::pin(async move { (name, version_value) }.into_actor(self).map(
+            move |(name, version_value), mock_database, _| {
+                let package_id = mock_database
+                    .packages
+                    .values()
+                    .find(|package| package.name == name)
+                    .map(|package| package.id)
+                    .ok_or(PackageServerError::DatabaseRecordNotFound)?;
+
+                let versions = mock_database
+                    .package_versions
+                    .get_mut(&package_id)
+                    .ok_or(PackageServerError::DatabaseRecordNotFound)?;
+                let index = versions
+                    .iter()
+                    .position(|version| version.version == version_value)
+                    .ok_or(PackageServerError::DatabaseRecordNotFound)?;
+                versions.remove(index);
+                Ok(version_value)
+            },
+        ))
+    }
+}
+
+impl From<NewApiToken> for ApiToken {
+    fn from(new_api_token: NewApiToken) -> Self {
+        Self {
+            id: new_api_token.id,
+            name: new_api_token.name,
+            email: new_api_token.email,
+            token_prefix: new_api_token.token_prefix,
+            token_salt: new_api_token.token_salt,
+            token_hash: new_api_token.token_hash,
+            user_id: new_api_token.user_id,
+            roles: new_api_token.roles,
+            scopes: new_api_token.scopes,
+            package_patterns: new_api_token.package_patterns,
+            expires_at: new_api_token.expires_at,
+            last_used_at: None,
+            created_at: Default::default(),
+            deleted_at: Default::default(),
+        }
+    }
+}
+
+impl From<NewTeam> for Team {
+    fn from(new_team: NewTeam) -> Self {
+        Self {
+            id: new_team.id,
+            name: new_team.name,
+            created_at: Default::default(),
+            updated_at: Default::default(),
+            deleted_at: Default::default(),
+        }
+    }
+}
+
+impl From<NewTeamMember> for TeamMember {
+    fn from(new_member: NewTeamMember) -> Self {
+        Self {
+            id: new_member.id,
+            team_id: new_member.team_id,
+            email: new_member.email,
+            is_admin: new_member.is_admin,
+            created_at: Default::default(),
+            updated_at: Default::default(),
+            deleted_at: Default::default(),
+        }
+    }
+}
+
+impl From<NewTeamOwner> for TeamOwner {
+    fn from(new_team_owner: NewTeamOwner) -> Self {
+        Self {
+            id: new_team_owner.id,
+            team_id: new_team_owner.team_id,
+            package_id: new_team_owner.package_id,
+            created_at: Default::default(),
+            updated_at: Default::default(),
+            deleted_at: Default::default(),
+        }
+    }
+}
+
+impl Handler<IsPackageOwner> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<bool>>;
+
+    fn handle(&mut self, msg: IsPackageOwner, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    let package_id = mock_database.package_id_by_name(&msg.package_name)?;
+                    Ok(mock_database
+                        .owned_package_ids(&msg.email)
+                        .contains(&package_id))
+                }),
+        )
+    }
+}
+
+impl Handler<AddTeamOwner> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<Team>>;
+
+    fn handle(&mut self, msg: AddTeamOwner, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    let package_id = mock_database.package_id_by_name(&msg.package_name)?;
+                    let team = mock_database
+                        .team_by_name(&msg.team_name)
+                        .ok_or(PackageServerError::TeamNotFound)?;
+                    let team_owner = TeamOwner::from(NewTeamOwner::new(team.id, package_id));
+                    mock_database.team_owners.insert(team_owner.id, team_owner);
+                    Ok(team)
+                }),
+        )
+    }
+}
+
+impl Handler<GetTeamOwners> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<Vec<Team>>>;
+
+    fn handle(&mut self, msg: GetTeamOwners, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    let package_id = mock_database.package_id_by_name(&msg.0)?;
+                    let team_ids = mock_database
+                        .team_owners
+                        .values()
+                        .filter(|team_owner| team_owner.package_id == package_id)
+                        .map(|team_owner| team_owner.team_id)
+                        .collect::<Vec<_>>();
+                    Ok(mock_database
+                        .teams
+                        .values()
+                        .filter(|team| team_ids.contains(&team.id))
+                        .cloned()
+                        .collect())
+                }),
+        )
+    }
+}
+
+impl Handler<DeleteTeamOwner> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<String>>;
+
+    fn handle(&mut self, msg: DeleteTeamOwner, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    let package_id = mock_database.package_id_by_name(&msg.package_name)?;
+                    let owner_count = mock_database
+                        .owners
+                        .values()
+                        .filter(|owner| owner.package_id == package_id)
+                        .count()
+                        + mock_database
+                            .team_owners
+                            .values()
+                            .filter(|team_owner| team_owner.package_id == package_id)
+                            .count();
+                    if owner_count == 1 {
+                        return Err(anyhow!("Can not delete the last owner of a package"));
+                    }
+                    let team = mock_database
+                        .team_by_name(&msg.team_name)
+                        .ok_or(PackageServerError::TeamNotFound)?;
+                    let team_owner_id = mock_database
+                        .team_owners
+                        .values()
+                        .find(|team_owner| {
+                            team_owner.package_id == package_id && team_owner.team_id == team.id
+                        })
+                        .map(|team_owner| team_owner.id)
+                        .ok_or(PackageServerError::DatabaseRecordNotFound)?;
+                    mock_database.team_owners.remove(&team_owner_id);
+                    Ok(team.name)
+                }),
+        )
+    }
+}
+
+impl Handler<CreateTeam> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<TeamWithMembers>>;
+
+    fn handle(&mut self, msg: CreateTeam, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    if mock_database.team_by_name(&msg.name).is_some() {
+                        return Err(PackageServerError::TeamExists.into());
+                    }
+                    let team = Team::from(NewTeam::new(msg.name));
+                    let member =
+                        TeamMember::from(NewTeamMember::new(team.id, msg.admin_email, true));
+                    mock_database.teams.insert(team.id, team.clone());
+                    mock_database.team_members.insert(member.id, member);
+                    Ok(mock_database.team_with_members(team))
+                }),
+        )
+    }
+}
+
+impl Handler<GetTeam> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<Option<TeamWithMembers>>>;
+
+    fn handle(&mut self, msg: GetTeam, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    Ok(mock_database
+                        .team_by_name(&msg.0)
+                        .map(|team| mock_database.team_with_members(team)))
+                }),
+        )
+    }
+}
+
+impl Handler<GetTeamsByMember> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<Vec<TeamWithMembers>>>;
+
+    fn handle(&mut self, msg: GetTeamsByMember, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    Ok(mock_database
+                        .teams
+                        .values()
+                        .map(|team| mock_database.team_with_members(team.clone()))
+                        .filter(|team| team.is_member(&msg.0))
+                        .collect())
+                }),
+        )
+    }
+}
+
+impl Handler<DeleteTeam> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<String>>;
+
+    fn handle(&mut self, msg: DeleteTeam, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    let team = mock_database
+                        .team_by_name(&msg.0)
+                        .ok_or(PackageServerError::TeamNotFound)?;
+                    if mock_database
+                        .team_owners
+                        .values()
+                        .any(|team_owner| team_owner.team_id == team.id)
+                    {
+                        return Err(PackageServerError::TeamOwnsPackages.into());
+                    }
+                    mock_database
+                        .team_members
+                        .retain(|_, member| member.team_id != team.id);
+                    mock_database.teams.remove(&team.id);
+                    Ok(team.name)
+                }),
+        )
+    }
+}
+
+impl Handler<AddTeamMember> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<TeamMember>>;
+
+    fn handle(&mut self, msg: AddTeamMember, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    let team = mock_database
+                        .team_by_name(&msg.team_name)
+                        .ok_or(PackageServerError::TeamNotFound)?;
+                    let member =
+                        TeamMember::from(NewTeamMember::new(team.id, msg.email, msg.is_admin));
+                    mock_database.team_members.insert(member.id, member.clone());
+                    Ok(member)
+                }),
+        )
+    }
+}
+
+impl Handler<RemoveTeamMember> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<String>>;
+
+    fn handle(&mut self, msg: RemoveTeamMember, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    let team = mock_database
+                        .team_by_name(&msg.team_name)
+                        .ok_or(PackageServerError::TeamNotFound)?;
+                    let team = mock_database.team_with_members(team);
+                    let member = team
+                        .members
+                        .iter()
+                        .find(|member| member.email == msg.email)
+                        .ok_or(PackageServerError::DatabaseRecordNotFound)?;
+                    let admin_count = team.members.iter().filter(|member| member.is_admin).count();
+                    if member.is_admin && admin_count == 1 {
+                        return Err(PackageServerError::TeamMemberRemoveLastAdmin.into());
+                    }
+                    mock_database.team_members.remove(&member.id);
+                    Ok(msg.email)
+                }),
+        )
+    }
+}
+
+impl Handler<CreateApiToken> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<ApiToken>>;
+
+    fn handle(&mut self, msg: CreateApiToken, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    let api_token = ApiToken::from(msg.0);
+                    mock_database.tokens.insert(api_token.id, api_token.clone());
+                    Ok(api_token)
+                }),
+        )
+    }
+}
+
+impl From<NewAuditLog> for AuditLog {
+    fn from(new_audit_log: NewAuditLog) -> Self {
+        Self {
+            id: new_audit_log.id,
+            actor_email: new_audit_log.actor_email,
+            actor_id: new_audit_log.actor_id,
+            token_id: new_audit_log.token_id,
+            action: new_audit_log.action,
+            package_name: new_audit_log.package_name,
+            package_version: new_audit_log.package_version,
+            details: new_audit_log.details,
+            ip_address: new_audit_log.ip_address,
+            user_agent: new_audit_log.user_agent,
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+}
+
+impl Handler<CreateAuditLog> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<()>>;
+
+    fn handle(&mut self, msg: CreateAuditLog, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    mock_database.audit_logs.push(AuditLog::from(msg.0));
+                    Ok(())
+                }),
+        )
+    }
+}
+
+impl Handler<GetAuditLogs> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<AuditLogsWithPages>>;
+
+    fn handle(&mut self, msg: GetAuditLogs, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    let audit_logs: Vec<AuditLog> = mock_database
+                        .audit_logs
+                        .iter()
+                        .rev()
+                        .filter(|audit_log| msg.filter.matches(audit_log))
+                        .cloned()
+                        .collect();
+                    let total_audit_logs = audit_logs.len() as i64;
+                    let per_page = msg.per_page.max(1);
+                    let total_pages = (total_audit_logs + per_page - 1) / per_page;
+                    let audit_logs = audit_logs
+                        .into_iter()
+                        .skip(((msg.page.max(1) - 1) * per_page) as usize)
+                        .take(per_page as usize)
+                        .collect();
+
+                    Ok(AuditLogsWithPages::from((
+                        audit_logs,
+                        total_pages,
+                        total_audit_logs,
+                    )))
+                }),
+        )
+    }
+}
+
+impl Handler<RecordDownload> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<()>>;
+
+    fn handle(&mut self, msg: RecordDownload, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    let package_id = mock_database.package_id_by_name(&msg.name)?;
+                    let version_id = mock_database
+                        .package_versions
+                        .get(&package_id)
+                        .and_then(|versions| {
+                            versions
+                                .iter()
+                                .find(|version| version.version == msg.version)
+                        })
+                        .map(|version| version.id)
+                        .ok_or(anyhow!("Mock Version not found"))?;
+                    let today = VersionDownload::today(version_id);
+                    match mock_database
+                        .version_downloads
+                        .iter_mut()
+                        .find(|version_download| {
+                            version_download.version_id == today.version_id
+                                && version_download.download_date == today.download_date
+                        }) {
+                        Some(version_download) => version_download.downloads += 1,
+                        None => mock_database.version_downloads.push(today),
+                    }
+                    if let Some(package) = mock_database.packages.get_mut(&package_id) {
+                        package.downloads += 1;
+                    }
+                    Ok(())
+                }),
+        )
+    }
+}
+
+impl Handler<GetDownloadStats> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<DownloadStatsRest>>;
+
+    fn handle(&mut self, msg: GetDownloadStats, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    let package_id = mock_database.package_id_by_name(&msg.name)?;
+                    let mut versions = mock_database
+                        .package_versions
+                        .get(&package_id)
+                        .cloned()
+                        .unwrap_or_default();
+                    if let Some(version) = msg.version {
+                        versions.retain(|package_version| package_version.version == version);
+                    }
+
+                    Ok(download_stats(
+                        msg.name.to_lowercase(),
+                        &versions,
+                        &mock_database.version_downloads,
+                        msg.since,
+                    ))
+                }),
+        )
+    }
+}
+
+impl From<NewWebhook> for Webhook {
+    fn from(new_webhook: NewWebhook) -> Self {
+        Self {
+            id: new_webhook.id,
+            package_id: new_webhook.package_id,
+            url: new_webhook.url,
+            events: new_webhook.events,
+            secret: new_webhook.secret,
+            created_by: new_webhook.created_by,
+            created_at: Utc::now().naive_utc(),
+            updated_at: Utc::now().naive_utc(),
+            deleted_at: None,
+        }
+    }
+}
+
+impl From<NewWebhookDelivery> for WebhookDelivery {
+    fn from(new_delivery: NewWebhookDelivery) -> Self {
+        Self {
+            id: new_delivery.id,
+            webhook_id: new_delivery.webhook_id,
+            event: new_delivery.event,
+            payload: new_delivery.payload,
+            status: DeliveryStatus::Pending.to_string(),
+            attempts: 0,
+            response_status: None,
+            last_error: None,
+            next_attempt_at: Utc::now().naive_utc(),
+            delivered_at: None,
+            created_at: Utc::now().naive_utc(),
+            claimed_until: None,
+        }
+    }
+}
+
+impl Handler<CreateWebhook> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<WebhookRest>>;
+
+    fn handle(&mut self, msg: CreateWebhook, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    let package_id = mock_database.webhook_package_id(msg.package_name.as_ref())?;
+                    let webhook =
+                        Webhook::from(NewWebhook::new(package_id, msg.new_webhook, msg.created_by));
+                    mock_database.webhooks.insert(webhook.id, webhook.clone());
+                    let secret = webhook.secret.clone();
+
+                    Ok(WebhookRest {
+                        secret: Some(secret),
+                        ..webhook.into_rest(msg.package_name)
+                    })
+                }),
+        )
+    }
+}
+
+impl Handler<GetWebhooks> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<Vec<WebhookRest>>>;
+
+    fn handle(&mut self, msg: GetWebhooks, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    let package_id = mock_database.webhook_package_id(msg.package_name.as_ref())?;
+                    Ok(mock_database
+                        .webhooks
+                        .values()
+                        .filter(|webhook| webhook.package_id == package_id)
+                        .map(|webhook| webhook.clone().into_rest(msg.package_name.clone()))
+                        .collect())
+                }),
+        )
+    }
+}
+
+impl Handler<DeleteWebhook> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<()>>;
+
+    fn handle(&mut self, msg: DeleteWebhook, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    let webhook =
+                        mock_database.webhook_in_scope(msg.id, msg.package_name.as_ref())?;
+                    mock_database.webhooks.remove(&webhook.id);
+                    Ok(())
+                }),
+        )
+    }
+}
+
+impl Handler<GetWebhookDeliveries> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<Vec<WebhookDeliveryRest>>>;
+
+    fn handle(&mut self, msg: GetWebhookDeliveries, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg }
+                .into_actor(self)
+                .map(move |msg, mock_database, _| {
+                    let webhook = mock_database
+                        .webhook_in_scope(msg.webhook_id, msg.package_name.as_ref())?;
+                    Ok(mock_database
+                        .webhook_deliveries
+                        .iter()
+                        .rev()
+                        .filter(|delivery| delivery.webhook_id == webhook.id)
+                        .cloned()
+                        .map(WebhookDeliveryRest::from)
+                        .collect())
+                }),
+        )
+    }
+}
+
+impl Handler<EnqueueWebhookEvent> for MockDatabase {
+    type Result = ResponseActFuture<Self, Result<()>>;
+
+    fn handle(&mut self, msg: EnqueueWebhookEvent, _ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            async move { msg.0 }
+                .into_actor(self)
+                .map(move |payload, mock_database, _| {
+                    let package_id = mock_database.package_id_by_name(&payload.package_name).ok();
+                    let webhook_ids = mock_database
+                        .webhooks
+                        .values()
+                        .filter(|webhook| {
+                            webhook.package_id.is_none() || webhook.package_id == package_id
+                        })
+                        .filter(|webhook| webhook.subscribes_to(payload.event))
+                        .map(|webhook| webhook.id)
+                        .collect::<Vec<_>>();
+                    for webhook_id in webhook_ids {
+                        mock_database
+                            .webhook_deliveries
+                            .push(NewWebhookDelivery::new(webhook_id, &payload)?.into());
+                    }
+                    Ok(())
+                }),
+        )
+    }
+}
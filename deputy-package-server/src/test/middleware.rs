@@ -55,6 +55,8 @@ where
                 .insert::<Rc<UserToken>>(Rc::new(UserToken {
                     id: "test-id".to_string(),
                     email: "test-email".to_string(),
+                    roles: Vec::new(),
+                    ..Default::default()
                 }));
 
             let res = service.call(req).await?;
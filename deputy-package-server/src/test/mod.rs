@@ -4,13 +4,24 @@ pub mod middleware;
 use self::database::MockDatabase;
 use crate::{
     routes::{
+        admin::{delete_package, delete_version, get_audit_logs},
+        apitoken::create_scoped_api_token,
         basic::{status, version},
-        owner::{add_owner, delete_owner, get_all_owners},
+        owner::{
+            add_owner, add_team_owner, delete_owner, delete_team_owner, get_all_owners,
+            get_team_owners,
+        },
         package::{
-            add_package, download_file, download_package, get_all_packages, get_all_versions,
-            get_package_version, yank_version,
+            add_package, deprecate_package, download_file, download_package, get_all_packages,
+            get_all_versions, get_deprecation, get_download_stats, get_package_version,
+            undeprecate_package, update_visibility, yank_version,
+        },
+        team::{
+            add_team_member, create_team, delete_team, get_team, get_user_teams, remove_team_member,
         },
+        webhook::{create_webhook, delete_webhook, get_webhook_deliveries, get_webhooks},
     },
+    services::storage::FilesystemStorage,
     test::middleware::MockTokenMiddlewareFactory,
     AppState,
 };
@@ -28,6 +39,7 @@ use std::{
     env,
     fs::{create_dir_all, remove_dir_all},
     path::{Path, PathBuf},
+    sync::Arc,
     thread,
     time::Duration,
 };
@@ -121,8 +133,12 @@ impl TestPackageServer {
         runtime.block_on(async {
             let database = MockDatabase::default().start();
             let app_data: AppState<MockDatabase> = AppState {
-                package_folder,
+                storage: Arc::new(FilesystemStorage::new(&package_folder)),
                 database_address: database,
+                extraction_limits: Default::default(),
+                package_size_limits: Default::default(),
+                role_requirements: Default::default(),
+                metrics: Default::default(),
             };
             try_join!(
                 HttpServer::new(move || {
@@ -133,78 +149,255 @@ impl TestPackageServer {
                         .service(version)
                         .service(
                             scope("/api").service(
-                                scope("/v1").service(
-                                    scope("/package")
-                                        .route("", get().to(get_all_packages::<MockDatabase>))
-                                        .service(
-                                            scope("/{package_name}")
-                                                .route(
-                                                    "",
-                                                    get().to(get_all_versions::<MockDatabase>),
-                                                )
-                                                .service(
-                                                    scope("/owner")
-                                                        .route(
-                                                            "",
-                                                            get()
-                                                                .to(get_all_owners::<MockDatabase>),
-                                                        )
-                                                        .service(
-                                                            scope("")
-                                                                .route(
-                                                                    "",
-                                                                    post().to(add_owner::<
-                                                                        MockDatabase,
-                                                                    >),
-                                                                )
-                                                                .route(
-                                                                    "/{owner_email}",
-                                                                    delete().to(delete_owner::<
-                                                                        MockDatabase,
-                                                                    >),
-                                                                )
-                                                                .wrap(MockTokenMiddlewareFactory),
-                                                        ),
-                                                )
-                                                .service(
-                                                    scope("/{version}")
-                                                        .route(
-                                                            "/download",
-                                                            get().to(download_package::<
-                                                                MockDatabase,
-                                                            >),
-                                                        )
-                                                        .route(
-                                                            "/path/{tail:.*}",
-                                                            get().to(download_file::<MockDatabase>),
-                                                        )
-                                                        .route(
-                                                            "",
-                                                            get().to(get_package_version::<
-                                                                MockDatabase,
-                                                            >),
-                                                        )
-                                                        .service(
-                                                            scope("")
-                                                                .route(
-                                                                    "/yank/{set_yank}",
-                                                                    put().to(yank_version::<
-                                                                        MockDatabase,
-                                                                    >),
-                                                                )
-                                                                .wrap(MockTokenMiddlewareFactory),
-                                                        ),
-                                                ),
-                                        )
-                                        .service(
-                                            scope("")
-                                                .service(scope("").route(
-                                                    "",
-                                                    post().to(add_package::<MockDatabase>),
-                                                ))
-                                                .wrap(MockTokenMiddlewareFactory),
-                                        ),
-                                ),
+                                scope("/v1")
+                                    .service(
+                                        scope("/admin")
+                                            .route(
+                                                "/audit-log",
+                                                get().to(get_audit_logs::<MockDatabase>),
+                                            )
+                                            .service(
+                                                scope("/webhook")
+                                                    .route(
+                                                        "",
+                                                        get().to(get_webhooks::<MockDatabase>),
+                                                    )
+                                                    .route(
+                                                        "",
+                                                        post().to(create_webhook::<MockDatabase>),
+                                                    )
+                                                    .route(
+                                                        "/{webhook_id}",
+                                                        delete().to(delete_webhook::<MockDatabase>),
+                                                    )
+                                                    .route(
+                                                        "/{webhook_id}/delivery",
+                                                        get().to(get_webhook_deliveries::<
+                                                            MockDatabase,
+                                                        >),
+                                                    ),
+                                            )
+                                            .service(
+                                                scope("/package/{package_name}")
+                                                    .route(
+                                                        "",
+                                                        delete().to(delete_package::<MockDatabase>),
+                                                    )
+                                                    .route(
+                                                        "/{version}",
+                                                        delete().to(delete_version::<MockDatabase>),
+                                                    ),
+                                            )
+                                            .wrap(MockTokenMiddlewareFactory),
+                                    )
+                                    .service(
+                                        scope("/token/scoped")
+                                            .route(
+                                                "",
+                                                post().to(create_scoped_api_token::<MockDatabase>),
+                                            )
+                                            .wrap(MockTokenMiddlewareFactory),
+                                    )
+                                    .service(
+                                        scope("/team")
+                                            .route("", post().to(create_team::<MockDatabase>))
+                                            .route("", get().to(get_user_teams::<MockDatabase>))
+                                            .service(
+                                                scope("/{team_name}")
+                                                    .route("", get().to(get_team::<MockDatabase>))
+                                                    .route(
+                                                        "",
+                                                        delete().to(delete_team::<MockDatabase>),
+                                                    )
+                                                    .route(
+                                                        "/member",
+                                                        post().to(add_team_member::<MockDatabase>),
+                                                    )
+                                                    .route(
+                                                        "/member/{email}",
+                                                        delete()
+                                                            .to(remove_team_member::<MockDatabase>),
+                                                    ),
+                                            )
+                                            .wrap(MockTokenMiddlewareFactory),
+                                    )
+                                    .service(
+                                        scope("/package")
+                                            .route("", get().to(get_all_packages::<MockDatabase>))
+                                            .service(
+                                                scope("/{package_name}")
+                                                    .route(
+                                                        "",
+                                                        get().to(get_all_versions::<MockDatabase>),
+                                                    )
+                                                    .route(
+                                                        "/stats",
+                                                        get()
+                                                            .to(get_download_stats::<MockDatabase>),
+                                                    )
+                                                    .service(
+                                                        scope("/webhook")
+                                                            .route(
+                                                                "",
+                                                                get().to(get_webhooks::<
+                                                                    MockDatabase,
+                                                                >),
+                                                            )
+                                                            .route(
+                                                                "",
+                                                                post().to(create_webhook::<
+                                                                    MockDatabase,
+                                                                >),
+                                                            )
+                                                            .route(
+                                                                "/{webhook_id}",
+                                                                delete().to(delete_webhook::<
+                                                                    MockDatabase,
+                                                                >),
+                                                            )
+                                                            .route(
+                                                                "/{webhook_id}/delivery",
+                                                                get().to(get_webhook_deliveries::<
+                                                                    MockDatabase,
+                                                                >),
+                                                            )
+                                                            .wrap(MockTokenMiddlewareFactory),
+                                                    )
+                                                    .service(
+                                                        scope("/owner")
+                                                            .route(
+                                                                "",
+                                                                get().to(get_all_owners::<
+                                                                    MockDatabase,
+                                                                >),
+                                                            )
+                                                            .route(
+                                                                "/team",
+                                                                get().to(get_team_owners::<
+                                                                    MockDatabase,
+                                                                >),
+                                                            )
+                                                            .service(
+                                                                scope("")
+                                                                    .route(
+                                                                        "",
+                                                                        post().to(add_owner::<
+                                                                            MockDatabase,
+                                                                        >),
+                                                                    )
+                                                                    .route(
+                                                                        "/{owner_email}",
+                                                                        delete().to(
+                                                                            delete_owner::<
+                                                                                MockDatabase,
+                                                                            >,
+                                                                        ),
+                                                                    )
+                                                                    .route(
+                                                                        "/team/{team_name}",
+                                                                        post().to(
+                                                                            add_team_owner::<
+                                                                                MockDatabase,
+                                                                            >,
+                                                                        ),
+                                                                    )
+                                                                    .route(
+                                                                        "/team/{team_name}",
+                                                                        delete().to(
+                                                                            delete_team_owner::<
+                                                                                MockDatabase,
+                                                                            >,
+                                                                        ),
+                                                                    )
+                                                                    .wrap(
+                                                                        MockTokenMiddlewareFactory,
+                                                                    ),
+                                                            ),
+                                                    )
+                                                    .service(
+                                                        scope("/deprecation")
+                                                            .route(
+                                                                "",
+                                                                get().to(get_deprecation::<
+                                                                    MockDatabase,
+                                                                >),
+                                                            )
+                                                            .service(
+                                                                scope("")
+                                                                    .route(
+                                                                        "",
+                                                                        put().to(
+                                                                            deprecate_package::<
+                                                                                MockDatabase,
+                                                                            >,
+                                                                        ),
+                                                                    )
+                                                                    .route(
+                                                                        "",
+                                                                        delete().to(
+                                                                            undeprecate_package::<
+                                                                                MockDatabase,
+                                                                            >,
+                                                                        ),
+                                                                    )
+                                                                    .wrap(
+                                                                        MockTokenMiddlewareFactory,
+                                                                    ),
+                                                            ),
+                                                    )
+                                                    .service(
+                                                        scope("/visibility")
+                                                            .route(
+                                                                "/{visibility}",
+                                                                put().to(update_visibility::<
+                                                                    MockDatabase,
+                                                                >),
+                                                            )
+                                                            .wrap(MockTokenMiddlewareFactory),
+                                                    )
+                                                    .service(
+                                                        scope("/{version}")
+                                                            .route(
+                                                                "/download",
+                                                                get().to(download_package::<
+                                                                    MockDatabase,
+                                                                >),
+                                                            )
+                                                            .route(
+                                                                "/path/{tail:.*}",
+                                                                get().to(download_file::<
+                                                                    MockDatabase,
+                                                                >),
+                                                            )
+                                                            .route(
+                                                                "",
+                                                                get().to(get_package_version::<
+                                                                    MockDatabase,
+                                                                >),
+                                                            )
+                                                            .service(
+                                                                scope("")
+                                                                    .route(
+                                                                        "/yank/{set_yank}",
+                                                                        put().to(yank_version::<
+                                                                            MockDatabase,
+                                                                        >),
+                                                                    )
+                                                                    .wrap(
+                                                                        MockTokenMiddlewareFactory,
+                                                                    ),
+                                                            ),
+                                                    ),
+                                            )
+                                            .service(
+                                                scope("")
+                                                    .service(scope("").route(
+                                                        "",
+                                                        post().to(add_package::<MockDatabase>),
+                                                    ))
+                                                    .wrap(MockTokenMiddlewareFactory),
+                                            ),
+                                    ),
                             ),
                         )
                 })
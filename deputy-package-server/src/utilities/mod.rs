@@ -1,10 +1,93 @@
-use diesel::mysql::Mysql;
+pub mod tls;
+
+use crate::services::database::DatabaseConnection;
+use diesel::{
+    backend::Backend,
+    migration::{MigrationSource, Result},
+};
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
-pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
+pub const MYSQL_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/mysql");
+pub const POSTGRESQL_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/postgres");
+pub const SQLITE_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/sqlite");
+
+/// Runs `$body` with the backend specific connection as `$harness` and the embedded migrations of
+/// that backend as `$source`.
+macro_rules! with_migration_harness {
+    ($connection:expr, |$harness:ident, $source:ident| $body:expr) => {
+        match $connection {
+            DatabaseConnection::Mysql($harness) => {
+                let $source = MYSQL_MIGRATIONS;
+                $body
+            }
+            DatabaseConnection::Postgresql($harness) => {
+                let $source = POSTGRESQL_MIGRATIONS;
+                $body
+            }
+            DatabaseConnection::Sqlite($harness) => {
+                let $source = SQLITE_MIGRATIONS;
+                $body
+            }
+        }
+    };
+}
 
-pub fn run_migrations(
-    connection: &mut impl MigrationHarness<Mysql>,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
-    connection.run_pending_migrations(MIGRATIONS)?;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationState {
+    pub name: String,
+    pub version: String,
+    pub is_applied: bool,
+}
+
+pub fn run_migrations(connection: &mut DatabaseConnection) -> Result<()> {
+    with_migration_harness!(connection, |harness, source| {
+        harness.run_pending_migrations(source)?;
+    });
     Ok(())
 }
+
+/// Every embedded migration of the backend in the order they are applied.
+pub fn migration_states(connection: &mut DatabaseConnection) -> Result<Vec<MigrationState>> {
+    with_migration_harness!(connection, |harness, source| states_of(harness, source))
+}
+
+pub fn pending_migrations(connection: &mut DatabaseConnection) -> Result<Vec<String>> {
+    Ok(migration_states(connection)?
+        .into_iter()
+        .filter(|state| !state.is_applied)
+        .map(|state| state.name)
+        .collect())
+}
+
+/// Reverts the `count` most recently applied migrations and returns their names, newest first.
+pub fn revert_migrations(connection: &mut DatabaseConnection, count: usize) -> Result<Vec<String>> {
+    let states = migration_states(connection)?;
+    let mut reverted_migrations = Vec::new();
+    for _ in 0..count {
+        let version = with_migration_harness!(&mut *connection, |harness, source| {
+            harness.revert_last_migration(source)?
+        });
+        let version = version.to_string();
+        let name = states
+            .iter()
+            .find(|state| state.version == version)
+            .map_or(version, |state| state.name.clone());
+        reverted_migrations.push(name);
+    }
+    Ok(reverted_migrations)
+}
+
+fn states_of<DB, H>(harness: &mut H, source: EmbeddedMigrations) -> Result<Vec<MigrationState>>
+where
+    DB: Backend,
+    H: MigrationHarness<DB>,
+{
+    let applied_versions = harness.applied_migrations()?;
+    Ok(MigrationSource::<DB>::migrations(&source)?
+        .iter()
+        .map(|migration| MigrationState {
+            name: migration.name().to_string(),
+            version: migration.name().version().to_string(),
+            is_applied: applied_versions.contains(&migration.name().version()),
+        })
+        .collect())
+}
@@ -0,0 +1,59 @@
+use crate::configuration::TlsConfiguration;
+use anyhow::{anyhow, Result};
+use rustls::{
+    server::AllowAnyAuthenticatedClient, Certificate, PrivateKey, RootCertStore, ServerConfig,
+};
+use rustls_pemfile::{certs, read_one, Item};
+use std::{fs::File, io::BufReader};
+
+/// Builds the rustls configuration from the PEM files, client certificates are required and
+/// verified against `client_ca_path` when it is set.
+pub fn server_configuration(tls_configuration: &TlsConfiguration) -> Result<ServerConfig> {
+    let certificates = read_certificates(&tls_configuration.certificate_path)?;
+    let private_key = read_private_key(&tls_configuration.private_key_path)?;
+
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let builder = match &tls_configuration.client_ca_path {
+        Some(client_ca_path) => {
+            let mut client_roots = RootCertStore::empty();
+            for certificate in read_certificates(client_ca_path)? {
+                client_roots.add(&certificate).map_err(|error| {
+                    anyhow!("Invalid client CA certificate in {client_ca_path}: {error}")
+                })?;
+            }
+            builder
+                .with_client_cert_verifier(AllowAnyAuthenticatedClient::new(client_roots).boxed())
+        }
+        None => builder.with_no_client_auth(),
+    };
+    builder
+        .with_single_cert(certificates, private_key)
+        .map_err(|error| anyhow!("Invalid TLS certificate or private key: {error}"))
+}
+
+fn open_pem_file(file_path: &str) -> Result<BufReader<File>> {
+    let file =
+        File::open(file_path).map_err(|error| anyhow!("Failed to open {file_path}: {error}"))?;
+    Ok(BufReader::new(file))
+}
+
+fn read_certificates(file_path: &str) -> Result<Vec<Certificate>> {
+    let certificates = certs(&mut open_pem_file(file_path)?)
+        .map_err(|error| anyhow!("Failed to read certificates from {file_path}: {error}"))?;
+    if certificates.is_empty() {
+        return Err(anyhow!("No certificates found in {file_path}"));
+    }
+    Ok(certificates.into_iter().map(Certificate).collect())
+}
+
+fn read_private_key(file_path: &str) -> Result<PrivateKey> {
+    let mut reader = open_pem_file(file_path)?;
+    while let Some(item) = read_one(&mut reader)
+        .map_err(|error| anyhow!("Failed to read the private key from {file_path}: {error}"))?
+    {
+        if let Item::RSAKey(key) | Item::PKCS8Key(key) | Item::ECKey(key) = item {
+            return Ok(PrivateKey(key));
+        }
+    }
+    Err(anyhow!("No private key found in {file_path}"))
+}
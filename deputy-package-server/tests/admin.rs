@@ -0,0 +1,123 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{set_mock_user_token, setup_package_server, upload_test_package, BodyTest};
+    use actix_http::StatusCode;
+    use actix_web::{
+        body::to_bytes,
+        test,
+        web::{delete, get},
+        App,
+    };
+    use anyhow::Result;
+    use deputy_package_server::{
+        routes::{
+            admin::{delete_package, delete_version},
+            package::get_all_versions,
+        },
+        test::database::MockDatabase,
+    };
+    use std::path::PathBuf;
+
+    #[actix_web::test]
+    async fn delete_package_version() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let package_name = upload_test_package(&app_state).await?;
+        let package_version = "1.0.4";
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state)
+                .route(
+                    "/admin/package/{package_name}/{version}",
+                    delete().to(delete_version::<MockDatabase>),
+                )
+                .route(
+                    "/package/{package_name}",
+                    get().to(get_all_versions::<MockDatabase>),
+                ),
+        )
+        .await;
+
+        let version_path = PathBuf::from(package_folder.path())
+            .join(&package_name)
+            .join(package_version);
+        assert!(version_path.exists());
+
+        let request = test::TestRequest::delete()
+            .uri(&format!(
+                "/admin/package/{package_name}/{package_version}?hard=true"
+            ))
+            .to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+        assert!(!version_path.exists());
+
+        let request = test::TestRequest::get()
+            .uri(&format!("/package/{package_name}"))
+            .to_request();
+        let body = test::call_and_read_body(&app, request).await;
+        assert_eq!(body.as_str(), "[]");
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn delete_whole_package() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let package_name = upload_test_package(&app_state).await?;
+        let app = test::init_service(App::new().app_data(app_state).route(
+            "/admin/package/{package_name}",
+            delete().to(delete_package::<MockDatabase>),
+        ))
+        .await;
+
+        let request = test::TestRequest::delete()
+            .uri(&format!("/admin/package/{package_name}"))
+            .to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+        assert!(PathBuf::from(package_folder.path())
+            .join(&package_name)
+            .exists());
+
+        let request = test::TestRequest::delete()
+            .uri(&format!("/admin/package/{package_name}"))
+            .to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(body.as_str(), "Not found");
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn hard_delete_whole_package() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let package_name = upload_test_package(&app_state).await?;
+        let app = test::init_service(App::new().app_data(app_state).route(
+            "/admin/package/{package_name}",
+            delete().to(delete_package::<MockDatabase>),
+        ))
+        .await;
+
+        let request = test::TestRequest::delete()
+            .uri(&format!("/admin/package/{package_name}?hard=true"))
+            .to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+        assert!(!PathBuf::from(package_folder.path())
+            .join(&package_name)
+            .exists());
+
+        package_folder.close()?;
+        Ok(())
+    }
+}
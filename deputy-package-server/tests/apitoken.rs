@@ -0,0 +1,103 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{set_scoped_mock_user_token, setup_package_server, BodyTest};
+    use actix_http::{Payload, StatusCode};
+    use actix_web::{test, web::post, App};
+    use anyhow::Result;
+    use deputy_library::{
+        package::{Package, PackageStream},
+        rest::{NewScopedTokenRest, TokenRestrictionsRest, TokenScope},
+        test::TempArchive,
+    };
+    use deputy_package_server::{
+        routes::{apitoken::create_scoped_api_token, package::add_package},
+        test::database::MockDatabase,
+    };
+
+    #[actix_web::test]
+    async fn scoped_token_publishes_only_permitted_packages() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state)
+                .route("/package", post().to(add_package::<MockDatabase>)),
+        )
+        .await;
+
+        let archive = TempArchive::builder().build()?;
+        let test_package: Package = (&archive).try_into()?;
+        let package_name = test_package.metadata.name.clone();
+
+        let stream: PackageStream = test_package.to_stream().await?;
+        let request = test::TestRequest::post().uri("/package").to_request();
+        let (request, _) = request.replace_payload(Payload::from(stream));
+        set_scoped_mock_user_token(&request, vec![TokenScope::Publish], vec!["other-*"]);
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let test_package: Package = (&archive).try_into()?;
+        let stream: PackageStream = test_package.to_stream().await?;
+        let request = test::TestRequest::post().uri("/package").to_request();
+        let (request, _) = request.replace_payload(Payload::from(stream));
+        set_scoped_mock_user_token(&request, vec![TokenScope::Yank], vec![&package_name]);
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let test_package: Package = (&archive).try_into()?;
+        let stream: PackageStream = test_package.to_stream().await?;
+        let request = test::TestRequest::post().uri("/package").to_request();
+        let (request, _) = request.replace_payload(Payload::from(stream));
+        set_scoped_mock_user_token(&request, vec![TokenScope::Publish], vec![&package_name]);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn scoped_token_cannot_exceed_parent_token() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let app = test::init_service(App::new().app_data(app_state).route(
+            "/token/scoped",
+            post().to(create_scoped_api_token::<MockDatabase>),
+        ))
+        .await;
+
+        let request = test::TestRequest::post()
+            .uri("/token/scoped")
+            .set_json(NewScopedTokenRest {
+                name: "ci".to_string(),
+                restrictions: TokenRestrictionsRest {
+                    scopes: vec![TokenScope::Yank],
+                    ..Default::default()
+                },
+            })
+            .to_request();
+        set_scoped_mock_user_token(&request, vec![TokenScope::Publish], vec!["my-*"]);
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let request = test::TestRequest::post()
+            .uri("/token/scoped")
+            .set_json(NewScopedTokenRest {
+                name: "ci".to_string(),
+                restrictions: TokenRestrictionsRest {
+                    scopes: vec![TokenScope::Publish],
+                    packages: vec!["my-package".to_string()],
+                    expires_in_days: Some(30),
+                },
+            })
+            .to_request();
+        set_scoped_mock_user_token(&request, vec![TokenScope::Publish], vec!["my-*"]);
+        let body = test::call_and_read_body(&app, request).await;
+        assert!(body.as_str().contains("\"scopes\":[\"publish\"]"));
+        assert!(body.as_str().contains("\"packages\":[\"my-package\"]"));
+        assert!(!body.as_str().contains("\"expiresAt\":null"));
+
+        package_folder.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{set_mock_user_token, setup_package_server, upload_test_package};
+    use actix_http::StatusCode;
+    use actix_web::{
+        test,
+        web::{delete, get},
+        App,
+    };
+    use anyhow::Result;
+    use deputy_package_server::{
+        models::AuditLogsWithPages,
+        routes::admin::{delete_version, get_audit_logs},
+        test::database::MockDatabase,
+    };
+
+    #[actix_web::test]
+    async fn mutations_are_recorded_in_audit_log() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let package_name = upload_test_package(&app_state).await?;
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state)
+                .route(
+                    "/admin/package/{package_name}/{version}",
+                    delete().to(delete_version::<MockDatabase>),
+                )
+                .route("/admin/audit-log", get().to(get_audit_logs::<MockDatabase>)),
+        )
+        .await;
+
+        let request = test::TestRequest::delete()
+            .uri(&format!("/admin/package/{package_name}/1.0.4"))
+            .insert_header(("User-Agent", "deputy-test"))
+            .to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        let request = test::TestRequest::get()
+            .uri("/admin/audit-log")
+            .to_request();
+        let audit_logs: AuditLogsWithPages = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(audit_logs.total_audit_logs, 2);
+        assert_eq!(audit_logs.audit_logs[0].action, "delete_version");
+        assert_eq!(audit_logs.audit_logs[1].action, "publish_package");
+
+        let request = test::TestRequest::get()
+            .uri("/admin/audit-log?action=delete_version")
+            .to_request();
+        let audit_logs: AuditLogsWithPages = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(audit_logs.total_audit_logs, 1);
+        let audit_log = &audit_logs.audit_logs[0];
+        assert_eq!(audit_log.actor_email, "test-email");
+        assert_eq!(
+            audit_log.package_name.as_deref(),
+            Some(package_name.as_str())
+        );
+        assert_eq!(audit_log.package_version.as_deref(), Some("1.0.4"));
+        assert_eq!(audit_log.user_agent.as_deref(), Some("deputy-test"));
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn unknown_audit_action_is_rejected() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state)
+                .route("/admin/audit-log", get().to(get_audit_logs::<MockDatabase>)),
+        )
+        .await;
+
+        let request = test::TestRequest::get()
+            .uri("/admin/audit-log?action=launch_rockets")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        package_folder.close()?;
+        Ok(())
+    }
+}
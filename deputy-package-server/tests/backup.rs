@@ -0,0 +1,319 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{set_mock_user_token, setup_sqlite_package_server};
+    use actix_http::{Payload, StatusCode};
+    use actix_web::{
+        test,
+        web::{delete, get, post, Data},
+        App,
+    };
+    use anyhow::Result;
+    use deputy_library::{
+        package::{Package, PackageStream},
+        test::TempArchive,
+    };
+    use deputy_package_server::{
+        routes::{
+            admin::delete_package,
+            package::{add_package, download_package},
+        },
+        services::{
+            backup::{create_backup, restore_backup, verify_backup, BackupManifest},
+            database::{Database, DatabaseConnection, DatabaseConnectionManager},
+            storage::package_key,
+        },
+        AppState,
+    };
+    use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+    use std::{fs::File, io::Read, path::Path};
+    use tar::{Archive, Builder, Header};
+    use tempfile::TempDir;
+
+    struct Instance {
+        package_folder: TempDir,
+        app_state: Data<AppState<Database>>,
+    }
+
+    impl Instance {
+        fn new() -> Result<Self> {
+            let (package_folder, app_state) = setup_sqlite_package_server(&Default::default())?;
+            Ok(Self {
+                package_folder,
+                app_state,
+            })
+        }
+
+        fn connection(&self) -> Result<DatabaseConnection> {
+            let database_url = format!(
+                "sqlite://{}",
+                self.package_folder.path().join("deputy.db").display()
+            );
+            Ok(DatabaseConnectionManager::new(&database_url).establish()?)
+        }
+
+        async fn publish(&self, package_name: &str) -> Result<String> {
+            let app = test::init_service(
+                App::new()
+                    .app_data(self.app_state.clone())
+                    .route("/package", post().to(add_package::<Database>)),
+            )
+            .await;
+            let archive = TempArchive::builder()
+                .set_package_name(package_name)
+                .build()?;
+            let test_package: Package = (&archive).try_into()?;
+            let key = package_key(package_name, &test_package.metadata.version);
+            let stream: PackageStream = test_package.to_stream().await?;
+            let request = test::TestRequest::post().uri("/package").to_request();
+            let (request, _) = request.replace_payload(Payload::from(stream));
+            set_mock_user_token(&request);
+            let response = test::call_service(&app, request).await;
+            assert!(response.status().is_success());
+            Ok(key)
+        }
+
+        async fn hard_delete(&self, package_name: &str) -> Result<()> {
+            let app = test::init_service(App::new().app_data(self.app_state.clone()).route(
+                "/admin/package/{package_name}",
+                delete().to(delete_package::<Database>),
+            ))
+            .await;
+            let request = test::TestRequest::delete()
+                .uri(&format!("/admin/package/{package_name}?hard=true"))
+                .to_request();
+            set_mock_user_token(&request);
+            let response = test::call_service(&app, request).await;
+            assert!(response.status().is_success());
+            Ok(())
+        }
+
+        async fn download_status(&self, key: &str) -> StatusCode {
+            let app = test::init_service(App::new().app_data(self.app_state.clone()).route(
+                "/package/{package_name}/{version}/download",
+                get().to(download_package::<Database>),
+            ))
+            .await;
+            let request = test::TestRequest::get()
+                .uri(&format!("/package/{key}/download"))
+                .to_request();
+            test::call_service(&app, request).await.status()
+        }
+
+        async fn backup(&self, backup_path: &Path) -> Result<BackupManifest> {
+            create_backup(
+                &mut self.connection()?,
+                self.app_state.storage.as_ref(),
+                backup_path,
+                None,
+            )
+            .await
+        }
+
+        async fn restore(&self, backup_path: &Path) -> Result<BackupManifest> {
+            restore_backup(
+                &mut self.connection()?,
+                self.app_state.storage.as_ref(),
+                backup_path,
+            )
+            .await
+        }
+    }
+
+    /// Copies the backup with the content of its entries passed through `alter` and `extra_entry`
+    /// appended.
+    fn rewrite_backup(
+        backup_path: &Path,
+        rewritten_path: &Path,
+        alter: impl Fn(&str, Vec<u8>) -> Vec<u8>,
+        extra_entry: Option<(&str, &[u8])>,
+    ) -> Result<()> {
+        let mut archive = Archive::new(MultiGzDecoder::new(File::open(backup_path)?));
+        let mut builder = Builder::new(GzEncoder::new(
+            File::create(rewritten_path)?,
+            Compression::default(),
+        ));
+        let mut append = |path: &str, content: &[u8]| -> Result<()> {
+            let mut header = Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, path, content)?;
+            Ok(())
+        };
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.to_str().unwrap().to_string();
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content)?;
+            append(&path, &alter(&path, content))?;
+        }
+        if let Some((path, content)) = extra_entry {
+            append(path, content)?;
+        }
+        builder.into_inner()?.finish()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn backup_is_restored_into_an_empty_instance() -> Result<()> {
+        let source = Instance::new()?;
+        let keys = [
+            source.publish("web-lab").await?,
+            source.publish("network-lab").await?,
+        ];
+        let backup_folder = TempDir::new()?;
+        let backup_path = backup_folder.path().join("registry.tar.gz");
+
+        let manifest = source.backup(&backup_path).await?;
+        assert_eq!(manifest.since, None);
+        assert_eq!(manifest.tables["packages"], 2);
+        assert_eq!(manifest.tables["versions"], 2);
+        assert_eq!(manifest.tables["owners"], 2);
+        assert_eq!(manifest.archives, vec![keys[1].clone(), keys[0].clone()]);
+
+        let target = Instance::new()?;
+        assert_eq!(target.restore(&backup_path).await?, manifest);
+        let restored_manifest = target
+            .backup(&backup_folder.path().join("restored.tar.gz"))
+            .await?;
+        assert_eq!(restored_manifest.tables, manifest.tables);
+        assert_eq!(restored_manifest.checksums, manifest.checksums);
+        for key in &keys {
+            assert_eq!(target.download_status(key).await, StatusCode::OK);
+        }
+
+        let error = target.restore(&backup_path).await.unwrap_err();
+        assert!(error.to_string().contains("not empty"));
+
+        source.package_folder.close()?;
+        target.package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn altered_backups_are_rejected() -> Result<()> {
+        let source = Instance::new()?;
+        source.publish("web-lab").await?;
+        let backup_folder = TempDir::new()?;
+        let backup_path = backup_folder.path().join("registry.tar.gz");
+        source.backup(&backup_path).await?;
+        assert!(verify_backup(&backup_path, TempDir::new()?.path()).is_ok());
+
+        let altered_path = backup_folder.path().join("altered.tar.gz");
+        rewrite_backup(
+            &backup_path,
+            &altered_path,
+            |path, content| match path.starts_with("archives/") {
+                true => b"bit rot".to_vec(),
+                false => content,
+            },
+            None,
+        )?;
+        let error = verify_backup(&altered_path, TempDir::new()?.path()).unwrap_err();
+        assert!(error.to_string().contains("does not match its checksum"));
+
+        rewrite_backup(
+            &backup_path,
+            &altered_path,
+            |_, content| content,
+            Some(("tables/extra.json", b"[]")),
+        )?;
+        let error = verify_backup(&altered_path, TempDir::new()?.path()).unwrap_err();
+        assert!(error.to_string().contains("is not in the manifest"));
+
+        let target = Instance::new()?;
+        assert!(target.restore(&altered_path).await.is_err());
+        let empty_manifest = target
+            .backup(&backup_folder.path().join("empty.tar.gz"))
+            .await?;
+        assert_eq!(empty_manifest.tables["packages"], 0);
+
+        source.package_folder.close()?;
+        target.package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn incremental_backups_are_restored_on_top_of_the_full_backup() -> Result<()> {
+        let source = Instance::new()?;
+        let first_key = source.publish("first-lab").await?;
+        let backup_folder = TempDir::new()?;
+        let full_backup_path = backup_folder.path().join("full.tar.gz");
+        let full_manifest = source.backup(&full_backup_path).await?;
+
+        let second_key = source.publish("second-lab").await?;
+        let incremental_backup_path = backup_folder.path().join("incremental.tar.gz");
+        let incremental_manifest = create_backup(
+            &mut source.connection()?,
+            source.app_state.storage.as_ref(),
+            &incremental_backup_path,
+            Some(full_manifest.created_at),
+        )
+        .await?;
+        assert_eq!(incremental_manifest.since, Some(full_manifest.created_at));
+        assert!(incremental_manifest.archives.contains(&second_key));
+
+        let target = Instance::new()?;
+        target.restore(&full_backup_path).await?;
+        assert_eq!(
+            target.download_status(&second_key).await,
+            StatusCode::NOT_FOUND
+        );
+        target.restore(&incremental_backup_path).await?;
+        let source_manifest = source
+            .backup(&backup_folder.path().join("source.tar.gz"))
+            .await?;
+        let target_manifest = target
+            .backup(&backup_folder.path().join("target.tar.gz"))
+            .await?;
+        assert_eq!(target_manifest.tables, source_manifest.tables);
+        assert_eq!(target_manifest.archives, source_manifest.archives);
+        assert_eq!(target.download_status(&first_key).await, StatusCode::OK);
+        assert_eq!(target.download_status(&second_key).await, StatusCode::OK);
+
+        source.package_folder.close()?;
+        target.package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn incremental_backups_delete_the_rows_deleted_since_the_full_backup() -> Result<()> {
+        let source = Instance::new()?;
+        let deleted_key = source.publish("deleted-lab").await?;
+        let kept_key = source.publish("kept-lab").await?;
+        let backup_folder = TempDir::new()?;
+        let full_backup_path = backup_folder.path().join("full.tar.gz");
+        let full_manifest = source.backup(&full_backup_path).await?;
+        assert_eq!(full_manifest.tables["packages"], 2);
+
+        source.hard_delete("deleted-lab").await?;
+        let incremental_backup_path = backup_folder.path().join("incremental.tar.gz");
+        create_backup(
+            &mut source.connection()?,
+            source.app_state.storage.as_ref(),
+            &incremental_backup_path,
+            Some(full_manifest.created_at),
+        )
+        .await?;
+
+        let target = Instance::new()?;
+        target.restore(&full_backup_path).await?;
+        assert_eq!(target.download_status(&deleted_key).await, StatusCode::OK);
+        target.restore(&incremental_backup_path).await?;
+        let source_manifest = source
+            .backup(&backup_folder.path().join("source.tar.gz"))
+            .await?;
+        let target_manifest = target
+            .backup(&backup_folder.path().join("target.tar.gz"))
+            .await?;
+        assert_eq!(target_manifest.tables["packages"], 1);
+        assert_eq!(target_manifest.tables, source_manifest.tables);
+        assert_eq!(target_manifest.archives, vec![kept_key.clone()]);
+        assert_eq!(target.download_status(&kept_key).await, StatusCode::OK);
+
+        source.package_folder.close()?;
+        target.package_folder.close()?;
+        Ok(())
+    }
+}
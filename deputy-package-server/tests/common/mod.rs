@@ -8,11 +8,17 @@ use actix_web::{test, web::post, App};
 use anyhow::Result;
 use deputy_library::{
     package::{Package, PackageStream},
+    rest::TokenScope,
     test::TempArchive,
 };
-use deputy_package_server::{middleware::authentication::local_token::UserToken, AppState};
+use deputy_package_server::{
+    configuration::DatabasePoolConfiguration,
+    middleware::authentication::local_token::UserToken,
+    services::{database::Database, storage::FilesystemStorage},
+    AppState,
+};
 use deputy_package_server::{routes::package::add_package, test::database::MockDatabase};
-use std::rc::Rc;
+use std::{path::Path, rc::Rc, sync::Arc};
 use tempfile::TempDir;
 
 pub trait BodyTest {
@@ -33,18 +39,75 @@ pub fn setup_package_server() -> Result<(TempDir, Data<AppState<MockDatabase>>)>
     Ok((
         package_folder,
         Data::new(AppState {
-            package_folder: package_folder_string,
+            storage: Arc::new(FilesystemStorage::new(&package_folder_string)),
             database_address,
+            extraction_limits: Default::default(),
+            package_size_limits: Default::default(),
+            role_requirements: Default::default(),
+            metrics: Default::default(),
         }),
     ))
 }
 
+/// Package server backed by the real database on a temporary SQLite file, the database file is
+/// kept in the returned package folder.
+pub fn setup_sqlite_package_server(
+    pool_configuration: &DatabasePoolConfiguration,
+) -> Result<(TempDir, Data<AppState<Database>>)> {
+    let package_folder = TempDir::new()?;
+    let database_url = format!(
+        "sqlite://{}",
+        package_folder.path().join("deputy.db").display()
+    );
+    let app_state =
+        setup_database_package_server(&database_url, package_folder.path(), pool_configuration)?;
+    Ok((package_folder, app_state))
+}
+
+pub fn setup_database_package_server(
+    database_url: &str,
+    package_folder: &Path,
+    pool_configuration: &DatabasePoolConfiguration,
+) -> Result<Data<AppState<Database>>> {
+    let database_address = Database::try_new(database_url, pool_configuration)?.start();
+    Ok(Data::new(AppState {
+        storage: Arc::new(FilesystemStorage::new(package_folder.to_str().unwrap())),
+        database_address,
+        extraction_limits: Default::default(),
+        package_size_limits: Default::default(),
+        role_requirements: Default::default(),
+        metrics: Default::default(),
+    }))
+}
+
 pub fn set_mock_user_token(request: &Request) {
+    set_mock_user_token_with_email(request, "test-email");
+}
+
+pub fn set_mock_user_token_with_email(request: &Request, email: &str) {
+    request
+        .extensions_mut()
+        .insert::<Rc<UserToken>>(Rc::new(UserToken {
+            id: "test-id".to_string(),
+            email: email.to_string(),
+            roles: Vec::new(),
+            ..Default::default()
+        }));
+}
+
+pub fn set_scoped_mock_user_token(request: &Request, scopes: Vec<TokenScope>, packages: Vec<&str>) {
     request
         .extensions_mut()
         .insert::<Rc<UserToken>>(Rc::new(UserToken {
             id: "test-id".to_string(),
             email: "test-email".to_string(),
+            roles: Vec::new(),
+            scopes,
+            packages: packages
+                .into_iter()
+                .map(|package| package.to_string())
+                .collect(),
+            ..Default::default()
         }));
 }
 
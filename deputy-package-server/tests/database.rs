@@ -0,0 +1,433 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{
+        set_mock_user_token, setup_database_package_server, setup_sqlite_package_server, BodyTest,
+    };
+    use actix_http::{Payload, StatusCode};
+    use actix_web::{
+        test,
+        web::{delete, get, post, put, scope, Data},
+        App,
+    };
+    use anyhow::Result;
+    use deputy_library::{
+        package::{Package, PackageStream},
+        rest::{DownloadStatsRest, PackagesWithVersionsAndPagesRest, VersionRest},
+        test::TempArchive,
+    };
+    use deputy_package_server::{
+        models::Version,
+        routes::{
+            admin::{delete_package, delete_version},
+            owner::{add_owner, delete_owner, get_all_owners},
+            package::{
+                add_package, download_package, get_all_packages, get_all_versions,
+                get_download_stats, yank_version,
+            },
+        },
+        schema::{package_search, version_downloads},
+        services::database::{Database, DatabaseConnectionManager},
+        test::middleware::MockTokenMiddlewareFactory,
+        utilities::{migration_states, pending_migrations, revert_migrations, run_migrations},
+        AppState,
+    };
+    use diesel::{QueryDsl, RunQueryDsl};
+    use std::env;
+    use tempfile::TempDir;
+
+    const POSTGRESQL_URL_VARIABLE: &str = "DEPUTY_TEST_POSTGRESQL_URL";
+
+    async fn publish_search_and_download(app_state: Data<AppState<Database>>) -> Result<()> {
+        let app = test::init_service(
+            App::new().app_data(app_state).service(
+                scope("/package")
+                    .route("", get().to(get_all_packages::<Database>))
+                    .route("", post().to(add_package::<Database>))
+                    .service(
+                        scope("/{package_name}")
+                            .route("/stats", get().to(get_download_stats::<Database>))
+                            .route(
+                                "/{version}/download",
+                                get().to(download_package::<Database>),
+                            ),
+                    ),
+            ),
+        )
+        .await;
+
+        for package_name in ["alpha-lab", "beta-lab"] {
+            let archive = TempArchive::builder()
+                .set_package_name(package_name)
+                .build()?;
+            let test_package: Package = (&archive).try_into()?;
+            let stream: PackageStream = test_package.to_stream().await?;
+            let request = test::TestRequest::post().uri("/package").to_request();
+            let (request, _) = request.replace_payload(Payload::from(stream));
+            set_mock_user_token(&request);
+            let response = test::call_service(&app, request).await;
+            assert!(response.status().is_success());
+        }
+
+        let request = test::TestRequest::get()
+            .uri("/package?search_term=beta%20manhours")
+            .to_request();
+        let packages: PackagesWithVersionsAndPagesRest =
+            test::call_and_read_body_json(&app, request).await;
+        let package_names: Vec<&str> = packages
+            .packages
+            .iter()
+            .map(|package| package.name.as_str())
+            .collect();
+        assert_eq!(package_names, vec!["beta-lab", "alpha-lab"]);
+        assert_eq!(packages.total_packages, 2);
+        assert_eq!(packages.facets.package_types.get("VM"), Some(&2));
+        assert_eq!(packages.facets.categories.get("category1"), Some(&2));
+
+        let request = test::TestRequest::get()
+            .uri("/package?search_term=nonexistentword")
+            .to_request();
+        let packages: PackagesWithVersionsAndPagesRest =
+            test::call_and_read_body_json(&app, request).await;
+        assert!(packages.packages.is_empty());
+
+        for _ in 0..2 {
+            let request = test::TestRequest::get()
+                .uri("/package/beta-lab/1.0.4/download")
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert!(response.status().is_success());
+        }
+
+        let request = test::TestRequest::get()
+            .uri("/package/beta-lab/stats?days=7")
+            .to_request();
+        let download_stats: DownloadStatsRest = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(download_stats.total_downloads, 2);
+        assert_eq!(download_stats.daily.len(), 1);
+        assert_eq!(download_stats.daily[0].downloads, 2);
+
+        let request = test::TestRequest::get()
+            .uri("/package?sort=downloads")
+            .to_request();
+        let packages: PackagesWithVersionsAndPagesRest =
+            test::call_and_read_body_json(&app, request).await;
+        assert_eq!(packages.packages[0].name, "beta-lab");
+        assert_eq!(packages.packages[0].downloads, 2);
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn packages_are_published_searched_and_downloaded_with_sqlite() -> Result<()> {
+        let (package_folder, app_state) = setup_sqlite_package_server(&Default::default())?;
+        publish_search_and_download(app_state).await?;
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires a PostgreSQL database in DEPUTY_TEST_POSTGRESQL_URL"]
+    async fn packages_are_published_searched_and_downloaded_with_postgresql() -> Result<()> {
+        let database_url = env::var(POSTGRESQL_URL_VARIABLE)?;
+        let package_folder = TempDir::new()?;
+        let app_state = setup_database_package_server(
+            &database_url,
+            package_folder.path(),
+            &Default::default(),
+        )?;
+        publish_search_and_download(app_state).await?;
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn versions_are_yanked_and_republishing_is_rejected_with_sqlite() -> Result<()> {
+        let (package_folder, app_state) = setup_sqlite_package_server(&Default::default())?;
+        let app = test::init_service(
+            App::new().app_data(app_state).service(
+                scope("/package")
+                    .route("", post().to(add_package::<Database>))
+                    .service(
+                        scope("/{package_name}")
+                            .route("", get().to(get_all_versions::<Database>))
+                            .route(
+                                "/{version}/yank/{set_yank}",
+                                put().to(yank_version::<Database>),
+                            ),
+                    ),
+            ),
+        )
+        .await;
+
+        let mut package_name = String::new();
+        for is_first_upload in [true, false] {
+            let archive = TempArchive::builder().build()?;
+            let test_package: Package = (&archive).try_into()?;
+            package_name = test_package.metadata.name.clone();
+            let stream: PackageStream = test_package.to_stream().await?;
+            let request = test::TestRequest::post().uri("/package").to_request();
+            let (request, _) = request.replace_payload(Payload::from(stream));
+            set_mock_user_token(&request);
+            let response = test::call_service(&app, request).await;
+            assert_eq!(response.status().is_success(), is_first_upload);
+        }
+
+        let request = test::TestRequest::put()
+            .uri(&format!("/package/{package_name}/1.0.4/yank/true"))
+            .to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        let request = test::TestRequest::get()
+            .uri(&format!("/package/{package_name}"))
+            .to_request();
+        let versions: Vec<VersionRest> = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(versions.len(), 1);
+        assert!(versions[0].is_yanked);
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn owners_are_added_and_removed_with_sqlite() -> Result<()> {
+        let (package_folder, app_state) = setup_sqlite_package_server(&Default::default())?;
+        let app = test::init_service(
+            App::new().app_data(app_state).service(
+                scope("/package")
+                    .route("", post().to(add_package::<Database>))
+                    .service(
+                        scope("/{package_name}/owner")
+                            .route("", get().to(get_all_owners::<Database>))
+                            .route("", post().to(add_owner::<Database>))
+                            .route("/{owner_email}", delete().to(delete_owner::<Database>))
+                            .wrap(MockTokenMiddlewareFactory),
+                    ),
+            ),
+        )
+        .await;
+
+        let archive = TempArchive::builder().build()?;
+        let test_package: Package = (&archive).try_into()?;
+        let package_name = test_package.metadata.name.clone();
+        let stream: PackageStream = test_package.to_stream().await?;
+        let request = test::TestRequest::post().uri("/package").to_request();
+        let (request, _) = request.replace_payload(Payload::from(stream));
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        let request = test::TestRequest::post()
+            .uri(&format!("/package/{package_name}/owner?email=new-owner"))
+            .to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        let request = test::TestRequest::delete()
+            .uri(&format!("/package/{package_name}/owner/new-owner"))
+            .to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        let request = test::TestRequest::get()
+            .uri(&format!("/package/{package_name}/owner"))
+            .to_request();
+        set_mock_user_token(&request);
+        let body = test::call_and_read_body(&app, request).await;
+        assert!(body.as_str().contains("test-email"));
+        assert!(!body.as_str().contains("new-owner"));
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn soft_delete_keeps_the_archive_and_hard_delete_removes_every_row_with_sqlite(
+    ) -> Result<()> {
+        let (package_folder, app_state) = setup_sqlite_package_server(&Default::default())?;
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state)
+                .route("/package", post().to(add_package::<Database>))
+                .route(
+                    "/package/{package_name}/{version}/download",
+                    get().to(download_package::<Database>),
+                )
+                .route(
+                    "/admin/package/{package_name}",
+                    delete().to(delete_package::<Database>),
+                ),
+        )
+        .await;
+
+        let archive = TempArchive::builder().build()?;
+        let test_package: Package = (&archive).try_into()?;
+        let package_name = test_package.metadata.name.clone();
+        let stream: PackageStream = test_package.to_stream().await?;
+        let request = test::TestRequest::post().uri("/package").to_request();
+        let (request, _) = request.replace_payload(Payload::from(stream));
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+        let request = test::TestRequest::get()
+            .uri(&format!("/package/{package_name}/1.0.4/download"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        let database_url = format!(
+            "sqlite://{}",
+            package_folder.path().join("deputy.db").display()
+        );
+        let mut connection = DatabaseConnectionManager::new(&database_url).establish()?;
+        let archive_path = package_folder.path().join(&package_name).join("1.0.4");
+
+        let request = test::TestRequest::delete()
+            .uri(&format!("/admin/package/{package_name}"))
+            .to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+        assert!(archive_path.exists());
+        assert_eq!(Version::load_stored(&mut connection)?.len(), 1);
+
+        let request = test::TestRequest::delete()
+            .uri(&format!("/admin/package/{package_name}?hard=true"))
+            .to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+        assert!(!archive_path.exists());
+        assert!(Version::load_stored(&mut connection)?.is_empty());
+        assert_eq!(
+            version_downloads::table
+                .count()
+                .get_result::<i64>(&mut connection)?,
+            0
+        );
+        assert_eq!(
+            package_search::table
+                .count()
+                .get_result::<i64>(&mut connection)?,
+            0
+        );
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn soft_deleted_names_and_versions_are_not_published_again_with_sqlite() -> Result<()> {
+        let (package_folder, app_state) = setup_sqlite_package_server(&Default::default())?;
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state)
+                .route("/package", post().to(add_package::<Database>))
+                .route(
+                    "/admin/package/{package_name}",
+                    delete().to(delete_package::<Database>),
+                )
+                .route(
+                    "/admin/package/{package_name}/{version}",
+                    delete().to(delete_version::<Database>),
+                ),
+        )
+        .await;
+
+        let mut package_name = String::new();
+        for (deletion, expected_status) in [
+            (None, StatusCode::OK),
+            (Some("/1.0.4"), StatusCode::CONFLICT),
+            (Some(""), StatusCode::CONFLICT),
+            (Some("?hard=true"), StatusCode::OK),
+        ] {
+            if let Some(deletion) = deletion {
+                let request = test::TestRequest::delete()
+                    .uri(&format!("/admin/package/{package_name}{deletion}"))
+                    .to_request();
+                set_mock_user_token(&request);
+                let response = test::call_service(&app, request).await;
+                assert!(response.status().is_success());
+            }
+
+            let archive = TempArchive::builder().build()?;
+            let test_package: Package = (&archive).try_into()?;
+            package_name = test_package.metadata.name.clone();
+            let stream: PackageStream = test_package.to_stream().await?;
+            let request = test::TestRequest::post().uri("/package").to_request();
+            let (request, _) = request.replace_payload(Payload::from(stream));
+            set_mock_user_token(&request);
+            let response = test::call_service(&app, request).await;
+            assert_eq!(response.status(), expected_status);
+        }
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn pool_without_migrations_leaves_the_schema_alone_with_sqlite() -> Result<()> {
+        let database_folder = TempDir::new()?;
+        let database_url = format!(
+            "sqlite://{}",
+            database_folder.path().join("deputy.db").display()
+        );
+
+        let database = Database::try_new_without_migrations(&database_url, &Default::default())?;
+        let pending = database.pending_migrations()?;
+        assert!(!pending.is_empty());
+        assert_eq!(database.pending_migrations()?, pending);
+
+        let database = Database::try_new(&database_url, &Default::default())?;
+        assert!(database.pending_migrations()?.is_empty());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn migrations_are_listed_reverted_and_run_again_with_sqlite() -> Result<()> {
+        let database_folder = TempDir::new()?;
+        let database_url = format!(
+            "sqlite://{}",
+            database_folder.path().join("deputy.db").display()
+        );
+        let mut connection = DatabaseConnectionManager::new(&database_url).establish()?;
+
+        let migration_names: Vec<String> = migration_states(&mut connection)
+            .unwrap()
+            .into_iter()
+            .map(|state| state.name)
+            .collect();
+        assert!(!migration_names.is_empty());
+        assert_eq!(
+            pending_migrations(&mut connection).unwrap(),
+            migration_names
+        );
+
+        run_migrations(&mut connection).unwrap();
+        assert!(pending_migrations(&mut connection).unwrap().is_empty());
+
+        let last_migration = migration_names.last().unwrap().clone();
+        assert_eq!(
+            revert_migrations(&mut connection, 1).unwrap(),
+            vec![last_migration.clone()]
+        );
+        assert_eq!(
+            pending_migrations(&mut connection).unwrap(),
+            vec![last_migration]
+        );
+
+        run_migrations(&mut connection).unwrap();
+        assert!(migration_states(&mut connection)
+            .unwrap()
+            .iter()
+            .all(|state| state.is_applied));
+
+        database_folder.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{setup_database_package_server, setup_sqlite_package_server};
+    use actix_http::StatusCode;
+    use actix_web::{
+        test,
+        web::{get, scope, Data},
+        App,
+    };
+    use anyhow::Result;
+    use deputy_library::rest::{HealthRest, HealthStatus};
+    use deputy_package_server::{
+        configuration::{Configuration, HealthConfiguration},
+        routes::health::{get_liveness, get_readiness},
+        services::{
+            database::{Database, DatabaseConnectionManager},
+            health::HealthChecker,
+        },
+        utilities::revert_migrations,
+        AppState,
+    };
+    use tempfile::{NamedTempFile, TempDir};
+
+    fn valid_pem_content() -> Result<String> {
+        let configuration: Configuration =
+            serde_yaml::from_str(include_str!("../example-config.yml"))?;
+        Ok(configuration.keycloak.pem_content)
+    }
+
+    async fn check_health(
+        app_state: Data<AppState<Database>>,
+        health_checker: HealthChecker,
+        path: &str,
+    ) -> (StatusCode, HealthRest) {
+        let app = test::init_service(
+            App::new().app_data(app_state).service(
+                scope("/health")
+                    .app_data(Data::new(health_checker))
+                    .route("/live", get().to(get_liveness::<Database>))
+                    .route("/ready", get().to(get_readiness::<Database>)),
+            ),
+        )
+        .await;
+        let request = test::TestRequest::get().uri(path).to_request();
+        let response = test::call_service(&app, request).await;
+        let status = response.status();
+        (status, test::read_body_json(response).await)
+    }
+
+    #[actix_web::test]
+    async fn readiness_follows_the_free_space_thresholds() -> Result<()> {
+        let (package_folder, app_state) = setup_sqlite_package_server(&Default::default())?;
+        let health_checker = |minimum_free_bytes, warning_free_bytes| -> Result<HealthChecker> {
+            Ok(HealthChecker::new(
+                HealthConfiguration {
+                    minimum_free_bytes,
+                    warning_free_bytes,
+                    ..Default::default()
+                },
+                valid_pem_content()?,
+            ))
+        };
+
+        let (status, health) =
+            check_health(app_state.clone(), health_checker(0, 0)?, "/health/ready").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(health.status, HealthStatus::Up);
+        for component in ["database", "storage", "jwtKey"] {
+            assert_eq!(health.checks[component].status, HealthStatus::Up);
+        }
+        assert!(health.checks["storage"].free_bytes.is_some());
+
+        let (status, health) = check_health(
+            app_state.clone(),
+            health_checker(0, u64::MAX)?,
+            "/health/ready",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(health.status, HealthStatus::Degraded);
+        assert_eq!(health.checks["storage"].status, HealthStatus::Degraded);
+
+        let (status, health) = check_health(
+            app_state,
+            health_checker(u64::MAX, u64::MAX)?,
+            "/health/ready",
+        )
+        .await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(health.status, HealthStatus::Down);
+        assert_eq!(health.checks["storage"].status, HealthStatus::Down);
+        assert_eq!(health.checks["database"].status, HealthStatus::Up);
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn unusable_dependencies_fail_readiness_but_not_liveness() -> Result<()> {
+        let database_folder = TempDir::new()?;
+        let database_url = format!(
+            "sqlite://{}",
+            database_folder.path().join("deputy.db").display()
+        );
+        // A file where the package folder should be can not be written to
+        let package_folder = NamedTempFile::new()?;
+        let app_state = setup_database_package_server(
+            &database_url,
+            package_folder.path(),
+            &Default::default(),
+        )?;
+        let mut connection = DatabaseConnectionManager::new(&database_url).establish()?;
+        let reverted_migrations =
+            revert_migrations(&mut connection, 1).map_err(|error| anyhow::anyhow!("{error}"))?;
+        let health_checker = HealthChecker::new(
+            HealthConfiguration {
+                minimum_free_bytes: 0,
+                warning_free_bytes: 0,
+                ..Default::default()
+            },
+            "not-a-key".to_string(),
+        );
+
+        let (status, health) =
+            check_health(app_state.clone(), health_checker.clone(), "/health/ready").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(health.status, HealthStatus::Down);
+        for component in ["database", "storage", "jwtKey"] {
+            assert_eq!(health.checks[component].status, HealthStatus::Down);
+            assert!(health.checks[component].message.is_some());
+        }
+        assert_eq!(
+            health.checks["database"].pending_migrations,
+            reverted_migrations
+        );
+
+        let (status, health) = check_health(app_state, health_checker, "/health/live").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(health.status, HealthStatus::Up);
+
+        database_folder.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{set_mock_user_token, setup_sqlite_package_server};
+    use actix_http::Payload;
+    use actix_web::{
+        test,
+        web::{get, post, scope},
+        App,
+    };
+    use anyhow::Result;
+    use deputy_library::{
+        package::{Package, PackageStream},
+        rest::DownloadStatsRest,
+        test::TempArchive,
+    };
+    use deputy_package_server::{
+        configuration::DatabasePoolConfiguration,
+        routes::package::{add_package, download_package, get_download_stats},
+        services::database::Database,
+    };
+    use futures::future::join_all;
+    use std::time::Instant;
+
+    const PACKAGE_COUNT: usize = 16;
+    const DOWNLOADS_PER_PACKAGE: usize = 8;
+
+    /// Every request is in flight at once while the pool only has two connections, the requests
+    /// wait for a connection on the blocking thread pool instead of stalling the server.
+    #[actix_web::test]
+    async fn concurrent_publishes_and_downloads_share_a_small_connection_pool() -> Result<()> {
+        let (package_folder, app_state) =
+            setup_sqlite_package_server(&DatabasePoolConfiguration {
+                max_connections: 2,
+                ..Default::default()
+            })?;
+        let app = test::init_service(
+            App::new().app_data(app_state).service(
+                scope("/package")
+                    .route("", post().to(add_package::<Database>))
+                    .service(
+                        scope("/{package_name}")
+                            .route("/stats", get().to(get_download_stats::<Database>))
+                            .route(
+                                "/{version}/download",
+                                get().to(download_package::<Database>),
+                            ),
+                    ),
+            ),
+        )
+        .await;
+
+        let package_names: Vec<String> = (0..PACKAGE_COUNT)
+            .map(|index| format!("load-test-package-{index}"))
+            .collect();
+        let mut publish_requests = Vec::new();
+        for package_name in &package_names {
+            let archive = TempArchive::builder()
+                .set_package_name(package_name)
+                .build()?;
+            let test_package: Package = (&archive).try_into()?;
+            let stream: PackageStream = test_package.to_stream().await?;
+            let request = test::TestRequest::post().uri("/package").to_request();
+            let (request, _) = request.replace_payload(Payload::from(stream));
+            set_mock_user_token(&request);
+            publish_requests.push(request);
+        }
+
+        let publish_start = Instant::now();
+        let responses = join_all(
+            publish_requests
+                .into_iter()
+                .map(|request| test::call_service(&app, request)),
+        )
+        .await;
+        let publish_duration = publish_start.elapsed();
+        assert!(responses
+            .iter()
+            .all(|response| response.status().is_success()));
+
+        let download_start = Instant::now();
+        let responses = join_all(
+            package_names
+                .iter()
+                .flat_map(|package_name| {
+                    (0..DOWNLOADS_PER_PACKAGE).map(move |_| {
+                        test::TestRequest::get()
+                            .uri(&format!("/package/{package_name}/1.0.4/download"))
+                            .to_request()
+                    })
+                })
+                .map(|request| test::call_service(&app, request)),
+        )
+        .await;
+        let download_duration = download_start.elapsed();
+        assert!(responses
+            .iter()
+            .all(|response| response.status().is_success()));
+
+        for package_name in &package_names {
+            let request = test::TestRequest::get()
+                .uri(&format!("/package/{package_name}/stats?days=1"))
+                .to_request();
+            let download_stats: DownloadStatsRest =
+                test::call_and_read_body_json(&app, request).await;
+            assert_eq!(download_stats.total_downloads, DOWNLOADS_PER_PACKAGE as u64);
+        }
+
+        println!(
+            "Published {PACKAGE_COUNT} packages in {publish_duration:?} ({:.1}/s), served {} downloads in {download_duration:?} ({:.1}/s)",
+            PACKAGE_COUNT as f64 / publish_duration.as_secs_f64(),
+            PACKAGE_COUNT * DOWNLOADS_PER_PACKAGE,
+            (PACKAGE_COUNT * DOWNLOADS_PER_PACKAGE) as f64 / download_duration.as_secs_f64(),
+        );
+        package_folder.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{set_mock_user_token, setup_sqlite_package_server, BodyTest};
+    use actix_http::{header::AUTHORIZATION, Payload, StatusCode};
+    use actix_web::{
+        test,
+        web::{get, post, scope, Data},
+        App,
+    };
+    use anyhow::Result;
+    use deputy_library::{
+        package::{Package, PackageStream},
+        test::TempArchive,
+    };
+    use deputy_package_server::{
+        configuration::MetricsConfiguration,
+        middleware::metrics::MetricsMiddlewareFactory,
+        routes::{
+            metrics::get_metrics,
+            package::{add_package, download_package},
+        },
+        services::database::Database,
+    };
+
+    #[actix_web::test]
+    async fn metrics_count_requests_per_route_and_transferred_bytes() -> Result<()> {
+        let (package_folder, app_state) = setup_sqlite_package_server(&Default::default())?;
+        let metrics_configuration = MetricsConfiguration {
+            bearer_token: Some("scrape-token".to_string()),
+            ..Default::default()
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .app_data(Data::new(metrics_configuration))
+                .wrap(MetricsMiddlewareFactory(app_state.metrics.clone()))
+                .route("/metrics", get().to(get_metrics::<Database>))
+                .service(
+                    scope("/package")
+                        .route("", post().to(add_package::<Database>))
+                        .route(
+                            "/{package_name}/{version}/download",
+                            get().to(download_package::<Database>),
+                        ),
+                ),
+        )
+        .await;
+
+        let archive = TempArchive::builder().build()?;
+        let test_package: Package = (&archive).try_into()?;
+        let package_name = test_package.metadata.name.clone();
+        let package_size = test_package.metadata.package_size;
+        let stream: PackageStream = test_package.to_stream().await?;
+        let request = test::TestRequest::post().uri("/package").to_request();
+        let (request, _) = request.replace_payload(Payload::from(stream));
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        let request = test::TestRequest::get()
+            .uri(&format!("/package/{package_name}/1.0.4/download"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        let request = test::TestRequest::get().uri("/metrics").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let request = test::TestRequest::get()
+            .uri("/metrics")
+            .insert_header((AUTHORIZATION, "Bearer scrape-token"))
+            .to_request();
+        let body = test::call_and_read_body(&app, request).await;
+        let metrics = body.as_str();
+
+        assert!(metrics.contains(
+            "deputy_http_requests_total{method=\"POST\",route=\"/package\",status=\"200\"} 1"
+        ));
+        assert!(metrics.contains(
+            "deputy_http_requests_total{method=\"GET\",route=\"/package/{package_name}/{version}/download\",status=\"200\"} 1"
+        ));
+        assert!(metrics.contains(
+            "deputy_http_requests_total{method=\"GET\",route=\"/metrics\",status=\"401\"} 1"
+        ));
+        assert!(metrics.contains(&format!("deputy_uploaded_bytes_total {package_size}")));
+        assert!(metrics.contains(&format!("deputy_downloaded_bytes_total {package_size}")));
+        assert!(metrics.contains("deputy_published_versions_total 1"));
+        assert!(metrics.contains("deputy_database_pool_max_connections 10"));
+        assert!(metrics.contains(&format!("deputy_storage_used_bytes {package_size}")));
+
+        package_folder.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{setup_sqlite_package_server, BodyTest};
+    use actix_http::{header::CONTENT_TYPE, Method, StatusCode};
+    use actix_web::{dev::ResourceDef, test, web::to, App, HttpResponse};
+    use anyhow::Result;
+    use deputy_package_server::{
+        configuration::Configuration,
+        openapi::{document, operations, Operation, API_PREFIX},
+        routes::{
+            api::configure_api,
+            documentation::{get_api_documentation, get_openapi_document},
+        },
+        services::rate_limit::RateLimiter,
+    };
+    use serde_json::Value;
+    use std::{
+        collections::{BTreeSet, HashSet},
+        sync::Arc,
+    };
+
+    /// Status of the requests no route is registered for, none of the handlers answers with it
+    /// and the authentication middleware of the routes fails with an error instead.
+    const UNROUTED: StatusCode = StatusCode::NOT_IMPLEMENTED;
+
+    fn configuration() -> Result<Configuration> {
+        Ok(serde_yaml::from_str(
+            "hostname: 127.0.0.1:9000
+package_folder: /tmp/packages
+database_url: sqlite:///tmp/deputy.db
+keycloak:
+  pem_content: test-key
+rate_limits:
+  enabled: false
+",
+        )?)
+    }
+
+    /// Path of the operation with `example` for every path parameter.
+    fn example_path(operation: &Operation) -> String {
+        operation
+            .openapi_path()
+            .split('/')
+            .map(|segment| match segment.starts_with('{') {
+                true => "example",
+                false => segment,
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    #[actix_web::test]
+    async fn registered_routes_match_the_documented_paths() -> Result<()> {
+        let (package_folder, app_state) = setup_sqlite_package_server(&Default::default())?;
+        let configuration = configuration()?;
+        let rate_limiter = Arc::new(RateLimiter::new(configuration.rate_limits.clone(), None));
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state)
+                .configure(configure_api(&configuration, rate_limiter))
+                .default_service(to(|| async { HttpResponse::new(UNROUTED) })),
+        )
+        .await;
+
+        let operations = operations();
+        let example_paths: BTreeSet<String> = operations.iter().map(example_path).collect();
+        for path in &example_paths {
+            for method in [
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::PATCH,
+                Method::DELETE,
+            ] {
+                let documented = operations.iter().any(|operation| {
+                    operation.method == method
+                        && ResourceDef::new(format!("{API_PREFIX}{}", operation.path))
+                            .is_match(path)
+                });
+                let request = test::TestRequest::default()
+                    .method(method.clone())
+                    .uri(path)
+                    .to_request();
+                let registered = match test::try_call_service(&app, request).await {
+                    Ok(response) => response.status() != UNROUTED,
+                    Err(_) => true,
+                };
+                assert_eq!(
+                    registered, documented,
+                    "{method} {path} is registered: {registered}, documented: {documented}"
+                );
+            }
+        }
+        let operation_ids: HashSet<&str> = operations
+            .iter()
+            .map(|operation| operation.operation_id)
+            .collect();
+        assert_eq!(operation_ids.len(), operations.len());
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn every_referenced_schema_is_defined() -> Result<()> {
+        let document = document();
+        let schemas = document["components"]["schemas"]
+            .as_object()
+            .expect("document has schemas");
+        let document_string = serde_json::to_string(&document)?;
+        for reference in document_string.split("\"$ref\":\"").skip(1) {
+            let reference = &reference[..reference.find('"').unwrap_or(reference.len())];
+            if let Some(schema) = reference.strip_prefix("#/components/schemas/") {
+                assert!(schemas.contains_key(schema), "{schema} is not defined");
+            }
+        }
+        for (name, schema) in schemas {
+            assert!(schema.get("type").is_some(), "{name} has no type");
+        }
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn serves_the_document_and_the_documentation_page() -> Result<()> {
+        let app = test::init_service(
+            App::new()
+                .service(get_api_documentation)
+                .service(get_openapi_document),
+        )
+        .await;
+
+        let request = test::TestRequest::get()
+            .uri("/api-docs/openapi.json")
+            .to_request();
+        let served_document: Value = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(served_document, document());
+        assert_eq!(served_document["openapi"], "3.0.3");
+        assert!(
+            served_document["paths"]["/api/v1/package/{package_name}/{version}/path/{tail}"]["get"]
+                .is_object()
+        );
+
+        let request = test::TestRequest::get().uri("/api-docs").to_request();
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).unwrap(),
+            "text/html; charset=utf-8"
+        );
+        let body = test::read_body(response).await;
+        assert!(body.as_str().contains("/api-docs/openapi.json"));
+
+        Ok(())
+    }
+}
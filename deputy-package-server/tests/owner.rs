@@ -2,7 +2,11 @@ mod common;
 
 #[cfg(test)]
 mod tests {
-    use crate::common::{set_mock_user_token, setup_package_server, upload_test_package};
+    use crate::common::{
+        set_mock_user_token, set_mock_user_token_with_email, setup_package_server,
+        upload_test_package,
+    };
+    use actix_http::{Payload, StatusCode};
     use actix_web::web::{delete, post};
     use actix_web::{
         test,
@@ -10,9 +14,16 @@ mod tests {
         App,
     };
     use anyhow::Result;
+    use deputy_library::{
+        package::{Package, PackageStream, Visibility},
+        test::TempArchive,
+    };
     use deputy_package_server::test::middleware::MockTokenMiddlewareFactory;
     use deputy_package_server::{
-        routes::owner::{add_owner, delete_owner, get_all_owners},
+        routes::{
+            owner::{add_owner, delete_owner, get_all_owners, get_team_owners},
+            package::add_package,
+        },
         test::database::MockDatabase,
     };
 
@@ -123,4 +134,54 @@ mod tests {
         assert!(response.status().is_success());
         Ok(())
     }
+
+    #[actix_web::test]
+    async fn owners_of_a_private_package_are_only_listed_to_its_readers() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let archive = TempArchive::builder().build()?;
+        let mut test_package: Package = (&archive).try_into()?;
+        test_package.metadata.visibility = Visibility::OwnersOnly;
+        let package_name = test_package.metadata.name.clone();
+
+        let app = test::init_service(
+            App::new().app_data(app_state).service(
+                scope("/package")
+                    .route("", post().to(add_package::<MockDatabase>))
+                    .service(
+                        scope("/{package_name}/owner")
+                            .route("", get().to(get_all_owners::<MockDatabase>))
+                            .route("/team", get().to(get_team_owners::<MockDatabase>)),
+                    ),
+            ),
+        )
+        .await;
+        let stream: PackageStream = test_package.to_stream().await?;
+        let request = test::TestRequest::post().uri("/package").to_request();
+        let (request, _) = request.replace_payload(Payload::from(stream));
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        for owners_uri in [
+            format!("/package/{package_name}/owner"),
+            format!("/package/{package_name}/owner/team"),
+        ] {
+            let request = test::TestRequest::get().uri(&owners_uri).to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+            let request = test::TestRequest::get().uri(&owners_uri).to_request();
+            set_mock_user_token_with_email(&request, "other-email");
+            let response = test::call_service(&app, request).await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+            let request = test::TestRequest::get().uri(&owners_uri).to_request();
+            set_mock_user_token(&request);
+            let response = test::call_service(&app, request).await;
+            assert!(response.status().is_success());
+        }
+
+        package_folder.close()?;
+        Ok(())
+    }
 }
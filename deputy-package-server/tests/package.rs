@@ -4,21 +4,24 @@ mod common;
 mod tests {
 
     use crate::common::{set_mock_user_token, setup_package_server, upload_test_package, BodyTest};
-    use actix_http::Payload;
+    use actix_http::{Payload, StatusCode};
     use actix_web::{
         body::to_bytes,
         test,
-        web::{get, post, put, scope},
+        web::{delete, get, post, put, scope, Data},
         App,
     };
     use anyhow::Result;
     use deputy_library::{
-        package::{Package, PackageStream},
+        package::{Package, PackageStream, Visibility},
+        rest::{DeprecationRest, DownloadStatsRest, PackagesWithVersionsAndPagesRest},
         test::TempArchive,
     };
     use deputy_package_server::{
         routes::package::{
-            add_package, download_file, download_package, get_all_categories, yank_version,
+            add_package, deprecate_package, download_file, download_package, get_all_categories,
+            get_all_packages, get_deprecation, get_download_stats, undeprecate_package,
+            update_visibility, yank_version,
         },
         test::{database::MockDatabase, middleware::MockTokenMiddlewareFactory},
     };
@@ -81,6 +84,143 @@ mod tests {
         Ok(())
     }
 
+    #[actix_web::test]
+    async fn send_package_with_mismatching_size() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state)
+                .route("/package", post().to(add_package::<MockDatabase>)),
+        )
+        .await;
+
+        let archive = TempArchive::builder().build()?;
+        let mut test_package: Package = (&archive).try_into()?;
+
+        test_package.metadata.package_size += 1;
+        let stream: PackageStream = test_package.to_stream().await?;
+        let request = test::TestRequest::post().uri("/package").to_request();
+        let (request, _) = request.replace_payload(Payload::from(stream));
+        set_mock_user_token(&request);
+
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = to_bytes(response.into_body()).await.unwrap();
+        assert!(body.as_str().starts_with("Package size mismatch"));
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn send_package_with_metadata_not_matching_contents() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state)
+                .route("/package", post().to(add_package::<MockDatabase>)),
+        )
+        .await;
+
+        let archive = TempArchive::builder().build()?;
+        let mut test_package: Package = (&archive).try_into()?;
+
+        let package_name = test_package.metadata.name.clone();
+        test_package.metadata.version = "9.9.9".to_string();
+        test_package.metadata.readme_path = "README.md".to_string();
+        let stream: PackageStream = test_package.to_stream().await?;
+        let request = test::TestRequest::post().uri("/package").to_request();
+        let (request, _) = request.replace_payload(Payload::from(stream));
+        set_mock_user_token(&request);
+
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let body = body.as_str();
+        assert!(body.starts_with("Invalid package contents"));
+        assert!(body.contains("version is \"9.9.9\" in metadata"));
+        assert!(body.contains("readme path"));
+        assert!(!PathBuf::from(package_folder.path())
+            .join(package_name)
+            .exists());
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn send_package_over_size_limit() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let mut limited_app_state = app_state.get_ref().clone();
+        limited_app_state.package_size_limits.default = Some(1);
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(limited_app_state))
+                .route("/package", post().to(add_package::<MockDatabase>)),
+        )
+        .await;
+
+        let archive = TempArchive::builder().build()?;
+        let test_package: Package = (&archive).try_into()?;
+        let package_name = test_package.metadata.name.clone();
+
+        let stream: PackageStream = test_package.to_stream().await?;
+        let request = test::TestRequest::post().uri("/package").to_request();
+        let (request, _) = request.replace_payload(Payload::from(stream));
+        set_mock_user_token(&request);
+
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body = to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(
+            body.as_str(),
+            "Package exceeds the maximum allowed size of 1 bytes"
+        );
+        assert!(!PathBuf::from(package_folder.path())
+            .join(package_name)
+            .exists());
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn send_package_without_required_role() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let mut restricted_app_state = app_state.get_ref().clone();
+        restricted_app_state.role_requirements.publish_package = Some("publisher".to_string());
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(restricted_app_state))
+                .route("/package", post().to(add_package::<MockDatabase>)),
+        )
+        .await;
+
+        let archive = TempArchive::builder().build()?;
+        let test_package: Package = (&archive).try_into()?;
+        let package_name = test_package.metadata.name.clone();
+
+        let stream: PackageStream = test_package.to_stream().await?;
+        let request = test::TestRequest::post().uri("/package").to_request();
+        let (request, _) = request.replace_payload(Payload::from(stream));
+        set_mock_user_token(&request);
+
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(body.as_str(), "Missing required role: publisher");
+        assert!(!PathBuf::from(package_folder.path())
+            .join(package_name)
+            .exists());
+
+        package_folder.close()?;
+        Ok(())
+    }
+
     #[actix_web::test]
     async fn submit_package_with_same_version_twice() -> Result<()> {
         let (package_folder, app_state) = setup_package_server()?;
@@ -243,6 +383,72 @@ mod tests {
         Ok(())
     }
 
+    #[actix_web::test]
+    async fn deprecate_package_with_replacement() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let package_name = upload_test_package(&app_state).await?;
+        let app = test::init_service(
+            App::new().app_data(app_state).service(
+                scope("/package")
+                    .route("", get().to(get_all_packages::<MockDatabase>))
+                    .service(
+                        scope("/{package_name}/deprecation")
+                            .route("", get().to(get_deprecation::<MockDatabase>))
+                            .route("", put().to(deprecate_package::<MockDatabase>))
+                            .route("", delete().to(undeprecate_package::<MockDatabase>))
+                            .wrap(MockTokenMiddlewareFactory),
+                    ),
+            ),
+        )
+        .await;
+        let uri = format!("/package/{package_name}/deprecation");
+
+        for (message, replacement) in [
+            (" ", None),
+            ("Use the other package", Some(package_name.clone())),
+            ("Use the other package", Some("missing-package".to_string())),
+        ] {
+            let request = test::TestRequest::put()
+                .uri(&uri)
+                .set_json(DeprecationRest {
+                    message: message.to_string(),
+                    replacement,
+                })
+                .to_request();
+            set_mock_user_token(&request);
+            let response = test::call_service(&app, request).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+
+        let deprecation = DeprecationRest {
+            message: "No longer maintained".to_string(),
+            replacement: None,
+        };
+        let request = test::TestRequest::put()
+            .uri(&uri)
+            .set_json(&deprecation)
+            .to_request();
+        set_mock_user_token(&request);
+        let stored: Option<DeprecationRest> = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(stored, Some(deprecation.clone()));
+
+        let request = test::TestRequest::get().uri("/package").to_request();
+        let packages: PackagesWithVersionsAndPagesRest =
+            test::call_and_read_body_json(&app, request).await;
+        assert_eq!(packages.packages[0].deprecation, Some(deprecation));
+
+        let request = test::TestRequest::delete().uri(&uri).to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+        let request = test::TestRequest::get().uri(&uri).to_request();
+        let stored: Option<DeprecationRest> = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(stored, None);
+
+        package_folder.close()?;
+        Ok(())
+    }
+
     #[actix_web::test]
     async fn test_get_all_categories() -> Result<()> {
         let (_package_folder, app_state) = setup_package_server()?;
@@ -268,4 +474,276 @@ mod tests {
 
         Ok(())
     }
+
+    #[actix_web::test]
+    async fn download_private_package_requires_token() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let archive = TempArchive::builder().build()?;
+        let mut test_package: Package = (&archive).try_into()?;
+        test_package.metadata.visibility = Visibility::OwnersOnly;
+
+        let package_name = test_package.metadata.name.clone();
+        let package_version = test_package.metadata.version.clone();
+
+        let app = test::init_service(
+            App::new().app_data(app_state).service(
+                scope("/package")
+                    .service(
+                        scope("/{package_name}").service(
+                            scope("/{version}")
+                                .route("/download", get().to(download_package::<MockDatabase>)),
+                        ),
+                    )
+                    .route("", post().to(add_package::<MockDatabase>)),
+            ),
+        )
+        .await;
+        let stream: PackageStream = test_package.to_stream().await?;
+        let request = test::TestRequest::post().uri("/package").to_request();
+        let (request, _) = request.replace_payload(Payload::from(stream));
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        let download_uri = format!("/package/{package_name}/{package_version}/download");
+        let request = test::TestRequest::get().uri(&download_uri).to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let request = test::TestRequest::get().uri(&download_uri).to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn private_package_is_made_public_by_its_owner() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let archive = TempArchive::builder().build()?;
+        let mut test_package: Package = (&archive).try_into()?;
+        test_package.metadata.visibility = Visibility::OwnersOnly;
+        let package_name = test_package.metadata.name.clone();
+        let package_version = test_package.metadata.version.clone();
+
+        let app = test::init_service(
+            App::new().app_data(app_state).service(
+                scope("/package")
+                    .route("", post().to(add_package::<MockDatabase>))
+                    .route(
+                        "/{package_name}/visibility/{visibility}",
+                        put().to(update_visibility::<MockDatabase>),
+                    )
+                    .route(
+                        "/{package_name}/{version}/download",
+                        get().to(download_package::<MockDatabase>),
+                    ),
+            ),
+        )
+        .await;
+        let stream: PackageStream = test_package.to_stream().await?;
+        let request = test::TestRequest::post().uri("/package").to_request();
+        let (request, _) = request.replace_payload(Payload::from(stream));
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        let download_uri = format!("/package/{package_name}/{package_version}/download");
+        let request = test::TestRequest::get().uri(&download_uri).to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let request = test::TestRequest::put()
+            .uri(&format!("/package/{package_name}/visibility/everyone"))
+            .to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let request = test::TestRequest::put()
+            .uri(&format!("/package/{package_name}/visibility/public"))
+            .to_request();
+        set_mock_user_token(&request);
+        let visibility: Visibility = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(visibility, Visibility::Public);
+
+        let request = test::TestRequest::get().uri(&download_uri).to_request();
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn private_package_hidden_from_listing() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let archive = TempArchive::builder().build()?;
+        let mut test_package: Package = (&archive).try_into()?;
+        test_package.metadata.visibility = Visibility::OwnersOnly;
+        let package_name = test_package.metadata.name.clone();
+
+        let app = test::init_service(
+            App::new().app_data(app_state).service(
+                scope("/package")
+                    .route("", get().to(get_all_packages::<MockDatabase>))
+                    .route("", post().to(add_package::<MockDatabase>)),
+            ),
+        )
+        .await;
+        let stream: PackageStream = test_package.to_stream().await?;
+        let request = test::TestRequest::post().uri("/package").to_request();
+        let (request, _) = request.replace_payload(Payload::from(stream));
+        set_mock_user_token(&request);
+        test::call_service(&app, request).await;
+
+        let request = test::TestRequest::get().uri("/package").to_request();
+        let body = test::call_and_read_body(&app, request).await;
+        assert!(!body.as_str().contains(&package_name));
+
+        let request = test::TestRequest::get().uri("/package").to_request();
+        set_mock_user_token(&request);
+        let body = test::call_and_read_body(&app, request).await;
+        assert!(body.as_str().contains(&package_name));
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn downloads_are_counted_per_version() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let package_name = upload_test_package(&app_state).await?;
+        let app = test::init_service(
+            App::new().app_data(app_state).service(
+                scope("/package")
+                    .route("", get().to(get_all_packages::<MockDatabase>))
+                    .service(
+                        scope("/{package_name}")
+                            .route("/stats", get().to(get_download_stats::<MockDatabase>))
+                            .route(
+                                "/{version}/download",
+                                get().to(download_package::<MockDatabase>),
+                            ),
+                    ),
+            ),
+        )
+        .await;
+
+        for _ in 0..2 {
+            let request = test::TestRequest::get()
+                .uri(&format!("/package/{package_name}/1.0.4/download"))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert!(response.status().is_success());
+        }
+
+        let request = test::TestRequest::get()
+            .uri(&format!("/package/{package_name}/stats?days=7"))
+            .to_request();
+        let download_stats: DownloadStatsRest = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(download_stats.total_downloads, 2);
+        assert_eq!(download_stats.versions[0].version, "1.0.4");
+        assert_eq!(download_stats.versions[0].downloads, 2);
+        assert_eq!(download_stats.daily.len(), 1);
+        assert_eq!(download_stats.daily[0].downloads, 2);
+
+        let request = test::TestRequest::get()
+            .uri("/package?sort=downloads")
+            .to_request();
+        let packages: PackagesWithVersionsAndPagesRest =
+            test::call_and_read_body_json(&app, request).await;
+        assert_eq!(packages.packages[0].name, package_name);
+        assert_eq!(packages.packages[0].downloads, 2);
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn download_stats_reject_days_out_of_range() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let package_name = upload_test_package(&app_state).await?;
+        let app = test::init_service(App::new().app_data(app_state).route(
+            "/package/{package_name}/stats",
+            get().to(get_download_stats::<MockDatabase>),
+        ))
+        .await;
+
+        for days in ["0", "3651", "200000000"] {
+            let request = test::TestRequest::get()
+                .uri(&format!("/package/{package_name}/stats?days={days}"))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+        let request = test::TestRequest::get()
+            .uri(&format!("/package/{package_name}/stats?days=3650"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn packages_are_searched_by_contents_and_ranked() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let app = test::init_service(
+            App::new().app_data(app_state).service(
+                scope("/package")
+                    .route("", get().to(get_all_packages::<MockDatabase>))
+                    .route("", post().to(add_package::<MockDatabase>)),
+            ),
+        )
+        .await;
+
+        for package_name in ["alpha-lab", "beta-lab"] {
+            let archive = TempArchive::builder()
+                .set_package_name(package_name)
+                .build()?;
+            let test_package: Package = (&archive).try_into()?;
+            let stream: PackageStream = test_package.to_stream().await?;
+            let request = test::TestRequest::post().uri("/package").to_request();
+            let (request, _) = request.replace_payload(Payload::from(stream));
+            set_mock_user_token(&request);
+            let response = test::call_service(&app, request).await;
+            assert!(response.status().is_success());
+        }
+
+        let request = test::TestRequest::get()
+            .uri("/package?search_term=beta%20manhours")
+            .to_request();
+        let packages: PackagesWithVersionsAndPagesRest =
+            test::call_and_read_body_json(&app, request).await;
+        let package_names: Vec<&str> = packages
+            .packages
+            .iter()
+            .map(|package| package.name.as_str())
+            .collect();
+        assert_eq!(package_names, vec!["beta-lab", "alpha-lab"]);
+        assert_eq!(packages.facets.package_types.get("VM"), Some(&2));
+        assert_eq!(packages.facets.categories.get("category1"), Some(&2));
+
+        let request = test::TestRequest::get()
+            .uri("/package?search_term=beta%20manhours&sort=name")
+            .to_request();
+        let packages: PackagesWithVersionsAndPagesRest =
+            test::call_and_read_body_json(&app, request).await;
+        assert_eq!(packages.packages[0].name, "alpha-lab");
+
+        let request = test::TestRequest::get()
+            .uri("/package?search_term=nonexistentword")
+            .to_request();
+        let packages: PackagesWithVersionsAndPagesRest =
+            test::call_and_read_body_json(&app, request).await;
+        assert!(packages.packages.is_empty());
+        assert!(packages.facets.package_types.is_empty());
+
+        package_folder.close()?;
+        Ok(())
+    }
 }
@@ -0,0 +1,162 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::setup_sqlite_package_server;
+    use actix_http::{
+        header::{AUTHORIZATION, RETRY_AFTER},
+        HttpMessage, StatusCode,
+    };
+    use actix_web::{
+        test,
+        web::{get, post, scope},
+        App,
+    };
+    use anyhow::Result;
+    use deputy_package_server::{
+        configuration::{RateLimitBudget, RateLimitConfiguration},
+        middleware::{authentication::jwt::User, rate_limit::RateLimitMiddlewareFactory},
+        models::apitoken::{FullApiTokenRest, NewApiTokenRest},
+        routes::{
+            apitoken::create_api_token,
+            package::{download_package, get_all_packages},
+        },
+        services::{database::Database, rate_limit::RateLimiter},
+    };
+    use std::{net::SocketAddr, rc::Rc, sync::Arc};
+
+    fn rate_limiter() -> Arc<RateLimiter> {
+        let budget = RateLimitBudget {
+            requests_per_minute: 1,
+            burst: 2,
+        };
+        Arc::new(RateLimiter::new(
+            RateLimitConfiguration {
+                read: budget,
+                download: budget,
+                publish: budget,
+                ..Default::default()
+            },
+            None,
+        ))
+    }
+
+    #[actix_web::test]
+    async fn clients_are_limited_per_address_and_budget() -> Result<()> {
+        let (package_folder, app_state) = setup_sqlite_package_server(&Default::default())?;
+        let app = test::init_service(
+            App::new().app_data(app_state).service(
+                scope("/api/v1/package")
+                    .route("", get().to(get_all_packages::<Database>))
+                    .route(
+                        "/{package_name}/{version}/download",
+                        get().to(download_package::<Database>),
+                    )
+                    .wrap(RateLimitMiddlewareFactory(rate_limiter())),
+            ),
+        )
+        .await;
+        let client_address: SocketAddr = "10.0.0.1:40000".parse()?;
+        let other_client_address: SocketAddr = "10.0.0.2:40000".parse()?;
+
+        for _ in 0..2 {
+            let request = test::TestRequest::get()
+                .uri("/api/v1/package")
+                .peer_addr(client_address)
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert!(response.status().is_success());
+        }
+        let request = test::TestRequest::get()
+            .uri("/api/v1/package")
+            .peer_addr(client_address)
+            .to_request();
+        let response = test::try_call_service(&app, request).await;
+        let error = response.expect_err("third read should be rate limited");
+        let error_response = error.error_response();
+        assert_eq!(error_response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            error_response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok()),
+            Some("60")
+        );
+
+        let request = test::TestRequest::get()
+            .uri("/api/v1/package/some-package/1.0.0/download")
+            .peer_addr(client_address)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let request = test::TestRequest::get()
+            .uri("/api/v1/package")
+            .peer_addr(other_client_address)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn api_tokens_have_their_own_budget() -> Result<()> {
+        let (package_folder, app_state) = setup_sqlite_package_server(&Default::default())?;
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state)
+                .route("/token", post().to(create_api_token::<Database>))
+                .service(
+                    scope("/api/v1/package")
+                        .route("", get().to(get_all_packages::<Database>))
+                        .wrap(RateLimitMiddlewareFactory(rate_limiter())),
+                ),
+        )
+        .await;
+        let request = test::TestRequest::post()
+            .uri("/token")
+            .set_json(NewApiTokenRest {
+                name: "ci".to_string(),
+                email: "test-email".to_string(),
+                restrictions: Default::default(),
+            })
+            .to_request();
+        request.extensions_mut().insert::<Rc<User>>(Rc::new(User {
+            id: "test-id".to_string(),
+            name: None,
+            email: Some("test-email".to_string()),
+            roles: Vec::new(),
+        }));
+        let api_token: FullApiTokenRest = test::call_and_read_body_json(&app, request).await;
+        let token = api_token.token;
+        let client_address: SocketAddr = "10.0.0.1:40000".parse()?;
+
+        for bearer_token in [Some(token.as_str()), None, Some(&token), Some(&token)] {
+            let mut request = test::TestRequest::get()
+                .uri("/api/v1/package")
+                .peer_addr(client_address);
+            if let Some(bearer_token) = bearer_token {
+                request = request.insert_header((AUTHORIZATION, format!("Bearer {bearer_token}")));
+            }
+            let response = test::call_service(&app, request.to_request()).await;
+            assert!(response.status().is_success());
+        }
+
+        let request = test::TestRequest::get()
+            .uri("/api/v1/package")
+            .peer_addr(client_address)
+            .insert_header((AUTHORIZATION, "Bearer unknown-token"))
+            .to_request();
+        let response = test::try_call_service(&app, request).await;
+        let error = response.expect_err("unknown tokens are charged to the address budget");
+        assert_eq!(
+            error.as_response_error().status_code(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+
+        package_folder.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,265 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{set_mock_user_token, setup_sqlite_package_server};
+    use actix_http::{Payload, StatusCode};
+    use actix_web::{
+        test,
+        web::{get, post, Data},
+        App,
+    };
+    use anyhow::Result;
+    use deputy_library::{
+        package::{Package, PackageStream},
+        test::TempArchive,
+    };
+    use deputy_package_server::{
+        configuration::{OrphanAction, ScrubConfiguration},
+        routes::package::{add_package, download_package},
+        services::{
+            database::Database,
+            scrub::StorageScrubber,
+            storage::{package_key, Storage, StorageFuture, StoredFile, QUARANTINE_PREFIX},
+        },
+        AppState,
+    };
+    use std::{
+        fs::{create_dir_all, read, remove_file, write},
+        path::{Path, PathBuf},
+        sync::Arc,
+        time::SystemTime,
+    };
+
+    /// Stores an archive without a version whenever the storage is listed, like a package that is
+    /// being published while the scrub runs.
+    #[derive(Debug)]
+    struct PublishingStorage {
+        storage: Arc<dyn Storage>,
+        key: String,
+        archive_path: PathBuf,
+    }
+
+    impl Storage for PublishingStorage {
+        fn put<'a>(&'a self, key: &'a str, file_path: &'a Path) -> StorageFuture<'a, ()> {
+            self.storage.put(key, file_path)
+        }
+
+        fn fetch<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Option<StoredFile>> {
+            self.storage.fetch(key)
+        }
+
+        fn delete<'a>(&'a self, key: &'a str) -> StorageFuture<'a, ()> {
+            self.storage.delete(key)
+        }
+
+        fn delete_package<'a>(&'a self, package_name: &'a str) -> StorageFuture<'a, ()> {
+            self.storage.delete_package(package_name)
+        }
+
+        fn keys(&self) -> StorageFuture<'_, Vec<String>> {
+            Box::pin(async move {
+                self.storage.put(&self.key, &self.archive_path).await?;
+                self.storage.keys().await
+            })
+        }
+
+        fn modified_at<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Option<SystemTime>> {
+            self.storage.modified_at(key)
+        }
+
+        fn used_bytes(&self) -> StorageFuture<'_, Option<u64>> {
+            self.storage.used_bytes()
+        }
+
+        fn check_health(&self) -> StorageFuture<'_, Option<u64>> {
+            self.storage.check_health()
+        }
+    }
+
+    async fn publish_packages(
+        app_state: &Data<AppState<Database>>,
+        package_names: &[&str],
+    ) -> Result<Vec<String>> {
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route("/package", post().to(add_package::<Database>)),
+        )
+        .await;
+        let mut keys = Vec::new();
+        for package_name in package_names {
+            let archive = TempArchive::builder()
+                .set_package_name(package_name)
+                .build()?;
+            let test_package: Package = (&archive).try_into()?;
+            keys.push(package_key(package_name, &test_package.metadata.version));
+            let stream: PackageStream = test_package.to_stream().await?;
+            let request = test::TestRequest::post().uri("/package").to_request();
+            let (request, _) = request.replace_payload(Payload::from(stream));
+            set_mock_user_token(&request);
+            let response = test::call_service(&app, request).await;
+            assert!(response.status().is_success());
+        }
+        Ok(keys)
+    }
+
+    async fn download_status(app_state: &Data<AppState<Database>>, key: &str) -> StatusCode {
+        let app = test::init_service(App::new().app_data(app_state.clone()).route(
+            "/package/{package_name}/{version}/download",
+            get().to(download_package::<Database>),
+        ))
+        .await;
+        let request = test::TestRequest::get()
+            .uri(&format!("/package/{key}/download"))
+            .to_request();
+        test::call_service(&app, request).await.status()
+    }
+
+    #[actix_web::test]
+    async fn broken_versions_are_reported_and_marked_unavailable() -> Result<()> {
+        let (package_folder, app_state) = setup_sqlite_package_server(&Default::default())?;
+        let keys =
+            publish_packages(&app_state, &["intact-lab", "missing-lab", "corrupted-lab"]).await?;
+        let (intact_key, missing_key, corrupted_key) = (&keys[0], &keys[1], &keys[2]);
+        let corrupted_path = package_folder.path().join(corrupted_key);
+        let original_archive = read(&corrupted_path)?;
+        remove_file(package_folder.path().join(missing_key))?;
+        write(&corrupted_path, b"bit rot")?;
+        let scrub = |mark_unavailable| {
+            StorageScrubber::scrub(
+                app_state.database_address.clone(),
+                app_state.storage.clone(),
+                ScrubConfiguration {
+                    mark_unavailable,
+                    ..Default::default()
+                },
+            )
+        };
+
+        let report = scrub(false).await?;
+        assert_eq!(report.verified, 1);
+        assert_eq!(report.missing, vec![missing_key.clone()]);
+        assert_eq!(report.corrupted, vec![corrupted_key.clone()]);
+        assert!(report.orphaned.is_empty());
+        assert!(report.marked_unavailable.is_empty());
+        assert!(!report.is_clean());
+        assert_eq!(
+            download_status(&app_state, corrupted_key).await,
+            StatusCode::OK
+        );
+
+        let report = scrub(true).await?;
+        assert_eq!(
+            report.marked_unavailable,
+            vec![corrupted_key.clone(), missing_key.clone()]
+        );
+        assert_eq!(
+            download_status(&app_state, corrupted_key).await,
+            StatusCode::GONE
+        );
+        assert_eq!(
+            download_status(&app_state, intact_key).await,
+            StatusCode::OK
+        );
+        assert!(scrub(true).await?.marked_unavailable.is_empty());
+
+        write(&corrupted_path, original_archive)?;
+        let report = scrub(true).await?;
+        assert_eq!(report.verified, 2);
+        assert_eq!(report.marked_available, vec![corrupted_key.clone()]);
+        assert_eq!(
+            download_status(&app_state, corrupted_key).await,
+            StatusCode::OK
+        );
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn orphaned_archives_are_quarantined_or_deleted() -> Result<()> {
+        let (package_folder, app_state) = setup_sqlite_package_server(&Default::default())?;
+        let keys = publish_packages(&app_state, &["published-lab"]).await?;
+        let orphan_key = "orphan-lab/1.0.0";
+        let orphan_path = package_folder.path().join(orphan_key);
+        create_dir_all(package_folder.path().join("orphan-lab"))?;
+        write(&orphan_path, b"left behind")?;
+        let scrub = |orphan_action| {
+            StorageScrubber::scrub(
+                app_state.database_address.clone(),
+                app_state.storage.clone(),
+                ScrubConfiguration {
+                    orphan_action,
+                    orphan_grace_seconds: 0,
+                    ..Default::default()
+                },
+            )
+        };
+
+        let report = scrub(OrphanAction::Report).await?;
+        assert_eq!(report.verified, 1);
+        assert_eq!(report.orphaned, vec![orphan_key.to_string()]);
+        assert!(orphan_path.is_file());
+
+        let report = scrub(OrphanAction::Quarantine).await?;
+        assert_eq!(report.orphaned, vec![orphan_key.to_string()]);
+        assert!(!orphan_path.exists());
+        let quarantined_path = package_folder
+            .path()
+            .join(QUARANTINE_PREFIX)
+            .join(orphan_key);
+        assert_eq!(read(quarantined_path)?, b"left behind");
+        assert!(scrub(OrphanAction::Report).await?.is_clean());
+
+        write(&orphan_path, b"left behind again")?;
+        let report = scrub(OrphanAction::Delete).await?;
+        assert_eq!(report.orphaned, vec![orphan_key.to_string()]);
+        assert!(!orphan_path.exists());
+        assert!(package_folder.path().join(&keys[0]).is_file());
+        assert!(scrub(OrphanAction::Report).await?.is_clean());
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn archives_of_packages_being_published_are_not_orphaned() -> Result<()> {
+        let (package_folder, app_state) = setup_sqlite_package_server(&Default::default())?;
+        publish_packages(&app_state, &["published-lab"]).await?;
+        let archive = TempArchive::builder()
+            .set_package_name("publishing-lab")
+            .build()?;
+        let publishing_package: Package = (&archive).try_into()?;
+        let publishing_key = package_key("publishing-lab", &publishing_package.metadata.version);
+        let publishing_path = package_folder.path().join(&publishing_key);
+        let storage: Arc<dyn Storage> = Arc::new(PublishingStorage {
+            storage: app_state.storage.clone(),
+            key: publishing_key.clone(),
+            archive_path: publishing_package.archive_path()?.to_path_buf(),
+        });
+        let scrub = |orphan_grace_seconds| {
+            StorageScrubber::scrub(
+                app_state.database_address.clone(),
+                storage.clone(),
+                ScrubConfiguration {
+                    orphan_action: OrphanAction::Delete,
+                    orphan_grace_seconds,
+                    ..Default::default()
+                },
+            )
+        };
+
+        let report = scrub(ScrubConfiguration::default().orphan_grace_seconds).await?;
+        assert_eq!(report.verified, 1);
+        assert!(report.is_clean());
+        assert!(publishing_path.is_file());
+
+        let report = scrub(0).await?;
+        assert_eq!(report.orphaned, vec![publishing_key.clone()]);
+        assert!(!publishing_path.exists());
+
+        package_folder.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        rt,
+        web::{Bytes, Data, Query},
+        App, HttpRequest, HttpResponse, HttpServer,
+    };
+    use anyhow::Result;
+    use chrono::Utc;
+    use deputy_library::{package::Package, test::TempArchive};
+    use deputy_package_server::{
+        configuration::S3Configuration,
+        services::storage::{package_key, FilesystemStorage, S3Storage, Storage},
+    };
+    use std::{collections::HashMap, fs::read, net::TcpListener, sync::Mutex};
+    use tempfile::TempDir;
+
+    const BUCKET: &str = "packages";
+
+    /// In-memory stand-in for an S3-compatible object store such as MinIO.
+    #[derive(Default)]
+    struct StandInBucket(Mutex<HashMap<String, Bytes>>);
+
+    async fn handle_request(
+        request: HttpRequest,
+        body: Bytes,
+        bucket: Data<StandInBucket>,
+    ) -> HttpResponse {
+        let is_signed = request
+            .headers()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("AWS4-HMAC-SHA256 Credential=minio/"));
+        if !is_signed || !request.headers().contains_key("x-amz-date") {
+            return HttpResponse::Forbidden().finish();
+        }
+
+        let mut objects = bucket.0.lock().unwrap();
+        let key = request
+            .path()
+            .trim_start_matches(&format!("/{BUCKET}"))
+            .strip_prefix('/');
+        match (request.method().as_str(), key) {
+            ("PUT", Some(key)) => {
+                objects.insert(key.to_string(), body);
+                HttpResponse::Ok().finish()
+            }
+            ("HEAD", Some(key)) => match objects.contains_key(key) {
+                true => HttpResponse::Ok()
+                    .insert_header((
+                        "last-modified",
+                        Utc::now().format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+                    ))
+                    .finish(),
+                false => HttpResponse::NotFound().finish(),
+            },
+            ("GET", Some(key)) => match objects.get(key) {
+                Some(content) => HttpResponse::Ok().body(content.clone()),
+                None => HttpResponse::NotFound().finish(),
+            },
+            ("DELETE", Some(key)) => {
+                objects.remove(key);
+                HttpResponse::NoContent().finish()
+            }
+            ("GET", None) => {
+                let query =
+                    Query::<HashMap<String, String>>::from_query(request.query_string()).unwrap();
+                let prefix = query.get("prefix").cloned().unwrap_or_default();
+                let contents: String = objects
+                    .keys()
+                    .filter(|key| key.starts_with(&prefix))
+                    .map(|key| format!("<Contents><Key>{key}</Key></Contents>"))
+                    .collect();
+                HttpResponse::Ok().body(format!(
+                    "<ListBucketResult><IsTruncated>false</IsTruncated>{contents}</ListBucketResult>"
+                ))
+            }
+            _ => HttpResponse::MethodNotAllowed().finish(),
+        }
+    }
+
+    fn start_stand_in() -> Result<S3Storage> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let bucket = Data::new(StandInBucket::default());
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(bucket.clone())
+                .default_service(actix_web::web::to(handle_request))
+        })
+        .workers(1)
+        .listen(listener)?
+        .run();
+        rt::spawn(server);
+
+        S3Storage::try_new(S3Configuration {
+            endpoint: format!("http://{address}"),
+            bucket: BUCKET.to_string(),
+            region: "us-east-1".to_string(),
+            access_key_id: "minio".to_string(),
+            secret_access_key: "minio-secret".to_string(),
+            request_timeout_seconds: 10,
+        })
+    }
+
+    #[actix_web::test]
+    async fn package_is_stored_in_s3_compatible_bucket() -> Result<()> {
+        let storage = start_stand_in()?;
+        let archive = TempArchive::builder().build()?;
+        let package: Package = (&archive).try_into()?;
+        let key = package_key(&package.metadata.name, &package.metadata.version);
+
+        storage.put(&key, package.archive_path()?).await?;
+        let stored_file = storage.fetch(&key).await?.unwrap();
+
+        assert_eq!(read(stored_file.path())?, read(package.archive_path()?)?);
+        assert!(storage.fetch("missing-package/1.0.0").await?.is_none());
+        assert!(storage.modified_at(&key).await?.is_some());
+        assert!(storage
+            .modified_at("missing-package/1.0.0")
+            .await?
+            .is_none());
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn package_versions_are_deleted_from_s3_compatible_bucket() -> Result<()> {
+        let storage = start_stand_in()?;
+        let archive = TempArchive::builder().build()?;
+        let package: Package = (&archive).try_into()?;
+        let package_name = &package.metadata.name;
+        for version in ["1.0.0", "1.0.1", "1.0.2"] {
+            storage
+                .put(&package_key(package_name, version), package.archive_path()?)
+                .await?;
+        }
+        storage
+            .put(
+                &package_key("other-package", "1.0.0"),
+                package.archive_path()?,
+            )
+            .await?;
+
+        storage.delete(&package_key(package_name, "1.0.0")).await?;
+        assert!(storage
+            .fetch(&package_key(package_name, "1.0.0"))
+            .await?
+            .is_none());
+        assert!(storage
+            .fetch(&package_key(package_name, "1.0.1"))
+            .await?
+            .is_some());
+
+        storage.delete_package(package_name).await?;
+        assert!(storage
+            .fetch(&package_key(package_name, "1.0.2"))
+            .await?
+            .is_none());
+        assert!(storage
+            .fetch(&package_key("other-package", "1.0.0"))
+            .await?
+            .is_some());
+        assert_eq!(
+            storage.keys().await?,
+            vec![package_key("other-package", "1.0.0")]
+        );
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn filesystem_used_bytes_are_reused_between_scrapes() -> Result<()> {
+        let package_folder = TempDir::new()?;
+        let storage = FilesystemStorage::new(package_folder.path().to_str().unwrap());
+        let archive = TempArchive::builder().build()?;
+        let package: Package = (&archive).try_into()?;
+        let archive_size = read(package.archive_path()?)?.len() as u64;
+
+        storage
+            .put(
+                &package_key("some-package", "1.0.0"),
+                package.archive_path()?,
+            )
+            .await?;
+        assert_eq!(storage.used_bytes().await?, Some(archive_size));
+        assert_eq!(
+            storage.keys().await?,
+            vec!["some-package/1.0.0".to_string()]
+        );
+
+        storage
+            .put(
+                &package_key("some-package", "1.0.1"),
+                package.archive_path()?,
+            )
+            .await?;
+        assert_eq!(storage.used_bytes().await?, Some(archive_size));
+        assert_eq!(storage.keys().await?.len(), 2);
+
+        package_folder.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{
+        set_mock_user_token, set_mock_user_token_with_email, setup_package_server, BodyTest,
+    };
+    use actix_http::{Payload, StatusCode};
+    use actix_web::{
+        test,
+        web::{delete, get, post, scope},
+        App,
+    };
+    use anyhow::Result;
+    use deputy_library::{
+        package::{Package, PackageStream, Visibility},
+        test::TempArchive,
+    };
+    use deputy_package_server::{
+        routes::{
+            owner::{add_team_owner, get_team_owners},
+            package::{add_package, get_all_packages},
+            team::{add_team_member, create_team, get_team, remove_team_member},
+        },
+        test::database::MockDatabase,
+    };
+
+    #[actix_web::test]
+    async fn create_team_and_add_member() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let app = test::init_service(
+            App::new().app_data(app_state).service(
+                scope("/team")
+                    .route("", post().to(create_team::<MockDatabase>))
+                    .service(
+                        scope("/{team_name}")
+                            .route("", get().to(get_team::<MockDatabase>))
+                            .route("/member", post().to(add_team_member::<MockDatabase>)),
+                    ),
+            ),
+        )
+        .await;
+
+        let request = test::TestRequest::post()
+            .uri("/team?name=platform")
+            .to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        let request = test::TestRequest::post()
+            .uri("/team/platform/member?email=teammate")
+            .to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        let request = test::TestRequest::get().uri("/team/platform").to_request();
+        let body = test::call_and_read_body(&app, request).await;
+        assert!(body.as_str().contains("\"email\":\"test-email\""));
+        assert!(body.as_str().contains("\"email\":\"teammate\""));
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn only_team_admin_can_manage_members() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let app = test::init_service(
+            App::new().app_data(app_state).service(
+                scope("/team")
+                    .route("", post().to(create_team::<MockDatabase>))
+                    .service(
+                        scope("/{team_name}")
+                            .route("/member", post().to(add_team_member::<MockDatabase>))
+                            .route(
+                                "/member/{email}",
+                                delete().to(remove_team_member::<MockDatabase>),
+                            ),
+                    ),
+            ),
+        )
+        .await;
+
+        let request = test::TestRequest::post()
+            .uri("/team?name=platform")
+            .to_request();
+        set_mock_user_token(&request);
+        test::call_service(&app, request).await;
+
+        let request = test::TestRequest::post()
+            .uri("/team/platform/member?email=intruder")
+            .to_request();
+        set_mock_user_token_with_email(&request, "outsider");
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let request = test::TestRequest::delete()
+            .uri("/team/platform/member/test-email")
+            .to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn team_members_can_see_team_owned_packages() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let archive = TempArchive::builder().build()?;
+        let mut test_package: Package = (&archive).try_into()?;
+        test_package.metadata.visibility = Visibility::OwnersOnly;
+        let package_name = test_package.metadata.name.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state)
+                .service(
+                    scope("/team")
+                        .route("", post().to(create_team::<MockDatabase>))
+                        .route(
+                            "/{team_name}/member",
+                            post().to(add_team_member::<MockDatabase>),
+                        ),
+                )
+                .service(
+                    scope("/package")
+                        .route("", get().to(get_all_packages::<MockDatabase>))
+                        .route("", post().to(add_package::<MockDatabase>))
+                        .route(
+                            "/{package_name}/owner/team",
+                            get().to(get_team_owners::<MockDatabase>),
+                        )
+                        .route(
+                            "/{package_name}/owner/team/{team_name}",
+                            post().to(add_team_owner::<MockDatabase>),
+                        ),
+                ),
+        )
+        .await;
+
+        let stream: PackageStream = test_package.to_stream().await?;
+        let request = test::TestRequest::post().uri("/package").to_request();
+        let (request, _) = request.replace_payload(Payload::from(stream));
+        set_mock_user_token(&request);
+        test::call_service(&app, request).await;
+
+        let request = test::TestRequest::post()
+            .uri("/team?name=platform")
+            .to_request();
+        set_mock_user_token(&request);
+        test::call_service(&app, request).await;
+        let request = test::TestRequest::post()
+            .uri("/team/platform/member?email=teammate")
+            .to_request();
+        set_mock_user_token(&request);
+        test::call_service(&app, request).await;
+
+        let request = test::TestRequest::get().uri("/package").to_request();
+        set_mock_user_token_with_email(&request, "teammate");
+        let body = test::call_and_read_body(&app, request).await;
+        assert!(!body.as_str().contains(&package_name));
+
+        let request = test::TestRequest::post()
+            .uri(&format!("/package/{package_name}/owner/team/platform"))
+            .to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        let request = test::TestRequest::get()
+            .uri(&format!("/package/{package_name}/owner/team"))
+            .to_request();
+        set_mock_user_token_with_email(&request, "teammate");
+        let body = test::call_and_read_body(&app, request).await;
+        assert!(body.as_str().contains("\"name\":\"platform\""));
+
+        let request = test::TestRequest::get().uri("/package").to_request();
+        set_mock_user_token_with_email(&request, "teammate");
+        let body = test::call_and_read_body(&app, request).await;
+        assert!(body.as_str().contains(&package_name));
+
+        package_folder.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{
+        set_mock_user_token, setup_package_server, setup_sqlite_package_server, upload_test_package,
+    };
+    use actix_http::StatusCode;
+    use actix_web::{
+        test,
+        web::{get, post, put},
+        App,
+    };
+    use anyhow::Result;
+    use chrono::{Duration, Utc};
+    use deputy_library::rest::{
+        NewWebhookRest, WebhookDeliveryRest, WebhookEvent, WebhookPayloadRest, WebhookRest,
+    };
+    use deputy_package_server::{
+        models::{NewWebhook, NewWebhookDelivery, WebhookDelivery},
+        routes::{
+            package::yank_version,
+            webhook::{create_webhook, get_webhook_deliveries, get_webhooks},
+        },
+        services::database::DatabaseConnectionManager,
+        test::{database::MockDatabase, middleware::MockTokenMiddlewareFactory},
+    };
+    use diesel::RunQueryDsl;
+
+    #[actix_web::test]
+    async fn yank_is_queued_for_subscribed_webhooks() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let package_name = upload_test_package(&app_state).await?;
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state)
+                .route(
+                    "/package/{package_name}/webhook",
+                    post().to(create_webhook::<MockDatabase>),
+                )
+                .route(
+                    "/package/{package_name}/webhook",
+                    get().to(get_webhooks::<MockDatabase>),
+                )
+                .route(
+                    "/package/{package_name}/webhook/{webhook_id}/delivery",
+                    get().to(get_webhook_deliveries::<MockDatabase>),
+                )
+                .route("/admin/webhook", post().to(create_webhook::<MockDatabase>))
+                .route(
+                    "/package/{package_name}/{version}/yank/{set_yank}",
+                    put().to(yank_version::<MockDatabase>),
+                )
+                .wrap(MockTokenMiddlewareFactory),
+        )
+        .await;
+
+        let request = test::TestRequest::post()
+            .uri(&format!("/package/{package_name}/webhook"))
+            .set_json(NewWebhookRest {
+                url: "https://hooks.example.com/deputy".to_string(),
+                events: vec![WebhookEvent::Yank],
+                secret: None,
+            })
+            .to_request();
+        set_mock_user_token(&request);
+        let webhook: WebhookRest = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(webhook.package_name.as_deref(), Some(package_name.as_str()));
+        assert_eq!(webhook.created_by, "test-email");
+        assert!(webhook.secret.is_some());
+
+        let request = test::TestRequest::post()
+            .uri("/admin/webhook")
+            .set_json(NewWebhookRest {
+                url: "https://hooks.example.com/global".to_string(),
+                events: vec![WebhookEvent::Publish],
+                secret: Some("global-secret".to_string()),
+            })
+            .to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        let request = test::TestRequest::get()
+            .uri(&format!("/package/{package_name}/webhook"))
+            .to_request();
+        let webhooks: Vec<WebhookRest> = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(webhooks.len(), 1);
+        assert!(webhooks[0].secret.is_none());
+
+        let request = test::TestRequest::put()
+            .uri(&format!("/package/{package_name}/1.0.4/yank/true"))
+            .to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        let request = test::TestRequest::get()
+            .uri(&format!(
+                "/package/{package_name}/webhook/{}/delivery",
+                webhook.id
+            ))
+            .to_request();
+        let deliveries: Vec<WebhookDeliveryRest> =
+            test::call_and_read_body_json(&app, request).await;
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].event, WebhookEvent::Yank);
+        assert_eq!(deliveries[0].status, "pending");
+        assert_eq!(deliveries[0].attempts, 0);
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn webhook_with_invalid_url_is_rejected() -> Result<()> {
+        let (package_folder, app_state) = setup_package_server()?;
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state)
+                .route("/admin/webhook", post().to(create_webhook::<MockDatabase>))
+                .wrap(MockTokenMiddlewareFactory),
+        )
+        .await;
+
+        let request = test::TestRequest::post()
+            .uri("/admin/webhook")
+            .set_json(NewWebhookRest {
+                url: "ftp://hooks.example.com".to_string(),
+                ..Default::default()
+            })
+            .to_request();
+        set_mock_user_token(&request);
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        package_folder.close()?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn delivery_is_claimed_by_a_single_replica_with_sqlite() -> Result<()> {
+        let (package_folder, _) = setup_sqlite_package_server(&Default::default())?;
+        let database_url = format!(
+            "sqlite://{}",
+            package_folder.path().join("deputy.db").display()
+        );
+        let mut connection = DatabaseConnectionManager::new(&database_url).establish()?;
+        let webhook = NewWebhook::new(
+            None,
+            NewWebhookRest {
+                url: "https://hooks.example.com/deputy".to_string(),
+                ..Default::default()
+            },
+            "admin".to_string(),
+        );
+        webhook.create_insert().execute(&mut connection)?;
+        let payload =
+            WebhookPayloadRest::new(WebhookEvent::Publish, "some-package", "admin".into());
+        NewWebhookDelivery::new(webhook.id, &payload)?
+            .create_insert()
+            .execute(&mut connection)?;
+
+        let delivery: WebhookDelivery =
+            WebhookDelivery::by_webhook_id(webhook.id).first(&mut connection)?;
+        let claimed_until = Utc::now().naive_utc() + Duration::seconds(60);
+        assert_eq!(delivery.claim(claimed_until).execute(&mut connection)?, 1);
+        assert_eq!(delivery.claim(claimed_until).execute(&mut connection)?, 0);
+
+        let mut expired_delivery: WebhookDelivery =
+            WebhookDelivery::by_webhook_id(webhook.id).first(&mut connection)?;
+        assert!(expired_delivery.claimed_until.is_some());
+        expired_delivery.claimed_until = Some(Utc::now().naive_utc() - Duration::seconds(1));
+        expired_delivery.create_update().execute(&mut connection)?;
+        assert_eq!(delivery.claim(claimed_until).execute(&mut connection)?, 1);
+
+        package_folder.close()?;
+        Ok(())
+    }
+}
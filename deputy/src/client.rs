@@ -1,17 +1,29 @@
 use crate::{
-    commands::{InfoOptions, ListOptions},
-    constants::endpoints::PACKAGE_UPLOAD_PATH,
+    commands::{AuditLogOptions, InfoOptions, ListOptions, StatsOptions},
+    constants::{
+        endpoints::PACKAGE_UPLOAD_PATH,
+        rate_limiting::{MAX_RETRIES, MAX_RETRY_DELAY_SECONDS},
+    },
     helpers::create_file_from_stream,
 };
+use actix_rt::time::sleep;
 use anyhow::{anyhow, Error, Ok, Result};
-use awc::{http::header, Client as ActixWebClient};
+use awc::{
+    error::SendRequestError,
+    http::{header, StatusCode},
+    Client as ActixWebClient, ClientRequest, ClientResponse, FrozenClientRequest,
+};
 use deputy_library::{
-    package::PackageStream,
-    rest::{OwnerRest, PackageWithVersionsRest, PackagesWithVersionsAndPagesRest, VersionRest},
+    package::{Package, PackageFile, Visibility},
+    rest::{
+        AuditLogsWithPagesRest, CreatedTokenRest, DeprecationRest, DownloadStatsRest,
+        NewScopedTokenRest, NewWebhookRest, OwnerRest, PackageSort, PackageWithVersionsRest,
+        PackagesWithVersionsAndPagesRest, TeamRest, VersionRest, WebhookDeliveryRest, WebhookRest,
+    },
 };
-use log::error;
+use log::{error, warn};
 use qstring::QString;
-use std::str::from_utf8;
+use std::{future::Future, io::Seek, str::from_utf8, time::Duration};
 use url::Url;
 
 pub struct Client {
@@ -44,23 +56,106 @@ impl Client {
         Ok(())
     }
 
+    fn add_optional_token_to_request(&self, request: &mut awc::ClientRequest) -> Result<()> {
+        if self.token.is_some() {
+            self.add_token_to_request(request)?;
+        }
+        Ok(())
+    }
+
     fn response_to_error(message: &str, payload: Vec<u8>) -> Result<Error> {
         let error_message = format!("{message}: {}", from_utf8(&payload)?);
         error!("{error_message}");
         Ok(anyhow!(error_message))
     }
 
-    pub async fn upload_package(&self, stream: PackageStream, timeout: u64) -> Result<()> {
+    fn unauthorized_error(message: &str) -> Error {
+        anyhow!("{message}: the package is not public, log in with `deputy login` to access it")
+    }
+
+    /// Delay before retrying a request that was answered with `429 Too Many Requests`, the
+    /// `Retry-After` seconds of the registry are followed and an exponential backoff used without
+    /// them. Nothing is retried once `MAX_RETRIES` retries were made.
+    fn retry_delay(
+        status: StatusCode,
+        retry_after: Option<&str>,
+        attempt: u32,
+    ) -> Option<Duration> {
+        if status != StatusCode::TOO_MANY_REQUESTS || attempt >= MAX_RETRIES {
+            return None;
+        }
+        let delay_seconds = retry_after
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .unwrap_or(1 << attempt)
+            .min(MAX_RETRY_DELAY_SECONDS);
+        Some(Duration::from_secs(delay_seconds))
+    }
+
+    fn rate_limit_delay<S>(response: &ClientResponse<S>, attempt: u32) -> Option<Duration> {
+        let retry_after = response
+            .headers()
+            .get(header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok());
+        let delay = Client::retry_delay(response.status(), retry_after, attempt)?;
+        warn!(
+            "Rate limited by the registry, retrying in {} seconds",
+            delay.as_secs()
+        );
+        Some(delay)
+    }
+
+    async fn send_with_backoff<F, R, S>(
+        client_request: ClientRequest,
+        send: F,
+    ) -> Result<ClientResponse<S>, SendRequestError>
+    where
+        F: Fn(&FrozenClientRequest) -> R,
+        R: Future<Output = Result<ClientResponse<S>, SendRequestError>>,
+    {
+        let client_request = client_request.freeze()?;
+        let mut attempt = 0;
+        loop {
+            let response = send(&client_request).await?;
+            match Client::rate_limit_delay(&response, attempt) {
+                Some(delay) => {
+                    sleep(delay).await;
+                    attempt += 1;
+                }
+                None => return std::result::Result::Ok(response),
+            }
+        }
+    }
+
+    /// The archive is streamed again from the start when the upload is retried after a rate limit.
+    pub async fn upload_package(&self, package: &Package, timeout: u64) -> Result<()> {
         let post_uri = self.api_base_url.join(PACKAGE_UPLOAD_PATH)?;
         let mut client_request = self.client.post(post_uri.to_string());
         self.add_token_to_request(&mut client_request)?;
-
-        let mut response = client_request
+        let client_request = client_request
             .timeout(std::time::Duration::from_secs(timeout))
-            .send_stream(stream)
-            .await
+            .freeze()
             .map_err(|error| anyhow!("Failed to upload package: {:?}", error))?;
 
+        let mut attempt = 0;
+        let mut response = loop {
+            let mut archive_file = package.file.try_clone()?;
+            archive_file.rewind()?;
+            let stream = Package::new(package.metadata.clone(), PackageFile(archive_file, None))
+                .to_stream()
+                .await?;
+            let response = client_request
+                .send_stream(stream)
+                .await
+                .map_err(|error| anyhow!("Failed to upload package: {:?}", error))?;
+            match Client::rate_limit_delay(&response, attempt) {
+                Some(delay) => {
+                    sleep(delay).await;
+                    attempt += 1;
+                }
+                None => break response,
+            }
+        };
+
         if response.status().is_success() {
             return Ok(());
         }
@@ -78,16 +173,18 @@ impl Client {
             .join(&format!("{name}/"))?
             .join(&format!("{version}/"))?
             .join("download")?;
-        let mut response = self
-            .client
-            .get(get_uri.to_string())
-            .send()
+        let mut client_request = self.client.get(get_uri.to_string());
+        self.add_optional_token_to_request(&mut client_request)?;
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
             .await
             .map_err(|error| anyhow!("Failed to download package: {:?}", error))?;
         if response.status().is_success() {
             create_file_from_stream(&mut response, file_path).await?;
             return Ok(());
         }
+        if response.status() == awc::http::StatusCode::UNAUTHORIZED {
+            return Err(Client::unauthorized_error("Failed to download package"));
+        }
 
         Err(Client::response_to_error(
             "Failed to download package",
@@ -101,10 +198,9 @@ impl Client {
             .join("api/v1/package/")?
             .join(&format!("{name}/"))?
             .join(&version)?;
-        let mut response = self
-            .client
-            .get(get_uri.to_string())
-            .send()
+        let mut client_request = self.client.get(get_uri.to_string());
+        self.add_optional_token_to_request(&mut client_request)?;
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
             .await
             .map_err(|error| anyhow!("Failed to fetch package metadata: {:?}", error))?;
         if response.status().is_success() {
@@ -131,12 +227,14 @@ impl Client {
                 .as_str(),
         ));
 
-        let mut response = self
-            .client
-            .get(get_uri.to_string())
-            .send()
+        let mut client_request = self.client.get(get_uri.to_string());
+        self.add_optional_token_to_request(&mut client_request)?;
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
             .await
             .map_err(|error| anyhow!("Failed to fetch packages: {:?}", error))?;
+        if response.status() == awc::http::StatusCode::UNAUTHORIZED {
+            return Err(Client::unauthorized_error("Failed to fetch packages"));
+        }
         if response.status().is_success() {
             let body = response.body().await?;
             let packages: Vec<VersionRest> = serde_json::from_slice(&body)?;
@@ -160,11 +258,12 @@ impl Client {
             .join("api/v1/package/")?
             .join(format!("{name}/").as_str())?;
 
-        let mut response = self
+        let mut client_request = self
             .client
             .get(get_uri.to_string())
-            .timeout(std::time::Duration::from_secs(100))
-            .send()
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_optional_token_to_request(&mut client_request)?;
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
             .await
             .map_err(|error| anyhow!("Failed to validate package version: {:?}", error))?;
 
@@ -202,8 +301,7 @@ impl Client {
             .put(put_uri.to_string())
             .timeout(std::time::Duration::from_secs(100));
         self.add_token_to_request(&mut client_request)?;
-        let mut response = client_request
-            .send()
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
             .await
             .map_err(|error| anyhow!("Failed to yank version: {:?}", error))?;
 
@@ -219,6 +317,115 @@ impl Client {
         )?)
     }
 
+    fn deprecation_url(&self, package_name: &str) -> Result<Url> {
+        Ok(self
+            .api_base_url
+            .join("api/v1/package/")?
+            .join(format!("{package_name}/deprecation").as_str())?)
+    }
+
+    pub async fn get_package_deprecation(
+        &self,
+        package_name: &str,
+    ) -> Result<Option<DeprecationRest>> {
+        let mut client_request = self
+            .client
+            .get(self.deprecation_url(package_name)?.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_optional_token_to_request(&mut client_request)?;
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
+            .await
+            .map_err(|error| anyhow!("Failed to get package deprecation: {:?}", error))?;
+
+        if response.status().is_success() {
+            let body = response.body().await?;
+            let deprecation: Option<DeprecationRest> = serde_json::from_slice(&body)?;
+            return Ok(deprecation);
+        }
+
+        Err(Client::response_to_error(
+            "Failed to get package deprecation",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    pub async fn deprecate_package(
+        &self,
+        package_name: &str,
+        deprecation: &DeprecationRest,
+    ) -> Result<Option<DeprecationRest>> {
+        let mut client_request = self
+            .client
+            .put(self.deprecation_url(package_name)?.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_token_to_request(&mut client_request)?;
+        let mut response =
+            Client::send_with_backoff(client_request, |request| request.send_json(deprecation))
+                .await
+                .map_err(|error| anyhow!("Failed to deprecate package: {:?}", error))?;
+
+        if response.status().is_success() {
+            let body = response.body().await?;
+            let deprecation: Option<DeprecationRest> = serde_json::from_slice(&body)?;
+            return Ok(deprecation);
+        }
+
+        Err(Client::response_to_error(
+            "Failed to deprecate package",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    pub async fn undeprecate_package(&self, package_name: &str) -> Result<()> {
+        let mut client_request = self
+            .client
+            .delete(self.deprecation_url(package_name)?.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_token_to_request(&mut client_request)?;
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
+            .await
+            .map_err(|error| anyhow!("Failed to undo package deprecation: {:?}", error))?;
+
+        if response.status().is_success() {
+            return Ok(());
+        }
+
+        Err(Client::response_to_error(
+            "Failed to undo package deprecation",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    pub async fn update_visibility(
+        &self,
+        package_name: &str,
+        visibility: Visibility,
+    ) -> Result<Visibility> {
+        let uri = self
+            .api_base_url
+            .join("api/v1/package/")?
+            .join(format!("{package_name}/visibility/{visibility}").as_str())?;
+        let mut client_request = self
+            .client
+            .put(uri.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_token_to_request(&mut client_request)?;
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
+            .await
+            .map_err(|error| anyhow!("Failed to change package visibility: {:?}", error))?;
+
+        if response.status().is_success() {
+            let body = response.body().await?;
+            let visibility: Visibility = serde_json::from_slice(&body)?;
+            return Ok(visibility);
+        }
+
+        Err(Client::response_to_error(
+            "Failed to change package visibility",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
     pub async fn add_owner(&self, package_name: &str, owner_email: &str) -> Result<()> {
         let uri = self
             .api_base_url
@@ -230,8 +437,7 @@ impl Client {
             .timeout(std::time::Duration::from_secs(100));
         self.add_token_to_request(&mut client_request)?;
 
-        let mut response = client_request
-            .send()
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
             .await
             .map_err(|error| anyhow!("Failed to add owner: {:?}", error))?;
 
@@ -256,8 +462,7 @@ impl Client {
             .timeout(std::time::Duration::from_secs(100));
         self.add_token_to_request(&mut client_request)?;
 
-        let mut response = client_request
-            .send()
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
             .await
             .map_err(|error| anyhow!("{:?}", error))?;
 
@@ -271,18 +476,516 @@ impl Client {
         )?)
     }
 
+    pub async fn add_team_owner(&self, package_name: &str, team_name: &str) -> Result<()> {
+        let uri = self
+            .api_base_url
+            .join("api/v1/package/")?
+            .join(format!("{package_name}/owner/team/{team_name}").as_str())?;
+        let mut client_request = self
+            .client
+            .post(uri.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_token_to_request(&mut client_request)?;
+
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
+            .await
+            .map_err(|error| anyhow!("Failed to add team owner: {:?}", error))?;
+
+        if response.status().is_success() {
+            return Ok(());
+        }
+
+        Err(Client::response_to_error(
+            "Failed to add team owner",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    pub async fn delete_team_owner(&self, package_name: &str, team_name: &str) -> Result<()> {
+        let uri = self
+            .api_base_url
+            .join("api/v1/package/")?
+            .join(format!("{package_name}/owner/team/{team_name}").as_str())?;
+        let mut client_request = self
+            .client
+            .delete(uri.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_token_to_request(&mut client_request)?;
+
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
+            .await
+            .map_err(|error| anyhow!("Failed to delete team owner: {:?}", error))?;
+
+        if response.status().is_success() {
+            return Ok(());
+        }
+
+        Err(Client::response_to_error(
+            "Failed to delete team owner",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    pub async fn list_team_owners(&self, package_name: &str) -> Result<Vec<String>> {
+        let uri = self
+            .api_base_url
+            .join("api/v1/package/")?
+            .join(format!("{package_name}/owner/team").as_str())?;
+        let mut client_request = self
+            .client
+            .get(uri.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_optional_token_to_request(&mut client_request)?;
+
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
+            .await
+            .map_err(|error| anyhow!("Failed to list team owners: {:?}", error))?;
+
+        if response.status().is_success() {
+            let body = response.body().await?;
+            let teams: Vec<TeamRest> = serde_json::from_slice(&body)?;
+            return Ok(teams.into_iter().map(|team| team.name).collect());
+        }
+
+        Err(Client::response_to_error(
+            "Failed to list team owners",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    pub async fn create_scoped_token(
+        &self,
+        new_token: &NewScopedTokenRest,
+    ) -> Result<CreatedTokenRest> {
+        let uri = self.api_base_url.join("api/v1/token/scoped")?;
+        let mut client_request = self
+            .client
+            .post(uri.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_token_to_request(&mut client_request)?;
+
+        let mut response =
+            Client::send_with_backoff(client_request, |request| request.send_json(new_token))
+                .await
+                .map_err(|error| anyhow!("Failed to create token: {:?}", error))?;
+
+        if response.status().is_success() {
+            let body = response.body().await?;
+            let token: CreatedTokenRest = serde_json::from_slice(&body)?;
+            return Ok(token);
+        }
+
+        Err(Client::response_to_error(
+            "Failed to create token",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    pub async fn create_team(&self, team_name: &str) -> Result<TeamRest> {
+        let mut uri = self.api_base_url.join("api/v1/team")?;
+        uri.query_pairs_mut().append_pair("name", team_name);
+        let mut client_request = self
+            .client
+            .post(uri.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_token_to_request(&mut client_request)?;
+
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
+            .await
+            .map_err(|error| anyhow!("Failed to create team: {:?}", error))?;
+
+        if response.status().is_success() {
+            let body = response.body().await?;
+            let team: TeamRest = serde_json::from_slice(&body)?;
+            return Ok(team);
+        }
+
+        Err(Client::response_to_error(
+            "Failed to create team",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    pub async fn delete_team(&self, team_name: &str) -> Result<()> {
+        let uri = self.api_base_url.join("api/v1/team/")?.join(team_name)?;
+        let mut client_request = self
+            .client
+            .delete(uri.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_token_to_request(&mut client_request)?;
+
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
+            .await
+            .map_err(|error| anyhow!("Failed to delete team: {:?}", error))?;
+
+        if response.status().is_success() {
+            return Ok(());
+        }
+
+        Err(Client::response_to_error(
+            "Failed to delete team",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    pub async fn list_teams(&self) -> Result<Vec<TeamRest>> {
+        let uri = self.api_base_url.join("api/v1/team")?;
+        let mut client_request = self
+            .client
+            .get(uri.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_token_to_request(&mut client_request)?;
+
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
+            .await
+            .map_err(|error| anyhow!("Failed to list teams: {:?}", error))?;
+
+        if response.status().is_success() {
+            let body = response.body().await?;
+            let teams: Vec<TeamRest> = serde_json::from_slice(&body)?;
+            return Ok(teams);
+        }
+
+        Err(Client::response_to_error(
+            "Failed to list teams",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    pub async fn get_team(&self, team_name: &str) -> Result<TeamRest> {
+        let uri = self.api_base_url.join("api/v1/team/")?.join(team_name)?;
+        let mut client_request = self
+            .client
+            .get(uri.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_token_to_request(&mut client_request)?;
+
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
+            .await
+            .map_err(|error| anyhow!("Failed to get team: {:?}", error))?;
+
+        if response.status().is_success() {
+            let body = response.body().await?;
+            let team: TeamRest = serde_json::from_slice(&body)?;
+            return Ok(team);
+        }
+
+        Err(Client::response_to_error(
+            "Failed to get team",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    pub async fn add_team_member(
+        &self,
+        team_name: &str,
+        member_email: &str,
+        is_admin: bool,
+    ) -> Result<()> {
+        let mut uri = self
+            .api_base_url
+            .join("api/v1/team/")?
+            .join(format!("{team_name}/member").as_str())?;
+        uri.query_pairs_mut()
+            .append_pair("email", member_email)
+            .append_pair("admin", &is_admin.to_string());
+        let mut client_request = self
+            .client
+            .post(uri.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_token_to_request(&mut client_request)?;
+
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
+            .await
+            .map_err(|error| anyhow!("Failed to add team member: {:?}", error))?;
+
+        if response.status().is_success() {
+            return Ok(());
+        }
+
+        Err(Client::response_to_error(
+            "Failed to add team member",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    pub async fn remove_team_member(&self, team_name: &str, member_email: &str) -> Result<()> {
+        let uri = self
+            .api_base_url
+            .join("api/v1/team/")?
+            .join(format!("{team_name}/member/{member_email}").as_str())?;
+        let mut client_request = self
+            .client
+            .delete(uri.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_token_to_request(&mut client_request)?;
+
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
+            .await
+            .map_err(|error| anyhow!("Failed to remove team member: {:?}", error))?;
+
+        if response.status().is_success() {
+            return Ok(());
+        }
+
+        Err(Client::response_to_error(
+            "Failed to remove team member",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    pub async fn delete_package(&self, package_name: &str, hard_delete: bool) -> Result<()> {
+        let uri = self
+            .api_base_url
+            .join("api/v1/admin/package/")?
+            .join(format!("{package_name}?hard={hard_delete}").as_str())?;
+        let mut client_request = self
+            .client
+            .delete(uri.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_token_to_request(&mut client_request)?;
+
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
+            .await
+            .map_err(|error| anyhow!("Failed to delete package: {:?}", error))?;
+
+        if response.status().is_success() {
+            return Ok(());
+        }
+
+        Err(Client::response_to_error(
+            "Failed to delete package",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    pub async fn delete_version(
+        &self,
+        package_name: &str,
+        version: &str,
+        hard_delete: bool,
+    ) -> Result<()> {
+        let uri = self
+            .api_base_url
+            .join("api/v1/admin/package/")?
+            .join(format!("{package_name}/{version}?hard={hard_delete}").as_str())?;
+        let mut client_request = self
+            .client
+            .delete(uri.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_token_to_request(&mut client_request)?;
+
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
+            .await
+            .map_err(|error| anyhow!("Failed to delete version: {:?}", error))?;
+
+        if response.status().is_success() {
+            return Ok(());
+        }
+
+        Err(Client::response_to_error(
+            "Failed to delete version",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    pub async fn get_audit_logs(
+        &self,
+        options: &AuditLogOptions,
+    ) -> Result<AuditLogsWithPagesRest> {
+        let mut uri = self.api_base_url.join("api/v1/admin/audit-log")?;
+        uri.query_pairs_mut()
+            .append_pair("page", &options.page.to_string())
+            .append_pair("limit", &options.limit.to_string());
+        if let Some(actor) = &options.actor {
+            uri.query_pairs_mut().append_pair("actor", actor);
+        }
+        if let Some(action) = &options.action {
+            uri.query_pairs_mut().append_pair("action", action);
+        }
+        if let Some(package) = &options.package {
+            uri.query_pairs_mut().append_pair("package", package);
+        }
+        let mut client_request = self
+            .client
+            .get(uri.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_token_to_request(&mut client_request)?;
+
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
+            .await
+            .map_err(|error| anyhow!("Failed to get audit log: {:?}", error))?;
+
+        if response.status().is_success() {
+            let body = response.body().await?;
+            let audit_logs: AuditLogsWithPagesRest = serde_json::from_slice(&body)?;
+            return Ok(audit_logs);
+        }
+
+        Err(Client::response_to_error(
+            "Failed to get audit log",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    fn webhook_url(&self, package_name: Option<&str>, path: &str) -> Result<Url> {
+        let base_url = match package_name {
+            Some(package_name) => self
+                .api_base_url
+                .join("api/v1/package/")?
+                .join(&format!("{package_name}/webhook"))?,
+            None => self.api_base_url.join("api/v1/admin/webhook")?,
+        };
+        Ok(Url::parse(&format!("{base_url}{path}"))?)
+    }
+
+    pub async fn create_webhook(
+        &self,
+        package_name: Option<&str>,
+        new_webhook: &NewWebhookRest,
+    ) -> Result<WebhookRest> {
+        let uri = self.webhook_url(package_name, "")?;
+        let mut client_request = self
+            .client
+            .post(uri.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_token_to_request(&mut client_request)?;
+
+        let mut response =
+            Client::send_with_backoff(client_request, |request| request.send_json(new_webhook))
+                .await
+                .map_err(|error| anyhow!("Failed to create webhook: {:?}", error))?;
+
+        if response.status().is_success() {
+            let body = response.body().await?;
+            let webhook: WebhookRest = serde_json::from_slice(&body)?;
+            return Ok(webhook);
+        }
+
+        Err(Client::response_to_error(
+            "Failed to create webhook",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    pub async fn get_webhooks(&self, package_name: Option<&str>) -> Result<Vec<WebhookRest>> {
+        let uri = self.webhook_url(package_name, "")?;
+        let mut client_request = self
+            .client
+            .get(uri.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_token_to_request(&mut client_request)?;
+
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
+            .await
+            .map_err(|error| anyhow!("Failed to get webhooks: {:?}", error))?;
+
+        if response.status().is_success() {
+            let body = response.body().await?;
+            let webhooks: Vec<WebhookRest> = serde_json::from_slice(&body)?;
+            return Ok(webhooks);
+        }
+
+        Err(Client::response_to_error(
+            "Failed to get webhooks",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    pub async fn delete_webhook(&self, package_name: Option<&str>, webhook_id: &str) -> Result<()> {
+        let uri = self.webhook_url(package_name, &format!("/{webhook_id}"))?;
+        let mut client_request = self
+            .client
+            .delete(uri.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_token_to_request(&mut client_request)?;
+
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
+            .await
+            .map_err(|error| anyhow!("Failed to delete webhook: {:?}", error))?;
+
+        if response.status().is_success() {
+            return Ok(());
+        }
+
+        Err(Client::response_to_error(
+            "Failed to delete webhook",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    pub async fn get_webhook_deliveries(
+        &self,
+        package_name: Option<&str>,
+        webhook_id: &str,
+    ) -> Result<Vec<WebhookDeliveryRest>> {
+        let uri = self.webhook_url(package_name, &format!("/{webhook_id}/delivery"))?;
+        let mut client_request = self
+            .client
+            .get(uri.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_token_to_request(&mut client_request)?;
+
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
+            .await
+            .map_err(|error| anyhow!("Failed to get webhook deliveries: {:?}", error))?;
+
+        if response.status().is_success() {
+            let body = response.body().await?;
+            let deliveries: Vec<WebhookDeliveryRest> = serde_json::from_slice(&body)?;
+            return Ok(deliveries);
+        }
+
+        Err(Client::response_to_error(
+            "Failed to get webhook deliveries",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
+    pub async fn get_download_stats(&self, options: &StatsOptions) -> Result<DownloadStatsRest> {
+        let mut uri = self
+            .api_base_url
+            .join("api/v1/package/")?
+            .join(format!("{}/stats", options.package_name).as_str())?;
+        uri.query_pairs_mut()
+            .append_pair("days", &options.days.to_string());
+        if let Some(version) = &options.version {
+            uri.query_pairs_mut().append_pair("version", version);
+        }
+        let mut client_request = self
+            .client
+            .get(uri.to_string())
+            .timeout(std::time::Duration::from_secs(100));
+        self.add_optional_token_to_request(&mut client_request)?;
+
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
+            .await
+            .map_err(|error| anyhow!("Failed to get download statistics: {:?}", error))?;
+
+        if response.status().is_success() {
+            let body = response.body().await?;
+            let download_stats: DownloadStatsRest = serde_json::from_slice(&body)?;
+            return Ok(download_stats);
+        }
+
+        Err(Client::response_to_error(
+            "Failed to get download statistics",
+            response.body().await?.to_vec(),
+        )?)
+    }
+
     pub async fn list_owners(&self, package_name: &str) -> Result<Vec<String>> {
         let uri = self
             .api_base_url
             .join("api/v1/package/")?
             .join(format!("{package_name}/owner").as_str())?;
-        let client_request = self
+        let mut client_request = self
             .client
             .get(uri.to_string())
             .timeout(std::time::Duration::from_secs(100));
+        self.add_optional_token_to_request(&mut client_request)?;
 
-        let mut response = client_request
-            .send()
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
             .await
             .map_err(|error| anyhow!("{:?}", error))?;
 
@@ -304,7 +1007,7 @@ impl Client {
     pub async fn list_packages(
         &self,
         options: &ListOptions,
-    ) -> Result<Vec<PackageWithVersionsRest>> {
+    ) -> Result<PackagesWithVersionsAndPagesRest> {
         let mut base_get_uri = self.api_base_url.join("api/v1/package")?;
 
         if let Some(search_term) = &options.search_term {
@@ -322,11 +1025,14 @@ impl Client {
                 .query_pairs_mut()
                 .append_pair("categories", category);
         }
+        let sort: PackageSort = options.sort.clone().into();
+        base_get_uri
+            .query_pairs_mut()
+            .append_pair("sort", sort.as_str());
 
-        let mut response = self
-            .client
-            .get(base_get_uri.to_string())
-            .send()
+        let mut client_request = self.client.get(base_get_uri.to_string());
+        self.add_optional_token_to_request(&mut client_request)?;
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
             .await
             .map_err(|error| anyhow!("Failed to list packages: {:?}", error))?;
 
@@ -334,7 +1040,7 @@ impl Client {
             let body = response.body().await?;
             let packages_with_versions_and_pages: PackagesWithVersionsAndPagesRest =
                 serde_json::from_slice(&body)?;
-            return Ok(packages_with_versions_and_pages.packages);
+            return Ok(packages_with_versions_and_pages);
         }
 
         Err(Client::response_to_error(
@@ -353,10 +1059,9 @@ impl Client {
             .query_pairs_mut()
             .append_pair("search_term", &options.search_term);
 
-        let mut response = self
-            .client
-            .get(base_get_uri.to_string())
-            .send()
+        let mut client_request = self.client.get(base_get_uri.to_string());
+        self.add_optional_token_to_request(&mut client_request)?;
+        let mut response = Client::send_with_backoff(client_request, FrozenClientRequest::send)
             .await
             .map_err(|error| anyhow!("Failed to get package info: {:?}", error))?;
 
@@ -373,3 +1078,52 @@ impl Client {
         )?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_after_seconds_are_followed_up_to_the_maximum_delay() {
+        let delay =
+            |retry_after| Client::retry_delay(StatusCode::TOO_MANY_REQUESTS, retry_after, 0);
+        assert_eq!(delay(Some("3")), Some(Duration::from_secs(3)));
+        assert_eq!(delay(Some(" 7 ")), Some(Duration::from_secs(7)));
+        assert_eq!(delay(Some("0")), Some(Duration::from_secs(0)));
+        assert_eq!(
+            delay(Some("3600")),
+            Some(Duration::from_secs(MAX_RETRY_DELAY_SECONDS))
+        );
+    }
+
+    #[test]
+    fn backoff_is_used_without_usable_retry_after() {
+        for (retry_after, attempt, expected_seconds) in [
+            (None, 0, 1),
+            (None, 3, 8),
+            (Some("Wed, 21 Oct 2015 07:28:00 GMT"), 2, 4),
+            (Some("-1"), 1, 2),
+        ] {
+            assert_eq!(
+                Client::retry_delay(StatusCode::TOO_MANY_REQUESTS, retry_after, attempt),
+                Some(Duration::from_secs(expected_seconds))
+            );
+        }
+    }
+
+    #[test]
+    fn only_rate_limited_requests_are_retried_a_bounded_number_of_times() {
+        assert_eq!(Client::retry_delay(StatusCode::OK, Some("1"), 0), None);
+        assert_eq!(
+            Client::retry_delay(StatusCode::SERVICE_UNAVAILABLE, Some("1"), 0),
+            None
+        );
+        assert!(
+            Client::retry_delay(StatusCode::TOO_MANY_REQUESTS, None, MAX_RETRIES - 1).is_some()
+        );
+        assert_eq!(
+            Client::retry_delay(StatusCode::TOO_MANY_REQUESTS, Some("1"), MAX_RETRIES),
+            None
+        );
+    }
+}
@@ -4,6 +4,10 @@ use crate::constants::{
     DEFAULT_REGISTRY_NAME,
 };
 use clap::{ArgEnum, Args, Subcommand};
+use deputy_library::{
+    package::Visibility,
+    rest::{PackageSort, TokenScope, WebhookEvent},
+};
 
 #[derive(ArgEnum, Clone, Debug)]
 pub enum UnpackLevel {
@@ -12,6 +16,82 @@ pub enum UnpackLevel {
     Regular,
 }
 
+#[derive(ArgEnum, Clone, Debug)]
+pub enum PackageVisibility {
+    Public,
+    Authenticated,
+    OwnersOnly,
+}
+
+impl From<PackageVisibility> for Visibility {
+    fn from(visibility: PackageVisibility) -> Self {
+        match visibility {
+            PackageVisibility::Public => Visibility::Public,
+            PackageVisibility::Authenticated => Visibility::Authenticated,
+            PackageVisibility::OwnersOnly => Visibility::OwnersOnly,
+        }
+    }
+}
+
+#[derive(ArgEnum, Clone, Debug)]
+pub enum TokenScopeArg {
+    Publish,
+    Yank,
+    ManageOwners,
+    ReadPrivate,
+}
+
+impl From<TokenScopeArg> for TokenScope {
+    fn from(scope: TokenScopeArg) -> Self {
+        match scope {
+            TokenScopeArg::Publish => TokenScope::Publish,
+            TokenScopeArg::Yank => TokenScope::Yank,
+            TokenScopeArg::ManageOwners => TokenScope::ManageOwners,
+            TokenScopeArg::ReadPrivate => TokenScope::ReadPrivate,
+        }
+    }
+}
+
+#[derive(ArgEnum, Clone, Debug)]
+pub enum WebhookEventArg {
+    Publish,
+    Yank,
+    Unyank,
+    OwnerAdd,
+    OwnerRemove,
+}
+
+impl From<WebhookEventArg> for WebhookEvent {
+    fn from(event: WebhookEventArg) -> Self {
+        match event {
+            WebhookEventArg::Publish => WebhookEvent::Publish,
+            WebhookEventArg::Yank => WebhookEvent::Yank,
+            WebhookEventArg::Unyank => WebhookEvent::Unyank,
+            WebhookEventArg::OwnerAdd => WebhookEvent::OwnerAdd,
+            WebhookEventArg::OwnerRemove => WebhookEvent::OwnerRemove,
+        }
+    }
+}
+
+#[derive(ArgEnum, Clone, Debug)]
+pub enum PackageSortArg {
+    Relevance,
+    Name,
+    Updated,
+    Downloads,
+}
+
+impl From<PackageSortArg> for PackageSort {
+    fn from(sort: PackageSortArg) -> Self {
+        match sort {
+            PackageSortArg::Relevance => PackageSort::Relevance,
+            PackageSortArg::Name => PackageSort::Name,
+            PackageSortArg::Updated => PackageSort::Updated,
+            PackageSortArg::Downloads => PackageSort::Downloads,
+        }
+    }
+}
+
 #[derive(Debug, Args)]
 pub struct FetchOptions {
     pub package_name: String,
@@ -71,6 +151,25 @@ pub struct PublishOptions {
     pub token: Option<String>,
     #[clap(short, long, help = "Path to the package to publish")]
     pub path: Option<String>,
+    #[clap(
+        arg_enum,
+        long,
+        conflicts_with = "private",
+        help = "Who is allowed to see and download the package"
+    )]
+    pub visibility: Option<PackageVisibility>,
+    #[clap(long, help = "Publish the package visible to its owners only")]
+    pub private: bool,
+}
+
+impl PublishOptions {
+    pub fn package_visibility(&self) -> Visibility {
+        match (&self.visibility, self.private) {
+            (_, true) => Visibility::OwnersOnly,
+            (Some(visibility), false) => visibility.clone().into(),
+            (None, false) => Visibility::default(),
+        }
+    }
 }
 
 #[derive(Debug, Args)]
@@ -126,15 +225,67 @@ pub struct YankOptions {
     pub token: Option<String>,
 }
 
+#[derive(Debug, Args)]
+pub struct DeprecateOptions {
+    pub package_name: String,
+    #[clap(
+        short,
+        long,
+        required_unless_present = "undo",
+        help = "Message shown to the users of the package"
+    )]
+    pub message: Option<String>,
+    #[clap(long, help = "Package to use instead of the deprecated package")]
+    pub replacement: Option<String>,
+    #[clap(
+        short,
+        long,
+        default_value = DEFAULT_REGISTRY_NAME,
+        help = "Registry to use for package deprecation"
+    )]
+    pub registry_name: String,
+    #[clap(
+        short,
+        long,
+        conflicts_with_all = &["message", "replacement"],
+        help = "Undo deprecation"
+    )]
+    pub undo: bool,
+    #[clap(short = 'T', long, help = "Override the login token")]
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct VisibilityOptions {
+    pub package_name: String,
+    #[clap(arg_enum, help = "Who is allowed to see and download the package")]
+    pub visibility: PackageVisibility,
+    #[clap(
+        short,
+        long,
+        default_value = DEFAULT_REGISTRY_NAME,
+        help = "Registry to use for changing the visibility"
+    )]
+    pub registry_name: String,
+    #[clap(short = 'T', long, help = "Override the login token")]
+    pub token: Option<String>,
+}
+
 #[derive(Subcommand, Debug, Clone)]
 pub enum OwnerSubcommands {
     Add {
+        #[clap(help = "Email of the new owner, or the team name when --team is set")]
         user_email: String,
         package_name: String,
+        #[clap(long, help = "Add a team as the owner instead of a single user")]
+        team: bool,
     },
     Remove {
+        #[clap(help = "Email of the owner, or the team name when --team is set")]
         user_email: String,
         package_name: String,
+        #[clap(long, help = "Remove a team from the owners instead of a single user")]
+        team: bool,
     },
     List {
         package_name: String,
@@ -154,6 +305,183 @@ pub struct OwnerOptions {
     pub subcommands: OwnerSubcommands,
 }
 
+#[derive(Subcommand, Debug, Clone)]
+pub enum TeamSubcommands {
+    #[clap(about = "Create a new team with yourself as its admin")]
+    Create { team_name: String },
+    #[clap(about = "Delete a team that no longer owns any packages")]
+    Delete { team_name: String },
+    #[clap(about = "List the teams you are a member of")]
+    List,
+    #[clap(about = "List the members of a team")]
+    Members { team_name: String },
+    #[clap(about = "Add a member to a team, requires team admin rights")]
+    AddMember {
+        team_name: String,
+        user_email: String,
+        #[clap(long, help = "Allow the new member to manage the team")]
+        admin: bool,
+    },
+    #[clap(about = "Remove a member from a team, requires team admin rights")]
+    RemoveMember {
+        team_name: String,
+        user_email: String,
+    },
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct TeamOptions {
+    #[clap(
+        short,
+        long,
+        default_value = DEFAULT_REGISTRY_NAME,
+        help = "Registry to use for team management"
+    )]
+    pub registry_name: String,
+    #[clap(subcommand)]
+    pub subcommands: TeamSubcommands,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TokenSubcommands {
+    #[clap(
+        about = "Create a token limited to the given scopes and packages, e.g. for CI pipelines"
+    )]
+    Create {
+        token_name: String,
+        #[clap(
+            short,
+            long = "scope",
+            arg_enum,
+            help = "Scope the token is allowed to use, can be repeated. Defaults to the scopes of the current token"
+        )]
+        scopes: Vec<TokenScopeArg>,
+        #[clap(
+            short,
+            long = "package",
+            help = "Package name or glob pattern the token is limited to, can be repeated"
+        )]
+        packages: Vec<String>,
+        #[clap(long, help = "Number of days until the token expires")]
+        expires_in_days: Option<u32>,
+    },
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct TokenOptions {
+    #[clap(
+        short,
+        long,
+        default_value = DEFAULT_REGISTRY_NAME,
+        help = "Registry to use for token management"
+    )]
+    pub registry_name: String,
+    #[clap(subcommand)]
+    pub subcommands: TokenSubcommands,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum AdminSubcommands {
+    #[clap(about = "Delete a package or a single version of it from the registry")]
+    Delete {
+        package_name: String,
+        #[clap(
+            help = "Version to delete. If no version is provided, the whole package is deleted"
+        )]
+        version: Option<String>,
+        #[clap(
+            long,
+            help = "Permanently remove the package records and archives instead of marking them as deleted"
+        )]
+        hard: bool,
+    },
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct AdminOptions {
+    #[clap(
+        short,
+        long,
+        default_value = DEFAULT_REGISTRY_NAME,
+        help = "Registry to administer"
+    )]
+    pub registry_name: String,
+    #[clap(short = 'T', long, help = "Override the login token")]
+    pub token: Option<String>,
+    #[clap(subcommand)]
+    pub subcommands: AdminSubcommands,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum WebhookSubcommands {
+    #[clap(about = "Create a webhook that is called on registry events")]
+    Create {
+        url: String,
+        #[clap(
+            short,
+            long = "event",
+            arg_enum,
+            help = "Event the webhook is called on, can be repeated. Defaults to every event"
+        )]
+        events: Vec<WebhookEventArg>,
+        #[clap(
+            long,
+            help = "Secret used to sign the webhook requests. Generated by the registry if not set"
+        )]
+        secret: Option<String>,
+    },
+    #[clap(about = "List the webhooks")]
+    List,
+    #[clap(about = "Delete a webhook")]
+    Delete { webhook_id: String },
+    #[clap(about = "Show the delivery history of a webhook")]
+    Deliveries { webhook_id: String },
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct WebhookOptions {
+    #[clap(
+        short,
+        long,
+        default_value = DEFAULT_REGISTRY_NAME,
+        help = "Registry to use for webhook management"
+    )]
+    pub registry_name: String,
+    #[clap(short = 'T', long, help = "Override the login token")]
+    pub token: Option<String>,
+    #[clap(
+        short,
+        long,
+        help = "Package whose webhooks to manage. Global webhooks, which require the admin role, are managed if not set"
+    )]
+    pub package: Option<String>,
+    #[clap(subcommand)]
+    pub subcommands: WebhookSubcommands,
+}
+
+#[derive(Debug, Args)]
+pub struct AuditLogOptions {
+    #[clap(
+        short,
+        long,
+        default_value = DEFAULT_REGISTRY_NAME,
+        help = "Registry to read the audit log from"
+    )]
+    pub registry_name: String,
+    #[clap(short = 'T', long, help = "Override the login token")]
+    pub token: Option<String>,
+    #[clap(long, help = "Only show entries made by the actor with this email")]
+    pub actor: Option<String>,
+    #[clap(long, help = "Only show entries of this action, e.g. publish_version")]
+    pub action: Option<String>,
+    #[clap(short, long, help = "Only show entries concerning this package")]
+    pub package: Option<String>,
+    #[clap(long, default_value_t = 1, help = "Page of the audit log to show")]
+    pub page: u32,
+    #[clap(short, long, default_value_t = 20, help = "Number of entries per page")]
+    pub limit: u32,
+}
+
 #[derive(Debug, Args)]
 pub struct CreateOptions {
     #[clap(
@@ -195,6 +523,42 @@ pub struct ListOptions {
     pub category: Option<String>,
     #[clap(short = 'a', long, help = "List all versions of the package")]
     pub all_versions: bool,
+    #[clap(
+        arg_enum,
+        short,
+        long,
+        default_value_t = PackageSortArg::Relevance,
+        help = "Order of the listed packages, relevance falls back to name without a search term"
+    )]
+    pub sort: PackageSortArg,
+    #[clap(
+        short,
+        long,
+        help = "Show the number of matching packages per type and category"
+    )]
+    pub facets: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct StatsOptions {
+    #[clap(
+        short,
+        long,
+        default_value = DEFAULT_REGISTRY_NAME,
+        help = "Registry to read the download statistics from"
+    )]
+    pub registry_name: String,
+    #[clap(help = "Name of the package")]
+    pub package_name: String,
+    #[clap(short, long, help = "Only count the downloads of this version")]
+    pub version: Option<String>,
+    #[clap(
+        short,
+        long,
+        default_value_t = 30,
+        help = "Number of days to show daily downloads for"
+    )]
+    pub days: u32,
 }
 
 #[derive(Debug, Args)]
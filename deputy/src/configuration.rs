@@ -1,6 +1,6 @@
 use crate::constants::{CONFIGURATION_FILE_RELATIVE_PATH, TOKEN_FILE_RELATIVE_PATH};
 use anyhow::Result;
-use deputy_library::constants::CONFIGURATION_FOLDER_PATH_ENV_KEY;
+use deputy_library::{archiver::ExtractionLimits, constants::CONFIGURATION_FOLDER_PATH_ENV_KEY};
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, env, fs::read_to_string, path::PathBuf};
 
@@ -12,6 +12,8 @@ pub struct Registry {
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct PackageDownload {
     pub download_path: String,
+    #[serde(default)]
+    pub extraction_limits: ExtractionLimits,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -13,3 +13,7 @@ pub mod inspecting {
 pub mod endpoints {
     pub const PACKAGE_UPLOAD_PATH: &str = "api/v1/package";
 }
+pub mod rate_limiting {
+    pub const MAX_RETRIES: u32 = 5;
+    pub const MAX_RETRY_DELAY_SECONDS: u64 = 60;
+}
@@ -1,15 +1,17 @@
 use crate::client::Client;
 use crate::commands::{
-    ChecksumOptions, CreateOptions, FetchOptions, InfoOptions, InspectOptions, ListOptions,
-    LoginOptions, NormalizeVersionOptions, OwnerOptions, PublishOptions, YankOptions,
+    AdminOptions, AuditLogOptions, ChecksumOptions, CreateOptions, DeprecateOptions, FetchOptions,
+    InfoOptions, InspectOptions, ListOptions, LoginOptions, NormalizeVersionOptions, OwnerOptions,
+    PackageSortArg, PublishOptions, StatsOptions, TeamOptions, TeamSubcommands, TokenOptions,
+    TokenSubcommands, VisibilityOptions, WebhookOptions, WebhookSubcommands, YankOptions,
 };
 use crate::configuration::Configuration;
 use crate::helpers::{
     banner_fields, condition_fields, create_default_readme, create_temporary_package_download_path,
     event_fields, exercise_fields, feature_fields, find_toml, get_download_target_name,
-    inject_fields, malware_fields, other_fields, print_latest_version_package_list_entry,
-    print_package_info, print_package_list_entry, set_assets_field, unpack_package_file,
-    virtual_machine_fields,
+    inject_fields, malware_fields, other_fields, print_deprecation_warning,
+    print_latest_version_package_list_entry, print_package_info, print_package_list_entry,
+    print_search_facets, set_assets_field, unpack_package_file, virtual_machine_fields,
 };
 use crate::progressbar::{AdvanceProgressBar, ProgressStatus, SpinnerProgressBar};
 use actix::Actor;
@@ -17,7 +19,10 @@ use anyhow::{anyhow, Ok, Result};
 use colored::Colorize;
 use deputy_library::constants::ASSETS_REQUIRED_PACKAGE_TYPES;
 use deputy_library::project::ContentType;
-use deputy_library::rest::{PackageWithVersionsRest, VersionRest};
+use deputy_library::rest::{
+    DeprecationRest, NewScopedTokenRest, NewWebhookRest, PackageWithVersionsRest,
+    TokenRestrictionsRest, VersionRest,
+};
 use deputy_library::validation::{validate_license, Validate};
 use deputy_library::{package::Package, project::create_project_from_toml_path};
 use dialoguer::{Input, Select};
@@ -90,6 +95,7 @@ impl Executor {
             )))
             .await??;
 
+        let visibility = options.package_visibility();
         let package_path = match options.path {
             Some(path) => match path.trim() {
                 "" => current_dir()?,
@@ -112,12 +118,13 @@ impl Executor {
         project.validate()?;
         project.validate_files(package_root_path)?;
 
-        let package = Package::from_file(&toml_path, options.compression).map_err(|e| {
+        let mut package = Package::from_file(&toml_path, options.compression).map_err(|e| {
             anyhow::anyhow!(
                 "Failed to create package based on TOML file: {}",
                 e.to_string()
             )
         })?;
+        package.metadata.visibility = visibility;
 
         progress_actor
             .send(AdvanceProgressBar(ProgressStatus::InProgress(
@@ -144,9 +151,7 @@ impl Executor {
             )))
             .await??;
 
-        client
-            .upload_package(package.to_stream().await?, options.timeout)
-            .await?;
+        client.upload_package(&package, options.timeout).await?;
         progress_actor
             .send(AdvanceProgressBar(ProgressStatus::Done))
             .await??;
@@ -187,8 +192,11 @@ impl Executor {
                 "Decompressing the package".to_string(),
             )))
             .await??;
-        let unpacked_file_path =
-            unpack_package_file(&temporary_package_path, &options.unpack_level)?;
+        let unpacked_file_path = unpack_package_file(
+            &temporary_package_path,
+            &options.unpack_level,
+            &self.configuration.package.extraction_limits,
+        )?;
         let target_path = get_download_target_name(
             &options.unpack_level,
             &options.save_path,
@@ -202,6 +210,11 @@ impl Executor {
         progress_actor
             .send(AdvanceProgressBar(ProgressStatus::Done))
             .await??;
+        if let Result::Ok(Some(deprecation)) =
+            client.get_package_deprecation(&options.package_name).await
+        {
+            print_deprecation_warning(&options.package_name, &deprecation);
+        }
 
         Ok(())
     }
@@ -292,14 +305,50 @@ impl Executor {
         Ok(())
     }
 
+    pub async fn deprecate(&self, options: DeprecateOptions) -> Result<()> {
+        let client = self.try_create_client(options.registry_name.clone(), options.token)?;
+        match options.undo {
+            true => {
+                client.undeprecate_package(&options.package_name).await?;
+                println!("{} deprecation successful undo", &options.package_name);
+            }
+            false => {
+                let deprecation = DeprecationRest {
+                    message: options
+                        .message
+                        .ok_or_else(|| anyhow!("Deprecation message is missing"))?,
+                    replacement: options.replacement,
+                };
+                client
+                    .deprecate_package(&options.package_name, &deprecation)
+                    .await?;
+                println!("{} deprecation successful", &options.package_name);
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn visibility(&self, options: VisibilityOptions) -> Result<()> {
+        let client = self.try_create_client(options.registry_name.clone(), options.token)?;
+        let visibility = client
+            .update_visibility(&options.package_name, options.visibility.into())
+            .await?;
+        println!("{} visibility set to {visibility}", &options.package_name);
+        Ok(())
+    }
+
     pub async fn add_owner(
         &self,
         owner_options: OwnerOptions,
         user_email: String,
         package_name: String,
+        is_team: bool,
     ) -> Result<()> {
         let client = self.try_create_client(owner_options.registry_name, None)?;
-        client.add_owner(&package_name, &user_email).await?;
+        match is_team {
+            true => client.add_team_owner(&package_name, &user_email).await?,
+            false => client.add_owner(&package_name, &user_email).await?,
+        }
 
         Ok(())
     }
@@ -309,9 +358,13 @@ impl Executor {
         owner_options: OwnerOptions,
         user_email: String,
         package_name: String,
+        is_team: bool,
     ) -> Result<()> {
         let client = self.try_create_client(owner_options.registry_name, None)?;
-        client.delete_owner(&package_name, &user_email).await?;
+        match is_team {
+            true => client.delete_team_owner(&package_name, &user_email).await?,
+            false => client.delete_owner(&package_name, &user_email).await?,
+        }
 
         Ok(())
     }
@@ -322,12 +375,251 @@ impl Executor {
         package_name: String,
     ) -> Result<()> {
         let client = self.try_create_client(owner_options.registry_name, None)?;
-        let owners = client.list_owners(&package_name).await?;
+        let mut owners = client.list_owners(&package_name).await?;
+        owners.extend(
+            client
+                .list_team_owners(&package_name)
+                .await?
+                .into_iter()
+                .map(|team_name| format!("team:{team_name}")),
+        );
 
         println!("{}", owners.join("\n"));
         Ok(())
     }
 
+    pub async fn team(&self, options: TeamOptions) -> Result<()> {
+        let client = self.try_create_client(options.registry_name, None)?;
+        match options.subcommands {
+            TeamSubcommands::Create { team_name } => {
+                let team = client.create_team(&team_name).await?;
+                println!("Team {} created", team.name);
+            }
+            TeamSubcommands::Delete { team_name } => {
+                client.delete_team(&team_name).await?;
+                println!("Team {team_name} deleted");
+            }
+            TeamSubcommands::List => {
+                let teams = client.list_teams().await?;
+                let team_names = teams
+                    .into_iter()
+                    .map(|team| team.name)
+                    .collect::<Vec<String>>();
+                println!("{}", team_names.join("\n"));
+            }
+            TeamSubcommands::Members { team_name } => {
+                let team = client.get_team(&team_name).await?;
+                for member in team.members {
+                    match member.is_admin {
+                        true => println!("{} (admin)", member.email),
+                        false => println!("{}", member.email),
+                    }
+                }
+            }
+            TeamSubcommands::AddMember {
+                team_name,
+                user_email,
+                admin,
+            } => {
+                client
+                    .add_team_member(&team_name, &user_email, admin)
+                    .await?;
+            }
+            TeamSubcommands::RemoveMember {
+                team_name,
+                user_email,
+            } => {
+                client.remove_team_member(&team_name, &user_email).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn token(&self, options: TokenOptions) -> Result<()> {
+        let client = self.try_create_client(options.registry_name, None)?;
+        match options.subcommands {
+            TokenSubcommands::Create {
+                token_name,
+                scopes,
+                packages,
+                expires_in_days,
+            } => {
+                let token = client
+                    .create_scoped_token(&NewScopedTokenRest {
+                        name: token_name,
+                        restrictions: TokenRestrictionsRest {
+                            scopes: scopes.into_iter().map(Into::into).collect(),
+                            packages,
+                            expires_in_days,
+                        },
+                    })
+                    .await?;
+                if let Some(expires_at) = token.expires_at {
+                    eprintln!("Token {} expires at {expires_at}", token.name);
+                }
+                println!("{}", token.token);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn delete_package(
+        &self,
+        admin_options: AdminOptions,
+        package_name: String,
+        version: Option<String>,
+        hard_delete: bool,
+    ) -> Result<()> {
+        let client = self.try_create_client(admin_options.registry_name, admin_options.token)?;
+        match version {
+            Some(version) => {
+                client
+                    .delete_version(&package_name, &version, hard_delete)
+                    .await?;
+                println!("{package_name} version {version} deleted");
+            }
+            None => {
+                client.delete_package(&package_name, hard_delete).await?;
+                println!("{package_name} deleted");
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn audit_log(&self, options: AuditLogOptions) -> Result<()> {
+        let client =
+            self.try_create_client(options.registry_name.clone(), options.token.clone())?;
+        let audit_logs = client.get_audit_logs(&options).await?;
+
+        for audit_log in &audit_logs.audit_logs {
+            let target = match (&audit_log.package_name, &audit_log.package_version) {
+                (Some(package_name), Some(package_version)) => {
+                    format!(" {package_name} {package_version}")
+                }
+                (Some(package_name), None) => format!(" {package_name}"),
+                _ => String::new(),
+            };
+            let details = audit_log
+                .details
+                .as_ref()
+                .map(|details| format!(" ({details})"))
+                .unwrap_or_default();
+            println!(
+                "{created_at} {actor} {action}{target}{details}",
+                created_at = audit_log.created_at,
+                actor = audit_log.actor_email,
+                action = audit_log.action.green(),
+            );
+        }
+        eprintln!(
+            "Page {page} of {total_pages}, {total_audit_logs} entries in total",
+            page = options.page,
+            total_pages = audit_logs.total_pages,
+            total_audit_logs = audit_logs.total_audit_logs,
+        );
+
+        Ok(())
+    }
+
+    pub async fn webhook(&self, options: WebhookOptions) -> Result<()> {
+        let client = self.try_create_client(options.registry_name, options.token)?;
+        let package_name = options.package.as_deref();
+        match options.subcommands {
+            WebhookSubcommands::Create {
+                url,
+                events,
+                secret,
+            } => {
+                let webhook = client
+                    .create_webhook(
+                        package_name,
+                        &NewWebhookRest {
+                            url,
+                            events: events.into_iter().map(Into::into).collect(),
+                            secret,
+                        },
+                    )
+                    .await?;
+                eprintln!("Webhook {} created for {}", webhook.id, webhook.url);
+                if let Some(secret) = webhook.secret {
+                    println!("{secret}");
+                }
+            }
+            WebhookSubcommands::List => {
+                for webhook in client.get_webhooks(package_name).await? {
+                    let events = match webhook.events.is_empty() {
+                        true => "all events".to_string(),
+                        false => webhook
+                            .events
+                            .iter()
+                            .map(|event| event.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    };
+                    println!(
+                        "{id} {url} ({events})",
+                        id = webhook.id.to_string().green(),
+                        url = webhook.url,
+                    );
+                }
+            }
+            WebhookSubcommands::Delete { webhook_id } => {
+                client.delete_webhook(package_name, &webhook_id).await?;
+                println!("Webhook {webhook_id} deleted");
+            }
+            WebhookSubcommands::Deliveries { webhook_id } => {
+                for delivery in client
+                    .get_webhook_deliveries(package_name, &webhook_id)
+                    .await?
+                {
+                    let response_status = delivery
+                        .response_status
+                        .map(|status| format!(" HTTP {status}"))
+                        .unwrap_or_default();
+                    let last_error = delivery
+                        .last_error
+                        .as_ref()
+                        .map(|error| format!(" ({error})"))
+                        .unwrap_or_default();
+                    println!(
+                        "{created_at} {event} {status} after {attempts} attempts{response_status}{last_error}",
+                        created_at = delivery.created_at,
+                        event = delivery.event,
+                        status = delivery.status.green(),
+                        attempts = delivery.attempts,
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn stats(&self, options: StatsOptions) -> Result<()> {
+        let client = self.try_create_client(options.registry_name.clone(), None)?;
+        let download_stats = client.get_download_stats(&options).await?;
+
+        println!(
+            "{name}: {total_downloads} downloads",
+            name = download_stats.package_name.green(),
+            total_downloads = download_stats.total_downloads,
+        );
+        for version in &download_stats.versions {
+            println!("  {}: {}", version.version, version.downloads);
+        }
+        if !download_stats.daily.is_empty() {
+            println!("Daily downloads over the last {} days:", options.days);
+            for day in &download_stats.daily {
+                println!("  {}: {}", day.date, day.downloads);
+            }
+        }
+
+        Ok(())
+    }
+
     pub async fn create(&self, options: CreateOptions) -> Result<()> {
         let package_path = options.package_path;
         let mut package_name: String = Input::new()
@@ -439,13 +731,16 @@ type = "{chosen_content_type}"
 
     pub async fn list_packages(&self, list_options: ListOptions) -> Result<()> {
         let client = self.try_create_client(list_options.registry_name.clone(), None)?;
-        let mut packages = client.list_packages(&list_options).await?;
+        let search_results = client.list_packages(&list_options).await?;
+        let mut packages = search_results.packages;
         PackageWithVersionsRest::remove_yanked_versions(&mut packages);
 
         if packages.is_empty() {
             println!("{error} No packages found", error = "Error:".red());
         }
-        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        if let PackageSortArg::Name = list_options.sort {
+            packages.sort_by(|a, b| a.name.cmp(&b.name));
+        }
 
         if list_options.all_versions {
             for package in &packages {
@@ -456,6 +751,9 @@ type = "{chosen_content_type}"
                 print_latest_version_package_list_entry(package)?;
             }
         }
+        if list_options.facets {
+            print_search_facets(&search_results.facets);
+        }
 
         Ok(())
     }
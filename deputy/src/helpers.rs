@@ -3,9 +3,12 @@ use anyhow::{anyhow, Error, Ok, Result};
 use awc::error::PayloadError;
 use bytes::Bytes;
 use colored::Colorize;
-use deputy_library::archiver::{decompress_archive, unpack_archive};
+use deputy_library::archiver::{decompress_archive, unpack_archive, ExtractionLimits};
+use deputy_library::package::Visibility;
 use deputy_library::project::FeatureType;
-use deputy_library::rest::{PackageWithVersionsRest, VersionRest};
+use deputy_library::rest::{
+    DeprecationRest, PackageWithVersionsRest, SearchFacetsRest, VersionRest,
+};
 use dialoguer::Select;
 use futures::{Stream, StreamExt};
 use human_bytes::human_bytes;
@@ -33,6 +36,20 @@ pub fn print_error_message(error: Error) {
     eprintln!("{} {}", "Error:".red(), error);
 }
 
+pub fn print_deprecation_warning(package_name: &str, deprecation: &DeprecationRest) {
+    eprintln!(
+        "{} {package_name} is deprecated: {deprecation}",
+        "Warning:".yellow()
+    );
+}
+
+fn deprecation_marker(package: &PackageWithVersionsRest) -> String {
+    match package.deprecation {
+        Some(_) => format!(" {}", "(deprecated)".yellow()),
+        None => String::new(),
+    }
+}
+
 pub fn create_temporary_package_download_path(
     package_name: &str,
     package_version: &str,
@@ -74,20 +91,23 @@ pub fn get_download_target_name(
 pub fn unpack_package_file(
     temporary_file_path: &str,
     unpack_level: &UnpackLevel,
+    extraction_limits: &ExtractionLimits,
 ) -> Result<String> {
     match unpack_level {
         UnpackLevel::Raw => Ok(temporary_file_path.to_string()),
         UnpackLevel::Uncompressed => {
-            let decompresesed_path = decompress_archive(&PathBuf::from(temporary_file_path))?;
+            let decompresesed_path =
+                decompress_archive(&PathBuf::from(temporary_file_path), extraction_limits)?;
             Ok(decompresesed_path
                 .to_str()
                 .ok_or_else(|| anyhow!("Failed to get decompressed path"))?
                 .to_string())
         }
         UnpackLevel::Regular => {
-            let decompresesed_path = decompress_archive(&PathBuf::from(temporary_file_path))?;
+            let decompresesed_path =
+                decompress_archive(&PathBuf::from(temporary_file_path), extraction_limits)?;
             let destination_path = PathBuf::from(format!("{}-dir", temporary_file_path));
-            unpack_archive(&decompresesed_path, &destination_path)?;
+            unpack_archive(&decompresesed_path, &destination_path, extraction_limits)?;
             Ok(destination_path
                 .to_str()
                 .ok_or_else(|| anyhow!("Failed to get destination path"))?
@@ -229,9 +249,10 @@ pub fn print_latest_version_package_list_entry(package: &PackageWithVersionsRest
         .ok_or_else(|| anyhow!("Package missing version"))?;
 
     println!(
-        "{name}/{type}, {latest_version}",
+        "{name}/{type}, {latest_version}{deprecated}",
         name = package.name.green(),
-        type = package.package_type
+        type = package.package_type,
+        deprecated = deprecation_marker(package),
     );
 
     Ok(())
@@ -242,10 +263,11 @@ pub fn print_package_list_entry(package: &PackageWithVersionsRest) -> Result<()>
     versions.sort_by(|a, b| b.version.cmp(&a.version));
     for version in versions {
         println!(
-            "{name}/{type}, {version}",
+            "{name}/{type}, {version}{deprecated}",
             name = package.name.green(),
             type = package.package_type,
             version = version.version,
+            deprecated = deprecation_marker(package),
         );
     }
     Ok(())
@@ -255,7 +277,14 @@ pub fn print_package_info(package: &PackageWithVersionsRest, package_version: &V
     println!("Name: {}", package.name);
     println!("Version: {}", package_version.version);
     println!("Type: {}", package.package_type);
+    if package.visibility != Visibility::Public {
+        println!("Visibility: {}", package.visibility);
+    }
+    if let Some(deprecation) = &package.deprecation {
+        println!("Deprecated: {}", deprecation.to_string().yellow());
+    }
     println!("License: {}", package_version.license);
+    println!("Downloads: {}", package.downloads);
     println!("Description: {}", package_version.description);
     println!(
         "Package Size: {}",
@@ -265,6 +294,18 @@ pub fn print_package_info(package: &PackageWithVersionsRest, package_version: &V
     println!()
 }
 
+pub fn print_search_facets(facets: &SearchFacetsRest) {
+    println!();
+    println!("Types:");
+    for (package_type, count) in &facets.package_types {
+        println!("  {package_type}: {count}");
+    }
+    println!("Categories:");
+    for (category, count) in &facets.categories {
+        println!("  {category}: {count}");
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,9 +2,10 @@ use anyhow::Result;
 use clap::{Parser, Subcommand};
 use deputy::{
     commands::{
-        ChecksumOptions, CreateOptions, FetchOptions, InfoOptions, InspectOptions, ListOptions,
-        LoginOptions, NormalizeVersionOptions, OwnerOptions, OwnerSubcommands, PublishOptions,
-        YankOptions,
+        AdminOptions, AdminSubcommands, AuditLogOptions, ChecksumOptions, CreateOptions,
+        DeprecateOptions, FetchOptions, InfoOptions, InspectOptions, ListOptions, LoginOptions,
+        NormalizeVersionOptions, OwnerOptions, OwnerSubcommands, PublishOptions, StatsOptions,
+        TeamOptions, TokenOptions, VisibilityOptions, WebhookOptions, YankOptions,
     },
     executor::Executor,
     helpers::print_error_message,
@@ -34,14 +35,30 @@ enum Commands {
     Login(LoginOptions),
     #[clap(about = "Remove previously published version of package from registry")]
     Yank(YankOptions),
+    #[clap(about = "Mark package as deprecated, its versions can still be fetched")]
+    Deprecate(DeprecateOptions),
+    #[clap(about = "Change who is allowed to see and download package")]
+    Visibility(VisibilityOptions),
     #[clap(about = "Manage the owners of package on the registry")]
     Owner(OwnerOptions),
+    #[clap(about = "Manage teams that can own packages on the registry")]
+    Team(TeamOptions),
+    #[clap(about = "Manage API tokens on the registry")]
+    Token(TokenOptions),
     #[clap(about = "Create new package")]
     Create(CreateOptions),
     #[clap(about = "List all packages")]
     List(ListOptions),
     #[clap(about = "Get detailed information of package")]
     Info(InfoOptions),
+    #[clap(about = "Show download statistics of package")]
+    Stats(StatsOptions),
+    #[clap(about = "Manage webhooks that are called on registry events")]
+    Webhook(WebhookOptions),
+    #[clap(about = "Administer the registry, requires the admin role")]
+    Admin(AdminOptions),
+    #[clap(about = "Show the audit log of registry changes, requires the admin role")]
+    AuditLog(AuditLogOptions),
 }
 
 #[actix_rt::main]
@@ -57,26 +74,50 @@ async fn main() -> Result<()> {
         Commands::NormalizeVersion(options) => executor.normalize_version(options).await,
         Commands::Login(options) => executor.login(options).await,
         Commands::Yank(options) => executor.yank(options).await,
+        Commands::Deprecate(options) => executor.deprecate(options).await,
+        Commands::Visibility(options) => executor.visibility(options).await,
         Commands::Owner(options) => match options.subcommands.clone() {
             OwnerSubcommands::Add {
                 user_email,
                 package_name,
-            } => executor.add_owner(options, user_email, package_name).await,
+                team,
+            } => {
+                executor
+                    .add_owner(options, user_email, package_name, team)
+                    .await
+            }
             OwnerSubcommands::Remove {
                 user_email,
                 package_name,
+                team,
             } => {
                 executor
-                    .remove_owner(options, user_email, package_name)
+                    .remove_owner(options, user_email, package_name, team)
                     .await
             }
             OwnerSubcommands::List { package_name } => {
                 executor.list_owners(options, package_name).await
             }
         },
+        Commands::Team(options) => executor.team(options).await,
+        Commands::Token(options) => executor.token(options).await,
         Commands::Create(options) => executor.create(options).await,
         Commands::List(options) => executor.list_packages(options).await,
         Commands::Info(options) => executor.package_info(options).await,
+        Commands::Stats(options) => executor.stats(options).await,
+        Commands::Webhook(options) => executor.webhook(options).await,
+        Commands::Admin(options) => match options.subcommands.clone() {
+            AdminSubcommands::Delete {
+                package_name,
+                version,
+                hard,
+            } => {
+                executor
+                    .delete_package(options, package_name, version, hard)
+                    .await
+            }
+        },
+        Commands::AuditLog(options) => executor.audit_log(options).await,
     };
     if let Err(error) = result {
         print_error_message(error);
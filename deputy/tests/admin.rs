@@ -0,0 +1,103 @@
+mod helpers;
+
+#[cfg(test)]
+mod tests {
+    use crate::helpers::{
+        setup_test_backend, upload_test_package, DeployerCLIConfigurationBuilder,
+    };
+    use anyhow::Result;
+    use assert_cmd::Command;
+    use deputy_library::constants::CONFIGURATION_FOLDER_PATH_ENV_KEY;
+
+    #[actix_web::test]
+    async fn delete_package_version() -> Result<()> {
+        let host = setup_test_backend().await?;
+        let cli_configuration = DeployerCLIConfigurationBuilder::builder()
+            .host(&host)
+            .build()?;
+        upload_test_package(&cli_configuration).await?;
+
+        let mut command = Command::cargo_bin("deputy")?;
+        command
+            .arg("admin")
+            .arg("delete")
+            .arg("some-package-name")
+            .arg("1.0.4")
+            .arg("--hard");
+        command.env(
+            CONFIGURATION_FOLDER_PATH_ENV_KEY,
+            cli_configuration.configuration_folder.path(),
+        );
+        command.assert().success();
+
+        let mut command = Command::cargo_bin("deputy")?;
+        command.arg("info").arg("some-package-name");
+        command.env(
+            CONFIGURATION_FOLDER_PATH_ENV_KEY,
+            cli_configuration.configuration_folder.path(),
+        );
+        let output_string = String::from_utf8(command.output()?.stdout)?;
+        assert!(!output_string.contains("Version: 1.0.4"));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn delete_missing_package_fails() -> Result<()> {
+        let host = setup_test_backend().await?;
+        let cli_configuration = DeployerCLIConfigurationBuilder::builder()
+            .host(&host)
+            .build()?;
+        upload_test_package(&cli_configuration).await?;
+
+        let mut command = Command::cargo_bin("deputy")?;
+        command
+            .arg("admin")
+            .arg("delete")
+            .arg("this-does-not-exist");
+        command.env(
+            CONFIGURATION_FOLDER_PATH_ENV_KEY,
+            cli_configuration.configuration_folder.path(),
+        );
+        command.assert().failure();
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn audit_log_lists_deletions() -> Result<()> {
+        let host = setup_test_backend().await?;
+        let cli_configuration = DeployerCLIConfigurationBuilder::builder()
+            .host(&host)
+            .build()?;
+        upload_test_package(&cli_configuration).await?;
+
+        let mut command = Command::cargo_bin("deputy")?;
+        command
+            .arg("admin")
+            .arg("delete")
+            .arg("some-package-name")
+            .arg("1.0.4");
+        command.env(
+            CONFIGURATION_FOLDER_PATH_ENV_KEY,
+            cli_configuration.configuration_folder.path(),
+        );
+        command.assert().success();
+
+        let mut command = Command::cargo_bin("deputy")?;
+        command
+            .arg("audit-log")
+            .arg("--action")
+            .arg("delete_version");
+        command.env(
+            CONFIGURATION_FOLDER_PATH_ENV_KEY,
+            cli_configuration.configuration_folder.path(),
+        );
+        command.assert().success();
+        let output_string = String::from_utf8(command.output()?.stdout)?;
+        assert!(output_string.contains("some-package-name 1.0.4"));
+        assert!(!output_string.contains("publish_package"));
+
+        Ok(())
+    }
+}
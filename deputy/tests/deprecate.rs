@@ -0,0 +1,63 @@
+mod helpers;
+
+#[cfg(test)]
+mod tests {
+    use crate::helpers::{
+        setup_test_backend, upload_test_package, DeployerCLIConfigurationBuilder,
+    };
+    use anyhow::Result;
+    use assert_cmd::Command;
+    use deputy_library::constants::CONFIGURATION_FOLDER_PATH_ENV_KEY;
+    use tempfile::TempDir;
+
+    #[actix_web::test]
+    async fn deprecated_package_is_shown_and_still_fetched() -> Result<()> {
+        let host = setup_test_backend().await?;
+        let cli_configuration = DeployerCLIConfigurationBuilder::builder()
+            .host(&host)
+            .build()?;
+        upload_test_package(&cli_configuration).await?;
+        let deputy = |arguments: &[&str]| -> Result<Command> {
+            let mut command = Command::cargo_bin("deputy")?;
+            command.args(arguments);
+            command.env(
+                CONFIGURATION_FOLDER_PATH_ENV_KEY,
+                cli_configuration.configuration_folder.path(),
+            );
+            Ok(command)
+        };
+
+        deputy(&["deprecate", "some-package-name"])?
+            .assert()
+            .failure();
+        deputy(&[
+            "deprecate",
+            "some-package-name",
+            "--message",
+            "No longer maintained",
+        ])?
+        .assert()
+        .success();
+
+        let output = deputy(&["info", "some-package-name"])?.output()?;
+        assert!(String::from_utf8(output.stdout)?.contains("Deprecated: No longer maintained"));
+        let output = deputy(&["list"])?.output()?;
+        assert!(String::from_utf8(output.stdout)?.contains("(deprecated)"));
+
+        let temp_dir = TempDir::new()?;
+        let mut fetch = deputy(&["fetch", "some-package-name"])?;
+        fetch.current_dir(temp_dir.path());
+        let output = fetch.output()?;
+        assert!(output.status.success());
+        assert!(String::from_utf8(output.stderr)?
+            .contains("some-package-name is deprecated: No longer maintained"));
+
+        deputy(&["deprecate", "some-package-name", "--undo"])?
+            .assert()
+            .success();
+        let output = deputy(&["info", "some-package-name"])?.output()?;
+        assert!(!String::from_utf8(output.stdout)?.contains("Deprecated"));
+
+        Ok(())
+    }
+}
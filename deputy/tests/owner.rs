@@ -136,4 +136,38 @@ mod tests {
 
         Ok(())
     }
+
+    #[actix_web::test]
+    async fn add_team_owner() -> Result<()> {
+        let host = setup_test_backend().await?;
+        let cli_configuration = DeployerCLIConfigurationBuilder::builder()
+            .host(&host)
+            .build()?;
+        upload_test_package(&cli_configuration).await?;
+
+        let mut command = Command::cargo_bin("deputy")?;
+        command.arg("team").arg("create").arg("platform");
+        command.env(
+            CONFIGURATION_FOLDER_PATH_ENV_KEY,
+            cli_configuration.configuration_folder.path(),
+        );
+        command.assert().success();
+
+        let mut command = Command::cargo_bin("deputy")?;
+        command
+            .arg("owner")
+            .arg("add")
+            .arg("--team")
+            .arg("platform")
+            .arg("some-package-name");
+        command.env(
+            CONFIGURATION_FOLDER_PATH_ENV_KEY,
+            cli_configuration.configuration_folder.path(),
+        );
+        command.assert().success();
+        let owners = execute_list_owners_command(&cli_configuration, "some-package-name").await?;
+        assert!(owners.contains(&"team:platform".to_string()));
+
+        Ok(())
+    }
 }
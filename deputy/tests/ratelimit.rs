@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::header::RETRY_AFTER,
+        web::{to, Payload},
+        App, Error, HttpResponse, HttpServer,
+    };
+    use anyhow::Result;
+    use deputy::{client::Client, constants::rate_limiting::MAX_RETRIES};
+    use deputy_library::{package::Package, test::TempArchive};
+    use futures::StreamExt;
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+    use tempfile::TempDir;
+
+    /// Registry that answers the first `rate_limited_requests` requests with `429 Too Many
+    /// Requests` and `Retry-After: 0`, the requests after them succeed. Returns the API address
+    /// and the count of the requests received so far.
+    async fn start_rate_limited_registry(
+        rate_limited_requests: u32,
+    ) -> Result<(String, Arc<AtomicU32>)> {
+        let requests = Arc::new(AtomicU32::new(0));
+        let received_requests = requests.clone();
+        let server = HttpServer::new(move || {
+            let received_requests = received_requests.clone();
+            App::new().default_service(to(move |mut payload: Payload| {
+                let received_requests = received_requests.clone();
+                async move {
+                    while let Some(chunk) = payload.next().await {
+                        chunk?;
+                    }
+                    let response = match received_requests.fetch_add(1, Ordering::SeqCst)
+                        < rate_limited_requests
+                    {
+                        true => HttpResponse::TooManyRequests()
+                            .insert_header((RETRY_AFTER, "0"))
+                            .finish(),
+                        false => HttpResponse::Ok().finish(),
+                    };
+                    Ok::<HttpResponse, Error>(response)
+                }
+            }))
+        })
+        .workers(1)
+        .bind("127.0.0.1:0")?;
+        let address = server.addrs()[0];
+        actix_rt::spawn(server.run());
+        Ok((format!("http://{address}/"), requests))
+    }
+
+    #[actix_web::test]
+    async fn download_gives_up_after_the_maximum_retries() -> Result<()> {
+        let (api_address, requests) = start_rate_limited_registry(u32::MAX).await?;
+        let client = Client::try_new(api_address, None)?;
+        let download_folder = TempDir::new()?;
+        let file_path = download_folder.path().join("some-package");
+
+        let result = client
+            .download_package("some-package", "1.0.0", file_path.to_str().unwrap())
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(requests.load(Ordering::SeqCst), MAX_RETRIES + 1);
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn upload_is_sent_again_after_a_rate_limit() -> Result<()> {
+        let (api_address, requests) = start_rate_limited_registry(2).await?;
+        let client = Client::try_new(api_address, Some("some-token".to_string()))?;
+        let archive = TempArchive::builder().build()?;
+        let package: Package = (&archive).try_into()?;
+
+        client.upload_package(&package, 60).await?;
+
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn upload_gives_up_after_the_maximum_retries() -> Result<()> {
+        let (api_address, requests) = start_rate_limited_registry(u32::MAX).await?;
+        let client = Client::try_new(api_address, Some("some-token".to_string()))?;
+        let archive = TempArchive::builder().build()?;
+        let package: Package = (&archive).try_into()?;
+
+        let result = client.upload_package(&package, 60).await;
+
+        assert!(result.is_err());
+        assert_eq!(requests.load(Ordering::SeqCst), MAX_RETRIES + 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+mod helpers;
+
+#[cfg(test)]
+mod tests {
+    use crate::helpers::{
+        setup_test_backend, upload_test_package, DeployerCLIConfigurationBuilder,
+    };
+    use anyhow::Result;
+    use assert_cmd::Command;
+    use deputy_library::constants::CONFIGURATION_FOLDER_PATH_ENV_KEY;
+
+    #[actix_web::test]
+    async fn show_download_stats_of_package() -> Result<()> {
+        let host = setup_test_backend().await?;
+        let cli_configuration = DeployerCLIConfigurationBuilder::builder()
+            .host(&host)
+            .build()?;
+        upload_test_package(&cli_configuration).await?;
+
+        let mut command = Command::cargo_bin("deputy")?;
+        command.arg("stats").arg("some-package-name");
+        command.env(
+            CONFIGURATION_FOLDER_PATH_ENV_KEY,
+            cli_configuration.configuration_folder.path(),
+        );
+        command.assert().success();
+        let output_string = String::from_utf8(command.output()?.stdout)?;
+        assert!(output_string.contains("0 downloads"));
+        assert!(output_string.contains("1.0.4: 0"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+mod helpers;
+
+#[cfg(test)]
+mod tests {
+    use crate::helpers::{setup_test_backend, DeployerCLIConfigurationBuilder};
+    use anyhow::Result;
+    use assert_cmd::Command;
+    use deputy_library::constants::CONFIGURATION_FOLDER_PATH_ENV_KEY;
+
+    #[actix_web::test]
+    async fn create_package_scoped_token() -> Result<()> {
+        let host = setup_test_backend().await?;
+        let cli_configuration = DeployerCLIConfigurationBuilder::builder()
+            .host(&host)
+            .build()?;
+
+        let mut command = Command::cargo_bin("deputy")?;
+        command
+            .arg("token")
+            .arg("create")
+            .arg("ci")
+            .arg("--scope")
+            .arg("publish")
+            .arg("--package")
+            .arg("some-package-name")
+            .arg("--expires-in-days")
+            .arg("30");
+        command.env(
+            CONFIGURATION_FOLDER_PATH_ENV_KEY,
+            cli_configuration.configuration_folder.path(),
+        );
+        command.assert().success();
+        let output_string = String::from_utf8(command.output()?.stdout)?;
+        assert!(!output_string.trim().is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+mod helpers;
+
+#[cfg(test)]
+mod tests {
+    use crate::helpers::{
+        setup_test_backend, upload_test_package, DeployerCLIConfigurationBuilder,
+    };
+    use anyhow::Result;
+    use assert_cmd::Command;
+    use deputy_library::constants::CONFIGURATION_FOLDER_PATH_ENV_KEY;
+
+    #[actix_web::test]
+    async fn package_visibility_is_changed() -> Result<()> {
+        let host = setup_test_backend().await?;
+        let cli_configuration = DeployerCLIConfigurationBuilder::builder()
+            .host(&host)
+            .build()?;
+        upload_test_package(&cli_configuration).await?;
+        let deputy = |arguments: &[&str]| -> Result<Command> {
+            let mut command = Command::cargo_bin("deputy")?;
+            command.args(arguments);
+            command.env(
+                CONFIGURATION_FOLDER_PATH_ENV_KEY,
+                cli_configuration.configuration_folder.path(),
+            );
+            Ok(command)
+        };
+
+        deputy(&["visibility", "some-package-name", "everyone"])?
+            .assert()
+            .failure();
+        let output = deputy(&["visibility", "some-package-name", "owners-only"])?.output()?;
+        assert!(output.status.success());
+        assert!(String::from_utf8(output.stdout)?
+            .contains("some-package-name visibility set to owners_only"));
+
+        let output = deputy(&["visibility", "some-package-name", "public"])?.output()?;
+        assert!(output.status.success());
+        assert!(String::from_utf8(output.stdout)?
+            .contains("some-package-name visibility set to public"));
+
+        Ok(())
+    }
+}
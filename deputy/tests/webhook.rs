@@ -0,0 +1,55 @@
+mod helpers;
+
+#[cfg(test)]
+mod tests {
+    use crate::helpers::{
+        setup_test_backend, upload_test_package, DeployerCLIConfigurationBuilder,
+    };
+    use anyhow::Result;
+    use assert_cmd::Command;
+    use deputy_library::constants::CONFIGURATION_FOLDER_PATH_ENV_KEY;
+
+    #[actix_web::test]
+    async fn create_and_list_package_webhooks() -> Result<()> {
+        let host = setup_test_backend().await?;
+        let cli_configuration = DeployerCLIConfigurationBuilder::builder()
+            .host(&host)
+            .build()?;
+        upload_test_package(&cli_configuration).await?;
+
+        let mut command = Command::cargo_bin("deputy")?;
+        command
+            .arg("webhook")
+            .arg("--package")
+            .arg("some-package-name")
+            .arg("create")
+            .arg("https://hooks.example.com/deputy")
+            .arg("--event")
+            .arg("yank")
+            .arg("--secret")
+            .arg("webhook-secret");
+        command.env(
+            CONFIGURATION_FOLDER_PATH_ENV_KEY,
+            cli_configuration.configuration_folder.path(),
+        );
+        command.assert().success();
+        let output_string = String::from_utf8(command.output()?.stdout)?;
+        assert!(output_string.contains("webhook-secret"));
+
+        let mut command = Command::cargo_bin("deputy")?;
+        command
+            .arg("webhook")
+            .arg("--package")
+            .arg("some-package-name")
+            .arg("list");
+        command.env(
+            CONFIGURATION_FOLDER_PATH_ENV_KEY,
+            cli_configuration.configuration_folder.path(),
+        );
+        command.assert().success();
+        let output_string = String::from_utf8(command.output()?.stdout)?;
+        assert!(output_string.contains("https://hooks.example.com/deputy (yank)"));
+
+        Ok(())
+    }
+}